@@ -29,7 +29,10 @@ pub use self::crypto_impl::{
 };
 #[cfg(feature = "sodiumoxide-crypto")]
 pub use self::crypto_lib::sodiumoxide::x25519;
-pub use self::utils::{generate_keys_file, read_keys_from_file};
+pub use self::utils::{
+    decrypt_with_passphrase, encrypt_with_passphrase, generate_keys_file, read_keys_from_file,
+};
+pub use pwbox::SensitiveData;
 
 use byteorder::{ByteOrder, LittleEndian};
 use chrono::{DateTime, Duration, Utc};
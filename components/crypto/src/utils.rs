@@ -16,7 +16,7 @@
 
 use super::{gen_keypair, gen_keypair_from_seed, PublicKey, SecretKey, Seed, SEED_LENGTH};
 use hex_buffer_serde::Hex;
-use pwbox::{sodium::Sodium, ErasedPwBox, Eraser, Suite};
+use pwbox::{sodium::Sodium, ErasedPwBox, Eraser, SensitiveData, Suite};
 use rand::thread_rng;
 use std::borrow::Cow;
 #[cfg(unix)]
@@ -63,6 +63,50 @@ pub fn read_keys_from_file<P: AsRef<Path>, W: AsRef<[u8]>>(
     keys.decrypt(pass_phrase)
 }
 
+/// Encrypts the data with the pass phrase using the same scheme as the key files
+/// and returns the encrypted container serialized to JSON.
+pub fn encrypt_with_passphrase<D: AsRef<[u8]>, W: AsRef<[u8]>>(
+    data: D,
+    pass_phrase: W,
+) -> Result<Vec<u8>, Error> {
+    let encrypted = seal(data, pass_phrase)?;
+    serde_json::to_vec(&encrypted).map_err(|e| Error::new(ErrorKind::Other, e))
+}
+
+/// Decrypts the data encrypted by `encrypt_with_passphrase`. The returned data
+/// is zeroized on drop.
+pub fn decrypt_with_passphrase<E: AsRef<[u8]>, W: AsRef<[u8]>>(
+    encrypted: E,
+    pass_phrase: W,
+) -> Result<SensitiveData, Error> {
+    let encrypted: ErasedPwBox = serde_json::from_slice(encrypted.as_ref())
+        .map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+    open(&encrypted, pass_phrase)
+}
+
+fn seal(data: impl AsRef<[u8]>, pass_phrase: impl AsRef<[u8]>) -> Result<ErasedPwBox, Error> {
+    let mut rng = thread_rng();
+    let mut eraser = Eraser::new();
+    eraser.add_suite::<Sodium>();
+    let pwbox = Sodium::build_box(&mut rng)
+        .seal(pass_phrase, data)
+        .map_err(|_| Error::new(ErrorKind::Other, "Couldn't create a pw box"))?;
+    eraser
+        .erase(&pwbox)
+        .map_err(|_| Error::new(ErrorKind::Other, "Couldn't convert a pw box"))
+}
+
+fn open(encrypted: &ErasedPwBox, pass_phrase: impl AsRef<[u8]>) -> Result<SensitiveData, Error> {
+    let mut eraser = Eraser::new();
+    eraser.add_suite::<Sodium>();
+    let restored = eraser
+        .restore(encrypted)
+        .map_err(|_| Error::new(ErrorKind::Other, "Couldn't restore a pw box"))?;
+    restored
+        .open(pass_phrase)
+        .map_err(|_| Error::new(ErrorKind::Other, "Couldn't open a pw box"))
+}
+
 #[cfg(unix)]
 #[cfg_attr(feature = "cargo-clippy", allow(clippy::verbose_bit_mask))]
 fn validate_file_mode(mode: u32) -> Result<(), Error> {
@@ -99,16 +143,8 @@ impl EncryptedKeys {
         secret_key: &SecretKey,
         pass_phrase: impl AsRef<[u8]>,
     ) -> Result<EncryptedKeys, Error> {
-        let mut rng = thread_rng();
-        let mut eraser = Eraser::new();
         let seed = &secret_key[..SEED_LENGTH];
-        eraser.add_suite::<Sodium>();
-        let pwbox = Sodium::build_box(&mut rng)
-            .seal(pass_phrase, &seed)
-            .map_err(|_| Error::new(ErrorKind::Other, "Couldn't create a pw box"))?;
-        let encrypted_key = eraser
-            .erase(&pwbox)
-            .map_err(|_| Error::new(ErrorKind::Other, "Couldn't convert a pw box"))?;
+        let encrypted_key = seal(seed, pass_phrase)?;
 
         Ok(EncryptedKeys {
             public_key,
@@ -117,15 +153,7 @@ impl EncryptedKeys {
     }
 
     fn decrypt(self, pass_phrase: impl AsRef<[u8]>) -> Result<(PublicKey, SecretKey), Error> {
-        let mut eraser = Eraser::new();
-        eraser.add_suite::<Sodium>();
-        let restored = eraser
-            .restore(&self.secret_key)
-            .map_err(|_| Error::new(ErrorKind::Other, "Couldn't restore a secret key"))?;
-        assert_eq!(restored.len(), SEED_LENGTH);
-        let seed_bytes = restored
-            .open(pass_phrase)
-            .map_err(|_| Error::new(ErrorKind::Other, "Couldn't open an encrypted key"))?;
+        let seed_bytes = open(&self.secret_key, pass_phrase)?;
         let seed = Seed::from_slice(&seed_bytes[..])
             .ok_or_else(|| Error::new(ErrorKind::Other, "Couldn't create seed from slice"))?;
         let (public_key, secret_key) = gen_keypair_from_seed(&seed);
@@ -197,6 +225,15 @@ mod tests {
         )
     }
 
+    #[test]
+    fn test_encrypt_decrypt_with_passphrase() {
+        let data = b"secret data";
+        let encrypted = encrypt_with_passphrase(data, b"passphrase").unwrap();
+        let decrypted = decrypt_with_passphrase(&encrypted, b"passphrase").unwrap();
+        assert_eq!(&*decrypted, data);
+        assert!(decrypt_with_passphrase(&encrypted, b"wrong passphrase").is_err());
+    }
+
     #[cfg(unix)]
     #[test]
     fn test_validate_file_mode() {
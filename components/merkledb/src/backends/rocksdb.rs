@@ -152,6 +152,16 @@ impl Database for RocksDB {
         w_opts.set_sync(true);
         self.do_merge(patch, &w_opts)
     }
+
+    fn compact(&self) -> crate::Result<()> {
+        let cf_names = rocksdb::DB::list_cf(&RocksDbOptions::default(), self.db.path())?;
+        for cf_name in cf_names {
+            if let Some(cf) = self.db.cf_handle(&cf_name) {
+                self.db.compact_range_cf(cf, None::<&[u8]>, None::<&[u8]>);
+            }
+        }
+        Ok(())
+    }
 }
 
 impl Snapshot for RocksDBSnapshot {
@@ -53,6 +53,10 @@ impl Database for TemporaryDB {
     fn merge_sync(&self, patch: Patch) -> Result<()> {
         self.inner.merge_sync(patch)
     }
+
+    fn compact(&self) -> Result<()> {
+        self.inner.compact()
+    }
 }
 
 impl Default for TemporaryDB {
@@ -22,7 +22,7 @@ use std::{
         HashMap,
     },
     fmt,
-    iter::{FromIterator, Iterator as StdIterator, Peekable},
+    iter::{Iterator as StdIterator, Peekable},
     mem,
     ops::{Deref, DerefMut},
 };
@@ -296,17 +296,27 @@ impl Patch {
     }
 
     /// Produces a patch that would reverse the effects of this patch for a given
-    /// snapshot.
+    /// snapshot. Entries removed by clearing indices are restored as well.
     pub fn undo(self, snapshot: &dyn Snapshot) -> Self {
         let mut rev_patch = Self::new();
 
         for (name, changes) in self {
-            let rev_changes = BTreeMap::from_iter(changes.into_iter().map(|(key, ..)| {
-                match snapshot.get(&name, &key) {
+            let mut rev_changes = BTreeMap::new();
+            for prefix in changes.prefixes_to_remove() {
+                let mut iter = snapshot.iter(&name, prefix);
+                while let Some((key, value)) = iter.next() {
+                    if !key.starts_with(prefix) {
+                        break;
+                    }
+                    rev_changes.insert(key.to_vec(), Change::Put(value.to_vec()));
+                }
+            }
+            rev_changes.extend(changes.into_iter().map(
+                |(key, ..)| match snapshot.get(&name, &key) {
                     Some(value) => (key, Change::Put(value)),
                     None => (key, Change::Delete),
-                }
-            }));
+                },
+            ));
 
             rev_patch.changes.insert(
                 name,
@@ -489,6 +499,20 @@ pub trait Database: Send + Sync + 'static {
     /// will be returned. In case of an error, the method guarantees no changes are applied to
     /// the database.
     fn merge_sync(&self, patch: Patch) -> Result<()>;
+
+    /// Explicitly triggers compaction of the whole database.
+    ///
+    /// Compaction may take a considerable amount of time, so this method should not be
+    /// called from latency-sensitive code. The default implementation does nothing,
+    /// which is suitable for backends without the notion of compaction.
+    ///
+    /// # Errors
+    ///
+    /// If this method encounters any form of I/O or other error during compaction, an error
+    /// variant will be returned.
+    fn compact(&self) -> Result<()> {
+        Ok(())
+    }
 }
 
 /// A read-only snapshot of a storage backend.
@@ -615,6 +639,12 @@ impl Fork {
         !self.working_patch.is_empty()
     }
 
+    /// Returns the names of the indices changed in this fork. Changes made after the latest
+    /// execution of the `flush` method are not taken into account.
+    pub fn changed_indices(&self) -> impl Iterator<Item = &str> {
+        self.flushed.patch.changes.keys().map(String::as_str)
+    }
+
     /// Patch containing current changes made in this fork.
     pub fn working_patch(&self) -> &WorkingPatch {
         &self.working_patch
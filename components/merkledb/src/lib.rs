@@ -141,7 +141,10 @@ pub use self::{
     sparse_list_index::SparseListIndex,
     value_set_index::ValueSetIndex,
     values::BinaryValue,
-    views::{IndexAccess, IndexAddress, IndexBuilder, ObjectAccess, Ref, RefMut, View},
+    views::{
+        index_type, IndexAccess, IndexAddress, IndexBuilder, IndexType, ObjectAccess, Ref, RefMut,
+        View,
+    },
 };
 
 #[macro_use]
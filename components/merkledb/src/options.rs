@@ -40,6 +40,32 @@ pub struct DbOptions {
     ///
     /// Defaults to `true`.
     pub create_if_missing: bool,
+    /// Interval in milliseconds between explicit compactions of the database.
+    ///
+    /// If set, the node compacts the database in the background with the specified
+    /// interval, skipping the compaction while there are unconfirmed transactions
+    /// in the pool. This allows to avoid latency spikes caused by compactions
+    /// triggered by the database itself during block commit.
+    ///
+    /// Defaults to `None`, meaning that compaction timing is left to the database.
+    #[serde(default)]
+    pub compaction_interval: Option<u64>,
+    /// Number of additional attempts to write a committed block to the database
+    /// if the write fails, e.g., because of a transient IO error.
+    ///
+    /// Defaults to `0`, meaning that a failed write is not retried.
+    #[serde(default)]
+    pub commit_retries: u32,
+    /// Delay in milliseconds between the attempts to write a committed block
+    /// to the database.
+    ///
+    /// Defaults to `100`.
+    #[serde(default = "default_commit_retry_delay")]
+    pub commit_retry_delay: u64,
+}
+
+fn default_commit_retry_delay() -> u64 {
+    100
 }
 
 impl Default for DbOptions {
@@ -47,6 +73,9 @@ impl Default for DbOptions {
         Self {
             max_open_files: None,
             create_if_missing: true,
+            compaction_interval: None,
+            commit_retries: 0,
+            commit_retry_delay: default_commit_retry_delay(),
         }
     }
 }
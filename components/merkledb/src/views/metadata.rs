@@ -32,14 +32,23 @@ const INDEXES_POOL_NAME: &str = "__INDEXES_POOL__";
 #[derive(Debug, Copy, Clone, PartialEq, Primitive, Serialize, Deserialize)]
 #[repr(u32)]
 pub enum IndexType {
+    /// `MapIndex`.
     Map = 1,
+    /// `ListIndex`.
     List = 2,
+    /// `Entry`.
     Entry = 3,
+    /// `ValueSetIndex`.
     ValueSet = 4,
+    /// `KeySetIndex`.
     KeySet = 5,
+    /// `SparseListIndex`.
     SparseList = 6,
+    /// `ProofListIndex`.
     ProofList = 7,
+    /// `ProofMapIndex`.
     ProofMap = 8,
+    /// Index of unknown type.
     Unknown = 255,
 }
 
@@ -188,6 +197,18 @@ where
     (index_address, index_state)
 }
 
+/// Returns the type of the index with the provided `index_address`, or `None`
+/// if the index has not been created yet.
+pub fn index_type<T: IndexAccess>(
+    index_access: T,
+    index_address: &IndexAddress,
+) -> Option<IndexType> {
+    let index_name = index_address.fully_qualified_name();
+    IndexesPool::new(index_access)
+        .index_metadata::<()>(&index_name)
+        .map(|metadata| metadata.index_type)
+}
+
 /// Persistent pool used to store indexes metadata in the database.
 /// Pool size is used as an identifier of newly created indexes.
 struct IndexesPool<T: IndexAccess>(View<T>);
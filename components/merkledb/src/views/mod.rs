@@ -15,7 +15,7 @@
 #![warn(missing_docs)]
 
 pub use self::{
-    metadata::{BinaryAttribute, IndexState, IndexType},
+    metadata::{index_type, BinaryAttribute, IndexState, IndexType},
     refs::{AnyObject, ObjectAccess, Ref, RefMut},
 };
 
@@ -14,7 +14,7 @@
 
 use crate::{
     db,
-    views::{IndexAccess, IndexAddress, IndexBuilder, IndexType, View},
+    views::{index_type, IndexAccess, IndexAddress, IndexBuilder, IndexType, View},
     Database, DbOptions, Fork, RocksDB, TemporaryDB,
 };
 
@@ -808,6 +808,36 @@ fn test_metadata_index_family_incorrect() {
         .build::<()>();
 }
 
+#[test]
+fn test_index_type() {
+    let db = TemporaryDB::new();
+    let fork = db.fork();
+    IndexBuilder::new(&fork)
+        .index_type(IndexType::ProofMap)
+        .index_name("simple")
+        .build::<()>();
+    IndexBuilder::new(&fork)
+        .index_type(IndexType::List)
+        .index_name("simple")
+        .family_id("family")
+        .build::<u64>();
+    db.merge(fork.into_patch()).unwrap();
+
+    let snapshot = db.snapshot();
+    assert_eq!(
+        index_type(&snapshot, &IndexAddress::with_root("simple")),
+        Some(IndexType::ProofMap)
+    );
+    assert_eq!(
+        index_type(&snapshot, &IndexAddress::from(("simple", "family"))),
+        Some(IndexType::List)
+    );
+    assert_eq!(
+        index_type(&snapshot, &IndexAddress::with_root("other")),
+        None
+    );
+}
+
 #[test]
 #[ignore]
 //TODO: fix test [ECR-2869]
@@ -14,8 +14,8 @@
 use exonum_merkledb::TemporaryDB;
 
 use exonum::{
-    blockchain::{GenesisConfig, ValidatorKeys},
-    node::{Node, NodeApiConfig, NodeConfig},
+    blockchain::{GenesisConfig, ValidatorKeys, DEFAULT_VALIDATOR_WEIGHT},
+    node::{Node, NodeApiConfig, NodeConfig, DEFAULT_CONSENSUS_LOG_SIZE},
 };
 use exonum_cryptocurrency::service::CurrencyService;
 
@@ -26,6 +26,7 @@ fn node_config() -> NodeConfig {
     let validator_keys = ValidatorKeys {
         consensus_key: consensus_public_key,
         service_key: service_public_key,
+        weight: DEFAULT_VALIDATOR_WEIGHT,
     };
     let genesis = GenesisConfig::new(vec![validator_keys].into_iter());
 
@@ -52,6 +53,29 @@ fn node_config() -> NodeConfig {
         services_configs: Default::default(),
         database: Default::default(),
         thread_pool_size: Default::default(),
+        record_consensus_messages: false,
+        consensus_log_size: DEFAULT_CONSENSUS_LOG_SIZE,
+        node_label: None,
+        consensus_cache_flush_interval: 0,
+        db_backend: None,
+        log_rejected_messages: false,
+        broadcast_target: Default::default(),
+        status_webhook: None,
+        accepted_services: None,
+        tx_broadcast_batch_interval: 0,
+        precompute_state_hashes: false,
+        max_tx_time_skew: None,
+        nonce_buffer_size: 0,
+        connect_list_source: None,
+        high_resolution_timeouts: false,
+        verify_execution_determinism: false,
+        halt_on_nondeterministic_execution: false,
+        expected_validators: None,
+        strict_expected_validators: false,
+        metrics_sink: None,
+        genesis_conflict_policy: Default::default(),
+        block_publisher: None,
+        prune_blocks_before: None,
     }
 }
 
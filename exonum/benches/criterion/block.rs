@@ -80,7 +80,7 @@ fn create_rocksdb(tempdir: &TempDir) -> RocksDB {
 
 fn create_blockchain(db: impl Database, services: Vec<Box<dyn Service>>) -> Blockchain {
     use exonum::{
-        blockchain::{GenesisConfig, ValidatorKeys},
+        blockchain::{GenesisConfig, ValidatorKeys, DEFAULT_VALIDATOR_WEIGHT},
         crypto,
     };
     use std::sync::Arc;
@@ -99,6 +99,7 @@ fn create_blockchain(db: impl Database, services: Vec<Box<dyn Service>>) -> Bloc
     let config = GenesisConfig::new(iter::once(ValidatorKeys {
         consensus_key: consensus_keypair.0,
         service_key: service_keypair.0,
+        weight: DEFAULT_VALIDATOR_WEIGHT,
     }));
     blockchain.initialize(config).unwrap();
 
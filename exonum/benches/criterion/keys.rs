@@ -0,0 +1,26 @@
+// Copyright 2019 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Deterministic derivation of the keys signing the generated transactions.
+
+use exonum::crypto::{self, PublicKey, SecretKey, Seed};
+
+/// Derives the `index`-th keypair from the given seed. The derivation uses only SHA-256
+/// and Ed25519 key generation, so the keys are the same across runs and platforms.
+pub fn gen_keypair_from_bench_seed(seed: u64, index: u64) -> (PublicKey, SecretKey) {
+    let mut bytes = seed.to_le_bytes().to_vec();
+    bytes.extend_from_slice(&index.to_le_bytes());
+    let seed = Seed::from_slice(crypto::hash(&bytes).as_ref()).unwrap();
+    crypto::gen_keypair_from_seed(&seed)
+}
@@ -26,6 +26,7 @@ use crate::transactions::bench_verify_transactions;
 
 mod block;
 mod crypto;
+mod keys;
 mod proto;
 mod storage;
 mod transactions;
@@ -14,6 +14,10 @@
 
 const MESSAGES_COUNT: usize = 1_000;
 const SAMPLE_SIZE: usize = 20;
+/// Environment variable with the seed of the keys used to sign the generated transactions.
+/// Command line arguments of the benchmarks are parsed by `criterion`, so the seed
+/// is passed through the environment, e.g. `EXONUM_BENCH_SEED=42 cargo bench`.
+const SEED_ENV_VAR: &str = "EXONUM_BENCH_SEED";
 
 use criterion::{
     AxisScale, Bencher, Criterion, ParameterizedBenchmark, PlotConfiguration, Throughput,
@@ -27,6 +31,7 @@ use futures::{
 use tokio_core::reactor::Core;
 
 use std::{
+    env,
     sync::{Arc, RwLock},
     thread::{self, JoinHandle},
 };
@@ -42,6 +47,8 @@ use exonum::{
 };
 use tokio_threadpool::Builder as ThreadPoolBuilder;
 
+use crate::keys::gen_keypair_from_bench_seed;
+
 struct MessagesHandler {
     txs_count: usize,
     expected_count: usize,
@@ -83,9 +90,23 @@ impl EventHandler for MessagesHandler {
     }
 }
 
-fn gen_messages(count: usize, tx_size: usize) -> Vec<Vec<u8>> {
+/// Returns the seed of the transaction keys set in the `SEED_ENV_VAR` environment variable.
+fn bench_seed() -> Option<u64> {
+    let seed = env::var(SEED_ENV_VAR).ok()?;
+    Some(
+        seed.parse()
+            .unwrap_or_else(|e| panic!("Invalid {} value {:?}: {}", SEED_ENV_VAR, seed, e)),
+    )
+}
+
+/// Generates the transactions signed by the key derived from `seed`, or by a random key
+/// if the seed is not set.
+fn gen_messages(count: usize, tx_size: usize, seed: Option<u64>) -> Vec<Vec<u8>> {
     use exonum_merkledb::BinaryValue;
-    let (p, s) = crypto::gen_keypair();
+    let (p, s) = match seed {
+        Some(seed) => gen_keypair_from_bench_seed(seed, 0),
+        None => crypto::gen_keypair(),
+    };
     (0..count)
         .map(|_| {
             let msg = Message::new(
@@ -206,8 +227,8 @@ impl MessageVerifier {
     }
 }
 
-fn bench_verify_messages_simple(b: &mut Bencher, &size: &usize) {
-    let messages = gen_messages(MESSAGES_COUNT, size);
+fn bench_verify_messages_simple(b: &mut Bencher, size: usize, seed: Option<u64>) {
+    let messages = gen_messages(MESSAGES_COUNT, size, seed);
     b.iter_with_setup(
         || messages.clone(),
         |messages| {
@@ -218,8 +239,8 @@ fn bench_verify_messages_simple(b: &mut Bencher, &size: &usize) {
     )
 }
 
-fn bench_verify_messages_event_loop(b: &mut Bencher, &size: &usize) {
-    let messages = gen_messages(MESSAGES_COUNT, size);
+fn bench_verify_messages_event_loop(b: &mut Bencher, size: usize, seed: Option<u64>) {
+    let messages = gen_messages(MESSAGES_COUNT, size, seed);
 
     let verifier = MessageVerifier::new();
     let mut core = Core::new().unwrap();
@@ -233,23 +254,35 @@ fn bench_verify_messages_event_loop(b: &mut Bencher, &size: &usize) {
     verifier.join();
 }
 
+/// Benchmarks the verification of transactions. If `SEED_ENV_VAR` is set, transactions
+/// are signed by the same key in every run.
 pub fn bench_verify_transactions(c: &mut Criterion) {
     crypto::init();
 
+    let seed = bench_seed();
+
     let parameters = (7..12).map(|i| 1 << i).collect::<Vec<_>>();
 
     c.bench(
         "transactions/simple",
-        ParameterizedBenchmark::new("size", bench_verify_messages_simple, parameters.clone())
-            .throughput(|_| Throughput::Elements(MESSAGES_COUNT as u32))
-            .plot_config(PlotConfiguration::default().summary_scale(AxisScale::Logarithmic))
-            .sample_size(SAMPLE_SIZE),
+        ParameterizedBenchmark::new(
+            "size",
+            move |b, &size| bench_verify_messages_simple(b, size, seed),
+            parameters.clone(),
+        )
+        .throughput(|_| Throughput::Elements(MESSAGES_COUNT as u32))
+        .plot_config(PlotConfiguration::default().summary_scale(AxisScale::Logarithmic))
+        .sample_size(SAMPLE_SIZE),
     );
     c.bench(
         "transactions/event_loop",
-        ParameterizedBenchmark::new("size", bench_verify_messages_event_loop, parameters.clone())
-            .throughput(|_| Throughput::Elements(MESSAGES_COUNT as u32))
-            .plot_config(PlotConfiguration::default().summary_scale(AxisScale::Logarithmic))
-            .sample_size(SAMPLE_SIZE),
+        ParameterizedBenchmark::new(
+            "size",
+            move |b, &size| bench_verify_messages_event_loop(b, size, seed),
+            parameters.clone(),
+        )
+        .throughput(|_| Throughput::Elements(MESSAGES_COUNT as u32))
+        .plot_config(PlotConfiguration::default().summary_scale(AxisScale::Logarithmic))
+        .sample_size(SAMPLE_SIZE),
     );
 }
@@ -4,9 +4,16 @@ extern crate exonum_build;
 
 use exonum_build::protobuf_generate;
 
-use std::{env, fs::File, io::Write, path::Path, process::Command};
+use std::{
+    env,
+    fs::File,
+    io::Write,
+    path::{Path, PathBuf},
+    process::Command,
+};
 
 static USER_AGENT_FILE_NAME: &str = "user_agent";
+static GIT_HASH_ENV: &str = "EXONUM_GIT_HASH";
 
 fn create_path_to_protobuf_schema_env() {
     // Workaround for https://github.com/rust-lang/cargo/issues/3544
@@ -31,8 +38,58 @@ fn write_user_agent_file() {
         .expect("Unable to write data to file");
 }
 
+// Passes the hash of the current git commit to the crate, unless the hash is already
+// set in the build environment. The hash is passed only if the crate is built from its
+// own repository rather than, e.g., vendored into the repository of a dependent project.
+fn set_git_hash_env() {
+    println!("cargo:rerun-if-env-changed={}", GIT_HASH_ENV);
+    if env::var_os(GIT_HASH_ENV).is_some() {
+        return;
+    }
+
+    let manifest_dir =
+        PathBuf::from(env::var("CARGO_MANIFEST_DIR").expect("Unable to get CARGO_MANIFEST_DIR"));
+    if !is_own_repository(&manifest_dir) {
+        return;
+    }
+
+    // The hash changes once another commit is checked out or the current branch moves.
+    if let Some(git_dir) = git(&["rev-parse", "--git-dir"]) {
+        let git_dir = manifest_dir.join(git_dir);
+        rerun_if_exists(&git_dir.join("HEAD"));
+        if let Some(head_ref) = git(&["symbolic-ref", "-q", "HEAD"]) {
+            rerun_if_exists(&git_dir.join(head_ref));
+        }
+        rerun_if_exists(&git_dir.join("packed-refs"));
+    }
+    if let Some(hash) = git(&["rev-parse", "HEAD"]) {
+        println!("cargo:rustc-env={}={}", GIT_HASH_ENV, hash);
+    }
+}
+
+// Checks that the crate is located in the `exonum` directory at the root
+// of the git repository, as in the Exonum repository.
+fn is_own_repository(manifest_dir: &Path) -> bool {
+    let toplevel = match git(&["rev-parse", "--show-toplevel"]) {
+        Some(toplevel) => PathBuf::from(toplevel),
+        None => return false,
+    };
+    let expected = manifest_dir
+        .parent()
+        .and_then(|dir| dir.canonicalize().ok());
+    expected.is_some() && expected == toplevel.canonicalize().ok()
+}
+
+fn rerun_if_exists(path: &Path) {
+    // Cargo reruns the build script on each build if the file does not exist.
+    if path.exists() {
+        println!("cargo:rerun-if-changed={}", path.display());
+    }
+}
+
 fn main() {
     write_user_agent_file();
+    set_git_hash_env();
 
     create_path_to_protobuf_schema_env();
 
@@ -63,3 +120,12 @@ fn rust_version() -> Option<String> {
     let output = Command::new(rustc).arg("-V").output().ok()?.stdout;
     String::from_utf8(output).ok()
 }
+
+fn git(args: &[&str]) -> Option<String> {
+    let output = Command::new("git").args(args).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let output = String::from_utf8(output.stdout).ok()?;
+    Some(output.trim().to_owned())
+}
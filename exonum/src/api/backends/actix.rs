@@ -23,8 +23,13 @@ use actix::{Addr, System};
 use actix_net::server::Server;
 use actix_web::{
     error::ResponseError,
-    server::{HttpServer, StopServer},
-    AsyncResponder, FromRequest, HttpMessage, HttpResponse, Query,
+    http::{
+        header::{HeaderName, HeaderValue},
+        StatusCode, Version,
+    },
+    middleware::{Middleware, Response, Started},
+    server::{HttpServer, IntoHttpHandler, StopServer},
+    AsyncResponder, Body, FromRequest, HttpMessage, HttpResponse, Query,
 };
 use futures::{Future, IntoFuture};
 use serde::{
@@ -33,18 +38,23 @@ use serde::{
 };
 
 use std::{
-    fmt,
-    net::SocketAddr,
+    cmp,
+    collections::HashMap,
+    fmt, io,
+    net::{IpAddr, SocketAddr},
+    path::PathBuf,
     result,
     str::FromStr,
-    sync::{mpsc, Arc},
+    sync::{mpsc, Arc, Mutex},
     thread::{self, JoinHandle},
+    time::{Duration, Instant},
 };
 
 use crate::api::{
     error::Error as ApiError, ApiAccess, ApiAggregator, ExtendApiBackend, FutureResult, Immutable,
     Mutable, NamedWith, Result, ServiceApiBackend, ServiceApiScope, ServiceApiState,
 };
+use crate::crypto::{self, PublicKey, SecretKey};
 
 /// Type alias for the concrete `actix-web` HTTP response.
 pub type FutureResponse = actix_web::FutureResponse<HttpResponse, actix_web::Error>;
@@ -133,6 +143,9 @@ impl ResponseError for ApiError {
             ApiError::Storage(err) => HttpResponse::InternalServerError().body(err.to_string()),
             ApiError::NotFound(err) => HttpResponse::NotFound().body(err.to_string()),
             ApiError::Unauthorized => HttpResponse::Unauthorized().finish(),
+            ApiError::ServiceUnavailable(err) => {
+                HttpResponse::ServiceUnavailable().body(err.to_string())
+            }
         }
     }
 }
@@ -260,6 +273,11 @@ pub(crate) fn create_app(aggregator: &ApiAggregator, runtime_config: ApiRuntimeC
     if let Some(app_config) = app_config {
         app = app_config(app);
     }
+    // The filter is added after the configured middleware, so that the rejected requests
+    // still pass through the CORS middleware and browsers are able to read the response.
+    if !runtime_config.http2 {
+        app = app.middleware(Http1Only);
+    }
     app
 }
 
@@ -272,6 +290,11 @@ pub struct ApiRuntimeConfig {
     pub access: ApiAccess,
     /// Optional App configuration.
     pub app_config: Option<AppConfig>,
+    /// Optional TLS configuration. If it is set, the API is served over HTTPS.
+    pub tls: Option<TlsConfig>,
+    /// Whether the API is served over HTTP/2 in addition to HTTP/1.1. HTTP/2 is negotiated
+    /// via ALPN for HTTPS and requires prior knowledge (h2c) for plain HTTP.
+    pub http2: bool,
 }
 
 impl ApiRuntimeConfig {
@@ -281,6 +304,8 @@ impl ApiRuntimeConfig {
             listen_address,
             access,
             app_config: Default::default(),
+            tls: None,
+            http2: false,
         }
     }
 }
@@ -291,6 +316,8 @@ impl fmt::Debug for ApiRuntimeConfig {
             .field("listen_address", &self.listen_address)
             .field("access", &self.access)
             .field("app_config", &self.app_config.as_ref().map(drop))
+            .field("tls", &self.tls)
+            .field("http2", &self.http2)
             .finish()
     }
 }
@@ -302,6 +329,77 @@ pub struct SystemRuntimeConfig {
     pub api_runtimes: Vec<ApiRuntimeConfig>,
     /// API aggregator.
     pub api_aggregator: ApiAggregator,
+    /// Number of additional attempts to bind an API address if it is in use.
+    pub bind_retries: u32,
+    /// Delay between the attempts to bind an API address.
+    pub bind_retry_delay: Duration,
+    /// Time given to the in-flight requests to complete when the runtime is stopped.
+    /// The timeout is rounded up to whole seconds.
+    pub shutdown_timeout: Duration,
+}
+
+/// Converts the shutdown timeout to the whole number of seconds expected by `HttpServer`,
+/// rounding it up.
+fn shutdown_timeout_secs(timeout: Duration) -> u16 {
+    let secs = timeout.as_secs() + u64::from(timeout.subsec_nanos() > 0);
+    cmp::min(secs, u64::from(u16::max_value())) as u16
+}
+
+/// Invokes `bind` until it succeeds, making at most `retries` additional attempts
+/// with the given delay between them if the address is in use.
+fn bind_with_retries<T, F>(
+    address: SocketAddr,
+    retries: u32,
+    delay: Duration,
+    mut bind: F,
+) -> io::Result<T>
+where
+    F: FnMut() -> io::Result<T>,
+{
+    let mut attempt = 0;
+    loop {
+        match bind() {
+            Err(ref e) if e.kind() == io::ErrorKind::AddrInUse && attempt < retries => {
+                attempt += 1;
+                warn!(
+                    "Address {} is in use, retrying to bind in {:?} (attempt {} of {})",
+                    address, delay, attempt, retries
+                );
+                thread::sleep(delay);
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Binds the server to the given address, serving the API over HTTPS.
+#[cfg(feature = "tls")]
+fn bind_tls<H, F>(
+    server: HttpServer<H, F>,
+    address: SocketAddr,
+    tls: &TlsConfig,
+) -> io::Result<HttpServer<H, F>>
+where
+    H: IntoHttpHandler + 'static,
+    F: Fn() -> H + Send + Clone + 'static,
+{
+    server.bind_rustls(address, tls.server_config()?)
+}
+
+#[cfg(not(feature = "tls"))]
+fn bind_tls<H, F>(
+    _server: HttpServer<H, F>,
+    _address: SocketAddr,
+    _tls: &TlsConfig,
+) -> io::Result<HttpServer<H, F>>
+where
+    H: IntoHttpHandler + 'static,
+    F: Fn() -> H + Send + Clone + 'static,
+{
+    Err(io::Error::new(
+        io::ErrorKind::Other,
+        "TLS for the API requires exonum to be built with the `tls` feature",
+    ))
 }
 
 /// Actix system runtime handle.
@@ -333,17 +431,34 @@ impl SystemRuntime {
                 "Create actix system runtime with api: {:#?}",
                 aggregator.inner
             );
+            let (bind_retries, bind_retry_delay) = (config.bind_retries, config.bind_retry_delay);
+            let shutdown_timeout = shutdown_timeout_secs(config.shutdown_timeout);
             let api_handlers = config.api_runtimes.into_iter().map(|runtime_config| {
                 debug!("Runtime: {:?}", runtime_config);
                 let access = runtime_config.access;
                 let listen_address = runtime_config.listen_address;
                 info!("Starting {} web api on {}", access, listen_address);
 
-                let aggregator = aggregator.clone();
-                HttpServer::new(move || create_app(&aggregator, runtime_config.clone()))
-                    .disable_signals()
-                    .bind(listen_address)
-                    .map(HttpServer::start)
+                bind_with_retries(listen_address, bind_retries, bind_retry_delay, || {
+                    let aggregator = aggregator.clone();
+                    let tls = runtime_config.tls.clone();
+                    let http2 = runtime_config.http2;
+                    let runtime_config = runtime_config.clone();
+                    let mut server =
+                        HttpServer::new(move || create_app(&aggregator, runtime_config.clone()))
+                            .disable_signals()
+                            .shutdown_timeout(shutdown_timeout);
+                    if !http2 {
+                        // Disables the negotiation of HTTP/2 via ALPN. Cleartext HTTP/2
+                        // connections are rejected by the `Http1Only` middleware.
+                        server = server.no_http2();
+                    }
+                    match tls {
+                        Some(ref tls) => bind_tls(server, listen_address, tls),
+                        None => server.bind(listen_address),
+                    }
+                })
+                .map(HttpServer::start)
             });
             // Sends addresses to the control thread.
             system_tx.send(System::current())?;
@@ -387,7 +502,9 @@ impl SystemRuntime {
         })
     }
 
-    /// Stops the actix system runtime along with all web runtimes.
+    /// Stops the actix system runtime along with all web runtimes. The web runtimes stop
+    /// accepting new connections and wait for the in-flight requests to complete
+    /// for at most `SystemRuntimeConfig::shutdown_timeout`.
     pub fn stop(self) -> result::Result<(), failure::Error> {
         // Stop all actix web servers.
         for api_runtime_address in self.api_runtime_addresses {
@@ -522,6 +639,62 @@ impl From<AllowOrigin> for Cors {
     }
 }
 
+/// TLS configuration of an API server.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TlsConfig {
+    /// Path to the PEM file with the server certificate chain.
+    pub tls_cert_path: PathBuf,
+    /// Path to the PEM file with the private key of the server certificate.
+    /// Both PKCS #8 and RSA keys are supported.
+    pub tls_key_path: PathBuf,
+}
+
+#[cfg(feature = "tls")]
+impl TlsConfig {
+    /// Loads the certificate and the key and creates the `rustls` server configuration.
+    fn server_config(&self) -> io::Result<rustls::ServerConfig> {
+        use rustls::{
+            internal::pemfile::{certs, pkcs8_private_keys, rsa_private_keys},
+            NoClientAuth, PrivateKey, ServerConfig,
+        };
+        use std::{fs::File, io::BufReader};
+
+        fn invalid_data(message: String) -> io::Error {
+            io::Error::new(io::ErrorKind::InvalidData, message)
+        }
+
+        let cert_chain =
+            certs(&mut BufReader::new(File::open(&self.tls_cert_path)?)).map_err(|_| {
+                invalid_data(format!(
+                    "Unable to read certificates from {}",
+                    self.tls_cert_path.display()
+                ))
+            })?;
+
+        type ReadKeys = fn(&mut dyn io::BufRead) -> result::Result<Vec<PrivateKey>, ()>;
+        let read_keys = |read: ReadKeys| {
+            File::open(&self.tls_key_path)
+                .map(|file| read(&mut BufReader::new(file)).unwrap_or_default())
+        };
+        let mut keys = read_keys(pkcs8_private_keys)?;
+        if keys.is_empty() {
+            keys = read_keys(rsa_private_keys)?;
+        }
+        if keys.is_empty() {
+            return Err(invalid_data(format!(
+                "No private keys found in {}",
+                self.tls_key_path.display()
+            )));
+        }
+
+        let mut config = ServerConfig::new(NoClientAuth::new());
+        config
+            .set_single_cert(cert_chain, keys.remove(0))
+            .map_err(|e| invalid_data(e.to_string()))?;
+        Ok(config)
+    }
+}
+
 #[test]
 fn allow_origin_from_str() {
     fn check(text: &str, expected: AllowOrigin) {
@@ -547,3 +720,244 @@ fn allow_origin_from_str() {
         AllowOrigin::Whitelist(vec!["http://a.org".to_string(), "http://b.org".to_string()]),
     );
 }
+
+/// Maximum number of tracked client addresses, after which the addresses with full
+/// token buckets are forgotten.
+const RATE_LIMITER_MAX_CLIENTS: usize = 10_000;
+
+/// Token bucket of a single client.
+#[derive(Debug, Clone, Copy)]
+struct TokenBucket {
+    tokens: f64,
+    updated_at: Instant,
+}
+
+/// Middleware limiting the number of requests per second from a single IP address.
+///
+/// Each client address has a token bucket holding up to `requests_per_second` tokens,
+/// which is refilled at the same rate. Requests not covered by a token are rejected
+/// with `429 Too Many Requests`. Clones of the limiter share the buckets, so a limiter
+/// can be added to the apps of all server workers.
+#[derive(Debug, Clone)]
+pub struct RateLimiter {
+    requests_per_second: u32,
+    buckets: Arc<Mutex<HashMap<IpAddr, TokenBucket>>>,
+}
+
+impl RateLimiter {
+    /// Creates a rate limiter allowing the given number of requests per second
+    /// from a single IP address.
+    pub fn new(requests_per_second: u32) -> Self {
+        Self {
+            requests_per_second,
+            buckets: Arc::default(),
+        }
+    }
+
+    /// Takes a token from the bucket of the given address. Returns `false` if
+    /// the bucket is empty, i.e., the request should be rejected.
+    pub fn try_acquire(&self, address: IpAddr) -> bool {
+        let capacity = f64::from(self.requests_per_second);
+        let now = Instant::now();
+        let refill = |bucket: &TokenBucket| {
+            let elapsed = now.duration_since(bucket.updated_at);
+            let elapsed = elapsed.as_secs() as f64 + f64::from(elapsed.subsec_nanos()) * 1e-9;
+            (bucket.tokens + elapsed * capacity).min(capacity)
+        };
+
+        let mut buckets = self.buckets.lock().expect("Expected rate limiter lock");
+        if buckets.len() >= RATE_LIMITER_MAX_CLIENTS && !buckets.contains_key(&address) {
+            buckets.retain(|_, bucket| refill(bucket) < capacity);
+        }
+        let bucket = buckets.entry(address).or_insert(TokenBucket {
+            tokens: capacity,
+            updated_at: now,
+        });
+        bucket.tokens = refill(bucket);
+        bucket.updated_at = now;
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+impl<S> Middleware<S> for RateLimiter {
+    fn start(&self, request: &actix_web::HttpRequest<S>) -> actix_web::Result<Started> {
+        match request.peer_addr() {
+            Some(address) if !self.try_acquire(address.ip()) => Ok(Started::Response(
+                HttpResponse::new(StatusCode::TOO_MANY_REQUESTS),
+            )),
+            _ => Ok(Started::Done),
+        }
+    }
+}
+
+/// Middleware rejecting HTTP/2 requests with `505 HTTP Version Not Supported`.
+///
+/// `actix-web` always accepts cleartext HTTP/2 connections with prior knowledge,
+/// so the requests are filtered if HTTP/2 is disabled for the API.
+#[derive(Debug, Clone, Copy)]
+struct Http1Only;
+
+impl<S> Middleware<S> for Http1Only {
+    fn start(&self, request: &actix_web::HttpRequest<S>) -> actix_web::Result<Started> {
+        if request.version() == Version::HTTP_2 {
+            Ok(Started::Response(HttpResponse::new(
+                StatusCode::HTTP_VERSION_NOT_SUPPORTED,
+            )))
+        } else {
+            Ok(Started::Done)
+        }
+    }
+}
+
+/// Name of the header containing the hex-encoded signature of the response body.
+pub const SIGNATURE_HEADER: &str = "x-exonum-signature";
+/// Name of the header containing the hex-encoded public key the response is signed with.
+pub const PUBLIC_KEY_HEADER: &str = "x-exonum-public-key";
+/// Context tag prepended to the signed data of the responses, so that a response signature
+/// cannot be passed off as a signature of a transaction or another object signed
+/// by the same key.
+pub const RESPONSE_SIGNATURE_CONTEXT: &[u8] = b"exonum-api-response-v1\0";
+
+/// Returns the data signed by `ResponseSigner` for the response to the request with
+/// the given method and path (including the query string, if any), i.e.,
+/// `RESPONSE_SIGNATURE_CONTEXT`, the method, the path and the body, separated by newlines.
+pub fn response_signature_payload(method: &str, path: &str, body: &[u8]) -> Vec<u8> {
+    let mut payload = RESPONSE_SIGNATURE_CONTEXT.to_vec();
+    payload.extend_from_slice(method.as_bytes());
+    payload.push(b'\n');
+    payload.extend_from_slice(path.as_bytes());
+    payload.push(b'\n');
+    payload.extend_from_slice(body);
+    payload
+}
+
+/// Middleware signing the responses with the service key of the node,
+/// so that clients can verify that a response comes from a specific node.
+///
+/// The body is signed together with the method and the path of the request as returned
+/// by `response_signature_payload`. The signature is passed in the `X-Exonum-Signature`
+/// header and the public key in the `X-Exonum-Public-Key` header. Streamed responses
+/// are not signed.
+#[derive(Debug, Clone)]
+pub struct ResponseSigner {
+    public_key: PublicKey,
+    secret_key: SecretKey,
+}
+
+impl ResponseSigner {
+    /// Creates a signer using the given keypair.
+    pub fn new(public_key: PublicKey, secret_key: SecretKey) -> Self {
+        Self {
+            public_key,
+            secret_key,
+        }
+    }
+}
+
+impl<S> Middleware<S> for ResponseSigner {
+    fn response(
+        &self,
+        request: &actix_web::HttpRequest<S>,
+        mut response: HttpResponse,
+    ) -> actix_web::Result<Response> {
+        let body = match response.body() {
+            Body::Empty => &[][..],
+            Body::Binary(body) => body.as_ref(),
+            _ => return Ok(Response::Done(response)),
+        };
+        let path = request
+            .uri()
+            .path_and_query()
+            .map_or_else(|| request.path(), |path| path.as_str());
+        let payload = response_signature_payload(request.method().as_str(), path, body);
+        let signature = crypto::sign(&payload, &self.secret_key);
+
+        let header_value =
+            |hex: String| HeaderValue::from_str(&hex).expect("Hex string is a valid header value");
+        let headers = response.headers_mut();
+        headers.insert(
+            HeaderName::from_static(SIGNATURE_HEADER),
+            header_value(signature.to_hex()),
+        );
+        headers.insert(
+            HeaderName::from_static(PUBLIC_KEY_HEADER),
+            header_value(self.public_key.to_hex()),
+        );
+        Ok(Response::Done(response))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io,
+        net::{SocketAddr, TcpListener},
+        thread,
+        time::Duration,
+    };
+
+    use super::{bind_with_retries, shutdown_timeout_secs, RateLimiter};
+
+    #[test]
+    fn test_shutdown_timeout_secs() {
+        assert_eq!(shutdown_timeout_secs(Duration::from_secs(0)), 0);
+        assert_eq!(shutdown_timeout_secs(Duration::from_secs(5)), 5);
+        assert_eq!(shutdown_timeout_secs(Duration::from_millis(1_500)), 2);
+        assert_eq!(
+            shutdown_timeout_secs(Duration::from_secs(1 << 20)),
+            u16::max_value()
+        );
+    }
+
+    #[test]
+    fn test_bind_with_retries() {
+        let occupied = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address: SocketAddr = occupied.local_addr().unwrap();
+
+        // Retries are exhausted while the address is occupied.
+        let err = bind_with_retries(address, 2, Duration::from_millis(10), || {
+            TcpListener::bind(address)
+        })
+        .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::AddrInUse);
+
+        // The address is released while retrying.
+        let releaser = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(100));
+            drop(occupied);
+        });
+        let mut attempts = 0;
+        let listener = bind_with_retries(address, 50, Duration::from_millis(20), || {
+            attempts += 1;
+            TcpListener::bind(address)
+        })
+        .unwrap();
+        releaser.join().unwrap();
+        assert!(attempts > 1);
+        assert_eq!(listener.local_addr().unwrap(), address);
+    }
+
+    #[test]
+    fn test_rate_limiter() {
+        let limiter = RateLimiter::new(5);
+        let client = "127.0.0.1".parse().unwrap();
+        let other_client = "127.0.0.2".parse().unwrap();
+
+        // Requests within the limit are allowed.
+        for _ in 0..5 {
+            assert!(limiter.try_acquire(client));
+        }
+        // Rapid requests above the limit are rejected, but only for the same client.
+        assert!(!limiter.try_acquire(client));
+        assert!(limiter.clone().try_acquire(other_client));
+
+        // Tokens are refilled over time.
+        thread::sleep(Duration::from_millis(250));
+        assert!(limiter.try_acquire(client));
+    }
+}
@@ -50,6 +50,11 @@ pub enum Error {
     /// authentication credentials.
     #[fail(display = "Unauthorized")]
     Unauthorized,
+
+    /// Service unavailable. This error occurs when the server cannot handle the request
+    /// at the moment, e.g., because it has not completed within the request timeout.
+    #[fail(display = "Service unavailable: {}", _0)]
+    ServiceUnavailable(String),
 }
 
 impl From<io::Error> for Error {
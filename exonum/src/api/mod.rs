@@ -363,6 +363,11 @@ impl ApiAggregator {
         self.inner.insert(prefix.into(), builder);
     }
 
+    /// Removes the API factory with the given prefix from the aggregator.
+    pub fn remove(&mut self, prefix: &str) -> Option<ServiceApiBuilder> {
+        self.inner.remove(prefix)
+    }
+
     fn explorer_api(
         blockchain: &Blockchain,
         shared_node_state: SharedNodeState,
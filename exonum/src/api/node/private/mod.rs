@@ -17,13 +17,20 @@
 //! Private API includes requests that are available only to the blockchain
 //! administrators, e.g. view the list of services on the current node.
 
+use exonum_merkledb::{index_type, IndexAddress, IndexType, MapProof, ProofMapIndex};
+
 use std::{collections::HashMap, net::SocketAddr};
 
 use crate::api::{Error as ApiError, ServiceApiScope, ServiceApiState};
-use crate::blockchain::{Service, SharedNodeState};
-use crate::crypto::PublicKey;
+use crate::blockchain::{
+    ConsensusLogEntry, ConsensusState, RejectedTransaction, RoundSchedule, Schema, Service,
+    SharedNodeState, TransactionResult,
+};
+use crate::crypto::{self, Hash, PublicKey, SecretKey, Signature};
+use crate::explorer::TxStatus;
+use crate::helpers::Height;
 use crate::messages::PROTOCOL_MAJOR_VERSION;
-use crate::node::{ConnectInfo, ExternalMessage};
+use crate::node::{ConnectInfo, EffectiveConfig, ExternalMessage};
 
 /// Short information about the service.
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
@@ -92,6 +99,8 @@ struct IncomingConnection {
 
 #[derive(Serialize, Deserialize)]
 struct PeersInfo {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    node_label: Option<String>,
     incoming_connections: Vec<ConnectInfo>,
     outgoing_connections: HashMap<SocketAddr, IncomingConnection>,
 }
@@ -101,6 +110,120 @@ struct ConsensusEnabledQuery {
     enabled: bool,
 }
 
+/// Consensus log query parameters.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Default)]
+pub struct ConsensusLogQuery {
+    /// The number of the latest recorded messages to return. All recorded messages
+    /// are returned if not specified.
+    pub count: Option<usize>,
+}
+
+/// Context tag prepended to the transaction hash signed in the cancellation requests,
+/// so that a cancellation signature cannot be passed off as a signature of another
+/// object signed by the same key, and vice versa.
+pub const CANCEL_SIGNATURE_CONTEXT: &[u8] = b"exonum-cancel-transaction-v1\0";
+
+/// Returns the data signed in the request cancelling the transaction with the given hash,
+/// i.e., `CANCEL_SIGNATURE_CONTEXT` followed by the transaction hash.
+pub fn cancel_signature_payload(tx_hash: &Hash) -> Vec<u8> {
+    let mut payload = CANCEL_SIGNATURE_CONTEXT.to_vec();
+    payload.extend_from_slice(tx_hash.as_ref());
+    payload
+}
+
+/// Request to remove an uncommitted transaction from the pool.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct CancelTransactionQuery {
+    /// Hash of the transaction to cancel.
+    pub tx_hash: Hash,
+    /// Signature of `cancel_signature_payload` of the transaction hash, made with the key
+    /// of the transaction author.
+    pub signature: Signature,
+}
+
+impl CancelTransactionQuery {
+    /// Creates a new cancellation request signing the transaction hash with the given key.
+    pub fn new(tx_hash: Hash, secret_key: &SecretKey) -> Self {
+        Self {
+            tx_hash,
+            signature: crypto::sign(&cancel_signature_payload(&tx_hash), secret_key),
+        }
+    }
+}
+
+/// Query parameters of the transaction replay request.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct TransactionReplayQuery {
+    /// Hash of the committed transaction to replay.
+    pub hash: Hash,
+}
+
+/// Result of the committed transaction execution against the current blockchain state.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct TransactionReplay {
+    /// Result of the execution against the current state.
+    #[serde(with = "TxStatus")]
+    pub status: TransactionResult,
+    /// Result of the execution at the moment the transaction was committed.
+    #[serde(with = "TxStatus")]
+    pub committed_status: TransactionResult,
+}
+
+/// Type of the keys of a `ProofMapIndex`, which determines how the keys are hashed
+/// into the paths of the proof.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum IndexKeyType {
+    /// Keys hashed as their binary representation, e.g., `PublicKey` or `String` keys.
+    Bytes,
+    /// `Hash` keys, which are used as the paths as is.
+    Hash,
+}
+
+impl Default for IndexKeyType {
+    fn default() -> Self {
+        IndexKeyType::Bytes
+    }
+}
+
+/// Index proof query parameters.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct IndexProofQuery {
+    /// Name of the `ProofMapIndex`.
+    pub index: String,
+    /// Hex-encoded binary representation of the key.
+    pub key: String,
+    /// Type of the keys of the index, `bytes` by default.
+    #[serde(default)]
+    pub key_type: IndexKeyType,
+}
+
+/// Proof for a key in a `ProofMapIndex` with the keys of the requested type.
+///
+/// Values of the index are treated as raw bytes, thus the proof can be checked only
+/// for the indices which values are hashed as their binary representation,
+/// e.g., Protobuf values.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(tag = "key_type", content = "proof", rename_all = "snake_case")]
+pub enum IndexMapProof {
+    /// Proof for an index with the keys hashed as their binary representation.
+    Bytes(MapProof<Vec<u8>, Vec<u8>>),
+    /// Proof for an index with `Hash` keys.
+    Hash(MapProof<Hash, Vec<u8>>),
+}
+
+/// Proof for a key in a `ProofMapIndex`.
+///
+/// The root hash of the checked proof is the hash of the index, which is aggregated
+/// into the state hash of the blockchain by the service `state_hash` implementation.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct IndexProof {
+    /// Height of the latest committed block.
+    pub height: Height,
+    /// Proof of existence or absence of the key in the index.
+    pub proof: IndexMapProof,
+}
+
 /// Private system API.
 #[derive(Clone, Debug)]
 pub struct SystemApi {
@@ -121,11 +244,24 @@ impl SystemApi {
     pub fn wire(self, api_scope: &mut ServiceApiScope) -> &mut ServiceApiScope {
         self.handle_peers_info("v1/peers", api_scope)
             .handle_peer_add("v1/peers", api_scope)
+            .handle_peers_latency("v1/peers/latency", api_scope)
+            .handle_peers_snapshot("v1/peers/snapshot", api_scope)
             .handle_network_info("v1/network", api_scope)
             .handle_is_consensus_enabled("v1/consensus_enabled", api_scope)
             .handle_set_consensus_enabled("v1/consensus_enabled", api_scope)
             .handle_shutdown("v1/shutdown", api_scope)
-            .handle_rebroadcast("v1/rebroadcast", api_scope);
+            .handle_rebroadcast("v1/rebroadcast", api_scope)
+            .handle_consensus_log("v1/debug/consensus_log", api_scope)
+            .handle_consensus_state("v1/consensus_state", api_scope)
+            .handle_round_schedule("v1/consensus/round_schedule", api_scope)
+            .handle_effective_config("v1/config/effective", api_scope)
+            .handle_channel_stats("v1/debug/channel_stats", api_scope)
+            .handle_timeout_drift("v1/debug/timeout_drift", api_scope)
+            .handle_compact("v1/debug/compact", api_scope)
+            .handle_cancel_transaction("v1/transactions/cancel", api_scope)
+            .handle_rejected_transactions("v1/transactions/rejected", api_scope)
+            .handle_replay_transaction("v1/transactions/replay", api_scope)
+            .handle_index_proof("v1/proof", api_scope);
         api_scope
     }
 
@@ -152,6 +288,7 @@ impl SystemApi {
             }
 
             Ok(PeersInfo {
+                node_label: self.shared_api_state.node_label(),
                 incoming_connections: self.shared_api_state.incoming_connections(),
                 outgoing_connections,
             })
@@ -172,6 +309,22 @@ impl SystemApi {
         self
     }
 
+    fn handle_peers_latency(self, name: &'static str, api_scope: &mut ServiceApiScope) -> Self {
+        let self_ = self.clone();
+        api_scope.endpoint(name, move |_state: &ServiceApiState, _query: ()| {
+            Ok(self.shared_api_state.peer_latencies())
+        });
+        self_
+    }
+
+    fn handle_peers_snapshot(self, name: &'static str, api_scope: &mut ServiceApiScope) -> Self {
+        let self_ = self.clone();
+        api_scope.endpoint(name, move |_state: &ServiceApiState, _query: ()| {
+            Ok(self.shared_api_state.connected_peers())
+        });
+        self_
+    }
+
     fn handle_network_info(self, name: &'static str, api_scope: &mut ServiceApiScope) -> Self {
         let self_ = self.clone();
         api_scope.endpoint(name, move |_state: &ServiceApiState, _query: ()| {
@@ -235,4 +388,246 @@ impl SystemApi {
         );
         self
     }
+
+    fn handle_effective_config(self, name: &'static str, api_scope: &mut ServiceApiScope) -> Self {
+        let self_ = self.clone();
+        api_scope.endpoint(
+            name,
+            move |_state: &ServiceApiState, _query: ()| -> Result<EffectiveConfig, ApiError> {
+                self.shared_api_state.effective_config().ok_or_else(|| {
+                    ApiError::NotFound("Effective configuration is not available".to_owned())
+                })
+            },
+        );
+        self_
+    }
+
+    fn handle_channel_stats(self, name: &'static str, api_scope: &mut ServiceApiScope) -> Self {
+        let self_ = self.clone();
+        api_scope.endpoint(name, move |_state: &ServiceApiState, _query: ()| {
+            Ok(self.shared_api_state.channel_stats())
+        });
+        self_
+    }
+
+    fn handle_timeout_drift(self, name: &'static str, api_scope: &mut ServiceApiScope) -> Self {
+        let self_ = self.clone();
+        api_scope.endpoint(name, move |_state: &ServiceApiState, _query: ()| {
+            Ok(self.shared_api_state.timeout_drift())
+        });
+        self_
+    }
+
+    fn handle_compact(self, name: &'static str, api_scope: &mut ServiceApiScope) -> Self {
+        api_scope.endpoint_mut(
+            name,
+            move |state: &ServiceApiState, _query: ()| -> Result<(), ApiError> {
+                // Handlers are executed by the API threads, so the compaction
+                // does not block the consensus.
+                state
+                    .blockchain()
+                    .compact_database()
+                    .map_err(|e| ApiError::InternalError(e.into()))
+            },
+        );
+        self
+    }
+
+    fn handle_consensus_log(self, name: &'static str, api_scope: &mut ServiceApiScope) -> Self {
+        let self_ = self.clone();
+        api_scope.endpoint(
+            name,
+            move |_state: &ServiceApiState,
+                  query: ConsensusLogQuery|
+                  -> Result<Vec<ConsensusLogEntry>, ApiError> {
+                if !self.shared_api_state.is_consensus_log_enabled() {
+                    return Err(ApiError::NotFound(
+                        "Consensus messages recording is disabled".to_owned(),
+                    ));
+                }
+
+                let mut log = self.shared_api_state.consensus_log();
+                if let Some(count) = query.count {
+                    let skipped = log.len().saturating_sub(count);
+                    log.drain(..skipped);
+                }
+                Ok(log)
+            },
+        );
+        self_
+    }
+
+    fn handle_consensus_state(self, name: &'static str, api_scope: &mut ServiceApiScope) -> Self {
+        let self_ = self.clone();
+        api_scope.endpoint(
+            name,
+            move |_state: &ServiceApiState, _query: ()| -> Result<ConsensusState, ApiError> {
+                self.shared_api_state.consensus_state().ok_or_else(|| {
+                    ApiError::NotFound("Consensus state is not available yet".to_owned())
+                })
+            },
+        );
+        self_
+    }
+
+    fn handle_round_schedule(self, name: &'static str, api_scope: &mut ServiceApiScope) -> Self {
+        let self_ = self.clone();
+        api_scope.endpoint(
+            name,
+            move |_state: &ServiceApiState, _query: ()| -> Result<RoundSchedule, ApiError> {
+                self.shared_api_state.round_schedule().ok_or_else(|| {
+                    ApiError::NotFound("Round schedule is not available yet".to_owned())
+                })
+            },
+        );
+        self_
+    }
+
+    fn handle_cancel_transaction(
+        self,
+        name: &'static str,
+        api_scope: &mut ServiceApiScope,
+    ) -> Self {
+        api_scope.endpoint_mut(
+            name,
+            move |state: &ServiceApiState, query: CancelTransactionQuery| -> Result<(), ApiError> {
+                let snapshot = state.snapshot();
+                let schema = Schema::new(&snapshot);
+                if schema.transactions_locations().contains(&query.tx_hash) {
+                    return Err(ApiError::BadRequest(
+                        "Committed transaction cannot be cancelled".to_owned(),
+                    ));
+                }
+                if !schema.transactions_pool().contains(&query.tx_hash) {
+                    return Err(ApiError::NotFound(format!(
+                        "Transaction {:?} not found in the pool",
+                        query.tx_hash
+                    )));
+                }
+
+                let tx = schema.transactions().get(&query.tx_hash).ok_or_else(|| {
+                    ApiError::InternalError(format_err!(
+                        "Pooled transaction {:?} is absent in transactions table",
+                        query.tx_hash
+                    ))
+                })?;
+                let payload = cancel_signature_payload(&query.tx_hash);
+                if !crypto::verify(&query.signature, &payload, &tx.author()) {
+                    return Err(ApiError::BadRequest(
+                        "Signature does not match the transaction author".to_owned(),
+                    ));
+                }
+
+                state
+                    .sender()
+                    .send_external_message(ExternalMessage::CancelTransaction(query.tx_hash))
+                    .map_err(ApiError::from)
+            },
+        );
+        self
+    }
+
+    fn handle_rejected_transactions(
+        self,
+        name: &'static str,
+        api_scope: &mut ServiceApiScope,
+    ) -> Self {
+        let self_ = self.clone();
+        api_scope.endpoint(
+            name,
+            move |_state: &ServiceApiState,
+                  _query: ()|
+                  -> Result<Vec<RejectedTransaction>, ApiError> {
+                Ok(self.shared_api_state.rejected_transactions())
+            },
+        );
+        self_
+    }
+
+    fn handle_replay_transaction(
+        self,
+        name: &'static str,
+        api_scope: &mut ServiceApiScope,
+    ) -> Self {
+        api_scope.endpoint(
+            name,
+            move |state: &ServiceApiState,
+                  query: TransactionReplayQuery|
+                  -> Result<TransactionReplay, ApiError> {
+                let committed_status = {
+                    let snapshot = state.snapshot();
+                    Schema::new(&snapshot)
+                        .transaction_results()
+                        .get(&query.hash)
+                        .ok_or_else(|| {
+                            ApiError::NotFound(format!(
+                                "Committed transaction {:?} not found",
+                                query.hash
+                            ))
+                        })?
+                };
+
+                let status = state
+                    .blockchain()
+                    .replay_transaction(query.hash)
+                    .map_err(ApiError::InternalError)?;
+                Ok(TransactionReplay {
+                    status,
+                    committed_status,
+                })
+            },
+        );
+        self
+    }
+
+    fn handle_index_proof(self, name: &'static str, api_scope: &mut ServiceApiScope) -> Self {
+        api_scope.endpoint(
+            name,
+            move |state: &ServiceApiState,
+                  query: IndexProofQuery|
+                  -> Result<IndexProof, ApiError> {
+                let key = hex::decode(&query.key)
+                    .map_err(|e| ApiError::BadRequest(format!("Invalid hex-encoded key: {}", e)))?;
+
+                let snapshot = state.snapshot();
+                let address = IndexAddress::with_root(query.index.as_str());
+                match index_type(&snapshot, &address) {
+                    Some(IndexType::ProofMap) => {}
+                    Some(_) => {
+                        return Err(ApiError::BadRequest(format!(
+                            "Index {} is not a proof map",
+                            query.index
+                        )));
+                    }
+                    None => {
+                        return Err(ApiError::NotFound(format!(
+                            "Index {} is unknown",
+                            query.index
+                        )));
+                    }
+                }
+
+                let proof = match query.key_type {
+                    IndexKeyType::Bytes => {
+                        let index: ProofMapIndex<_, Vec<u8>, Vec<u8>> =
+                            ProofMapIndex::new(query.index, &snapshot);
+                        IndexMapProof::Bytes(index.get_proof(key))
+                    }
+                    IndexKeyType::Hash => {
+                        let key = Hash::from_slice(&key).ok_or_else(|| {
+                            ApiError::BadRequest("Invalid length of the hash key".to_owned())
+                        })?;
+                        let index: ProofMapIndex<_, Hash, Vec<u8>> =
+                            ProofMapIndex::new(query.index, &snapshot);
+                        IndexMapProof::Hash(index.get_proof(key))
+                    }
+                };
+                Ok(IndexProof {
+                    height: Schema::new(&snapshot).height(),
+                    proof,
+                })
+            },
+        );
+        self
+    }
 }
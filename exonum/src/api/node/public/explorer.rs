@@ -14,27 +14,46 @@
 
 //! Exonum blockchain explorer API.
 
-use actix::Arbiter;
-use actix_web::{http, ws, AsyncResponder, Error as ActixError, FromRequest, Query};
+use actix::{Actor, Addr, Arbiter};
+use actix_web::{
+    error, http, ws, AsyncResponder, Error as ActixError, FromRequest, HttpMessage, HttpResponse,
+    Query,
+};
+use bytes::Bytes;
 use chrono::{DateTime, Utc};
-use futures::{Future, IntoFuture};
+use exonum_merkledb::{BinaryValue, ListProof};
+use futures::{
+    future, stream,
+    sync::{mpsc, oneshot},
+    Future, IntoFuture, Stream,
+};
+use tokio::util::FutureExt;
+use tokio_threadpool::{Builder as ThreadPoolBuilder, ThreadPool};
 
+use std::cmp;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
 use std::ops::{Bound, Range};
-use std::sync::{Arc, Mutex};
-use std::time::UNIX_EPOCH;
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc, Mutex,
+};
+use std::time::{Duration, Instant, UNIX_EPOCH};
 
 use crate::{
     api::{
         backends::actix::{
             self as actix_backend, FutureResponse, HttpRequest, RawHandler, RequestHandler,
         },
-        websocket::{Server, Session, SubscriptionType, TransactionFilter},
-        Error as ApiError, ServiceApiBackend, ServiceApiScope, ServiceApiState,
+        websocket::{
+            Server, Session, StreamSession, SubscriptionType, TransactionFilter, STREAM_BUFFER_SIZE,
+        },
+        Error as ApiError, FutureResult, ServiceApiBackend, ServiceApiScope, ServiceApiState,
     },
-    blockchain::{Block, SharedNodeState},
-    crypto::Hash,
-    explorer::{self, BlockchainExplorer, TransactionInfo},
-    helpers::Height,
+    blockchain::{Block, GenesisConfig, PoolDelta, Schema, SharedNodeState},
+    crypto::{Hash, PublicKey},
+    explorer::{self, BlockchainExplorer, TransactionCache, TransactionInfo},
+    helpers::{Height, Milliseconds, Round, ValidatorId},
     messages::{Message, Precommit, RawTransaction, Signed, SignedMessage},
 };
 
@@ -42,6 +61,113 @@ use crate::{
 /// the parameter limits the maximum execution time for such requests.
 pub const MAX_BLOCKS_PER_REQUEST: usize = 1000;
 
+/// The maximum number of blocks to return per blocks request if the transaction bodies
+/// are included into the response. Blocks with bodies can be much larger than the headers,
+/// so the limit is lower than `MAX_BLOCKS_PER_REQUEST`.
+pub const MAX_BLOCKS_WITH_TX_BODIES_PER_REQUEST: usize = 100;
+
+/// The maximum number of transactions to return per transactions range request
+/// in the JSON array form. Streamed responses are not limited.
+pub const MAX_TRANSACTIONS_PER_REQUEST: usize = 1000;
+
+/// Media type of the newline-delimited JSON responses.
+pub const NDJSON_CONTENT_TYPE: &str = "application/x-ndjson";
+
+/// Media type of the binary responses.
+pub const OCTET_STREAM_CONTENT_TYPE: &str = "application/octet-stream";
+
+/// Name of the HTTP header with the optional idempotency key of a transaction submission.
+/// Repeated submissions of the transactions signed by the same author with the same key
+/// return the hash of the originally submitted transaction and are not broadcast.
+pub const IDEMPOTENCY_KEY_HEADER: &str = "Idempotency-Key";
+
+/// Time in seconds during which the node remembers idempotency keys of transaction submissions.
+pub const IDEMPOTENCY_KEY_TTL: u64 = 600;
+
+/// The maximum number of idempotency keys remembered by the node. If the limit is reached,
+/// the oldest keys are forgotten first.
+pub const MAX_IDEMPOTENCY_KEYS: usize = 10_000;
+
+/// The number of the latest blocks which average interval is used to estimate the commit
+/// time of the future blocks.
+pub const BLOCK_ETA_WINDOW: u64 = 100;
+
+/// Default number of threads executing the explorer requests limited in time.
+pub const API_WORKER_THREADS: usize = 4;
+
+/// Default maximum number of the explorer requests limited in time which are executed
+/// or wait for a worker thread. Further requests are rejected with
+/// `503 Service Unavailable` until some of them complete.
+pub const MAX_PENDING_API_REQUESTS: usize = 256;
+
+/// Worker threads executing the explorer requests limited in time. The workers are
+/// created for each node which has `api_request_timeout` set.
+pub struct ApiWorkers {
+    timeout: Duration,
+    pool: ThreadPool,
+    pending_requests: Arc<AtomicUsize>,
+    max_pending_requests: usize,
+}
+
+impl ApiWorkers {
+    /// Creates `threads` workers aborting the requests after `timeout`. At most
+    /// `max_pending_requests` requests may be executed or wait for a worker at once.
+    pub fn new(timeout: Duration, threads: usize, max_pending_requests: usize) -> Self {
+        Self {
+            timeout,
+            pool: ThreadPoolBuilder::new()
+                .pool_size(threads)
+                .name_prefix("explorer-api-")
+                .build(),
+            pending_requests: Arc::new(AtomicUsize::new(0)),
+            max_pending_requests,
+        }
+    }
+
+    /// Creates the workers according to the API configuration stored in `SharedNodeState`,
+    /// or returns `None` if the request timeout is not set.
+    pub fn from_node_state(shared_node_state: &SharedNodeState) -> Option<Self> {
+        let timeout = shared_node_state.api_request_timeout()?;
+        Some(Self::new(
+            timeout,
+            shared_node_state.api_worker_threads(),
+            shared_node_state.max_pending_api_requests(),
+        ))
+    }
+}
+
+impl fmt::Debug for ApiWorkers {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ApiWorkers")
+            .field("timeout", &self.timeout)
+            .field("pending_requests", &self.pending_requests)
+            .field("max_pending_requests", &self.max_pending_requests)
+            .finish()
+    }
+}
+
+/// Accounts a request in the pending requests of `ApiWorkers` until it is dropped,
+/// even if the handler has panicked.
+struct PendingApiRequest(Arc<AtomicUsize>);
+
+impl PendingApiRequest {
+    fn new(workers: &ApiWorkers) -> Option<Self> {
+        let pending_requests = &workers.pending_requests;
+        if pending_requests.fetch_add(1, Ordering::SeqCst) < workers.max_pending_requests {
+            Some(PendingApiRequest(Arc::clone(pending_requests)))
+        } else {
+            pending_requests.fetch_sub(1, Ordering::SeqCst);
+            None
+        }
+    }
+}
+
+impl Drop for PendingApiRequest {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
 /// Information on blocks coupled with the corresponding range in the blockchain.
 #[derive(Debug, Serialize, Deserialize, PartialEq)]
 pub struct BlocksRange {
@@ -66,9 +192,39 @@ pub struct BlockInfo {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub txs: Option<Vec<Hash>>,
 
+    /// Bodies of transactions in the block.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tx_bodies: Option<Vec<Signed<RawTransaction>>>,
+
     /// Median time from the block precommits.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub time: Option<DateTime<Utc>>,
+
+    /// Number of validators which precommits are stored for the block. It may exceed
+    /// the quorum if more validators have signed the block by the time it was committed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub signatures_count: Option<usize>,
+
+    /// Whether the transaction bodies of the block have been pruned by the node, so that
+    /// only the header, precommits and transaction hashes are available.
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub header_only: bool,
+}
+
+/// Order of the blocks returned by the blocks request.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum BlocksOrder {
+    /// Blocks are returned starting from the lowest height.
+    Asc,
+    /// Blocks are returned starting from the highest height.
+    Desc,
+}
+
+impl Default for BlocksOrder {
+    fn default() -> Self {
+        BlocksOrder::Desc
+    }
 }
 
 /// Blocks in range parameters.
@@ -78,17 +234,21 @@ pub struct BlocksQuery {
     pub count: usize,
     /// The maximum height of the returned blocks.
     ///
-    /// The blocks are returned in reverse order,
+    /// In the default descending order the blocks are returned in reverse order,
     /// starting from the latest and at least up to the `latest - count + 1`.
     /// The default value is the height of the latest block in the blockchain.
     pub latest: Option<Height>,
     /// The minimum height of the returned blocks. The default value is `Height(0)` (the genesis
     /// block).
     ///
-    /// Note that `earliest` has the least priority compared to `latest` and `count`;
-    /// it can only truncate the list of otherwise returned blocks if some of them have a lesser
-    /// height.
+    /// In the default descending order `earliest` has the least priority compared to `latest`
+    /// and `count`; it can only truncate the list of otherwise returned blocks if some of them
+    /// have a lesser height. In the ascending order the blocks are returned starting from
+    /// `earliest`, and `latest` can only truncate the list.
     pub earliest: Option<Height>,
+    /// The order of the returned blocks. The default value is `Desc`.
+    #[serde(default)]
+    pub order: BlocksOrder,
     /// If true, then only non-empty blocks are returned. The default value is false.
     #[serde(default)]
     pub skip_empty_blocks: bool,
@@ -100,6 +260,16 @@ pub struct BlocksQuery {
     /// corresponding returned blocks.
     #[serde(default)]
     pub add_precommits: bool,
+    /// If true, then the returned blocks will contain the number of their precommits
+    /// in `BlockInfo.signatures_count`. The number is also returned if `add_precommits`
+    /// is set.
+    #[serde(default)]
+    pub add_signatures_count: bool,
+    /// If true, then the returned blocks will contain the bodies of their transactions
+    /// in `BlockInfo.tx_bodies`. In this case, `count` should not be greater than
+    /// `MAX_BLOCKS_WITH_TX_BODIES_PER_REQUEST`.
+    #[serde(default)]
+    pub add_tx_bodies: bool,
 }
 
 /// Block query parameters.
@@ -107,13 +277,204 @@ pub struct BlocksQuery {
 pub struct BlockQuery {
     /// The height of the desired block.
     pub height: Height,
+    /// The index of the first transaction hash returned in `BlockInfo.txs`.
+    /// The default value is zero.
+    pub tx_offset: Option<usize>,
+    /// The maximum number of transaction hashes returned in `BlockInfo.txs`.
+    /// By default, all the hashes starting from `tx_offset` are returned.
+    ///
+    /// The total number of transactions in the block is reported by the `tx_count` field
+    /// of the block header.
+    pub tx_count: Option<usize>,
+    /// If true, then the returned `BlockInfo.tx_bodies` will contain the bodies of
+    /// the transactions, limited by `tx_offset` and `tx_count` in the same way as hashes.
+    #[serde(default)]
+    pub add_tx_bodies: bool,
 }
 
 impl BlockQuery {
     /// Creates a new block query with the given height.
     pub fn new(height: Height) -> Self {
-        Self { height }
+        Self {
+            height,
+            tx_offset: None,
+            tx_count: None,
+            add_tx_bodies: false,
+        }
     }
+
+    /// Limits the returned transaction hashes to `count` hashes starting from `offset`.
+    pub fn with_txs_slice(self, offset: usize, count: usize) -> Self {
+        Self {
+            tx_offset: Some(offset),
+            tx_count: Some(count),
+            ..self
+        }
+    }
+
+    /// Includes the bodies of the block transactions into the response.
+    pub fn with_tx_bodies(self) -> Self {
+        Self {
+            add_tx_bodies: true,
+            ..self
+        }
+    }
+}
+
+/// Query parameters of the block lookup by time.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct BlockByTimeQuery {
+    /// The instant to look up the block for, in the RFC 3339 format.
+    pub at: DateTime<Utc>,
+}
+
+/// Query parameters of the committed blocks rounds.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct BlocksRoundsQuery {
+    /// The number of the latest blocks to return. Should not be greater
+    /// than `MAX_BLOCKS_PER_REQUEST`.
+    pub count: usize,
+}
+
+/// Round at which the block was committed.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct BlockRound {
+    /// Height of the block.
+    pub height: Height,
+    /// Round of the block precommits.
+    pub round: Round,
+}
+
+/// Query parameters of the block votes.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct BlockVotesQuery {
+    /// The height of the block.
+    pub height: Height,
+}
+
+/// Query parameters of the serialized block.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct RawBlockQuery {
+    /// The height of the block.
+    pub height: Height,
+}
+
+/// Query parameters of the block existence check.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct BlockExistsQuery {
+    /// The hash of the block.
+    pub hash: Hash,
+}
+
+/// Result of the block existence check.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct BlockExists {
+    /// Whether a block with the requested hash has been committed.
+    pub exists: bool,
+    /// The height of the block, if it exists.
+    pub height: Option<Height>,
+}
+
+/// Query parameters of the block state hash.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct BlockStateHashQuery {
+    /// The height of the block.
+    pub height: Height,
+}
+
+/// The oldest block which transactions are available on the node.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct OldestAvailableBlock {
+    /// The height of the block. Transaction bodies of the preceding blocks have been pruned.
+    pub height: Height,
+}
+
+/// State hash recorded in the header of the committed block.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct BlockStateHash {
+    /// The height of the block.
+    pub height: Height,
+    /// Hash of the blockchain state after the block has been applied.
+    pub state_hash: Hash,
+}
+
+/// Query parameters of the block commit time estimation.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct BlockEtaQuery {
+    /// The height of the block.
+    pub height: Height,
+}
+
+/// Commit time of the block, either actual or estimated.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct BlockEta {
+    /// The height of the block.
+    pub height: Height,
+    /// Median precommit time of the committed block, or the estimated commit time
+    /// of the future block.
+    pub time: DateTime<Utc>,
+    /// Whether the block is not committed yet and the time is estimated.
+    pub estimated: bool,
+}
+
+/// Precommit of a validator for the committed block.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct PrecommitVote {
+    /// Identifier of the validator in the configuration actual for the block.
+    pub validator: ValidatorId,
+    /// Consensus public key of the validator.
+    pub author: PublicKey,
+    /// Round of the precommit.
+    pub round: Round,
+    /// Local time of the validator when the precommit was created.
+    pub time: DateTime<Utc>,
+}
+
+/// Latest block query parameters.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Default)]
+pub struct LatestBlockQuery {
+    /// If true, then the returned `BlockInfo.time` will contain median time from the block
+    /// precommits.
+    #[serde(default)]
+    pub add_blocks_time: bool,
+    /// If true, then the returned `BlockInfo.precommits` will contain precommits for the block
+    /// and `BlockInfo.signatures_count` will contain their number.
+    #[serde(default)]
+    pub add_precommits: bool,
+}
+
+/// Transactions range query parameters.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Default)]
+pub struct TransactionsRangeQuery {
+    /// The height of the first block to take transactions from. Defaults to the genesis block.
+    pub earliest: Option<Height>,
+    /// The height of the last block to take transactions from. Defaults to the latest block.
+    pub latest: Option<Height>,
+    /// The maximum number of transactions to return. Defaults to
+    /// `MAX_TRANSACTIONS_PER_REQUEST` for the JSON array form and is unlimited for
+    /// the streamed form.
+    pub count: Option<usize>,
+}
+
+/// Committed transactions stream query parameters.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Default)]
+pub struct TransactionsStreamQuery {
+    /// ID of the service to stream transactions of. If not set, transactions
+    /// of all services are streamed.
+    pub service_id: Option<u16>,
+}
+
+/// Query parameters for the transactions signed by the given public key.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct TransactionsByAuthorQuery {
+    /// Public key the transactions are signed with.
+    pub author: PublicKey,
+    /// The height of the first block to take transactions from. Defaults to the genesis block.
+    pub earliest: Option<Height>,
+    /// The height of the last block to take transactions from. Defaults to the latest block.
+    pub latest: Option<Height>,
+    /// The maximum number of transactions to return. Defaults to `MAX_TRANSACTIONS_PER_REQUEST`.
+    pub count: Option<usize>,
 }
 
 /// Raw Transaction in hex representation.
@@ -126,8 +487,23 @@ pub struct TransactionHex {
 /// Transaction response.
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
 pub struct TransactionResponse {
-    /// The hex value of the transaction to be broadcasted.
+    /// Hash of the signed transaction message, which is used as the transaction identifier.
     pub tx_hash: Hash,
+    /// Hash of the transaction payload, which covers neither the transaction author
+    /// nor the signature. Set to the zero hash when deserializing responses of nodes
+    /// that do not report it.
+    #[serde(default)]
+    pub payload_hash: Hash,
+}
+
+impl TransactionResponse {
+    /// Creates a response for the given transaction.
+    pub fn new(tx: &Signed<RawTransaction>) -> Self {
+        Self {
+            tx_hash: tx.hash(),
+            payload_hash: tx.payload().payload_hash(),
+        }
+    }
 }
 
 /// Transaction query parameters.
@@ -144,6 +520,111 @@ impl TransactionQuery {
     }
 }
 
+/// Transaction statuses query parameters.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TransactionStatusesQuery {
+    /// Hashes of the transactions. The number of hashes should not be greater than
+    /// `MAX_TRANSACTIONS_PER_REQUEST`.
+    pub hashes: Vec<Hash>,
+}
+
+/// Status of a transaction known to the node.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TransactionStatus {
+    /// The transaction is not known to the node.
+    Unknown,
+    /// The transaction is in the pool of uncommitted transactions.
+    Pending,
+    /// The transaction is committed.
+    Committed {
+        /// Height of the block containing the transaction.
+        height: Height,
+    },
+}
+
+/// Proof of the transaction inclusion into a block.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TransactionProof {
+    /// Height of the block containing the transaction.
+    pub height: Height,
+    /// The committed transaction, or `None` if its body has been pruned by the node.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub transaction: Option<Signed<RawTransaction>>,
+    /// Merkle path from the transaction hash to the `tx_hash` of the block.
+    pub proof: ListProof<Hash>,
+}
+
+/// Uncommitted transaction from the pool.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PendingTransaction {
+    /// The transaction message.
+    pub content: Signed<RawTransaction>,
+    /// Time when the transaction was added to the pool. It is not known
+    /// for the transactions added to the pool by the previous versions of the node.
+    pub arrived_at: Option<DateTime<Utc>>,
+}
+
+/// Size of the pool of uncommitted transactions.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct TransactionsPoolSize {
+    /// Number of transactions in the pool.
+    pub size: u64,
+    /// Total size of transactions in the pool in bytes.
+    pub bytes: u64,
+}
+
+/// Time for which the breakdown of the transactions pool by services is cached,
+/// in milliseconds.
+pub const POOL_BY_SERVICE_CACHE_TIME: u64 = 1_000;
+
+/// Breakdown of the transactions pool by services, cached so that frequent requests
+/// do not rescan large pools.
+pub struct PoolByServiceCache {
+    inner: Mutex<Option<(Instant, BTreeMap<u16, u64>)>>,
+    clock: Box<dyn Fn() -> Instant + Send + Sync>,
+}
+
+impl PoolByServiceCache {
+    /// Creates a cache measuring the age of the cached breakdown with the given clock
+    /// instead of `Instant::now`.
+    pub(crate) fn with_clock<F>(clock: F) -> Self
+    where
+        F: Fn() -> Instant + Send + Sync + 'static,
+    {
+        Self {
+            inner: Mutex::new(None),
+            clock: Box::new(clock),
+        }
+    }
+}
+
+impl Default for PoolByServiceCache {
+    fn default() -> Self {
+        Self::with_clock(Instant::now)
+    }
+}
+
+impl fmt::Debug for PoolByServiceCache {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("PoolByServiceCache")
+            .field("inner", &self.inner)
+            .finish()
+    }
+}
+
+/// Transactions pool delta query parameters.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct PoolDeltaQuery {
+    /// Revision of the pool returned in `PoolDelta.token` by the previous request.
+    /// Zero value corresponds to the start of the node.
+    pub since: u64,
+    /// Epoch of the revision returned in `PoolDelta.epoch` by the previous request.
+    /// May be omitted only if `since` is zero.
+    #[serde(default)]
+    pub epoch: Option<u64>,
+}
+
 /// Exonum blockchain explorer API.
 #[derive(Debug, Clone, Copy)]
 pub struct ExplorerApi;
@@ -162,6 +643,12 @@ impl ExplorerApi {
                 MAX_BLOCKS_PER_REQUEST
             )));
         }
+        if query.add_tx_bodies && query.count > MAX_BLOCKS_WITH_TX_BODIES_PER_REQUEST {
+            return Err(ApiError::BadRequest(format!(
+                "Max block count per request with transaction bodies exceeded ({})",
+                MAX_BLOCKS_WITH_TX_BODIES_PER_REQUEST
+            )));
+        }
 
         let (upper, upper_bound) = if let Some(upper) = query.latest {
             if upper > explorer.height() {
@@ -181,83 +668,653 @@ impl ExplorerApi {
             Bound::Unbounded
         };
 
-        let blocks: Vec<_> = explorer
-            .blocks((lower_bound, upper_bound))
-            .rev()
+        let blocks = explorer.blocks((lower_bound, upper_bound));
+        let blocks: Box<dyn Iterator<Item = explorer::BlockInfo>> = match query.order {
+            BlocksOrder::Asc => Box::new(blocks),
+            BlocksOrder::Desc => Box::new(blocks.rev()),
+        };
+        let blocks = blocks
             .filter(|block| !query.skip_empty_blocks || !block.is_empty())
             .take(query.count)
-            .map(|block| BlockInfo {
-                txs: None,
-
-                time: if query.add_blocks_time {
-                    Some(median_precommits_time(&block.precommits()))
+            .map(|block| {
+                let header_only = block.is_pruned();
+                let tx_bodies = if query.add_tx_bodies && !header_only {
+                    Some(transaction_bodies(state, &block.transaction_hashes())?)
                 } else {
                     None
-                },
+                };
+                Ok(BlockInfo {
+                    txs: None,
+                    tx_bodies,
+                    header_only,
 
-                precommits: if query.add_precommits {
-                    Some(block.precommits().to_vec())
-                } else {
-                    None
-                },
+                    time: if query.add_blocks_time {
+                        Some(block_time(&block))
+                    } else {
+                        None
+                    },
+
+                    precommits: if query.add_precommits {
+                        Some(block.precommits().to_vec())
+                    } else {
+                        None
+                    },
 
-                block: block.into_header(),
+                    signatures_count: if query.add_precommits || query.add_signatures_count {
+                        Some(block.precommits().len())
+                    } else {
+                        None
+                    },
+
+                    block: block.into_header(),
+                })
             })
-            .collect();
+            .collect::<Result<Vec<_>, ApiError>>()?;
 
-        let height = if blocks.len() < query.count {
-            query.earliest.unwrap_or(Height(0))
+        let lower = query.earliest.unwrap_or(Height(0));
+        let range = if blocks.len() < query.count {
+            lower..upper.next()
         } else {
-            blocks.last().map_or(Height(0), |info| info.block.height())
+            let last = blocks.last().map(|info| info.block.height());
+            match query.order {
+                BlocksOrder::Asc => lower..last.map_or(lower, |height| height.next()),
+                BlocksOrder::Desc => last.unwrap_or(Height(0))..upper.next(),
+            }
         };
 
-        Ok(BlocksRange {
-            range: height..upper.next(),
-            blocks,
-        })
+        Ok(BlocksRange { range, blocks })
+    }
+
+    /// Returns the rounds at which the latest `count` blocks were committed, starting
+    /// from the latest block. The genesis block has no precommits and is omitted.
+    pub fn blocks_rounds(
+        state: &ServiceApiState,
+        query: BlocksRoundsQuery,
+    ) -> Result<Vec<BlockRound>, ApiError> {
+        if query.count > MAX_BLOCKS_PER_REQUEST {
+            return Err(ApiError::BadRequest(format!(
+                "Max block count per request exceeded ({})",
+                MAX_BLOCKS_PER_REQUEST
+            )));
+        }
+        let explorer = BlockchainExplorer::new(state.blockchain());
+        Ok(explorer
+            .blocks(..)
+            .rev()
+            .take(query.count)
+            .filter_map(|block| {
+                let round = precommits_round(&block.precommits())?;
+                Some(BlockRound {
+                    height: block.height(),
+                    round,
+                })
+            })
+            .collect())
     }
 
     /// Returns the content for a block at a specific height.
+    ///
+    /// If `tx_offset` or `tx_count` is specified in the [`BlockQuery`], only the corresponding
+    /// slice of the block transaction hashes is returned. If `add_tx_bodies` is set,
+    /// the bodies of the same slice of transactions are returned as well.
+    ///
+    /// [`BlockQuery`]: struct.BlockQuery.html
     pub fn block(state: &ServiceApiState, query: BlockQuery) -> Result<BlockInfo, ApiError> {
-        BlockchainExplorer::new(state.blockchain())
-            .block(query.height)
-            .map(From::from)
+        let explorer = BlockchainExplorer::new(state.blockchain());
+        let block = explorer.block(query.height).ok_or_else(|| {
+            ApiError::NotFound(format!("Block for height: {} not found", query.height))
+        })?;
+
+        let txs = block.transaction_hashes();
+        let start = cmp::min(query.tx_offset.unwrap_or(0), txs.len());
+        let end = query.tx_count.map_or(txs.len(), |count| {
+            cmp::min(start.saturating_add(count), txs.len())
+        });
+        let txs = txs[start..end].to_vec();
+        let header_only = block.is_pruned();
+        let tx_bodies = if query.add_tx_bodies && !header_only {
+            Some(transaction_bodies(state, &txs)?)
+        } else {
+            None
+        };
+
+        Ok(BlockInfo {
+            block: block.header().clone(),
+            precommits: Some(block.precommits().to_vec()),
+            txs: Some(txs),
+            tx_bodies,
+            time: Some(block_time(&block)),
+            signatures_count: Some(block.precommits().len()),
+            header_only,
+        })
+    }
+
+    /// Returns the precommits the block at a specific height was committed with,
+    /// ordered by the validator identifier.
+    pub fn block_votes(
+        state: &ServiceApiState,
+        query: BlockVotesQuery,
+    ) -> Result<Vec<PrecommitVote>, ApiError> {
+        let explorer = BlockchainExplorer::new(state.blockchain());
+        let block = explorer.block(query.height).ok_or_else(|| {
+            ApiError::NotFound(format!("Block for height: {} not found", query.height))
+        })?;
+
+        let mut votes = block
+            .precommits()
+            .iter()
+            .map(|precommit| PrecommitVote {
+                validator: precommit.validator(),
+                author: precommit.author(),
+                round: precommit.round(),
+                time: precommit.time(),
+            })
+            .collect::<Vec<_>>();
+        votes.sort_by_key(|vote| vote.validator);
+        Ok(votes)
+    }
+
+    /// Returns the first block with the median precommit time at or after the given instant.
+    /// If the instant precedes the genesis block, the genesis block is returned; if it follows
+    /// the latest block, the latest block is returned.
+    ///
+    /// Median block times are expected to grow with the block height, so the block
+    /// is found with a binary search.
+    pub fn block_by_time(
+        state: &ServiceApiState,
+        query: BlockByTimeQuery,
+    ) -> Result<BlockInfo, ApiError> {
+        let explorer = BlockchainExplorer::new(state.blockchain());
+        let block = |height| {
+            explorer.block(height).ok_or_else(|| {
+                ApiError::InternalError(format_err!("Block for height {} not found", height))
+            })
+        };
+
+        let (mut lower, mut upper) = (0, explorer.height().0);
+        while lower < upper {
+            let middle = lower + (upper - lower) / 2;
+            if block_time(&block(Height(middle))?) < query.at {
+                lower = middle + 1;
+            } else {
+                upper = middle;
+            }
+        }
+        block(Height(lower)).map(From::from)
+    }
+
+    /// Returns the content of the latest committed block.
+    ///
+    /// Precommits and the median block time are included only if requested
+    /// in the [`LatestBlockQuery`] struct.
+    ///
+    /// [`LatestBlockQuery`]: struct.LatestBlockQuery.html
+    pub fn latest_block(
+        state: &ServiceApiState,
+        query: LatestBlockQuery,
+    ) -> Result<BlockInfo, ApiError> {
+        let explorer = BlockchainExplorer::new(state.blockchain());
+        let block = explorer
+            .block(explorer.height())
+            .ok_or_else(|| ApiError::NotFound("Latest block not found".to_owned()))?;
+        let txs = block.transaction_hashes().to_vec();
+        let time = if query.add_blocks_time {
+            Some(block_time(&block))
+        } else {
+            None
+        };
+        let precommits = if query.add_precommits {
+            Some(block.precommits().to_vec())
+        } else {
+            None
+        };
+        let signatures_count = precommits.as_ref().map(Vec::len);
+        let header_only = block.is_pruned();
+
+        Ok(BlockInfo {
+            block: block.into_header(),
+            precommits,
+            txs: Some(txs),
+            tx_bodies: None,
+            time,
+            signatures_count,
+            header_only,
+        })
+    }
+
+    /// Returns the state hash recorded in the header of the block at the given height,
+    /// so that the states of different nodes can be compared height by height.
+    pub fn block_state_hash(
+        state: &ServiceApiState,
+        query: BlockStateHashQuery,
+    ) -> Result<BlockStateHash, ApiError> {
+        let snapshot = state.snapshot();
+        let schema = Schema::new(&snapshot);
+        let block = schema
+            .block_hash_by_height(query.height)
+            .and_then(|hash| schema.blocks().get(&hash))
             .ok_or_else(|| {
                 ApiError::NotFound(format!("Block for height: {} not found", query.height))
+            })?;
+        Ok(BlockStateHash {
+            height: query.height,
+            state_hash: *block.state_hash(),
+        })
+    }
+
+    /// Returns the commit time of the block at the given height. The time of a future block
+    /// is estimated from the average interval between the latest `BLOCK_ETA_WINDOW` blocks
+    /// and the number of the remaining blocks.
+    pub fn block_eta(state: &ServiceApiState, query: BlockEtaQuery) -> Result<BlockEta, ApiError> {
+        let explorer = BlockchainExplorer::new(state.blockchain());
+        let block = |height| {
+            explorer.block(height).ok_or_else(|| {
+                ApiError::InternalError(format_err!("Block for height {} not found", height))
+            })
+        };
+
+        let current = explorer.height();
+        if query.height <= current {
+            return Ok(BlockEta {
+                height: query.height,
+                time: block_time(&block(query.height)?),
+                estimated: false,
+            });
+        }
+
+        // The genesis block has no precommits, so its time is not taken into account.
+        let first = cmp::max(current.0.saturating_sub(BLOCK_ETA_WINDOW), 1);
+        if first >= current.0 {
+            return Err(ApiError::NotFound(
+                "Not enough committed blocks to estimate the block time".to_owned(),
+            ));
+        }
+        let latest_time = block_time(&block(current)?);
+        let elapsed = latest_time - block_time(&block(Height(first))?);
+        let interval = cmp::max(elapsed.num_milliseconds(), 0) as u64 / (current.0 - first);
+        let time = (query.height.0 - current.0)
+            .checked_mul(interval)
+            .filter(|&millis| millis <= i64::max_value() as u64)
+            .and_then(|millis| {
+                latest_time.checked_add_signed(chrono::Duration::milliseconds(millis as i64))
             })
+            .ok_or_else(|| {
+                ApiError::BadRequest(format!("Height {} is too far in the future", query.height))
+            })?;
+        Ok(BlockEta {
+            height: query.height,
+            time,
+            estimated: true,
+        })
+    }
+
+    /// Returns the canonical serialization of the block header at the given height.
+    /// The hash of the returned bytes is the block hash referenced by the precommits.
+    pub fn raw_block(state: &ServiceApiState, query: RawBlockQuery) -> Result<Vec<u8>, ApiError> {
+        let snapshot = state.snapshot();
+        let schema = Schema::new(&snapshot);
+        schema
+            .block_hash_by_height(query.height)
+            .and_then(|hash| schema.blocks().get(&hash))
+            .map(|block| block.into_bytes())
+            .ok_or_else(|| {
+                ApiError::NotFound(format!("Block for height: {} not found", query.height))
+            })
+    }
+
+    /// Checks whether a block with the given hash has been committed, without loading
+    /// the block contents.
+    pub fn block_exists(
+        state: &ServiceApiState,
+        query: BlockExistsQuery,
+    ) -> Result<BlockExists, ApiError> {
+        let snapshot = state.snapshot();
+        let height = Schema::new(&snapshot)
+            .blocks()
+            .get(&query.hash)
+            .map(|block| block.height());
+        Ok(BlockExists {
+            exists: height.is_some(),
+            height,
+        })
+    }
+
+    /// Returns the oldest block which transactions are available on the node. All the blocks
+    /// are available unless pruning is enabled in the node configuration.
+    pub fn oldest_available(
+        state: &ServiceApiState,
+        _query: (),
+    ) -> Result<OldestAvailableBlock, ApiError> {
+        let explorer = BlockchainExplorer::new(state.blockchain());
+        Ok(OldestAvailableBlock {
+            height: explorer.oldest_available(),
+        })
+    }
+
+    /// Returns the original configuration the genesis block was created with.
+    pub fn genesis(state: &ServiceApiState, _query: ()) -> Result<GenesisConfig, ApiError> {
+        let snapshot = state.snapshot();
+        Schema::new(&snapshot)
+            .genesis_config()
+            .ok_or_else(|| ApiError::NotFound("Genesis configuration not found".to_owned()))
     }
 
     /// Searches for a transaction, either committed or uncommitted, by the hash.
+    /// The location of a committed transaction is returned in the error if the body
+    /// of the transaction has been pruned.
     pub fn transaction_info(
         state: &ServiceApiState,
         query: TransactionQuery,
     ) -> Result<TransactionInfo, ApiError> {
         BlockchainExplorer::new(state.blockchain())
             .transaction(&query.hash)
-            .ok_or_else(|| {
-                let description = serde_json::to_string(&json!({ "type": "unknown" })).unwrap();
-                debug!("{}", description);
-                ApiError::NotFound(description)
+            .ok_or_else(|| missing_transaction(state, &query.hash))
+    }
+
+    /// Searches for a transaction by the hash, taking committed transactions from the cache
+    /// if possible.
+    pub fn cached_transaction_info(
+        cache: &TransactionCache,
+        state: &ServiceApiState,
+        query: TransactionQuery,
+    ) -> Result<TransactionInfo, ApiError> {
+        let blockchain = state.blockchain();
+        cache
+            .get_or_load(blockchain, &query.hash, |hash| {
+                BlockchainExplorer::new(blockchain).transaction(hash)
             })
+            .ok_or_else(|| missing_transaction(state, &query.hash))
     }
+
+    /// Returns the statuses of the transactions with the given hashes.
+    pub fn transaction_statuses(
+        state: &ServiceApiState,
+        query: TransactionStatusesQuery,
+    ) -> Result<HashMap<Hash, TransactionStatus>, ApiError> {
+        if query.hashes.len() > MAX_TRANSACTIONS_PER_REQUEST {
+            return Err(ApiError::BadRequest(format!(
+                "Max transaction count per request exceeded ({})",
+                MAX_TRANSACTIONS_PER_REQUEST
+            )));
+        }
+
+        let explorer = BlockchainExplorer::new(state.blockchain());
+        let snapshot = state.snapshot();
+        let locations = Schema::new(&snapshot).transactions_locations();
+        Ok(query
+            .hashes
+            .into_iter()
+            .map(|hash| {
+                let status = match explorer.transaction(&hash) {
+                    // Bodies of the transactions from the pruned blocks are removed,
+                    // but their locations are kept.
+                    None => match locations.get(&hash) {
+                        Some(location) => TransactionStatus::Committed {
+                            height: location.block_height(),
+                        },
+                        None => TransactionStatus::Unknown,
+                    },
+                    Some(TransactionInfo::InPool { .. }) => TransactionStatus::Pending,
+                    Some(TransactionInfo::Committed(tx)) => TransactionStatus::Committed {
+                        height: tx.location().block_height(),
+                    },
+                };
+                (hash, status)
+            })
+            .collect())
+    }
+
+    /// Returns a proof that the committed transaction with the given hash is included
+    /// into a block. The proof does not contain the transaction if its body has been pruned.
+    pub fn transaction_proof(
+        state: &ServiceApiState,
+        query: TransactionQuery,
+    ) -> Result<TransactionProof, ApiError> {
+        let snapshot = state.snapshot();
+        let schema = Schema::new(&snapshot);
+        let location = schema
+            .transactions_locations()
+            .get(&query.hash)
+            .ok_or_else(|| ApiError::NotFound("Transaction is not committed".to_owned()))?;
+        let transaction = schema.transactions().get(&query.hash);
+        if transaction.is_none() && location.block_height() >= schema.oldest_available_height() {
+            return Err(ApiError::InternalError(format_err!(
+                "Transaction content not found for hash {:?}",
+                query.hash
+            )));
+        }
+        let proof = schema
+            .block_transactions(location.block_height())
+            .get_proof(location.position_in_block());
+
+        Ok(TransactionProof {
+            height: location.block_height(),
+            transaction,
+            proof,
+        })
+    }
+
+    /// Returns the latest committed transactions signed by the given public key, starting
+    /// from the most recent one. The transactions are looked up in the index maintained
+    /// on commit, so the blocks are not scanned.
+    pub fn transactions_by_author(
+        state: &ServiceApiState,
+        query: TransactionsByAuthorQuery,
+    ) -> Result<Vec<TransactionInfo>, ApiError> {
+        let count = query.count.unwrap_or(MAX_TRANSACTIONS_PER_REQUEST);
+        if count > MAX_TRANSACTIONS_PER_REQUEST {
+            return Err(ApiError::BadRequest(format!(
+                "Max transaction count per request exceeded ({})",
+                MAX_TRANSACTIONS_PER_REQUEST
+            )));
+        }
+
+        let explorer = BlockchainExplorer::new(state.blockchain());
+        let snapshot = state.snapshot();
+        let schema = Schema::new(&snapshot);
+        let locations = schema.transactions_locations();
+        let hashes = schema.transactions_by_author(&query.author);
+        let earliest = query.earliest.unwrap_or(Height(0));
+        let latest = query.latest.unwrap_or_else(|| explorer.height());
+
+        // Transactions in the index are ordered by height, so the search stops
+        // at the first transaction below `earliest`.
+        Ok((0..hashes.len())
+            .rev()
+            .filter_map(|i| hashes.get(i))
+            .filter_map(|hash| Some((locations.get(&hash)?.block_height(), hash)))
+            .skip_while(|&(height, _)| height > latest)
+            .take_while(|&(height, _)| height >= earliest)
+            .take(count)
+            .filter_map(|(_, hash)| explorer.transaction(&hash))
+            .collect())
+    }
+
+    /// Returns the transaction with the given hash only if it is currently in the pool
+    /// of uncommitted transactions.
+    pub fn pending_transaction(
+        state: &ServiceApiState,
+        query: TransactionQuery,
+    ) -> Result<PendingTransaction, ApiError> {
+        let snapshot = state.snapshot();
+        let schema = Schema::new(&snapshot);
+        if !schema.transactions_pool().contains(&query.hash) {
+            return Err(ApiError::NotFound(
+                "Transaction is not in the pool".to_owned(),
+            ));
+        }
+        let content = schema.transactions().get(&query.hash).ok_or_else(|| {
+            ApiError::InternalError(format_err!(
+                "Transaction content not found for hash {:?}",
+                query.hash
+            ))
+        })?;
+
+        Ok(PendingTransaction {
+            content,
+            arrived_at: schema.transactions_pool_arrival_time().get(&query.hash),
+        })
+    }
+
+    /// Returns the number and the total size of uncommitted transactions.
+    pub fn transactions_pool_size(
+        state: &ServiceApiState,
+        _query: (),
+    ) -> Result<TransactionsPoolSize, ApiError> {
+        let snapshot = state.snapshot();
+        let schema = Schema::new(&snapshot);
+        Ok(TransactionsPoolSize {
+            size: schema.transactions_pool_len(),
+            bytes: schema.transactions_pool_bytes(),
+        })
+    }
+
+    /// Returns the number of uncommitted transactions of each service. The pool is scanned
+    /// at most once per `POOL_BY_SERVICE_CACHE_TIME`, the cached breakdown is returned
+    /// in between.
+    pub fn transactions_pool_by_service(
+        cache: &PoolByServiceCache,
+        state: &ServiceApiState,
+        _query: (),
+    ) -> Result<BTreeMap<u16, u64>, ApiError> {
+        let mut cached = cache.inner.lock().expect("Expected mutex lock");
+        // The clock is read under the lock, so that the cached breakdown is never newer
+        // than `now`.
+        let now = (cache.clock)();
+        if let Some((computed_at, ref breakdown)) = *cached {
+            if now < computed_at + Duration::from_millis(POOL_BY_SERVICE_CACHE_TIME) {
+                return Ok(breakdown.clone());
+            }
+        }
+
+        let snapshot = state.snapshot();
+        let schema = Schema::new(&snapshot);
+        let transactions = schema.transactions();
+        let mut breakdown = BTreeMap::new();
+        for hash in schema.transactions_pool().iter() {
+            if let Some(tx) = transactions.get(&hash) {
+                *breakdown.entry(tx.payload().service_id()).or_insert(0) += 1;
+            }
+        }
+        *cached = Some((now, breakdown.clone()));
+        Ok(breakdown)
+    }
+
+    /// Returns the hashes of the transactions added into the pool and removed from it
+    /// since the given revision of the pool.
+    pub fn pool_delta(shared_node_state: &SharedNodeState, query: PoolDeltaQuery) -> PoolDelta {
+        shared_node_state.pool_delta(query.epoch, query.since)
+    }
+
     /// Adds transaction into unconfirmed tx pool, and broadcast transaction to other nodes.
     pub fn add_transaction(
         state: &ServiceApiState,
         query: TransactionHex,
     ) -> Result<TransactionResponse, ApiError> {
-        use crate::events::error::into_failure;
-        use crate::messages::ProtocolMessage;
-
-        let buf: Vec<u8> = ::hex::decode(query.tx_body).map_err(into_failure)?;
-        let signed = SignedMessage::from_raw_buffer(buf)?;
-        let tx_hash = signed.hash();
-        let signed = RawTransaction::try_from(Message::deserialize(signed)?)
-            .map_err(|_| format_err!("Couldn't deserialize transaction message."))?;
+        let signed = parse_transaction(query)?;
+        Ok(Self::submit_transaction(state, signed))
+    }
+
+    fn add_transaction_with_key(
+        state: &ServiceApiState,
+        keys: &Mutex<IdempotencyKeys>,
+        query: TransactionHex,
+        key: Option<String>,
+    ) -> Result<TransactionResponse, ApiError> {
+        let signed = parse_transaction(query)?;
+        let key = match key {
+            Some(key) => (signed.author(), key),
+            None => return Ok(Self::submit_transaction(state, signed)),
+        };
+        let mut keys = keys.lock().expect("Expected mutex lock");
+        if let Some(response) = keys.get(&key) {
+            return Ok(response);
+        }
+        let response = Self::submit_transaction(state, signed);
+        keys.insert(key, response);
+        Ok(response)
+    }
+
+    fn submit_transaction(
+        state: &ServiceApiState,
+        signed: Signed<RawTransaction>,
+    ) -> TransactionResponse {
+        let response = TransactionResponse::new(&signed);
         let _ = state
             .sender()
             .broadcast_transaction(signed)
             .map_err(ApiError::from);
-        Ok(TransactionResponse { tx_hash })
+        response
+    }
+
+    /// Adds transaction into unconfirmed tx pool, taking into account the idempotency key
+    /// of the request passed in the `Idempotency-Key` header, if any. The keys are scoped
+    /// by the transaction author, so the keys of different clients never collide.
+    /// The transaction can be passed either as a JSON object or as
+    /// an `application/x-www-form-urlencoded` body with the `tx_body` field.
+    pub fn handle_add_transaction(name: &'static str, backend: &mut actix_backend::ApiBuilder) {
+        let keys = Arc::new(Mutex::new(IdempotencyKeys::default()));
+
+        let index = move |request: HttpRequest| -> FutureResponse {
+            let keys = keys.clone();
+            let context = request.state().clone();
+            let key = match idempotency_key(&request) {
+                Ok(key) => key,
+                Err(e) => return Box::new(future::err::<HttpResponse, ActixError>(e.into())),
+            };
+
+            transaction_hex(&request)
+                .and_then(move |query: TransactionHex| {
+                    Self::add_transaction_with_key(&context, &keys, query, key)
+                        .map(|value| HttpResponse::Ok().json(value))
+                        .map_err(From::from)
+                })
+                .responder()
+        };
+
+        backend.raw_handler(RequestHandler {
+            name: name.to_owned(),
+            method: http::Method::POST,
+            inner: Arc::from(index) as Arc<RawHandler>,
+        });
+    }
+
+    /// Returns committed transactions from the blocks in the given range, from the oldest
+    /// to the newest one. If the request accepts `application/x-ndjson`, transactions
+    /// are streamed one per line, otherwise a JSON array is returned.
+    pub fn handle_transactions_range(name: &'static str, backend: &mut actix_backend::ApiBuilder) {
+        let index = |request: HttpRequest| -> FutureResponse {
+            Box::new(transactions_range_response(&request).into_future())
+        };
+
+        backend.raw_handler(RequestHandler {
+            name: name.to_owned(),
+            method: http::Method::GET,
+            inner: Arc::from(index) as Arc<RawHandler>,
+        });
+    }
+
+    /// Returns the serialized block as `application/octet-stream`, so that it can be hashed
+    /// and verified against the precommits offline.
+    pub fn handle_raw_block(name: &'static str, backend: &mut actix_backend::ApiBuilder) {
+        let index = |request: HttpRequest| -> FutureResponse {
+            let response = Query::<RawBlockQuery>::from_request(&request, &Default::default())
+                .and_then(|query| {
+                    Self::raw_block(request.state(), query.into_inner()).map_err(From::from)
+                })
+                .map(|bytes| {
+                    HttpResponse::Ok()
+                        .content_type(OCTET_STREAM_CONTENT_TYPE)
+                        .body(bytes)
+                });
+            Box::new(response.into_future())
+        };
+
+        backend.raw_handler(RequestHandler {
+            name: name.to_owned(),
+            method: http::Method::GET,
+            inner: Arc::from(index) as Arc<RawHandler>,
+        });
     }
 
     /// Subscribes to events.
@@ -270,19 +1327,10 @@ impl ExplorerApi {
     ) where
         Q: Fn(&HttpRequest) -> Result<SubscriptionType, ActixError> + Send + Sync + 'static,
     {
-        let server = Arc::new(Mutex::new(None));
         let service_api_state = Arc::new(service_api_state);
 
         let index = move |request: HttpRequest| -> FutureResponse {
-            let server = server.clone();
-            let service_api_state = service_api_state.clone();
-            let mut address = server.lock().expect("Expected mutex lock");
-            if address.is_none() {
-                *address = Some(Arbiter::start(|_| Server::new(service_api_state)));
-
-                shared_node_state.set_broadcast_server_address(address.to_owned().unwrap());
-            }
-            let address = address.to_owned().unwrap();
+            let address = broadcast_server_address(&shared_node_state, &service_api_state);
 
             extract_query(&request)
                 .into_future()
@@ -300,6 +1348,48 @@ impl ExplorerApi {
         });
     }
 
+    /// Streams committed transactions, one `TransactionInfo` per line, as they are
+    /// committed. The connection is held open until the client disconnects
+    /// or the node shuts down.
+    pub fn handle_transactions_stream(
+        name: &'static str,
+        backend: &mut actix_backend::ApiBuilder,
+        service_api_state: ServiceApiState,
+        shared_node_state: SharedNodeState,
+    ) {
+        let service_api_state = Arc::new(service_api_state);
+
+        let index = move |request: HttpRequest| -> FutureResponse {
+            let response = Query::<TransactionsStreamQuery>::from_request(
+                &request,
+                &Default::default(),
+            )
+            .map(|query| {
+                let address = broadcast_server_address(&shared_node_state, &service_api_state);
+                let subscription = SubscriptionType::Transactions {
+                    filter: query
+                        .service_id
+                        .map(|service_id| TransactionFilter::new(service_id, None)),
+                };
+
+                let (sender, receiver) = mpsc::channel(STREAM_BUFFER_SIZE);
+                StreamSession::new(address, subscription, service_api_state.clone(), sender)
+                    .start();
+                let lines = receiver.map_err(|()| error::ErrorInternalServerError("Stream closed"));
+                HttpResponse::Ok()
+                    .content_type(NDJSON_CONTENT_TYPE)
+                    .streaming(lines)
+            });
+            Box::new(response.into_future())
+        };
+
+        backend.raw_handler(RequestHandler {
+            name: name.to_owned(),
+            method: http::Method::GET,
+            inner: Arc::from(index) as Arc<RawHandler>,
+        });
+    }
+
     /// Adds explorer API endpoints to the corresponding scope.
     pub fn wire(
         api_scope: &mut ServiceApiScope,
@@ -338,11 +1428,168 @@ impl ExplorerApi {
             shared_node_state.clone(),
             |_| Ok(SubscriptionType::None),
         );
+        Self::handle_transactions_range("v1/transactions/range", api_scope.web_backend());
+        Self::handle_raw_block("v1/block/raw", api_scope.web_backend());
+        Self::handle_transactions_stream(
+            "v1/transactions/stream",
+            api_scope.web_backend(),
+            service_api_state.clone(),
+            shared_node_state.clone(),
+        );
+        Self::handle_add_transaction("v1/transactions", api_scope.web_backend());
+        let api_workers = ApiWorkers::from_node_state(&shared_node_state).map(Arc::new);
+        let transaction_cache = Arc::new(TransactionCache::new(
+            shared_node_state.transaction_cache_size(),
+        ));
+        let pool_by_service_cache = Arc::new(PoolByServiceCache::default());
         api_scope
-            .endpoint("v1/blocks", Self::blocks)
-            .endpoint("v1/block", Self::block)
-            .endpoint("v1/transactions", Self::transaction_info)
-            .endpoint_mut("v1/transactions", Self::add_transaction)
+            .endpoint(
+                "v1/blocks",
+                Self::with_request_timeout(&api_workers, Self::blocks),
+            )
+            .endpoint(
+                "v1/blocks/rounds",
+                Self::with_request_timeout(&api_workers, Self::blocks_rounds),
+            )
+            .endpoint(
+                "v1/blocks/state_hash",
+                Self::with_request_timeout(&api_workers, Self::block_state_hash),
+            )
+            .endpoint(
+                "v1/blocks/eta",
+                Self::with_request_timeout(&api_workers, Self::block_eta),
+            )
+            .endpoint(
+                "v1/blocks/oldest_available",
+                Self::with_request_timeout(&api_workers, Self::oldest_available),
+            )
+            .endpoint(
+                "v1/block",
+                Self::with_request_timeout(&api_workers, Self::block),
+            )
+            .endpoint(
+                "v1/block/exists",
+                Self::with_request_timeout(&api_workers, Self::block_exists),
+            )
+            .endpoint(
+                "v1/block/latest",
+                Self::with_request_timeout(&api_workers, Self::latest_block),
+            )
+            .endpoint(
+                "v1/block/by_time",
+                Self::with_request_timeout(&api_workers, Self::block_by_time),
+            )
+            .endpoint(
+                "v1/block/votes",
+                Self::with_request_timeout(&api_workers, Self::block_votes),
+            )
+            .endpoint(
+                "v1/genesis",
+                Self::with_request_timeout(&api_workers, Self::genesis),
+            )
+            .endpoint(
+                "v1/transactions",
+                Self::with_request_timeout(&api_workers, move |state, query| {
+                    Self::cached_transaction_info(&transaction_cache, state, query)
+                }),
+            )
+            .endpoint_mut(
+                "v1/transactions/statuses",
+                Self::with_request_timeout(&api_workers, Self::transaction_statuses),
+            )
+            .endpoint(
+                "v1/transactions/proof",
+                Self::with_request_timeout(&api_workers, Self::transaction_proof),
+            )
+            .endpoint(
+                "v1/transactions/by_author",
+                Self::with_request_timeout(&api_workers, Self::transactions_by_author),
+            )
+            .endpoint(
+                "v1/transactions/pending",
+                Self::with_request_timeout(&api_workers, Self::pending_transaction),
+            )
+            .endpoint(
+                "v1/transactions/pool/size",
+                Self::with_request_timeout(&api_workers, Self::transactions_pool_size),
+            )
+            .endpoint(
+                "v1/transactions/pool/by_service",
+                Self::with_request_timeout(&api_workers, move |state, query| {
+                    Self::transactions_pool_by_service(&pool_by_service_cache, state, query)
+                }),
+            )
+            .endpoint(
+                "v1/transactions/pool/delta",
+                move |_state: &ServiceApiState,
+                      query: PoolDeltaQuery|
+                      -> Result<PoolDelta, ApiError> {
+                    Ok(Self::pool_delta(&shared_node_state, query))
+                },
+            )
+    }
+
+    /// Wraps the handler so that it is executed by one of the `workers` threads and aborted
+    /// with `503 Service Unavailable` if it does not complete within the request timeout
+    /// of the workers. The worker itself is not interrupted, the result of the handler
+    /// is discarded after the timeout. Requests exceeding the maximum number of pending
+    /// requests are rejected right away. If there are no workers, the handler is executed
+    /// directly.
+    pub fn with_request_timeout<Q, I, F>(
+        workers: &Option<Arc<ApiWorkers>>,
+        handler: F,
+    ) -> impl for<'r> Fn(&'r ServiceApiState, Q) -> FutureResult<I> + Clone + Send + Sync + 'static
+    where
+        F: for<'r> Fn(&'r ServiceApiState, Q) -> Result<I, ApiError>
+            + Clone
+            + Send
+            + Sync
+            + 'static,
+        Q: Send + 'static,
+        I: Send + 'static,
+    {
+        let workers = workers.clone();
+        move |state: &ServiceApiState, query: Q| -> FutureResult<I> {
+            let workers = match workers {
+                Some(ref workers) => workers,
+                None => return Box::new(handler(state, query).into_future()),
+            };
+
+            let timeout = workers.timeout;
+            let pending_request = match PendingApiRequest::new(workers) {
+                Some(pending_request) => pending_request,
+                None => {
+                    return Box::new(future::err(ApiError::ServiceUnavailable(
+                        "Too many pending requests".to_owned(),
+                    )));
+                }
+            };
+            let (sender, receiver) = oneshot::channel();
+            let (handler, state) = (handler.clone(), state.clone());
+            workers.pool.spawn(future::lazy(move || {
+                let _pending_request = pending_request;
+                // The receiver is dropped if the request has timed out.
+                let _ = sender.send(handler(&state, query));
+                Ok(())
+            }));
+            let response = receiver
+                .map_err(|_| ApiError::InternalError(format_err!("Request handler has panicked")))
+                .and_then(|result| result)
+                .timeout(timeout)
+                .map_err(move |e| {
+                    if e.is_elapsed() {
+                        ApiError::ServiceUnavailable(format!(
+                            "Request has not completed within {:?}",
+                            timeout
+                        ))
+                    } else if e.is_inner() {
+                        e.into_inner().unwrap()
+                    } else {
+                        ApiError::InternalError(format_err!("Request timer error: {}", e))
+                    }
+                });
+            Box::new(response)
+        }
     }
 }
 
@@ -352,17 +1599,232 @@ impl<'a> From<explorer::BlockInfo<'a>> for BlockInfo {
             block: inner.header().clone(),
             precommits: Some(inner.precommits().to_vec()),
             txs: Some(inner.transaction_hashes().to_vec()),
-            time: Some(median_precommits_time(&inner.precommits())),
+            tx_bodies: None,
+            time: Some(block_time(&inner)),
+            signatures_count: Some(inner.precommits().len()),
+            header_only: inner.is_pruned(),
         }
     }
 }
 
-fn median_precommits_time(precommits: &[Signed<Precommit>]) -> DateTime<Utc> {
-    if precommits.is_empty() {
-        UNIX_EPOCH.into()
+/// Idempotency key of a transaction submission scoped by the transaction author.
+type IdempotencyKey = (PublicKey, String);
+
+/// Idempotency keys of the recent transaction submissions.
+#[derive(Debug, Default)]
+struct IdempotencyKeys {
+    entries: HashMap<IdempotencyKey, (TransactionResponse, Instant)>,
+}
+
+impl IdempotencyKeys {
+    fn ttl() -> Duration {
+        Duration::from_secs(IDEMPOTENCY_KEY_TTL)
+    }
+
+    fn get(&mut self, key: &IdempotencyKey) -> Option<TransactionResponse> {
+        match self.entries.get(key) {
+            Some(&(response, submitted_at)) if submitted_at.elapsed() < Self::ttl() => {
+                Some(response)
+            }
+            Some(_) => {
+                self.entries.remove(key);
+                None
+            }
+            None => None,
+        }
+    }
+
+    fn insert(&mut self, key: IdempotencyKey, response: TransactionResponse) {
+        if self.entries.len() >= MAX_IDEMPOTENCY_KEYS {
+            let ttl = Self::ttl();
+            self.entries
+                .retain(|_, &mut (_, submitted_at)| submitted_at.elapsed() < ttl);
+        }
+        if self.entries.len() >= MAX_IDEMPOTENCY_KEYS {
+            let oldest = self
+                .entries
+                .iter()
+                .min_by_key(|(_, &(_, submitted_at))| submitted_at)
+                .map(|(key, _)| key.clone());
+            if let Some(oldest) = oldest {
+                self.entries.remove(&oldest);
+            }
+        }
+        self.entries.insert(key, (response, Instant::now()));
+    }
+}
+
+fn idempotency_key(request: &HttpRequest) -> Result<Option<String>, ApiError> {
+    request
+        .headers()
+        .get(IDEMPOTENCY_KEY_HEADER)
+        .map(|value| {
+            value.to_str().map(ToOwned::to_owned).map_err(|_| {
+                ApiError::BadRequest(format!("Invalid `{}` header", IDEMPOTENCY_KEY_HEADER))
+            })
+        })
+        .transpose()
+}
+
+/// Decodes the transaction from its hex representation.
+fn parse_transaction(query: TransactionHex) -> Result<Signed<RawTransaction>, ApiError> {
+    use crate::events::error::into_failure;
+    use crate::messages::ProtocolMessage;
+
+    let buf: Vec<u8> = ::hex::decode(query.tx_body).map_err(into_failure)?;
+    let signed = SignedMessage::from_raw_buffer(buf)?;
+    let signed = RawTransaction::try_from(Message::deserialize(signed)?)
+        .map_err(|_| format_err!("Couldn't deserialize transaction message."))?;
+    Ok(signed)
+}
+
+/// Extracts the transaction from the request body, which is either a JSON object or
+/// an `application/x-www-form-urlencoded` form.
+fn transaction_hex(
+    request: &HttpRequest,
+) -> Box<dyn Future<Item = TransactionHex, Error = ActixError>> {
+    if request.content_type() == "application/x-www-form-urlencoded" {
+        Box::new(request.urlencoded().from_err())
     } else {
-        let mut times: Vec<_> = precommits.iter().map(|p| p.time()).collect();
-        times.sort();
-        times[times.len() / 2]
+        Box::new(request.json().from_err())
+    }
+}
+
+/// Returns the error for the requests of the transactions unknown to the node.
+fn unknown_transaction() -> ApiError {
+    let description = serde_json::to_string(&json!({ "type": "unknown" })).unwrap();
+    debug!("{}", description);
+    ApiError::NotFound(description)
+}
+
+/// Returns the error for a transaction which is not found, with the location
+/// of the transaction if it is committed, but its body has been pruned.
+fn missing_transaction(state: &ServiceApiState, tx_hash: &Hash) -> ApiError {
+    let snapshot = state.snapshot();
+    match Schema::new(&snapshot).transactions_locations().get(tx_hash) {
+        Some(location) => {
+            let description =
+                serde_json::to_string(&json!({ "type": "pruned", "location": location })).unwrap();
+            debug!("{}", description);
+            ApiError::NotFound(description)
+        }
+        None => unknown_transaction(),
+    }
+}
+
+/// Returns the address of the websocket server broadcasting the node events,
+/// starting the server on the first call.
+fn broadcast_server_address(
+    shared_node_state: &SharedNodeState,
+    service_api_state: &Arc<ServiceApiState>,
+) -> Addr<Server> {
+    shared_node_state
+        .broadcast_server_address(|| Arbiter::start(|_| Server::new(service_api_state.clone())))
+}
+
+fn transactions_range_response(request: &HttpRequest) -> Result<HttpResponse, ActixError> {
+    let query =
+        Query::<TransactionsRangeQuery>::from_request(request, &Default::default())?.into_inner();
+    let blockchain = request.state().blockchain().clone();
+    let height = BlockchainExplorer::new(&blockchain).height();
+    let earliest = query.earliest.unwrap_or(Height(0));
+    let latest = query
+        .latest
+        .map_or(height, |latest| cmp::min(latest, height));
+
+    let transactions = (earliest.0..=latest.0).flat_map(move |height| {
+        BlockchainExplorer::new(&blockchain)
+            .block(Height(height))
+            .map(|block| block.iter().map(TransactionInfo::Committed).collect())
+            .unwrap_or_else(Vec::new)
+    });
+
+    let accepts_ndjson = request
+        .headers()
+        .get(http::header::ACCEPT)
+        .and_then(|value| value.to_str().ok())
+        .map_or(false, |value| value.contains(NDJSON_CONTENT_TYPE));
+    if accepts_ndjson {
+        let lines = transactions
+            .take(query.count.unwrap_or(usize::max_value()))
+            .map(|tx| -> Result<Bytes, ActixError> {
+                let mut line = serde_json::to_vec(&tx)?;
+                line.push(b'\n');
+                Ok(Bytes::from(line))
+            });
+        return Ok(HttpResponse::Ok()
+            .content_type(NDJSON_CONTENT_TYPE)
+            .streaming(stream::iter_result::<_, _, ActixError>(lines)));
+    }
+
+    let count = query.count.unwrap_or(MAX_TRANSACTIONS_PER_REQUEST);
+    if count > MAX_TRANSACTIONS_PER_REQUEST {
+        return Err(ApiError::BadRequest(format!(
+            "Max transaction count per request exceeded ({})",
+            MAX_TRANSACTIONS_PER_REQUEST
+        ))
+        .into());
+    }
+    let transactions = transactions.take(count).collect::<Vec<_>>();
+    Ok(HttpResponse::Ok().json(transactions))
+}
+
+/// Returns the round of the block precommits, i.e., the round at which the block was committed.
+pub(crate) fn precommits_round(precommits: &[Signed<Precommit>]) -> Option<Round> {
+    precommits.first().map(|precommit| precommit.round())
+}
+
+/// Returns the median time of the precommits. If `max_clock_skew` is set, precommit times
+/// exceeding the reference time of the precommits by more than `max_clock_skew`
+/// milliseconds are clamped.
+///
+/// The reference time is the `f + 1`-th earliest precommit time, where `f` is the maximal
+/// number of byzantine precommits among the given ones. Thus, it depends only on the block
+/// itself and lies between the times of honest validators.
+pub(crate) fn median_precommits_time(
+    precommits: &[Signed<Precommit>],
+    max_clock_skew: Option<Milliseconds>,
+) -> DateTime<Utc> {
+    if precommits.is_empty() {
+        return UNIX_EPOCH.into();
     }
+
+    let mut times: Vec<_> = precommits.iter().map(Signed::time).collect();
+    times.sort();
+    if let Some(skew) = max_clock_skew {
+        let reference_time = times[(times.len() - 1) / 3];
+        let latest_time = reference_time + chrono::Duration::milliseconds(skew as i64);
+        for time in &mut times {
+            if *time > latest_time {
+                *time = latest_time;
+            }
+        }
+    }
+    times[times.len() / 2]
+}
+
+#[cfg_attr(feature = "cargo-clippy", allow(clippy::trivially_copy_pass_by_ref))]
+fn is_false(value: &bool) -> bool {
+    !*value
+}
+
+fn block_time(block: &explorer::BlockInfo) -> DateTime<Utc> {
+    median_precommits_time(&block.precommits(), block.consensus_config().max_clock_skew)
+}
+
+/// Returns the bodies of the committed transactions with the given hashes.
+fn transaction_bodies(
+    state: &ServiceApiState,
+    hashes: &[Hash],
+) -> Result<Vec<Signed<RawTransaction>>, ApiError> {
+    let snapshot = state.snapshot();
+    let transactions = Schema::new(&snapshot).transactions();
+    hashes
+        .iter()
+        .map(|hash| {
+            transactions.get(hash).ok_or_else(|| {
+                ApiError::InternalError(format_err!("Transaction body not found: {:?}", hash))
+            })
+        })
+        .collect()
 }
@@ -14,9 +14,22 @@
 
 //! Public system API.
 
-use crate::api::{ServiceApiScope, ServiceApiState};
-use crate::blockchain::{Schema, SharedNodeState};
-use crate::helpers::user_agent;
+use chrono::{DateTime, Utc};
+use exonum_merkledb::Snapshot;
+
+use std::{cmp, collections::BTreeMap};
+
+use super::explorer::median_precommits_time;
+use crate::api::{Error as ApiError, ServiceApiScope, ServiceApiState};
+use crate::blockchain::{
+    ConsensusConfig, Schema, SharedNodeState, StoredConfiguration, ValidatorKeys,
+};
+use crate::crypto::Hash;
+use crate::helpers::{user_agent, Height};
+use crate::messages::PROTOCOL_MAJOR_VERSION;
+
+/// Maximum number of the latest blocks used to calculate the average block time.
+pub const AVERAGE_BLOCK_TIME_WINDOW: u64 = 100;
 
 /// Information about the current state of the node memory pool.
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
@@ -27,6 +40,24 @@ pub struct StatsInfo {
     pub tx_count: u64,
 }
 
+/// Aggregated statistics of the blockchain.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct StatsSummary {
+    /// Height of the latest committed block.
+    pub height: Height,
+    /// Total number of committed transactions.
+    pub tx_count: u64,
+    /// Average interval between the latest blocks in milliseconds, calculated over
+    /// at most `AVERAGE_BLOCK_TIME_WINDOW` blocks. Missing if there are no committed
+    /// blocks besides the genesis one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub average_block_time: Option<u64>,
+    /// Number of services in the blockchain.
+    pub services_count: usize,
+    /// Number of validators in the actual configuration.
+    pub validators_count: usize,
+}
+
 /// Information about whether it is possible to achieve the consensus between
 /// validators in the current state.
 #[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
@@ -47,6 +78,111 @@ pub struct HealthCheckInfo {
     pub consensus_status: ConsensusStatus,
     /// The number of connected peers to the node.
     pub connected_peers: usize,
+    /// Human-readable label of the node, if set in the node configuration.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub node_label: Option<String>,
+    /// Whether the node is ready to serve fresh data, i.e., it has caught up with
+    /// its peers after the start or the API warmup timeout has elapsed.
+    #[serde(default = "default_ready")]
+    pub ready: bool,
+    /// Time elapsed since the start of the node process, in seconds.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub uptime: Option<u64>,
+    /// Time when the node has been started on its database for the first time.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub first_start: Option<DateTime<Utc>>,
+    /// Number of times the node has been restarted on its database.
+    #[serde(default)]
+    pub restarts: u64,
+}
+
+fn default_ready() -> bool {
+    true
+}
+
+/// Information about the software of the node.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct SoftwareInfo {
+    /// Version of the `exonum` crate, the same as in the user agent of the node.
+    pub core_version: String,
+    /// Version of the Exonum protocol.
+    pub protocol_version: u8,
+    /// Hash of the git commit the node was built from, if known at build time.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub git_hash: Option<String>,
+}
+
+/// Query parameters for the configuration activation request.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct ConfigActivationQuery {
+    /// Hash of the configuration.
+    pub hash: Hash,
+}
+
+/// Information about the activation of a configuration.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ConfigActivation {
+    /// Height starting from which the configuration is active.
+    pub height: Height,
+    /// The activated configuration.
+    pub config: StoredConfiguration,
+}
+
+/// Query parameters for the configuration diff request.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct ConfigDiffQuery {
+    /// Hash of the original configuration.
+    pub from: Hash,
+    /// Hash of the configuration to compare the original one with.
+    pub to: Hash,
+}
+
+/// Change of a single configuration parameter.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ParameterChange {
+    /// Value of the parameter in the original configuration, missing if the parameter
+    /// is not set there.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub from: Option<serde_json::Value>,
+    /// Value of the parameter in the compared configuration, missing if the parameter
+    /// is not set there.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub to: Option<serde_json::Value>,
+}
+
+/// Difference between two stored configurations.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ConfigDiff {
+    /// Validators present only in the compared configuration.
+    pub added_validators: Vec<ValidatorKeys>,
+    /// Validators present only in the original configuration.
+    pub removed_validators: Vec<ValidatorKeys>,
+    /// Changed consensus parameters, keyed by the parameter name.
+    pub consensus: BTreeMap<String, ParameterChange>,
+    /// Changed service configurations, keyed by the service name.
+    pub services: BTreeMap<String, ParameterChange>,
+}
+
+impl ConfigDiff {
+    /// Computes the difference between the `from` and `to` configurations.
+    pub fn new(from: &StoredConfiguration, to: &StoredConfiguration) -> Self {
+        let only_in = |keys: &[ValidatorKeys], other: &[ValidatorKeys]| -> Vec<ValidatorKeys> {
+            keys.iter()
+                .filter(|validator| !other.contains(validator))
+                .cloned()
+                .collect()
+        };
+
+        Self {
+            added_validators: only_in(&to.validator_keys, &from.validator_keys),
+            removed_validators: only_in(&from.validator_keys, &to.validator_keys),
+            consensus: changed_parameters(
+                &consensus_parameters(&from.consensus),
+                &consensus_parameters(&to.consensus),
+            ),
+            services: changed_parameters(&from.services, &to.services),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -85,6 +221,21 @@ impl SystemApi {
         self
     }
 
+    fn handle_stats_summary(self, name: &'static str, api_scope: &mut ServiceApiScope) -> Self {
+        api_scope.endpoint(name, move |state: &ServiceApiState, _query: ()| {
+            let snapshot = state.snapshot();
+            let schema = Schema::new(snapshot.as_ref());
+            Ok(StatsSummary {
+                height: schema.height(),
+                tx_count: schema.transactions_len(),
+                average_block_time: average_block_time(&schema),
+                services_count: state.blockchain().service_map().len(),
+                validators_count: schema.actual_configuration().validator_keys.len(),
+            })
+        });
+        self
+    }
+
     fn handle_user_agent_info(self, name: &'static str, api_scope: &mut ServiceApiScope) -> Self {
         api_scope.endpoint(name, move |_state: &ServiceApiState, _query: ()| {
             Ok(user_agent::get())
@@ -92,12 +243,33 @@ impl SystemApi {
         self
     }
 
+    fn handle_software_info(self, name: &'static str, api_scope: &mut ServiceApiScope) -> Self {
+        api_scope.endpoint(name, move |_state: &ServiceApiState, _query: ()| {
+            Ok(SoftwareInfo {
+                core_version: user_agent::exonum_version().to_owned(),
+                protocol_version: PROTOCOL_MAJOR_VERSION,
+                git_hash: user_agent::git_hash().map(ToOwned::to_owned),
+            })
+        });
+        self
+    }
+
     fn handle_healthcheck_info(self, name: &'static str, api_scope: &mut ServiceApiScope) -> Self {
         let self_ = self.clone();
-        api_scope.endpoint(name, move |_state: &ServiceApiState, _query: ()| {
+        api_scope.endpoint(name, move |state: &ServiceApiState, _query: ()| {
+            let snapshot = state.snapshot();
+            let schema = Schema::new(&snapshot);
             Ok(HealthCheckInfo {
                 consensus_status: self.get_consensus_status(),
                 connected_peers: self.get_number_of_connected_peers(),
+                node_label: self.shared_api_state.node_label(),
+                ready: self.shared_api_state.is_ready(),
+                uptime: self
+                    .shared_api_state
+                    .uptime()
+                    .map(|uptime| uptime.as_secs()),
+                first_start: schema.node_first_start(),
+                restarts: schema.node_restarts(),
             })
         });
         self_
@@ -123,6 +295,54 @@ impl SystemApi {
         self
     }
 
+    fn handle_config_activation(self, name: &'static str, api_scope: &mut ServiceApiScope) -> Self {
+        api_scope.endpoint(
+            name,
+            move |state: &ServiceApiState,
+                  query: ConfigActivationQuery|
+                  -> Result<ConfigActivation, ApiError> {
+                let snapshot = state.snapshot();
+                let schema = Schema::new(&snapshot);
+                // Committed configurations which `actual_from` height has not been
+                // reached yet are not active.
+                let next_height = schema.height().next();
+                schema
+                    .configuration_by_hash(&query.hash)
+                    .filter(|config| config.actual_from <= next_height)
+                    .map(|config| ConfigActivation {
+                        height: config.actual_from,
+                        config,
+                    })
+                    .ok_or_else(|| {
+                        ApiError::NotFound(format!(
+                            "Configuration with hash {:?} has not been activated",
+                            query.hash
+                        ))
+                    })
+            },
+        );
+        self
+    }
+
+    fn handle_config_diff(self, name: &'static str, api_scope: &mut ServiceApiScope) -> Self {
+        api_scope.endpoint(
+            name,
+            move |state: &ServiceApiState,
+                  query: ConfigDiffQuery|
+                  -> Result<ConfigDiff, ApiError> {
+                let snapshot = state.snapshot();
+                let schema = Schema::new(&snapshot);
+                let config = |hash: &Hash| {
+                    schema.configuration_by_hash(hash).ok_or_else(|| {
+                        ApiError::NotFound(format!("Configuration with hash {:?} not found", hash))
+                    })
+                };
+                Ok(ConfigDiff::new(&config(&query.from)?, &config(&query.to)?))
+            },
+        );
+        self
+    }
+
     fn get_number_of_connected_peers(&self) -> usize {
         let in_conn = self.shared_api_state.incoming_connections().len();
         let out_conn = self.shared_api_state.outgoing_connections().len();
@@ -146,9 +366,65 @@ impl SystemApi {
     /// Adds public system API endpoints to the corresponding scope.
     pub fn wire(self, api_scope: &mut ServiceApiScope) -> &mut ServiceApiScope {
         self.handle_stats_info("v1/stats", api_scope)
+            .handle_stats_summary("v1/stats/summary", api_scope)
             .handle_healthcheck_info("v1/healthcheck", api_scope)
             .handle_user_agent_info("v1/user_agent", api_scope)
-            .handle_list_services_info("v1/services", api_scope);
+            .handle_software_info("v1/info", api_scope)
+            .handle_list_services_info("v1/services", api_scope)
+            .handle_config_activation("v1/config/activation", api_scope)
+            .handle_config_diff("v1/config/diff", api_scope);
         api_scope
     }
 }
+
+/// Returns the average interval in milliseconds between the latest committed blocks,
+/// based on the median time of their precommits. The genesis block has no precommits,
+/// so it is not taken into account.
+fn average_block_time(schema: &Schema<&dyn Snapshot>) -> Option<u64> {
+    let latest = schema.height();
+    let earliest = Height(cmp::max(
+        1,
+        latest.0.saturating_sub(AVERAGE_BLOCK_TIME_WINDOW),
+    ));
+    if latest <= earliest {
+        return None;
+    }
+    let block_time = |height| {
+        let block_hash = schema.block_hash_by_height(height)?;
+        let precommits = schema.precommits(&block_hash).iter().collect::<Vec<_>>();
+        let max_clock_skew = schema
+            .configuration_by_height(height)
+            .consensus
+            .max_clock_skew;
+        Some(median_precommits_time(&precommits, max_clock_skew))
+    };
+    let elapsed = block_time(latest)? - block_time(earliest)?;
+    let elapsed = cmp::max(elapsed.num_milliseconds(), 0) as u64;
+    Some(elapsed / (latest.0 - earliest.0))
+}
+
+/// Returns the consensus parameters keyed by their names in the JSON representation.
+fn consensus_parameters(consensus: &ConsensusConfig) -> BTreeMap<String, serde_json::Value> {
+    match serde_json::to_value(consensus) {
+        Ok(serde_json::Value::Object(params)) => params.into_iter().collect(),
+        _ => BTreeMap::new(),
+    }
+}
+
+/// Returns the parameters which values differ in the `from` and `to` maps.
+fn changed_parameters(
+    from: &BTreeMap<String, serde_json::Value>,
+    to: &BTreeMap<String, serde_json::Value>,
+) -> BTreeMap<String, ParameterChange> {
+    from.keys()
+        .chain(to.keys())
+        .filter(|name| from.get(*name) != to.get(*name))
+        .map(|name| {
+            let change = ParameterChange {
+                from: from.get(name).cloned(),
+                to: to.get(name).cloned(),
+            };
+            (name.clone(), change)
+        })
+        .collect()
+}
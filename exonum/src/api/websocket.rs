@@ -19,9 +19,10 @@ use actix_web::ws;
 
 use rand::{rngs::ThreadRng, Rng};
 
-use futures::Future;
+use bytes::Bytes;
+use futures::{sync::mpsc, Future};
 
-use log::error;
+use log::{error, warn};
 
 use std::{
     cell::RefCell,
@@ -36,7 +37,7 @@ use crate::api::{
 use crate::blockchain::{Block, Schema, TransactionResult, TxLocation};
 use crate::crypto::Hash;
 use crate::events::error::into_failure;
-use crate::explorer::TxStatus;
+use crate::explorer::{BlockchainExplorer, TxStatus};
 use crate::messages::{Message as ExonumMessage, ProtocolMessage, RawTransaction, SignedMessage};
 
 use exonum_merkledb::{IndexAccess, ListProof, Snapshot};
@@ -345,14 +346,14 @@ impl Handler<Transaction> for Server {
     ) -> Self::Result {
         let buf: Vec<u8> = hex::decode(tx.tx_body).map_err(into_failure)?;
         let signed = SignedMessage::from_raw_buffer(buf)?;
-        let tx_hash = signed.hash();
         let signed = RawTransaction::try_from(ExonumMessage::deserialize(signed)?)
             .map_err(|_| format_err!("Couldn't deserialize transaction message."))?;
+        let response = TransactionResponse::new(&signed);
         let _ = self
             .service_api_state
             .sender()
             .broadcast_transaction(signed);
-        Ok(TransactionResponse { tx_hash })
+        Ok(response)
     }
 }
 
@@ -480,6 +481,99 @@ impl Handler<Message> for Session {
     }
 }
 
+/// Maximal number of lines waiting to be sent to a client of the transactions stream.
+/// The client is disconnected if it falls further behind.
+pub(crate) const STREAM_BUFFER_SIZE: usize = 1_024;
+
+/// Client of the `Server` forwarding committed transactions into an HTTP response
+/// stream as newline-delimited JSON.
+pub(crate) struct StreamSession {
+    server_address: Addr<Server>,
+    subscription: SubscriptionType,
+    service_api_state: Arc<ServiceApiState>,
+    sender: mpsc::Sender<Bytes>,
+    id: u64,
+}
+
+impl StreamSession {
+    pub fn new(
+        server_address: Addr<Server>,
+        subscription: SubscriptionType,
+        service_api_state: Arc<ServiceApiState>,
+        sender: mpsc::Sender<Bytes>,
+    ) -> Self {
+        Self {
+            server_address,
+            subscription,
+            service_api_state,
+            sender,
+            id: 0,
+        }
+    }
+
+    fn transaction_line(&self, tx_hash: &Hash) -> Option<Bytes> {
+        let info =
+            BlockchainExplorer::new(self.service_api_state.blockchain()).transaction(tx_hash)?;
+        let mut line = serde_json::to_vec(&info).ok()?;
+        line.push(b'\n');
+        Some(Bytes::from(line))
+    }
+}
+
+impl Actor for StreamSession {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        let address: Recipient<_> = ctx.address().recipient();
+        self.server_address
+            .send(Subscribe {
+                address,
+                subscriptions: vec![self.subscription.clone()],
+            })
+            .into_actor(self)
+            .then(|response, actor, context| {
+                match response {
+                    Ok(result) => {
+                        actor.id = result;
+                    }
+                    _ => context.stop(),
+                }
+                fut::ok(())
+            })
+            .wait(ctx);
+    }
+
+    fn stopping(&mut self, _ctx: &mut Self::Context) -> Running {
+        self.server_address.do_send(Unsubscribe { id: self.id });
+        Running::Stop
+    }
+}
+
+impl Handler<Message> for StreamSession {
+    type Result = ();
+
+    fn handle(&mut self, msg: Message, ctx: &mut Self::Context) {
+        match msg {
+            Message::Data(x) => {
+                let tx_hash = match serde_json::from_str(&x) {
+                    Ok(Notification::Transaction(summary)) => summary.tx_hash,
+                    _ => return,
+                };
+                if let Some(line) = self.transaction_line(&tx_hash) {
+                    // The receiver is dropped once the client disconnects.
+                    if let Err(e) = self.sender.try_send(line) {
+                        if e.is_full() {
+                            warn!("Transactions stream client falls behind, disconnecting");
+                        }
+                        ctx.stop();
+                    }
+                }
+            }
+            Message::Close => ctx.stop(),
+        }
+    }
+}
+
 #[serde(tag = "result", rename_all = "kebab-case")]
 #[derive(Debug, Serialize, Deserialize)]
 enum WsStatus {
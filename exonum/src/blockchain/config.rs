@@ -31,6 +31,9 @@ use crate::helpers::{Height, Milliseconds};
 use crate::messages::EMPTY_SIGNED_MESSAGE_SIZE;
 use exonum_merkledb::{BinaryValue, ObjectHash};
 
+/// Default voting weight of a validator.
+pub const DEFAULT_VALIDATOR_WEIGHT: u64 = 1;
+
 /// Public keys of a validator. Each validator has two public keys: the
 /// `consensus_key` is used for internal operations in the consensus process,
 /// while the `service_key` is used in services.
@@ -41,6 +44,26 @@ pub struct ValidatorKeys {
     /// Service key is used for services, for example, the configuration
     /// updater service, the anchoring service, etc.
     pub service_key: PublicKey,
+    /// Voting weight (stake) of the validator. The consensus requires votes from validators
+    /// with more than 2/3 of the total weight. If all the validators have equal weights,
+    /// this is the same as requiring votes from more than 2/3 of the validators.
+    ///
+    /// The weight is not serialized if it equals `DEFAULT_VALIDATOR_WEIGHT`, so that
+    /// configurations with equal weights are serialized as before.
+    #[serde(
+        default = "default_validator_weight",
+        skip_serializing_if = "is_default_validator_weight"
+    )]
+    pub weight: u64,
+}
+
+fn default_validator_weight() -> u64 {
+    DEFAULT_VALIDATOR_WEIGHT
+}
+
+#[cfg_attr(feature = "cargo-clippy", allow(clippy::trivially_copy_pass_by_ref))]
+fn is_default_validator_weight(weight: &u64) -> bool {
+    *weight == DEFAULT_VALIDATOR_WEIGHT
 }
 
 /// Exonum blockchain global configuration. Services
@@ -121,6 +144,46 @@ pub struct ConsensusConfig {
     /// in a block if the transaction pool is almost empty, and create blocks faster when there are
     /// enough transactions in the pool.
     pub propose_timeout_threshold: u32,
+    /// Minimal number of validators, including the node itself, which should be
+    /// connected to the node for it to take part in the consensus. If fewer validators
+    /// are online, the node stops proposing and voting until enough validators reconnect.
+    /// The check is disabled if the value is not set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_online_validators: Option<usize>,
+    /// Minimal total weight of the validators, including the node itself, which should be
+    /// connected to the node for it to take part in the consensus. If the online validators
+    /// have a smaller weight, the node stops proposing and voting until enough validators
+    /// reconnect. The check is disabled if the value is not set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_online_weight: Option<u64>,
+    /// Maximal allowed skew of the validator clocks, in milliseconds. Precommit times
+    /// exceeding the reference time of the block precommits by more than this value are
+    /// clamped when calculating the median time of the block precommits. The reference
+    /// time is determined by the precommits themselves, so the block time does not change
+    /// over time. Precommits are not rejected. Times are not clamped if the value is not set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_clock_skew: Option<Milliseconds>,
+    /// Maximal total size of transactions in a block, in bytes. The proposer skips
+    /// transactions which would make the total serialized size exceed this value, even
+    /// if `txs_block_limit` is not reached. The size is not limited if the value
+    /// is not set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_block_bytes: Option<u32>,
+    /// Maximal rate of block production, in blocks per second. The proposer delays
+    /// its proposal so that blocks are committed no more often than once in
+    /// `1 / max_blocks_per_second` seconds, even if the transaction pool is full.
+    /// This trades throughput for the ability of auditors to keep up with validators.
+    /// The rate is not limited if the value is not set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_blocks_per_second: Option<f64>,
+    /// Maximal number of transactions in a propose. Proposes with more transactions
+    /// are rejected by the validators and are not relayed to other peers; the proposer
+    /// itself does not include more transactions into its proposes. Unlike
+    /// `txs_block_limit`, which only restricts the proposer, this bound protects the
+    /// node from oversized proposes crafted by a malicious leader. The number of
+    /// transactions is not checked if the value is not set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_propose_txs: Option<u32>,
 }
 
 impl ConsensusConfig {
@@ -130,6 +193,13 @@ impl ConsensusConfig {
     /// Time that will be added to round timeout for each next round in terms of percent of first_round_timeout.
     pub const TIMEOUT_LINEAR_INCREASE_PERCENT: u64 = 10; //default value 10%
 
+    /// Returns the minimal interval between blocks in milliseconds implied
+    /// by `max_blocks_per_second`, or `None` if the block rate is not limited.
+    pub fn min_block_interval(&self) -> Option<Milliseconds> {
+        self.max_blocks_per_second
+            .map(|rate| (1000.0 / rate).ceil() as Milliseconds)
+    }
+
     /// Produces warnings if configuration contains non-optimal values.
     ///
     /// Validation for logical correctness is performed in the `StoredConfiguration::try_deserialize`
@@ -176,6 +246,12 @@ impl Default for ConsensusConfig {
             min_propose_timeout: 10,
             max_propose_timeout: 200,
             propose_timeout_threshold: 500,
+            min_online_validators: None,
+            min_online_weight: None,
+            max_clock_skew: None,
+            max_block_bytes: None,
+            max_blocks_per_second: None,
+            max_propose_txs: None,
         }
     }
 }
@@ -210,6 +286,18 @@ impl StoredConfiguration {
             }
         }
 
+        // Check validator weights.
+        if config.validator_keys.iter().any(|k| k.weight == 0) {
+            return Err(JsonError::custom(
+                "Validator weight should not be equal to zero",
+            ));
+        }
+        let total_weight = config
+            .validator_keys
+            .iter()
+            .try_fold(0_u64, |total, k| total.checked_add(k.weight))
+            .ok_or_else(|| JsonError::custom("Total weight of validators overflows u64"))?;
+
         // Check timeouts.
         if config.consensus.min_propose_timeout > config.consensus.max_propose_timeout {
             return Err(JsonError::custom(format!(
@@ -226,6 +314,25 @@ impl StoredConfiguration {
             )));
         }
 
+        if let Some(min_online_validators) = config.consensus.min_online_validators {
+            if min_online_validators > config.validator_keys.len() {
+                return Err(JsonError::custom(format!(
+                    "min_online_validators ({}) must not exceed the number of validators ({})",
+                    min_online_validators,
+                    config.validator_keys.len()
+                )));
+            }
+        }
+
+        if let Some(min_online_weight) = config.consensus.min_online_weight {
+            if min_online_weight > total_weight {
+                return Err(JsonError::custom(format!(
+                    "min_online_weight ({}) must not exceed the total weight of validators ({})",
+                    min_online_weight, total_weight
+                )));
+            }
+        }
+
         // Check transactions limit.
         if config.consensus.txs_block_limit == 0 {
             return Err(JsonError::custom(
@@ -233,6 +340,36 @@ impl StoredConfiguration {
             ));
         }
 
+        if config.consensus.max_block_bytes == Some(0) {
+            return Err(JsonError::custom(
+                "max_block_bytes should not be equal to zero",
+            ));
+        }
+
+        if config.consensus.max_propose_txs == Some(0) {
+            return Err(JsonError::custom(
+                "max_propose_txs should not be equal to zero",
+            ));
+        }
+
+        if let Some(rate) = config.consensus.max_blocks_per_second {
+            if !(rate.is_finite() && rate > 0.0) {
+                return Err(JsonError::custom(format!(
+                    "max_blocks_per_second ({}) must be a positive number",
+                    rate
+                )));
+            }
+        }
+        if let Some(interval) = config.consensus.min_block_interval() {
+            if config.consensus.first_round_timeout <= interval {
+                return Err(JsonError::custom(format!(
+                    "first_round_timeout({}) must be strictly larger than the minimal block \
+                     interval({}) implied by max_blocks_per_second",
+                    config.consensus.first_round_timeout, interval
+                )));
+            }
+        }
+
         // Check maximum message length for sanity.
         if config.consensus.max_message_len < MINIMAL_MESSAGE_LENGTH {
             return Err(JsonError::custom(format!(
@@ -325,10 +462,38 @@ mod tests {
         configuration.validator_keys.push(ValidatorKeys {
             consensus_key: PublicKey::zero(),
             service_key: PublicKey::zero(),
+            weight: DEFAULT_VALIDATOR_WEIGHT,
         });
         serialize_deserialize(&configuration);
     }
 
+    #[test]
+    #[should_panic(expected = "Validator weight should not be equal to zero")]
+    fn zero_validator_weight() {
+        let mut configuration = create_test_configuration();
+        configuration.validator_keys[0].weight = 0;
+        serialize_deserialize(&configuration);
+    }
+
+    #[test]
+    #[should_panic(expected = "Total weight of validators overflows u64")]
+    fn validator_weights_overflow() {
+        let mut configuration = create_test_configuration();
+        configuration.validator_keys[0].weight = u64::max_value();
+        configuration.validator_keys[1].weight = 1;
+        serialize_deserialize(&configuration);
+    }
+
+    #[test]
+    fn default_validator_weight_is_not_serialized() {
+        let mut configuration = create_test_configuration();
+        let serialized = String::from_utf8(configuration.try_serialize().unwrap()).unwrap();
+        assert!(!serialized.contains("weight"));
+
+        configuration.validator_keys[0].weight = 5;
+        assert_eq!(configuration, serialize_deserialize(&configuration));
+    }
+
     #[test]
     #[should_panic(expected = "Invalid propose timeouts: min_propose_timeout should be less or")]
     fn min_max_propose_timeouts() {
@@ -357,6 +522,38 @@ mod tests {
         serialize_deserialize(&configuration);
     }
 
+    #[test]
+    #[should_panic(expected = "max_block_bytes should not be equal to zero")]
+    fn invalid_max_block_bytes() {
+        let mut configuration = create_test_configuration();
+        configuration.consensus.max_block_bytes = Some(0);
+        serialize_deserialize(&configuration);
+    }
+
+    #[test]
+    #[should_panic(expected = "max_propose_txs should not be equal to zero")]
+    fn invalid_max_propose_txs() {
+        let mut configuration = create_test_configuration();
+        configuration.consensus.max_propose_txs = Some(0);
+        serialize_deserialize(&configuration);
+    }
+
+    #[test]
+    #[should_panic(expected = "max_blocks_per_second (0) must be a positive number")]
+    fn invalid_max_blocks_per_second() {
+        let mut configuration = create_test_configuration();
+        configuration.consensus.max_blocks_per_second = Some(0.0);
+        serialize_deserialize(&configuration);
+    }
+
+    #[test]
+    #[should_panic(expected = "must be strictly larger than the minimal block interval")]
+    fn too_low_max_blocks_per_second() {
+        let mut configuration = create_test_configuration();
+        configuration.consensus.max_blocks_per_second = Some(0.1);
+        serialize_deserialize(&configuration);
+    }
+
     #[test]
     #[should_panic(expected = "max_message_len (128) must be at least")]
     fn too_small_max_message_len() {
@@ -365,11 +562,32 @@ mod tests {
         serialize_deserialize(&configuration);
     }
 
+    #[test]
+    #[should_panic(
+        expected = "min_online_validators (4) must not exceed the number of validators (3)"
+    )]
+    fn too_large_min_online_validators() {
+        let mut configuration = create_test_configuration();
+        configuration.consensus.min_online_validators = Some(4);
+        serialize_deserialize(&configuration);
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "min_online_weight (4) must not exceed the total weight of validators (3)"
+    )]
+    fn too_large_min_online_weight() {
+        let mut configuration = create_test_configuration();
+        configuration.consensus.min_online_weight = Some(4);
+        serialize_deserialize(&configuration);
+    }
+
     fn create_test_configuration() -> StoredConfiguration {
         let validator_keys = (1..4)
             .map(|i| ValidatorKeys {
                 consensus_key: gen_keypair_from_seed(&Seed::new([i; SEED_LENGTH])).0,
                 service_key: gen_keypair_from_seed(&Seed::new([i * 10; SEED_LENGTH])).0,
+                weight: DEFAULT_VALIDATOR_WEIGHT,
             })
             .collect();
 
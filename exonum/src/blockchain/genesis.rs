@@ -12,7 +12,12 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use exonum_merkledb::{BinaryValue, ObjectHash};
+
+use std::borrow::Cow;
+
 use super::config::{ConsensusConfig, ValidatorKeys};
+use crate::crypto::Hash;
 
 /// The initial configuration which is committed into the genesis block.
 ///
@@ -47,3 +52,15 @@ impl GenesisConfig {
         }
     }
 }
+
+impl_object_hash_for_binary_value! { GenesisConfig }
+
+impl BinaryValue for GenesisConfig {
+    fn to_bytes(&self) -> Vec<u8> {
+        serde_json::to_vec(self).expect("Unable to serialize genesis configuration")
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Result<Self, failure::Error> {
+        serde_json::from_slice(bytes.as_ref()).map_err(Into::into)
+    }
+}
@@ -33,10 +33,15 @@
 
 pub use self::{
     block::{Block, BlockProof},
-    config::{ConsensusConfig, StoredConfiguration, ValidatorKeys},
+    config::{ConsensusConfig, StoredConfiguration, ValidatorKeys, DEFAULT_VALIDATOR_WEIGHT},
     genesis::GenesisConfig,
     schema::{Schema, TxLocation},
-    service::{Service, ServiceContext, SharedNodeState},
+    service::{
+        ChannelStats, ConsensusLogEntry, ConsensusState, PeerLatency, PoolDelta,
+        RejectedTransaction, RoundSchedule, RoundTiming, Service, ServiceContext, SharedNodeState,
+        TimeoutDrift, TxNonce, POOL_CHANGES_CAPACITY, REJECTED_TRANSACTIONS_CAPACITY,
+        ROUND_SCHEDULE_LENGTH,
+    },
     transaction::{
         ExecutionError, ExecutionResult, Transaction, TransactionContext, TransactionError,
         TransactionErrorType, TransactionMessage, TransactionResult, TransactionSet,
@@ -46,16 +51,23 @@ pub use self::{
 pub mod config;
 
 use byteorder::{ByteOrder, LittleEndian};
+use chrono::{DateTime, Utc};
 
 use std::{
-    collections::{BTreeMap, HashMap},
-    fmt, iter, mem, panic,
-    sync::Arc,
+    cmp,
+    collections::{BTreeMap, HashMap, VecDeque},
+    fmt, mem, panic,
+    sync::{
+        mpsc::{sync_channel, SyncSender},
+        Arc, Mutex,
+    },
+    thread,
+    time::Duration,
 };
 
 use crate::crypto::{self, CryptoHash, Hash, PublicKey, SecretKey};
 use crate::helpers::{Height, Round, ValidatorId};
-use crate::messages::{Connect, Message, Precommit, ProtocolMessage, RawTransaction, Signed};
+use crate::messages::{Connect, Message, Precommit, RawTransaction, Signed};
 use crate::node::ApiSender;
 use exonum_merkledb::{
     self, Database, Error as StorageError, Fork, IndexAccess, ObjectHash, Patch,
@@ -74,6 +86,15 @@ mod tests;
 /// Id of core service table family.
 pub const CORE_SERVICE: u16 = 0;
 
+/// Maximal number of the latest blocks which can be reverted by `Blockchain::rollback_to`.
+/// The reverting patches of the older blocks are discarded.
+#[cfg(feature = "rollback")]
+pub const MAX_ROLLBACK_BLOCKS: usize = 1_000;
+
+/// Maximal number of blocks pruned after a single commit. If pruning is enabled for a long
+/// blockchain, the old blocks are pruned gradually over the subsequent commits.
+pub const MAX_PRUNED_BLOCKS_PER_COMMIT: u64 = 100;
+
 /// Exonum blockchain instance with a certain services set and data storage.
 ///
 /// Only nodes with an identical set of services and genesis block can be combined
@@ -81,9 +102,35 @@ pub const CORE_SERVICE: u16 = 0;
 pub struct Blockchain {
     db: Arc<dyn Database>,
     service_map: Arc<HashMap<u16, Box<dyn Service>>>,
+    service_order: Arc<Vec<u16>>,
     #[doc(hidden)]
     pub service_keypair: (PublicKey, SecretKey),
     pub(crate) api_sender: ApiSender,
+    // Number of additional attempts to write a committed block and the delay between them.
+    commit_retries: u32,
+    commit_retry_delay: Duration,
+    // Patches reverting the blocks committed by this instance, from the oldest to the newest.
+    #[cfg(feature = "rollback")]
+    rollback_log: Arc<Mutex<VecDeque<Patch>>>,
+    // Background computation of the service state hashes, if enabled.
+    state_hash_precomputer: Option<StateHashPrecomputer>,
+}
+
+/// Service state hashes computed for the state after the block with the given hash.
+#[derive(Debug, Default)]
+struct StateHashCache {
+    block_hash: Hash,
+    hashes: HashMap<u16, Vec<Hash>>,
+}
+
+/// Handle of the thread computing the state hashes of the services opted into
+/// the precomputation. The thread stops once all the handles are dropped.
+#[derive(Clone)]
+struct StateHashPrecomputer {
+    cache: Arc<Mutex<StateHashCache>>,
+    // Prefixes of the index names the state hash of each opted-in service depends on.
+    index_prefixes: Arc<HashMap<u16, Vec<String>>>,
+    requests: SyncSender<()>,
 }
 
 impl Blockchain {
@@ -96,6 +143,7 @@ impl Blockchain {
         api_sender: ApiSender,
     ) -> Self {
         let mut service_map = HashMap::new();
+        let mut service_order = Vec::with_capacity(services.len());
         for service in services {
             let id = service.service_id();
             if service_map.contains_key(&id) {
@@ -105,16 +153,81 @@ impl Blockchain {
                 );
             }
             service_map.insert(id, service);
+            service_order.push(id);
         }
 
         Self {
             db: storage.into(),
             service_map: Arc::new(service_map),
+            service_order: Arc::new(service_order),
             service_keypair: (service_public_key, service_secret_key),
             api_sender,
+            commit_retries: 0,
+            commit_retry_delay: Duration::default(),
+            #[cfg(feature = "rollback")]
+            rollback_log: Arc::default(),
+            state_hash_precomputer: None,
         }
     }
 
+    /// Sets the number of additional attempts to write a committed block to the storage
+    /// if the write fails, and the delay between the attempts.
+    pub fn set_commit_retries(&mut self, retries: u32, delay: Duration) {
+        self.commit_retries = retries;
+        self.commit_retry_delay = delay;
+    }
+
+    /// Enables precomputation of the service state hashes. After each committed block,
+    /// the state hashes are computed on a background thread, and the next block reuses
+    /// the hashes of the services whose indices it does not change.
+    ///
+    /// Only the services declaring the indices their state hash depends on via
+    /// `Service::state_hash_index_prefixes` take part in the precomputation; the state
+    /// hashes of the other services are computed for each block as usual.
+    pub fn enable_state_hash_precomputation(&mut self) {
+        if self.state_hash_precomputer.is_some() {
+            return;
+        }
+
+        let index_prefixes: HashMap<_, _> = self
+            .service_map
+            .iter()
+            .filter_map(|(service_id, service)| {
+                service
+                    .state_hash_index_prefixes()
+                    .map(|prefixes| (*service_id, prefixes))
+            })
+            .collect();
+        let cache = Arc::<Mutex<StateHashCache>>::default();
+        let index_prefixes = Arc::new(index_prefixes);
+        // A single pending request is enough, since only the latest block is of interest.
+        let (requests, receiver) = sync_channel(1);
+
+        let db = Arc::clone(&self.db);
+        let service_map = Arc::clone(&self.service_map);
+        let worker_cache = Arc::clone(&cache);
+        let worker_prefixes = Arc::clone(&index_prefixes);
+        thread::Builder::new()
+            .name("state-hash-precomputer".to_owned())
+            .spawn(move || {
+                for () in receiver {
+                    update_state_hash_cache(
+                        db.as_ref(),
+                        &service_map,
+                        &worker_prefixes,
+                        &worker_cache,
+                    );
+                }
+            })
+            .expect("Unable to spawn the state hash precomputation thread");
+
+        self.state_hash_precomputer = Some(StateHashPrecomputer {
+            cache,
+            index_prefixes,
+            requests,
+        });
+    }
+
     /// Recreates the blockchain to reuse with a sandbox.
     #[doc(hidden)]
     pub fn clone_with_api_sender(&self, api_sender: ApiSender) -> Self {
@@ -155,12 +268,33 @@ impl Blockchain {
         service.tx_from_raw(raw)
     }
 
+    /// Returns the creation time of the transaction as reported by its service.
+    pub(crate) fn tx_timestamp(&self, raw: &RawTransaction) -> Option<DateTime<Utc>> {
+        self.service_map
+            .get(&raw.service_id())
+            .and_then(|service| service.tx_timestamp(raw))
+    }
+
+    /// Returns the nonce of the transaction as reported by its service.
+    pub(crate) fn tx_nonce(&self, tx: &Signed<RawTransaction>) -> Option<TxNonce> {
+        let snapshot = self.snapshot();
+        self.service_map
+            .get(&tx.payload().service_id())
+            .and_then(|service| service.tx_nonce(snapshot.as_ref(), tx))
+    }
+
     /// Commits changes from the patch to the blockchain storage.
     /// See [`Fork`](../../exonum_merkledb/struct.Fork.html) for details.
     pub fn merge(&mut self, patch: Patch) -> StorageResult<()> {
         self.db.merge(patch)
     }
 
+    /// Explicitly triggers compaction of the blockchain storage.
+    /// This method blocks until the compaction is finished.
+    pub fn compact_database(&self) -> StorageResult<()> {
+        self.db.compact()
+    }
+
     /// Returns the hash of the latest committed block.
     ///
     /// # Panics
@@ -200,8 +334,8 @@ impl Blockchain {
         let mut config_propose = StoredConfiguration {
             previous_cfg_hash: Hash::zero(),
             actual_from: Height::zero(),
-            validator_keys: cfg.validator_keys,
-            consensus: cfg.consensus,
+            validator_keys: cfg.validator_keys.clone(),
+            consensus: cfg.consensus.clone(),
             services: BTreeMap::new(),
         };
 
@@ -226,6 +360,7 @@ impl Blockchain {
                     // TODO create genesis block for MemoryDB and compare it hash with zero block. (ECR-1630)
                     return Ok(());
                 }
+                schema.genesis_config_entry().set(cfg);
                 schema.commit_configuration(config_propose);
             };
             self.merge(fork.into_patch())?;
@@ -307,6 +442,8 @@ impl Blockchain {
             }
 
             // Get tx & state hash.
+            fork.flush();
+            let mut cached_state_hashes = self.cached_state_hashes(&fork);
             let (tx_hash, state_hash) = {
                 let state_hashes = {
                     let schema = Schema::new(&fork);
@@ -321,7 +458,9 @@ impl Blockchain {
 
                     for service in self.service_map.values() {
                         let service_id = service.service_id();
-                        let vec_service_state = service.state_hash((&fork).snapshot());
+                        let vec_service_state = cached_state_hashes
+                            .remove(&service_id)
+                            .unwrap_or_else(|| service.state_hash((&fork).snapshot()));
                         for (idx, service_table_hash) in vec_service_state.into_iter().enumerate() {
                             let key = Self::service_table_unique_key(service_id, idx);
                             state_hashes.push((key, service_table_hash));
@@ -370,6 +509,94 @@ impl Blockchain {
         (block_hash, fork.into_patch())
     }
 
+    /// Returns the precomputed state hashes of the services whose indices are not changed
+    /// in the given fork.
+    fn cached_state_hashes(&self, fork: &Fork) -> HashMap<u16, Vec<Hash>> {
+        let precomputer = match self.state_hash_precomputer {
+            Some(ref precomputer) => precomputer,
+            None => return HashMap::new(),
+        };
+        let cache = precomputer
+            .cache
+            .lock()
+            .expect("Expected lock on state hash cache");
+        if cache.block_hash != self.last_hash() {
+            return HashMap::new();
+        }
+
+        cache
+            .hashes
+            .iter()
+            .filter(|(service_id, _)| {
+                let prefixes = &precomputer.index_prefixes[*service_id];
+                !fork.changed_indices().any(|name| {
+                    prefixes
+                        .iter()
+                        .any(|prefix| name.starts_with(prefix.as_str()))
+                })
+            })
+            .map(|(service_id, hashes)| (*service_id, hashes.clone()))
+            .collect()
+    }
+
+    /// Requests the computation of the state hashes of the services for the latest
+    /// committed block on the background thread, if the precomputation is enabled.
+    fn precompute_state_hashes(&self) {
+        if let Some(ref precomputer) = self.state_hash_precomputer {
+            // If a request is already pending, it covers the latest block as well.
+            let _ = precomputer.requests.try_send(());
+        }
+    }
+
+    /// Computes the state hashes of the services for the latest committed block
+    /// on the current thread, if the precomputation is enabled.
+    #[cfg(test)]
+    fn update_state_hash_cache(&self) {
+        if let Some(ref precomputer) = self.state_hash_precomputer {
+            update_state_hash_cache(
+                self.db.as_ref(),
+                &self.service_map,
+                &precomputer.index_prefixes,
+                &precomputer.cache,
+            );
+        }
+    }
+
+    /// Loads the transaction with the given hash from the database and parses it
+    /// with the corresponding service. Returns the parsed transaction, its message
+    /// and the name of the service.
+    fn load_transaction(
+        &self,
+        fork: &Fork,
+        tx_hash: Hash,
+    ) -> Result<(Box<dyn Transaction>, Signed<RawTransaction>, &str), failure::Error> {
+        let schema = Schema::new(fork);
+
+        let raw = schema.transactions().get(&tx_hash).ok_or_else(|| {
+            failure::err_msg(format!(
+                "BUG: Cannot find transaction in database. tx: {:?}",
+                tx_hash
+            ))
+        })?;
+
+        let service_name = self
+            .service_map
+            .get(&raw.service_id())
+            .ok_or_else(|| {
+                failure::err_msg(format!(
+                    "Service not found. Service id: {}",
+                    raw.service_id()
+                ))
+            })?
+            .service_name();
+
+        let tx = self.tx_from_raw(raw.payload().clone()).map_err(|error| {
+            format_err!("Service <{}>: {}, tx: {:?}", service_name, error, tx_hash)
+        })?;
+
+        Ok((tx, raw, service_name))
+    }
+
     fn execute_transaction(
         &self,
         tx_hash: Hash,
@@ -377,35 +604,7 @@ impl Blockchain {
         index: usize,
         fork: &mut Fork,
     ) -> Result<(), failure::Error> {
-        let (tx, raw, service_name) = {
-            let new_fork = &*fork;
-            let snapshot = new_fork.snapshot();
-            let schema = Schema::new(snapshot);
-
-            let raw = schema.transactions().get(&tx_hash).ok_or_else(|| {
-                failure::err_msg(format!(
-                    "BUG: Cannot find transaction in database. tx: {:?}",
-                    tx_hash
-                ))
-            })?;
-
-            let service_name = self
-                .service_map
-                .get(&raw.service_id())
-                .ok_or_else(|| {
-                    failure::err_msg(format!(
-                        "Service not found. Service id: {}",
-                        raw.service_id()
-                    ))
-                })?
-                .service_name();
-
-            let tx = self.tx_from_raw(raw.payload().clone()).map_err(|error| {
-                format_err!("Service <{}>: {}, tx: {:?}", service_name, error, tx_hash)
-            })?;
-
-            (tx, raw, service_name)
-        };
+        let (tx, raw, service_name) = self.load_transaction(&*fork, tx_hash)?;
 
         let catch_result = panic::catch_unwind(panic::AssertUnwindSafe(|| {
             let context = TransactionContext::new(&*fork, service_name, &raw);
@@ -446,13 +645,40 @@ impl Blockchain {
         schema.block_transactions(height).push(tx_hash);
         let location = TxLocation::new(height, index as u64);
         schema.transactions_locations().put(&tx_hash, location);
+        schema.transactions_by_author(&raw.author()).push(tx_hash);
         fork.flush();
         Ok(())
     }
 
+    /// Executes the transaction with the given hash against the current blockchain state
+    /// and returns the execution result. The changes made by the transaction are discarded.
+    ///
+    /// This is useful for debugging, e.g., to check whether a committed transaction would
+    /// have the same result if executed now.
+    pub fn replay_transaction(&self, tx_hash: Hash) -> Result<TransactionResult, failure::Error> {
+        let fork = self.fork();
+        let (tx, raw, service_name) = self.load_transaction(&fork, tx_hash)?;
+
+        let catch_result = panic::catch_unwind(panic::AssertUnwindSafe(|| {
+            let context = TransactionContext::new(&fork, service_name, &raw);
+            tx.execute(context)
+        }));
+
+        Ok(TransactionResult(match catch_result {
+            Ok(execution_result) => execution_result.map_err(TransactionError::from),
+            Err(err) => {
+                if err.is::<StorageError>() {
+                    // Continue panic unwind if the reason is StorageError.
+                    panic::resume_unwind(err);
+                }
+                Err(TransactionError::from_panic(&err))
+            }
+        }))
+    }
+
     /// Commits to the blockchain a new block with the indicated changes (patch),
     /// hash and Precommit messages. After that invokes `after_commit`
-    /// for each service in the increasing order of their identifiers.
+    /// for each service in the ascending order of their identifiers.
     pub fn commit<I>(
         &mut self,
         patch: &Patch,
@@ -474,8 +700,9 @@ impl Blockchain {
                 schema.precommits(&block_hash).extend(precommits);
 
                 // Consensus messages cache is useful only during one height, so it should be
-                // cleared when a new height is achieved.
-                schema.consensus_messages_cache().clear();
+                // pruned when a new height is achieved.
+                let next_height = schema.height().next();
+                schema.prune_consensus_messages_cache(next_height);
                 let txs_in_block = schema.last_block().tx_count();
                 let txs_count = schema.transactions_pool_len_index().get().unwrap_or(0);
                 debug_assert!(txs_count >= u64::from(txs_in_block));
@@ -486,22 +713,184 @@ impl Blockchain {
             }
             fork.into_patch()
         };
-        self.merge(patch)?;
+        // The reverting patch is computed against the state before the merge,
+        // but recorded only once the block is written.
+        #[cfg(feature = "rollback")]
+        let undo_patch = self.rollback_patch(&patch);
+        self.merge_with_retries(patch)?;
+        #[cfg(feature = "rollback")]
+        self.log_rollback_patch(undo_patch);
+        self.precompute_state_hashes();
 
-        // Invokes `after_commit` for each service in order of their identifiers
-        for (service_id, service) in self.service_map.iter() {
+        // Invokes `after_commit` for each service in the ascending order of their identifiers,
+        // so that the order is the same on all nodes regardless of the `HashMap` iteration order.
+        let mut service_ids = self.service_map.keys().cloned().collect::<Vec<_>>();
+        service_ids.sort();
+        for service_id in service_ids {
             let context = ServiceContext::new(
                 self.service_keypair.0,
                 self.service_keypair.1.clone(),
                 self.api_sender.clone(),
                 self.fork(),
-                *service_id,
+                service_id,
             );
-            service.after_commit(&context);
+            self.service_map[&service_id].after_commit(&context);
+        }
+        Ok(())
+    }
+
+    /// Commits changes from the patch to the storage, retrying the write at most
+    /// `commit_retries` times if it fails.
+    fn merge_with_retries(&mut self, patch: Patch) -> StorageResult<()> {
+        if self.commit_retries == 0 {
+            return self.merge(patch);
+        }
+
+        let mut attempt = 0;
+        loop {
+            match self.db.merge(patch.clone()) {
+                Err(ref e) if attempt < self.commit_retries => {
+                    attempt += 1;
+                    warn!(
+                        "Unable to write the block to the storage, retrying in {:?} \
+                         (attempt {} of {}): {}",
+                        self.commit_retry_delay, attempt, self.commit_retries, e
+                    );
+                    thread::sleep(self.commit_retry_delay);
+                }
+                Err(e) => {
+                    error!(
+                        "Unable to write the block to the storage after {} attempts: {}",
+                        attempt + 1,
+                        e
+                    );
+                    return Err(e);
+                }
+                Ok(()) => return Ok(()),
+            }
+        }
+    }
+
+    /// Rolls the blockchain back to the specified height, reverting the changes made by
+    /// the blocks committed after it.
+    ///
+    /// Rollback is intended for development networks and is allowed only if the network
+    /// consists of a single validator. Only the blocks committed by this blockchain instance
+    /// can be reverted, since the reverting patches are kept in memory, and no more than
+    /// `MAX_ROLLBACK_BLOCKS` latest blocks are kept. The node must not be running during
+    /// the rollback.
+    #[cfg(feature = "rollback")]
+    pub fn rollback_to(&mut self, height: Height) -> Result<(), failure::Error> {
+        let current_height = {
+            let snapshot = self.snapshot();
+            let schema = Schema::new(&snapshot);
+            let validators_count = schema.actual_configuration().validator_keys.len();
+            if validators_count != 1 {
+                bail!(
+                    "Rollback is allowed only in single-validator networks, but there are {} \
+                     validators",
+                    validators_count
+                );
+            }
+            schema.height()
+        };
+        if height > current_height {
+            bail!(
+                "Cannot roll back to height {}, which is greater than the current height {}",
+                height,
+                current_height
+            );
+        }
+
+        let mut rollback_log = self.rollback_log.lock().expect("Expected mutex lock");
+        let blocks_count = (current_height.0 - height.0) as usize;
+        if blocks_count > rollback_log.len() {
+            bail!(
+                "Cannot roll back to height {}, only {} latest blocks can be reverted",
+                height,
+                rollback_log.len()
+            );
+        }
+        for _ in 0..blocks_count {
+            let patch = rollback_log.pop_back().expect("Rollback log is empty");
+            self.db.merge(patch)?;
+        }
+
+        // Transactions may have been added into the pool after the reverted blocks.
+        let fork = self.fork();
+        {
+            let mut schema = Schema::new(&fork);
+            schema.rebuild_transactions_pool_stats();
+            schema.consensus_messages_cache().clear();
         }
+        self.db.merge(fork.into_patch())?;
         Ok(())
     }
 
+    /// Returns the patch reverting the given one, if the blockchain can be rolled back.
+    #[cfg(feature = "rollback")]
+    fn rollback_patch(&self, patch: &Patch) -> Option<Patch> {
+        let snapshot = self.snapshot();
+        let validators_count = Schema::new(&snapshot)
+            .actual_configuration()
+            .validator_keys
+            .len();
+        if validators_count == 1 {
+            Some(patch.clone().undo(snapshot.as_ref()))
+        } else {
+            None
+        }
+    }
+
+    /// Records the reverting patch of the committed block, discarding the oldest one
+    /// if the log exceeds `MAX_ROLLBACK_BLOCKS`.
+    #[cfg(feature = "rollback")]
+    fn log_rollback_patch(&self, undo_patch: Option<Patch>) {
+        if let Some(undo_patch) = undo_patch {
+            let mut rollback_log = self.rollback_log.lock().expect("Expected mutex lock");
+            rollback_log.push_back(undo_patch);
+            if rollback_log.len() > MAX_ROLLBACK_BLOCKS {
+                rollback_log.pop_front();
+            }
+        }
+    }
+
+    /// Invokes `before_shutdown` for each service in the order of their registration
+    /// and merges the changes made by the service into the database. Panics in the handlers
+    /// are caught and logged, so that every service gets a chance to flush its state.
+    pub fn before_shutdown(&self) {
+        for service_id in self.service_order.iter() {
+            let service = &self.service_map[service_id];
+            let context = ServiceContext::new(
+                self.service_keypair.0,
+                self.service_keypair.1.clone(),
+                self.api_sender.clone(),
+                self.fork(),
+                *service_id,
+            );
+            let result = panic::catch_unwind(panic::AssertUnwindSafe(|| {
+                service.before_shutdown(&context)
+            }));
+            match result {
+                Ok(()) => {
+                    let patch = context.into_fork().into_patch();
+                    if let Err(err) = self.db.merge(patch) {
+                        error!(
+                            "Unable to save the state of {} service at shutdown: {}",
+                            service.service_name(),
+                            err
+                        );
+                    }
+                }
+                Err(err) => error!(
+                    "{} service before_shutdown failed with error: {:?}",
+                    service.service_name(),
+                    err
+                ),
+            }
+        }
+    }
+
     /// Saves the `Connect` message from a peer to the cache.
     pub(crate) fn save_peer(&mut self, pubkey: &PublicKey, peer: Signed<Connect>) {
         let fork = self.fork();
@@ -518,17 +907,31 @@ impl Blockchain {
             .expect("Unable to remove peer from the peers cache");
     }
 
+    /// Removes the transaction bodies of the blocks older than the latest `keep_blocks`
+    /// blocks, but no more than `MAX_PRUNED_BLOCKS_PER_COMMIT` blocks at once. Block headers
+    /// and precommits are kept.
+    pub(crate) fn prune_blocks(&mut self, keep_blocks: u32) {
+        let fork = self.fork();
+        {
+            let mut schema = Schema::new(&fork);
+            let blocks_count = schema.block_hashes_by_height().len();
+            let oldest_available = schema.oldest_available_height().0;
+            let prune_before = cmp::min(
+                blocks_count.saturating_sub(u64::from(keep_blocks)),
+                oldest_available.saturating_add(MAX_PRUNED_BLOCKS_PER_COMMIT),
+            );
+            schema.prune_blocks_before(Height(prune_before));
+        }
+        self.merge(fork.into_patch())
+            .expect("Unable to prune old blocks");
+    }
+
     /// Returns `Connect` messages from peers saved in the cache, if any.
     pub fn get_saved_peers(&self) -> HashMap<PublicKey, Signed<Connect>> {
         let snapshot = self.snapshot();
         Schema::new(&snapshot).peers_cache().iter().collect()
     }
 
-    /// Saves the given raw message to the consensus messages cache.
-    pub(crate) fn save_message<T: ProtocolMessage>(&mut self, round: Round, raw: Signed<T>) {
-        self.save_messages(round, iter::once(raw.into()));
-    }
-
     /// Saves a collection of SignedMessage to the consensus messages cache with single access to the
     /// `Fork` instance.
     pub(crate) fn save_messages<I>(&mut self, round: Round, iter: I)
@@ -566,6 +969,38 @@ fn before_commit(service: &dyn Service, fork: &mut Fork) {
     }
 }
 
+/// Computes the state hashes of the opted-in services for the latest committed block
+/// and saves them to the cache.
+fn update_state_hash_cache(
+    db: &dyn Database,
+    service_map: &HashMap<u16, Box<dyn Service>>,
+    index_prefixes: &HashMap<u16, Vec<String>>,
+    cache: &Mutex<StateHashCache>,
+) {
+    let snapshot = db.snapshot();
+    let block_hash = Schema::new(&snapshot)
+        .block_hashes_by_height()
+        .last()
+        .unwrap_or_default();
+    let hashes = index_prefixes
+        .keys()
+        .map(|service_id| {
+            let hashes = service_map[service_id].state_hash(snapshot.as_ref());
+            (*service_id, hashes)
+        })
+        .collect();
+
+    let mut cache = cache.lock().expect("Expected lock on state hash cache");
+    // A newer block could have been committed while the hashes were computed.
+    let last_hash = Schema::new(&db.snapshot())
+        .block_hashes_by_height()
+        .last()
+        .unwrap_or_default();
+    if last_hash == block_hash {
+        *cache = StateHashCache { block_hash, hashes };
+    }
+}
+
 impl fmt::Debug for Blockchain {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "Blockchain(..)")
@@ -577,8 +1012,14 @@ impl Clone for Blockchain {
         Self {
             db: Arc::clone(&self.db),
             service_map: Arc::clone(&self.service_map),
+            service_order: Arc::clone(&self.service_order),
             api_sender: self.api_sender.clone(),
             service_keypair: self.service_keypair.clone(),
+            commit_retries: self.commit_retries,
+            commit_retry_delay: self.commit_retry_delay,
+            #[cfg(feature = "rollback")]
+            rollback_log: Arc::clone(&self.rollback_log),
+            state_hash_precomputer: self.state_hash_precomputer.clone(),
         }
     }
 }
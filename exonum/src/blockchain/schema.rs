@@ -12,12 +12,15 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use chrono::{DateTime, Utc};
 use exonum_merkledb::{
     Entry, IndexAccess, KeySetIndex, ListIndex, MapIndex, MapProof, ObjectHash, ProofListIndex,
     ProofMapIndex,
 };
 
-use super::{config::StoredConfiguration, Block, BlockProof, Blockchain, TransactionResult};
+use super::{
+    config::StoredConfiguration, Block, BlockProof, Blockchain, GenesisConfig, TransactionResult,
+};
 use crate::{
     crypto::{CryptoHash, Hash, PublicKey},
     helpers::{Height, Round},
@@ -42,7 +45,14 @@ define_names!(
     TRANSACTIONS_LEN => "transactions_len";
     TRANSACTIONS_POOL => "transactions_pool";
     TRANSACTIONS_POOL_LEN => "transactions_pool_len";
+    TRANSACTIONS_POOL_BYTES => "transactions_pool_bytes";
+    TRANSACTIONS_POOL_ARRIVAL => "transactions_pool_arrival";
+    TRANSACTIONS_POOL_ARRIVAL_COUNTER => "transactions_pool_arrival_counter";
+    TRANSACTIONS_POOL_BY_ARRIVAL => "transactions_pool_by_arrival";
+    SERVICE_TRANSACTIONS_POOL_BY_ARRIVAL => "service_transactions_pool_by_arrival";
+    TRANSACTIONS_POOL_ARRIVAL_TIME => "transactions_pool_arrival_time";
     TRANSACTIONS_LOCATIONS => "transactions_locations";
+    TRANSACTIONS_BY_AUTHOR => "transactions_by_author";
     BLOCKS => "blocks";
     BLOCK_HASHES_BY_HEIGHT => "block_hashes_by_height";
     BLOCK_TRANSACTIONS => "block_transactions";
@@ -53,6 +63,10 @@ define_names!(
     PEERS_CACHE => "peers_cache";
     CONSENSUS_MESSAGES_CACHE => "consensus_messages_cache";
     CONSENSUS_ROUND => "consensus_round";
+    GENESIS_CONFIG => "genesis_config";
+    NODE_FIRST_START => "node_first_start";
+    NODE_RESTARTS => "node_restarts";
+    OLDEST_AVAILABLE_HEIGHT => "oldest_available_height";
 );
 
 /// Configuration index.
@@ -88,7 +102,7 @@ impl ConfigReference {
 /// Transaction location in a block.
 /// The given entity defines the block where the transaction was
 /// included and the position of this transaction in that block.
-#[derive(Debug, Serialize, Deserialize, PartialEq, ProtobufConvert)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, ProtobufConvert)]
 #[exonum(pb = "proto::TxLocation", crate = "crate")]
 pub struct TxLocation {
     /// Height of the block where the transaction was included.
@@ -178,12 +192,62 @@ where
         pool.get().unwrap_or(0)
     }
 
+    /// Returns an entry that represents total size of uncommitted transactions in bytes.
+    pub(crate) fn transactions_pool_bytes_index(&self) -> Entry<T, u64> {
+        Entry::new(TRANSACTIONS_POOL_BYTES, self.access.clone())
+    }
+
+    /// Returns the total size of transactions in the pool in bytes.
+    pub fn transactions_pool_bytes(&self) -> u64 {
+        let pool = self.transactions_pool_bytes_index();
+        pool.get().unwrap_or(0)
+    }
+
+    /// Returns a table that keeps the arrival sequence number for every uncommitted
+    /// transaction hash. Transactions added to the pool earlier have smaller numbers.
+    pub fn transactions_pool_arrival(&self) -> MapIndex<T, Hash, u64> {
+        MapIndex::new(TRANSACTIONS_POOL_ARRIVAL, self.access.clone())
+    }
+
+    /// Returns a table that keeps the time when every uncommitted transaction
+    /// was added to the pool.
+    pub fn transactions_pool_arrival_time(&self) -> MapIndex<T, Hash, DateTime<Utc>> {
+        MapIndex::new(TRANSACTIONS_POOL_ARRIVAL_TIME, self.access.clone())
+    }
+
+    /// Returns an entry that keeps the next arrival sequence number.
+    fn transactions_pool_arrival_counter(&self) -> Entry<T, u64> {
+        Entry::new(TRANSACTIONS_POOL_ARRIVAL_COUNTER, self.access.clone())
+    }
+
+    /// Returns a table that keeps the hashes of the uncommitted transactions by their
+    /// arrival sequence numbers, so that the iteration follows the arrival order.
+    pub fn transactions_pool_by_arrival(&self) -> MapIndex<T, u64, Hash> {
+        MapIndex::new(TRANSACTIONS_POOL_BY_ARRIVAL, self.access.clone())
+    }
+
+    /// Returns a table that keeps the hashes of the uncommitted transactions of the given
+    /// service by their arrival sequence numbers.
+    pub fn service_transactions_pool_by_arrival(&self, service_id: u16) -> MapIndex<T, u64, Hash> {
+        MapIndex::new_in_family(
+            SERVICE_TRANSACTIONS_POOL_BY_ARRIVAL,
+            &service_id,
+            self.access.clone(),
+        )
+    }
+
     /// Returns a table that keeps the block height and transaction position inside the block for every
     /// transaction hash.
     pub fn transactions_locations(&self) -> MapIndex<T, Hash, TxLocation> {
         MapIndex::new(TRANSACTIONS_LOCATIONS, self.access.clone())
     }
 
+    /// Returns a table that keeps hashes of the committed transactions signed by the given
+    /// public key, in the order they were committed.
+    pub fn transactions_by_author(&self, author: &PublicKey) -> ListIndex<T, Hash> {
+        ListIndex::new_in_family(TRANSACTIONS_BY_AUTHOR, author, self.access.clone())
+    }
+
     /// Returns a table that stores a block object for every block height.
     pub fn blocks(&self) -> MapIndex<T, Hash, Block> {
         MapIndex::new(BLOCKS, self.access.clone())
@@ -257,6 +321,43 @@ where
             .unwrap_or_else(Round::first)
     }
 
+    /// Returns the time when the node was started on this database for the first time,
+    /// or `None` if the node has never been started on it.
+    pub fn node_first_start(&self) -> Option<DateTime<Utc>> {
+        Entry::new(NODE_FIRST_START, self.access.clone()).get()
+    }
+
+    /// Returns the number of times the node has been restarted on this database.
+    pub fn node_restarts(&self) -> u64 {
+        Entry::new(NODE_RESTARTS, self.access.clone())
+            .get()
+            .unwrap_or(0)
+    }
+
+    /// Returns an entry that keeps the genesis configuration exactly as it was passed
+    /// to the blockchain initialization.
+    pub(crate) fn genesis_config_entry(&self) -> Entry<T, GenesisConfig> {
+        Entry::new(GENESIS_CONFIG, self.access.clone())
+    }
+
+    /// Returns the original configuration used to create the genesis block.
+    ///
+    /// Unlike `configuration_by_height(Height(0))`, the returned value does not contain
+    /// service configurations, and it is not affected by the subsequent configuration changes.
+    /// Returns `None` for blockchains created before the genesis configuration was stored.
+    pub fn genesis_config(&self) -> Option<GenesisConfig> {
+        self.genesis_config_entry().get()
+    }
+
+    /// Returns the height of the oldest block which transaction bodies are stored.
+    /// Bodies of the transactions in the preceding blocks have been pruned, while
+    /// the headers, precommits, transaction hashes and results of these blocks are kept.
+    pub fn oldest_available_height(&self) -> Height {
+        Entry::new(OLDEST_AVAILABLE_HEIGHT, self.access.clone())
+            .get()
+            .map_or(Height(0), Height)
+    }
+
     /// Returns the block hash for the given height.
     pub fn block_hash_by_height(&self, height: Height) -> Option<Hash> {
         self.block_hashes_by_height().get(height.into())
@@ -414,6 +515,64 @@ where
         entry.set(round);
     }
 
+    /// Removes the messages of the heights preceding the given one from the consensus messages
+    /// cache, so that only the messages relevant to the current height are replayed
+    /// on the node restart. The saved consensus round is reset accordingly.
+    pub(crate) fn prune_consensus_messages_cache(&mut self, height: Height) {
+        let retained = self
+            .consensus_messages_cache()
+            .iter()
+            .filter_map(|message| match message {
+                Message::Consensus(msg) => Some(msg),
+                _ => None,
+            })
+            .filter(|msg| msg.height() >= height)
+            .collect::<Vec<_>>();
+        let round = retained
+            .iter()
+            .map(|msg| msg.round())
+            .max()
+            .unwrap_or_else(Round::first);
+
+        let mut cache = self.consensus_messages_cache();
+        cache.clear();
+        cache.extend(retained.into_iter().map(Message::Consensus));
+        self.set_consensus_round(round);
+    }
+
+    /// Records the start of the node. The first start time is saved on the first call,
+    /// and the restart counter is incremented on each subsequent one.
+    pub(crate) fn record_node_start(&mut self, time: DateTime<Utc>) {
+        let mut first_start: Entry<T, DateTime<Utc>> =
+            Entry::new(NODE_FIRST_START, self.access.clone());
+        if first_start.exists() {
+            let mut restarts: Entry<T, u64> = Entry::new(NODE_RESTARTS, self.access.clone());
+            let count = restarts.get().unwrap_or(0);
+            restarts.set(count + 1);
+        } else {
+            first_start.set(time);
+        }
+    }
+
+    /// Removes the transaction bodies of the blocks preceding the given height. The blocks
+    /// pruned earlier are skipped. Block headers, precommits, transaction hashes, locations
+    /// and results are kept, so the blockchain state is not affected.
+    pub(crate) fn prune_blocks_before(&mut self, height: Height) {
+        let oldest_available = self.oldest_available_height();
+        if height <= oldest_available {
+            return;
+        }
+
+        let mut transactions = self.transactions();
+        for block_height in oldest_available.0..height.0 {
+            for tx_hash in self.block_transactions(Height(block_height)).iter() {
+                transactions.remove(&tx_hash);
+            }
+        }
+        let mut entry: Entry<T, u64> = Entry::new(OLDEST_AVAILABLE_HEIGHT, self.access.clone());
+        entry.set(height.0);
+    }
+
     /// Adds a new configuration to the blockchain, which will become actual at
     /// the `actual_from` height in `config_data`.
     pub fn commit_configuration(&mut self, config_data: StoredConfiguration) {
@@ -459,12 +618,69 @@ where
         self.transactions_pool().insert(tx.hash());
         let x = self.transactions_pool_len_index().get().unwrap_or(0);
         self.transactions_pool_len_index().set(x + 1);
+        let bytes = self.transactions_pool_bytes_index().get().unwrap_or(0);
+        self.transactions_pool_bytes_index()
+            .set(bytes + tx.signed_message().raw().len() as u64);
+        let seq = self.transactions_pool_arrival_counter().get().unwrap_or(0);
+        self.transactions_pool_arrival_counter().set(seq + 1);
+        self.transactions_pool_arrival().put(&tx.hash(), seq);
+        self.transactions_pool_by_arrival().put(&seq, tx.hash());
+        self.service_transactions_pool_by_arrival(tx.payload().service_id())
+            .put(&seq, tx.hash());
+        self.transactions_pool_arrival_time()
+            .put(&tx.hash(), Utc::now());
         self.transactions().put(&tx.hash(), tx);
     }
 
     /// Changes the transaction status from `in_pool`, to `committed`.
     pub(crate) fn commit_transaction(&mut self, hash: &Hash) {
+        if self.transactions_pool().contains(hash) {
+            self.sub_transaction_pool_bytes(hash);
+        }
         self.transactions_pool().remove(hash);
+        self.remove_transaction_arrival(hash);
+        self.transactions_pool_arrival_time().remove(hash);
+    }
+
+    /// Removes the arrival sequence number of the given pool transaction.
+    fn remove_transaction_arrival(&mut self, hash: &Hash) {
+        if let Some(seq) = self.transactions_pool_arrival().get(hash) {
+            self.transactions_pool_by_arrival().remove(&seq);
+            if let Some(tx) = self.transactions().get(hash) {
+                self.service_transactions_pool_by_arrival(tx.payload().service_id())
+                    .remove(&seq);
+            }
+        }
+        self.transactions_pool_arrival().remove(hash);
+    }
+
+    /// Subtracts the size of the given pool transaction from `transactions_pool_bytes_index`.
+    fn sub_transaction_pool_bytes(&mut self, hash: &Hash) {
+        let tx_len = self
+            .transactions()
+            .get(hash)
+            .map_or(0, |tx| tx.signed_message().raw().len() as u64);
+        let bytes = self.transactions_pool_bytes_index().get().unwrap_or(0);
+        self.transactions_pool_bytes_index()
+            .set(bytes.saturating_sub(tx_len));
+    }
+
+    /// Recalculates the number and the total size of the pool transactions
+    /// from the contents of the pool.
+    #[cfg(feature = "rollback")]
+    pub(crate) fn rebuild_transactions_pool_stats(&mut self) {
+        let transactions = self.transactions();
+        let (len, bytes) = self
+            .transactions_pool()
+            .iter()
+            .fold((0, 0), |(len, bytes), hash| {
+                let tx_len = transactions
+                    .get(&hash)
+                    .map_or(0, |tx| tx.signed_message().raw().len() as u64);
+                (len + 1, bytes + tx_len)
+            });
+        self.transactions_pool_len_index().set(len);
+        self.transactions_pool_bytes_index().set(bytes);
     }
 
     /// Updates transaction count of the blockchain.
@@ -475,10 +691,16 @@ where
     }
 
     /// Removes transaction from the persistent pool.
-    #[cfg(test)]
-    pub(crate) fn reject_transaction(&mut self, hash: &Hash) -> Result<(), ()> {
+    /// Returns an error if the transaction is not in the pool.
+    #[doc(hidden)]
+    pub fn reject_transaction(&mut self, hash: &Hash) -> Result<(), ()> {
         let contains = self.transactions_pool().contains(hash);
+        if contains {
+            self.sub_transaction_pool_bytes(hash);
+        }
         self.transactions_pool().remove(hash);
+        self.remove_transaction_arrival(hash);
+        self.transactions_pool_arrival_time().remove(hash);
         self.transactions().remove(hash);
 
         if contains {
@@ -18,23 +18,30 @@
 use exonum_merkledb::{Fork, Snapshot};
 
 use actix::Addr;
+use chrono::{DateTime, Utc};
 use serde_json::Value;
 
 use std::{
-    collections::{HashMap, HashSet},
+    cmp,
+    collections::{BTreeSet, HashMap, HashSet, VecDeque},
     fmt,
     net::SocketAddr,
     sync::{Arc, RwLock},
+    time::{Duration, SystemTime},
 };
 
 use crate::{
-    api::{websocket, ServiceApiBuilder},
+    api::{
+        node::public::explorer::{API_WORKER_THREADS, MAX_PENDING_API_REQUESTS},
+        websocket, ServiceApiBuilder,
+    },
     blockchain::{ConsensusConfig, Schema, StoredConfiguration, ValidatorKeys},
     crypto::{Hash, PublicKey, SecretKey},
     events::network::ConnectedPeerAddr,
-    helpers::{Height, Milliseconds, ValidatorId},
+    explorer::TRANSACTION_CACHE_SIZE,
+    helpers::{Height, Milliseconds, Round, ValidatorId},
     messages::{Message, RawTransaction, ServiceTransaction, Signed},
-    node::{ApiSender, ConnectInfo, NodeRole, State},
+    node::{ApiSender, ConnectInfo, ConnectListConfig, EffectiveConfig, NodeRole, State},
 };
 
 use super::transaction::Transaction;
@@ -156,6 +163,17 @@ pub trait Service: Send + Sync + 'static {
     /// [2]: struct.Blockchain.html#method.service_table_unique_key
     fn state_hash(&self, snapshot: &dyn Snapshot) -> Vec<Hash>;
 
+    /// Returns the prefixes of the names of all the indices the state hash of the service
+    /// depends on, if the service opts into the precomputation of state hashes enabled by
+    /// `Blockchain::enable_state_hash_precomputation`. The precomputed state hash is reused
+    /// for a block only if the block changes no index with a name starting with one of
+    /// the prefixes, so the list must cover every index read by `state_hash`.
+    ///
+    /// *Default implementation returns `None`, i.e., the state hash is computed for each block*
+    fn state_hash_index_prefixes(&self) -> Option<Vec<String>> {
+        None
+    }
+
     /// Tries to create a `Transaction` from the given raw message.
     ///
     /// Exonum framework only guarantees that `SERVICE_ID` of the message is equal to the
@@ -176,6 +194,26 @@ pub trait Service: Send + Sync + 'static {
     /// [the `Service` example above](#examples).
     fn tx_from_raw(&self, raw: RawTransaction) -> Result<Box<dyn Transaction>, failure::Error>;
 
+    /// Returns the creation time of the given transaction if the transactions of the service
+    /// carry it. If `max_tx_time_skew` is set in the node configuration, the node rejects
+    /// incoming transactions created later than its current time plus the skew.
+    ///
+    /// *Default implementation returns `None`*
+    fn tx_timestamp(&self, raw: &RawTransaction) -> Option<DateTime<Utc>> {
+        None
+    }
+
+    /// Returns the nonce of the given transaction together with the nonce expected
+    /// from its author in the current state, if the transactions of the service are ordered
+    /// by nonces. If `nonce_buffer_size` is set in the node configuration, the node holds
+    /// incoming transactions with slightly higher nonces than expected until the preceding
+    /// transactions of the same author arrive.
+    ///
+    /// *Default implementation returns `None`*
+    fn tx_nonce(&self, snapshot: &dyn Snapshot, tx: &Signed<RawTransaction>) -> Option<TxNonce> {
+        None
+    }
+
     /// Invoked for all deployed services during the blockchain initialization
     /// on genesis block creation each time a node is started.
     /// During the handling of the method the service is able to perform the following activities:
@@ -204,9 +242,24 @@ pub trait Service: Send + Sync + 'static {
     /// For example, a service can create one or more transactions if a specific condition
     /// has occurred.
     ///
+    /// The handler is invoked for the services in the ascending order of their identifiers,
+    /// which is the same on all nodes. Nevertheless, the handler must not change the blockchain
+    /// state directly: the context only provides a snapshot of the committed state, and any
+    /// changes should be made by transactions sent via `ServiceContext::broadcast_transaction`,
+    /// so that they are applied through the fork of a block on every node.
+    ///
     /// *Try not to perform long operations in this handler*.
     fn after_commit(&self, context: &ServiceContext) {}
 
+    /// Handles node shutdown. This handler is invoked for each service in the order
+    /// of their registration before the node stops its event loop and closes the database.
+    /// Services may use it to flush caches or other background state.
+    ///
+    /// Changes made via `ServiceContext::fork` are merged into the database after
+    /// the handler returns. Panics in this handler are caught and logged, and the changes
+    /// made by the panicked handler are discarded.
+    fn before_shutdown(&self, context: &ServiceContext) {}
+
     /// Extends API by handlers of this service. The request handlers are mounted on
     /// the `/api/services/{service_name}` path at the listen address of every
     /// full node in the blockchain network.
@@ -278,6 +331,17 @@ impl ServiceContext {
         self.fork.as_ref()
     }
 
+    /// Returns the fork of the current database state. Changes made to the fork are
+    /// persisted only in `Service::before_shutdown`; in other handlers they are discarded.
+    pub fn fork(&self) -> &Fork {
+        &self.fork
+    }
+
+    /// Consumes the context and returns its fork.
+    pub(crate) fn into_fork(self) -> Fork {
+        self.fork
+    }
+
     /// Returns the current blockchain height. This height is "height of the last committed block".
     pub fn height(&self) -> Height {
         self.height
@@ -339,6 +403,151 @@ impl ServiceContext {
     }
 }
 
+/// Consensus message processed by the node, as recorded in the consensus log.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ConsensusLogEntry {
+    /// Message type: `propose`, `prevote`, `precommit` or `status`.
+    pub message_type: String,
+    /// Height the message relates to.
+    pub height: Height,
+    /// Round the message relates to. `Status` messages have no round.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub round: Option<Round>,
+    /// Public key of the message author.
+    pub author: PublicKey,
+    /// Signed message in hex representation.
+    pub message: String,
+}
+
+/// Progress of the consensus algorithm at the current height.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConsensusState {
+    /// Current height of the node.
+    pub height: Height,
+    /// Number of rounds spent at the current height, which equals to the current round
+    /// since the rounds start anew at each height.
+    pub current_height_round_count: u64,
+    /// Maximal number of rounds spent at a single height since the node start.
+    pub max_height_round_count: u64,
+    /// Number of validators in the actual configuration.
+    pub validators_count: usize,
+}
+
+/// Number of the upcoming rounds included into the round schedule.
+pub const ROUND_SCHEDULE_LENGTH: usize = 5;
+
+/// Start times and timeouts of the upcoming consensus rounds at the current height,
+/// starting from the current round.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RoundSchedule {
+    /// Current height of the node.
+    pub height: Height,
+    /// Upcoming rounds in the ascending order.
+    pub rounds: Vec<RoundTiming>,
+}
+
+/// Start time and timeout of a single consensus round.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct RoundTiming {
+    /// Round number.
+    pub round: Round,
+    /// Time the round starts at.
+    pub start_time: DateTime<Utc>,
+    /// Time after which the node moves to the next round, in milliseconds.
+    pub timeout: Milliseconds,
+}
+
+/// Default number of the latest transactions pool changes retained for
+/// the pool delta requests.
+pub const POOL_CHANGES_CAPACITY: usize = 10_000;
+
+/// Change of the transactions pool.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum PoolChange {
+    Added(Hash),
+    Removed(Hash),
+}
+
+/// Changes of the transactions pool since the given revision.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PoolDelta {
+    /// Hashes of the transactions added into the pool, sorted by hash.
+    pub added: Vec<Hash>,
+    /// Hashes of the transactions removed from the pool, i.e., committed or cancelled,
+    /// sorted by hash.
+    pub removed: Vec<Hash>,
+    /// The current revision of the pool, which should be passed to the next request.
+    pub token: u64,
+    /// Epoch of the pool revisions, which should be passed to the next request along with
+    /// `token`. The epoch is chosen randomly on every start of the node, since the revisions
+    /// are not persisted.
+    pub epoch: u64,
+    /// If `true`, the requested revision is no longer retained, so the pool should be
+    /// synchronized from scratch. `added` and `removed` are empty in this case.
+    pub resync_required: bool,
+}
+
+/// Default number of the latest rejected transactions retained by the node.
+pub const REJECTED_TRANSACTIONS_CAPACITY: usize = 100;
+
+/// Transaction submitted to the node and rejected by it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RejectedTransaction {
+    /// Hash of the rejected transaction.
+    pub tx_hash: Hash,
+    /// Human-readable reason of the rejection.
+    pub reason: String,
+    /// Time when the transaction was rejected.
+    pub time: DateTime<Utc>,
+}
+
+/// Nonce of a transaction as reported by `Service::tx_nonce`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TxNonce {
+    /// Nonce the service expects from the next transaction of the author.
+    pub expected: u64,
+    /// Nonce of the transaction.
+    pub actual: u64,
+}
+
+/// Numbers of messages dropped by the node because sending them to the bounded
+/// event loop channels has failed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct ChannelStats {
+    /// Messages dropped on the way to the network thread.
+    pub network_requests_dropped: u64,
+    /// Timeouts and other internal requests dropped on the way to the internal thread.
+    pub internal_requests_dropped: u64,
+}
+
+/// Delays between the time the node timeouts were scheduled for and the time they were
+/// handled, in microseconds. Growing delays indicate that the node event loop is overloaded.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct TimeoutDrift {
+    /// Number of handled timeouts.
+    pub timeouts: u64,
+    /// Delay of the latest handled timeout.
+    pub last: u64,
+    /// Maximal delay among the handled timeouts.
+    pub max: u64,
+    /// Average delay of the handled timeouts.
+    pub average: u64,
+}
+
+/// Number of the latest round-trip time samples used to calculate the average
+/// latency of a peer.
+const PEER_LATENCY_WINDOW: usize = 16;
+
+/// Round-trip latency of the consensus requests sent to a peer.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct PeerLatency {
+    /// Average round-trip time over the latest samples in milliseconds, or `None`
+    /// if the peer has not responded to any request yet.
+    pub average: Option<Milliseconds>,
+    /// Number of samples the average is calculated over.
+    pub samples: usize,
+}
+
 #[derive(Default)]
 pub struct ApiNodeState {
     // TODO: Update on event? (ECR-1632)
@@ -347,9 +556,32 @@ pub struct ApiNodeState {
     reconnects_timeout: HashMap<SocketAddr, Milliseconds>,
     is_enabled: bool,
     node_role: NodeRole,
-    majority_count: usize,
+    majority_weight: u64,
     validators: Vec<ValidatorKeys>,
     broadcast_server_address: Option<Addr<websocket::Server>>,
+    consensus_log: VecDeque<ConsensusLogEntry>,
+    consensus_log_capacity: usize,
+    node_label: Option<String>,
+    start_time: Option<SystemTime>,
+    effective_config: Option<EffectiveConfig>,
+    channel_stats: ChannelStats,
+    timeout_drift: TimeoutDrift,
+    timeout_drift_total: u64,
+    peer_latencies: HashMap<PublicKey, VecDeque<Duration>>,
+    connected_peers: ConnectListConfig,
+    consensus_state: Option<ConsensusState>,
+    round_schedule: Option<RoundSchedule>,
+    api_request_timeout: Option<Duration>,
+    api_worker_threads: usize,
+    max_pending_api_requests: usize,
+    transaction_cache_size: usize,
+    pool_epoch: u64,
+    pool_revision: u64,
+    pool_changes: VecDeque<PoolChange>,
+    pool_changes_capacity: usize,
+    rejected_transactions: VecDeque<RejectedTransaction>,
+    rejected_transactions_capacity: usize,
+    is_ready: bool,
 }
 
 impl fmt::Debug for ApiNodeState {
@@ -360,8 +592,32 @@ impl fmt::Debug for ApiNodeState {
             .field("reconnects_timeout", &self.reconnects_timeout)
             .field("is_enabled", &self.is_enabled)
             .field("node_role", &self.node_role)
-            .field("majority_count", &self.majority_count)
+            .field("majority_weight", &self.majority_weight)
             .field("validators", &self.validators)
+            .field("consensus_log", &self.consensus_log)
+            .field("consensus_log_capacity", &self.consensus_log_capacity)
+            .field("node_label", &self.node_label)
+            .field("start_time", &self.start_time)
+            .field("effective_config", &self.effective_config)
+            .field("channel_stats", &self.channel_stats)
+            .field("timeout_drift", &self.timeout_drift)
+            .field("peer_latencies", &self.peer_latencies)
+            .field("connected_peers", &self.connected_peers)
+            .field("consensus_state", &self.consensus_state)
+            .field("round_schedule", &self.round_schedule)
+            .field("api_request_timeout", &self.api_request_timeout)
+            .field("api_worker_threads", &self.api_worker_threads)
+            .field("max_pending_api_requests", &self.max_pending_api_requests)
+            .field("transaction_cache_size", &self.transaction_cache_size)
+            .field("pool_epoch", &self.pool_epoch)
+            .field("pool_revision", &self.pool_revision)
+            .field("pool_changes_capacity", &self.pool_changes_capacity)
+            .field("rejected_transactions", &self.rejected_transactions)
+            .field(
+                "rejected_transactions_capacity",
+                &self.rejected_transactions_capacity,
+            )
+            .field("is_ready", &self.is_ready)
             .finish()
     }
 }
@@ -370,6 +626,13 @@ impl ApiNodeState {
     fn new() -> Self {
         Self {
             is_enabled: true,
+            pool_epoch: rand::random(),
+            pool_changes_capacity: POOL_CHANGES_CAPACITY,
+            rejected_transactions_capacity: REJECTED_TRANSACTIONS_CAPACITY,
+            api_worker_threads: API_WORKER_THREADS,
+            max_pending_api_requests: MAX_PENDING_API_REQUESTS,
+            transaction_cache_size: TRANSACTION_CACHE_SIZE,
+            is_ready: true,
             ..Default::default()
         }
     }
@@ -433,9 +696,16 @@ impl SharedNodeState {
 
         lock.incoming_connections.clear();
         lock.outgoing_connections.clear();
-        lock.majority_count = state.majority_count();
+        lock.majority_weight = state.majority_weight();
         lock.node_role = NodeRole::new(state.validator_id());
         lock.validators = state.validators().to_vec();
+        lock.connected_peers = ConnectListConfig::from_connected_peers(state);
+        lock.consensus_state = Some(ConsensusState {
+            height: state.height(),
+            current_height_round_count: u64::from(state.round().0),
+            max_height_round_count: u64::from(state.max_round().0),
+            validators_count: state.validators().len(),
+        });
 
         for (p, a) in state.connections() {
             match a {
@@ -457,29 +727,67 @@ impl SharedNodeState {
         }
     }
 
-    /// Returns a boolean value which indicates whether the consensus is achieved.
+    /// Returns the connect list built from the currently connected peers
+    /// as of the latest API state update.
+    pub fn connected_peers(&self) -> ConnectListConfig {
+        self.state
+            .read()
+            .expect("Expected read lock.")
+            .connected_peers
+            .clone()
+    }
+
+    /// Returns the progress of the consensus algorithm as of the latest API state update,
+    /// or `None` if the state has not been updated yet.
+    pub fn consensus_state(&self) -> Option<ConsensusState> {
+        self.state
+            .read()
+            .expect("Expected read lock.")
+            .consensus_state
+    }
+
+    /// Updates the schedule of the upcoming consensus rounds.
+    pub fn set_round_schedule(&self, schedule: RoundSchedule) {
+        self.state
+            .write()
+            .expect("Expected write lock.")
+            .round_schedule = Some(schedule);
+    }
+
+    /// Returns the schedule of the upcoming consensus rounds as of the latest API state
+    /// update, or `None` if the state has not been updated yet.
+    pub fn round_schedule(&self) -> Option<RoundSchedule> {
+        self.state
+            .read()
+            .expect("Expected read lock.")
+            .round_schedule
+            .clone()
+    }
+
+    /// Returns a boolean value which indicates whether the consensus is achieved, i.e.,
+    /// whether the node is connected to validators with +2/3 of the validators weight.
     pub fn consensus_status(&self) -> bool {
         let lock = self.state.read().expect("Expected read lock.");
-        let mut active_validators = lock
+        let mut active_weight: u64 = lock
             .incoming_connections
             .iter()
             .chain(lock.outgoing_connections.iter())
-            .filter(|ci| {
+            .filter_map(|ci| {
                 lock.validators
                     .iter()
-                    .any(|v| v.consensus_key == ci.public_key)
+                    .find(|v| v.consensus_key == ci.public_key)
             })
-            .count();
+            .map(|v| v.weight)
+            .sum();
 
-        if lock.node_role.is_validator() {
-            // Peers list doesn't include current node address, so we have to increment its length.
-            // E.g. if we have 3 items in peers list, it means that we have 4 nodes overall.
-            active_validators += 1;
+        if let NodeRole::Validator(id) = lock.node_role {
+            // Peers list doesn't include current node address, so we have to add its weight.
+            active_weight += lock.validators.get(id.0 as usize).map_or(0, |v| v.weight);
         }
 
-        // Just after Node is started (node status isn't updated) majority_count = 0,
-        // so we have to check that majority count is greater than 0.
-        active_validators >= lock.majority_count && lock.majority_count > 0
+        // Just after Node is started (node status isn't updated) majority_weight = 0,
+        // so we have to check that majority weight is greater than 0.
+        active_weight >= lock.majority_weight && lock.majority_weight > 0
     }
 
     /// Returns a boolean value which indicates whether the node is enabled
@@ -528,9 +836,17 @@ impl SharedNodeState {
             .remove(addr)
     }
 
-    pub(crate) fn set_broadcast_server_address(&self, address: Addr<websocket::Server>) {
+    /// Returns the address of the broadcast server, starting the server with `start`
+    /// if it is not running yet.
+    pub(crate) fn broadcast_server_address<F>(&self, start: F) -> Addr<websocket::Server>
+    where
+        F: FnOnce() -> Addr<websocket::Server>,
+    {
         let mut state = self.state.write().expect("Expected write lock");
-        state.broadcast_server_address = Some(address);
+        state
+            .broadcast_server_address
+            .get_or_insert_with(start)
+            .clone()
     }
 
     /// Broadcast message to all subscribers.
@@ -547,6 +863,355 @@ impl SharedNodeState {
         }
     }
 
+    /// Sets the human-readable label of the node.
+    pub fn set_node_label(&self, label: Option<String>) {
+        self.state.write().expect("Expected write lock").node_label = label;
+    }
+
+    /// Sets the time when the node process has been started.
+    pub fn set_start_time(&self, time: SystemTime) {
+        self.state.write().expect("Expected write lock").start_time = Some(time);
+    }
+
+    /// Returns the time elapsed since the node process has been started, or `None`
+    /// if the start time is not set.
+    pub fn uptime(&self) -> Option<Duration> {
+        let start_time = self.state.read().expect("Expected read lock").start_time?;
+        Some(
+            SystemTime::now()
+                .duration_since(start_time)
+                .unwrap_or_default(),
+        )
+    }
+
+    /// Sets the maximal time of handling a request by the explorer API. If `None`,
+    /// the requests are not limited in time.
+    pub fn set_api_request_timeout(&self, timeout: Option<Duration>) {
+        self.state
+            .write()
+            .expect("Expected write lock")
+            .api_request_timeout = timeout;
+    }
+
+    /// Returns the maximal time of handling a request by the explorer API, if any.
+    pub fn api_request_timeout(&self) -> Option<Duration> {
+        self.state
+            .read()
+            .expect("Expected read lock")
+            .api_request_timeout
+    }
+
+    /// Sets the number of threads executing the explorer requests limited in time and
+    /// the maximal number of such requests waiting for them. The default values are
+    /// `API_WORKER_THREADS` and `MAX_PENDING_API_REQUESTS`. Should be set before the API
+    /// is started to take effect.
+    pub fn set_api_workers(&self, threads: usize, max_pending_requests: usize) {
+        let mut state = self.state.write().expect("Expected write lock");
+        state.api_worker_threads = threads;
+        state.max_pending_api_requests = max_pending_requests;
+    }
+
+    /// Returns the number of threads executing the explorer requests limited in time.
+    pub fn api_worker_threads(&self) -> usize {
+        self.state
+            .read()
+            .expect("Expected read lock")
+            .api_worker_threads
+    }
+
+    /// Returns the maximal number of the explorer requests limited in time which are
+    /// executed or wait for a worker thread.
+    pub fn max_pending_api_requests(&self) -> usize {
+        self.state
+            .read()
+            .expect("Expected read lock")
+            .max_pending_api_requests
+    }
+
+    /// Sets the number of the committed transactions cached by the explorer API.
+    /// The default value is `TRANSACTION_CACHE_SIZE`. Should be set before the API
+    /// is started to take effect.
+    pub fn set_transaction_cache_size(&self, size: usize) {
+        self.state
+            .write()
+            .expect("Expected write lock")
+            .transaction_cache_size = size;
+    }
+
+    /// Returns the number of the committed transactions cached by the explorer API.
+    pub fn transaction_cache_size(&self) -> usize {
+        self.state
+            .read()
+            .expect("Expected read lock")
+            .transaction_cache_size
+    }
+
+    /// Marks the node as ready or not ready to serve fresh data via the API.
+    pub fn set_ready(&self, is_ready: bool) {
+        self.state.write().expect("Expected write lock").is_ready = is_ready;
+    }
+
+    /// Returns `true` if the node is ready to serve fresh data via the API, i.e.,
+    /// the API warmup is over.
+    pub fn is_ready(&self) -> bool {
+        self.state.read().expect("Expected read lock").is_ready
+    }
+
+    /// Returns the human-readable label of the node, if any.
+    pub fn node_label(&self) -> Option<String> {
+        self.state
+            .read()
+            .expect("Expected read lock")
+            .node_label
+            .clone()
+    }
+
+    /// Sets the effective configuration of the node.
+    pub fn set_effective_config(&self, config: EffectiveConfig) {
+        self.state
+            .write()
+            .expect("Expected write lock")
+            .effective_config = Some(config);
+    }
+
+    /// Returns the effective configuration of the node, if it is known.
+    pub fn effective_config(&self) -> Option<EffectiveConfig> {
+        self.state
+            .read()
+            .expect("Expected read lock")
+            .effective_config
+            .clone()
+    }
+
+    /// Increments the number of dropped network requests and returns the updated value.
+    pub(crate) fn add_dropped_network_request(&self) -> u64 {
+        let mut state = self.state.write().expect("Expected write lock");
+        state.channel_stats.network_requests_dropped += 1;
+        state.channel_stats.network_requests_dropped
+    }
+
+    /// Increments the number of dropped internal requests and returns the updated value.
+    pub(crate) fn add_dropped_internal_request(&self) -> u64 {
+        let mut state = self.state.write().expect("Expected write lock");
+        state.channel_stats.internal_requests_dropped += 1;
+        state.channel_stats.internal_requests_dropped
+    }
+
+    /// Returns the numbers of messages dropped because of failed sends to the node channels.
+    pub fn channel_stats(&self) -> ChannelStats {
+        self.state.read().expect("Expected read lock").channel_stats
+    }
+
+    /// Records the delay of a handled timeout in microseconds.
+    pub(crate) fn add_timeout_drift(&self, drift: u64) {
+        let mut state = self.state.write().expect("Expected write lock");
+        let total = state.timeout_drift_total.saturating_add(drift);
+        state.timeout_drift_total = total;
+        let stats = &mut state.timeout_drift;
+        stats.timeouts += 1;
+        stats.last = drift;
+        stats.max = cmp::max(stats.max, drift);
+        stats.average = total / stats.timeouts;
+    }
+
+    /// Returns the delays between the scheduled and the actual handling time of the node
+    /// timeouts.
+    pub fn timeout_drift(&self) -> TimeoutDrift {
+        self.state.read().expect("Expected read lock").timeout_drift
+    }
+
+    /// Registers a consensus request sent to the peer, so that the peer is reported
+    /// as unmeasured until it responds.
+    pub(crate) fn add_peer_request(&self, peer: PublicKey) {
+        let mut state = self.state.write().expect("Expected write lock");
+        state.peer_latencies.entry(peer).or_default();
+    }
+
+    /// Records a round-trip time of the consensus request sent to the peer.
+    pub(crate) fn add_peer_latency_sample(&self, peer: PublicKey, rtt: Duration) {
+        let mut state = self.state.write().expect("Expected write lock");
+        let samples = state.peer_latencies.entry(peer).or_default();
+        if samples.len() == PEER_LATENCY_WINDOW {
+            samples.pop_front();
+        }
+        samples.push_back(rtt);
+    }
+
+    /// Returns the average round-trip latencies of the consensus requests
+    /// for the peers the node has sent requests to.
+    pub fn peer_latencies(&self) -> HashMap<PublicKey, PeerLatency> {
+        let state = self.state.read().expect("Expected read lock");
+        state
+            .peer_latencies
+            .iter()
+            .map(|(peer, samples)| {
+                let average = if samples.is_empty() {
+                    None
+                } else {
+                    let total = samples.iter().sum::<Duration>();
+                    let total_millis = total.as_secs() * 1000 + u64::from(total.subsec_millis());
+                    Some(total_millis / samples.len() as u64)
+                };
+                let latency = PeerLatency {
+                    average,
+                    samples: samples.len(),
+                };
+                (*peer, latency)
+            })
+            .collect()
+    }
+
+    /// Enables recording of the consensus messages processed by the node.
+    /// Only the latest `capacity` messages are kept.
+    pub fn enable_consensus_log(&self, capacity: usize) {
+        let mut state = self.state.write().expect("Expected write lock");
+        state.consensus_log_capacity = capacity;
+        state.consensus_log = VecDeque::with_capacity(capacity);
+    }
+
+    /// Returns `true` if the consensus messages are recorded.
+    pub fn is_consensus_log_enabled(&self) -> bool {
+        self.state
+            .read()
+            .expect("Expected read lock")
+            .consensus_log_capacity
+            > 0
+    }
+
+    /// Returns the recorded consensus messages, from the oldest to the newest one.
+    pub fn consensus_log(&self) -> Vec<ConsensusLogEntry> {
+        self.state
+            .read()
+            .expect("Expected read lock")
+            .consensus_log
+            .iter()
+            .cloned()
+            .collect()
+    }
+
+    /// Appends an entry to the consensus log, evicting the oldest one if the log is full.
+    pub(crate) fn record_consensus_message(&self, entry: ConsensusLogEntry) {
+        let mut state = self.state.write().expect("Expected write lock");
+        if state.consensus_log_capacity == 0 {
+            return;
+        }
+        if state.consensus_log.len() == state.consensus_log_capacity {
+            state.consensus_log.pop_front();
+        }
+        state.consensus_log.push_back(entry);
+    }
+
+    /// Sets the number of the latest rejected transactions retained by the node.
+    /// The default value is `REJECTED_TRANSACTIONS_CAPACITY`.
+    pub fn set_rejected_transactions_capacity(&self, capacity: usize) {
+        let mut state = self.state.write().expect("Expected write lock");
+        state.rejected_transactions_capacity = capacity;
+        while state.rejected_transactions.len() > capacity {
+            state.rejected_transactions.pop_front();
+        }
+    }
+
+    /// Returns the latest rejected transactions, from the oldest to the newest one.
+    pub fn rejected_transactions(&self) -> Vec<RejectedTransaction> {
+        self.state
+            .read()
+            .expect("Expected read lock")
+            .rejected_transactions
+            .iter()
+            .cloned()
+            .collect()
+    }
+
+    /// Records a rejected transaction, evicting the oldest entry if the buffer is full.
+    pub(crate) fn record_rejected_transaction(&self, entry: RejectedTransaction) {
+        let mut state = self.state.write().expect("Expected write lock");
+        if state.rejected_transactions_capacity == 0 {
+            return;
+        }
+        if state.rejected_transactions.len() == state.rejected_transactions_capacity {
+            state.rejected_transactions.pop_front();
+        }
+        state.rejected_transactions.push_back(entry);
+    }
+
+    /// Sets the number of the latest transactions pool changes retained for the pool delta
+    /// requests. The default value is `POOL_CHANGES_CAPACITY`.
+    pub fn set_pool_changes_capacity(&self, capacity: usize) {
+        let mut state = self.state.write().expect("Expected write lock");
+        state.pool_changes_capacity = capacity;
+        while state.pool_changes.len() > capacity {
+            state.pool_changes.pop_front();
+        }
+    }
+
+    /// Returns the changes of the transactions pool made after the given revision of
+    /// the given epoch. A transaction added and then removed after the revision is reported
+    /// in neither list. Revisions of other epochs, as well as non-zero revisions without
+    /// an epoch, require resynchronization.
+    pub fn pool_delta(&self, epoch: Option<u64>, since: u64) -> PoolDelta {
+        let state = self.state.read().expect("Expected read lock");
+        let token = state.pool_revision;
+        let epoch_matches = epoch.map_or(since == 0, |epoch| epoch == state.pool_epoch);
+        let earliest = token - state.pool_changes.len() as u64;
+        if !epoch_matches || since < earliest || since > token {
+            return PoolDelta {
+                added: Vec::new(),
+                removed: Vec::new(),
+                token,
+                epoch: state.pool_epoch,
+                resync_required: true,
+            };
+        }
+
+        let (mut added, mut removed) = (BTreeSet::new(), BTreeSet::new());
+        for change in state.pool_changes.iter().skip((since - earliest) as usize) {
+            match *change {
+                PoolChange::Added(hash) => {
+                    if !removed.remove(&hash) {
+                        added.insert(hash);
+                    }
+                }
+                PoolChange::Removed(hash) => {
+                    if !added.remove(&hash) {
+                        removed.insert(hash);
+                    }
+                }
+            }
+        }
+        PoolDelta {
+            added: added.into_iter().collect(),
+            removed: removed.into_iter().collect(),
+            token,
+            epoch: state.pool_epoch,
+            resync_required: false,
+        }
+    }
+
+    /// Records the transactions added into the pool.
+    pub(crate) fn record_pool_additions<I: IntoIterator<Item = Hash>>(&self, tx_hashes: I) {
+        self.record_pool_changes(tx_hashes.into_iter().map(PoolChange::Added));
+    }
+
+    /// Records the transactions removed from the pool.
+    pub(crate) fn record_pool_removals<I: IntoIterator<Item = Hash>>(&self, tx_hashes: I) {
+        self.record_pool_changes(tx_hashes.into_iter().map(PoolChange::Removed));
+    }
+
+    /// Appends the changes to the pool changes log, evicting the oldest ones if it is full.
+    fn record_pool_changes<I: IntoIterator<Item = PoolChange>>(&self, changes: I) {
+        let mut state = self.state.write().expect("Expected write lock");
+        for change in changes {
+            state.pool_revision += 1;
+            if state.pool_changes_capacity == 0 {
+                continue;
+            }
+            if state.pool_changes.len() == state.pool_changes_capacity {
+                state.pool_changes.pop_front();
+            }
+            state.pool_changes.push_back(change);
+        }
+    }
+
     pub(crate) fn shutdown_broadcast_server(&self) {
         let state = self.state.read().expect("Expected read lock");
         if let Some(server) = state.broadcast_server_address.as_ref() {
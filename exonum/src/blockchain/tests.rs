@@ -16,11 +16,17 @@
 
 use rand::{distributions::Alphanumeric, thread_rng, Rng};
 
-use std::borrow::Cow;
+use std::{
+    borrow::Cow,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+};
 
 use crate::blockchain::{
-    Blockchain, ExecutionError, ExecutionResult, Schema, Service, Transaction, TransactionContext,
-    TransactionSet,
+    Blockchain, ExecutionError, ExecutionResult, Schema, Service, ServiceContext, Transaction,
+    TransactionContext, TransactionSet,
 };
 use crate::crypto::{gen_keypair, Hash};
 use crate::helpers::{Height, ValidatorId};
@@ -164,6 +170,63 @@ fn handling_tx_panic_storage_error(blockchain: &mut Blockchain) {
     );
 }
 
+#[cfg(feature = "rollback")]
+fn rollback_to_height(blockchain: &mut Blockchain) {
+    use std::iter;
+
+    use crate::blockchain::{GenesisConfig, ValidatorKeys, DEFAULT_VALIDATOR_WEIGHT};
+    use exonum_merkledb::ObjectHash;
+
+    let (pk, sec_key) = gen_keypair();
+    let validator_keys = ValidatorKeys {
+        consensus_key: gen_keypair().0,
+        service_key: blockchain.service_keypair.0,
+        weight: DEFAULT_VALIDATOR_WEIGHT,
+    };
+    blockchain
+        .initialize(GenesisConfig::new(iter::once(validator_keys)))
+        .unwrap();
+
+    let mut expected_block = None;
+    for value in 1..=5 {
+        let tx = Message::sign_transaction(Tx::new(value), TEST_SERVICE_ID, pk, &sec_key);
+        let fork = blockchain.fork();
+        Schema::new(&fork).add_transaction_into_pool(tx.clone());
+        blockchain.merge(fork.into_patch()).unwrap();
+
+        let (block_hash, patch) =
+            blockchain.create_patch(ValidatorId::zero(), Height(value), &[tx.hash()]);
+        blockchain
+            .commit(&patch, block_hash, iter::empty())
+            .unwrap();
+        if value == 2 {
+            expected_block = Some(Schema::new(&blockchain.snapshot()).last_block());
+        }
+    }
+
+    // Pending transaction which should survive the rollback.
+    let tx = Message::sign_transaction(Tx::new(10), TEST_SERVICE_ID, pk, &sec_key);
+    let fork = blockchain.fork();
+    Schema::new(&fork).add_transaction_into_pool(tx);
+    blockchain.merge(fork.into_patch()).unwrap();
+
+    blockchain.rollback_to(Height(2)).unwrap();
+    assert!(blockchain.rollback_to(Height(3)).is_err());
+
+    let expected_block = expected_block.unwrap();
+    let snapshot = blockchain.snapshot();
+    let schema = Schema::new(&snapshot);
+    assert_eq!(schema.height(), Height(2));
+    assert_eq!(schema.last_block(), expected_block);
+    assert_eq!(
+        schema.state_hash_aggregator().object_hash(),
+        *expected_block.state_hash()
+    );
+    // Transactions from the reverted blocks return into the pool.
+    assert_eq!(schema.transactions_pool_len(), 4);
+    assert_eq!(ListIndex::<_, u64>::new(IDX_NAME, &snapshot).len(), 4);
+}
+
 mod transactions_tests {
     use super::TEST_SERVICE_ID;
     use crate::blockchain::{ExecutionResult, Transaction, TransactionContext, TransactionSet};
@@ -346,6 +409,73 @@ impl Service for ServicePanicStorageError {
     }
 }
 
+/// Service counting the computations of its state hash. The state of the service
+/// is changed in `before_commit` only if `write_state` is set.
+struct StateHashSpyService {
+    state_hash_calls: Arc<AtomicUsize>,
+    write_state: Arc<AtomicBool>,
+    index_prefixes: Option<Vec<String>>,
+}
+
+impl Service for StateHashSpyService {
+    fn service_id(&self) -> u16 {
+        2
+    }
+
+    fn service_name(&self) -> &'static str {
+        "spy"
+    }
+
+    fn state_hash(&self, _snapshot: &dyn Snapshot) -> Vec<Hash> {
+        self.state_hash_calls.fetch_add(1, Ordering::SeqCst);
+        vec![]
+    }
+
+    fn state_hash_index_prefixes(&self) -> Option<Vec<String>> {
+        self.index_prefixes.clone()
+    }
+
+    fn tx_from_raw(&self, _raw: RawTransaction) -> Result<Box<dyn Transaction>, failure::Error> {
+        unimplemented!()
+    }
+
+    fn before_commit(&self, fork: &Fork) {
+        if self.write_state.load(Ordering::SeqCst) {
+            let mut index = ListIndex::new("spy.values", fork);
+            index.push(1_u64);
+        }
+    }
+}
+
+/// Service recording its identifier into the shared list on each `after_commit` invocation.
+struct AfterCommitSpyService {
+    id: u16,
+    name: &'static str,
+    calls: Arc<Mutex<Vec<u16>>>,
+}
+
+impl Service for AfterCommitSpyService {
+    fn service_id(&self) -> u16 {
+        self.id
+    }
+
+    fn service_name(&self) -> &'static str {
+        self.name
+    }
+
+    fn state_hash(&self, _snapshot: &dyn Snapshot) -> Vec<Hash> {
+        vec![]
+    }
+
+    fn tx_from_raw(&self, _raw: RawTransaction) -> Result<Box<dyn Transaction>, failure::Error> {
+        unimplemented!()
+    }
+
+    fn after_commit(&self, _context: &ServiceContext) {
+        self.calls.lock().unwrap().push(self.id);
+    }
+}
+
 fn assert_service_execute(blockchain: &Blockchain, db: &mut dyn Database) {
     let (_, patch) = blockchain.create_patch(ValidatorId::zero(), Height(1), &[]);
     db.merge(patch).unwrap();
@@ -366,12 +496,63 @@ fn assert_service_execute_panic(blockchain: &Blockchain, db: &mut dyn Database)
 mod memorydb_tests {
     use futures::sync::mpsc;
 
-    use crate::blockchain::{Blockchain, Service};
+    use std::{
+        iter,
+        sync::{
+            atomic::{AtomicBool, AtomicUsize, Ordering},
+            Arc, Mutex,
+        },
+        time::Duration,
+    };
+
+    use crate::blockchain::{
+        Blockchain, GenesisConfig, Schema, Service, ValidatorKeys, DEFAULT_VALIDATOR_WEIGHT,
+        MAX_PRUNED_BLOCKS_PER_COMMIT,
+    };
     use crate::crypto::gen_keypair;
+    use crate::helpers::{Height, ValidatorId};
     use crate::node::ApiSender;
-    use exonum_merkledb::{Database, TemporaryDB};
+    use exonum_merkledb::{
+        Database, Error as StorageError, Patch, Result as StorageResult, Snapshot, TemporaryDB,
+    };
 
-    use super::{ServiceGood, ServicePanic, ServicePanicStorageError};
+    use super::{
+        AfterCommitSpyService, ServiceGood, ServicePanic, ServicePanicStorageError,
+        StateHashSpyService,
+    };
+
+    /// Database failing the given number of writes before writing successfully.
+    struct FlakyDb {
+        inner: TemporaryDB,
+        failures: Arc<AtomicUsize>,
+    }
+
+    impl FlakyDb {
+        fn fail_write(&self) -> StorageResult<()> {
+            let failures = self.failures.load(Ordering::SeqCst);
+            if failures > 0 {
+                self.failures.store(failures - 1, Ordering::SeqCst);
+                return Err(StorageError::new("Transient IO error"));
+            }
+            Ok(())
+        }
+    }
+
+    impl Database for FlakyDb {
+        fn snapshot(&self) -> Box<dyn Snapshot> {
+            self.inner.snapshot()
+        }
+
+        fn merge(&self, patch: Patch) -> StorageResult<()> {
+            self.fail_write()?;
+            self.inner.merge(patch)
+        }
+
+        fn merge_sync(&self, patch: Patch) -> StorageResult<()> {
+            self.fail_write()?;
+            self.inner.merge_sync(patch)
+        }
+    }
 
     fn create_database() -> Box<dyn Database> {
         Box::new(TemporaryDB::new())
@@ -401,6 +582,31 @@ mod memorydb_tests {
         )
     }
 
+    /// Creates a blockchain with the given database and services, and initializes it
+    /// with a genesis configuration containing a single validator.
+    fn create_initialized_blockchain<D: Into<Arc<dyn Database>>>(
+        db: D,
+        services: Vec<Box<dyn Service>>,
+    ) -> Blockchain {
+        let service_keypair = gen_keypair();
+        let mut blockchain = Blockchain::new(
+            db,
+            services,
+            service_keypair.0,
+            service_keypair.1,
+            ApiSender::new(mpsc::unbounded().0),
+        );
+        let validator_keys = ValidatorKeys {
+            consensus_key: gen_keypair().0,
+            service_key: blockchain.service_keypair.0,
+            weight: DEFAULT_VALIDATOR_WEIGHT,
+        };
+        blockchain
+            .initialize(GenesisConfig::new(iter::once(validator_keys)))
+            .unwrap();
+        blockchain
+    }
+
     #[test]
     fn handling_tx_panic() {
         let mut blockchain = create_blockchain();
@@ -414,6 +620,144 @@ mod memorydb_tests {
         super::handling_tx_panic_storage_error(&mut blockchain);
     }
 
+    #[cfg(feature = "rollback")]
+    #[test]
+    fn rollback_to_height() {
+        let mut blockchain = create_blockchain();
+        super::rollback_to_height(&mut blockchain);
+    }
+
+    #[test]
+    fn commit_retries_failed_writes() {
+        let failures = Arc::new(AtomicUsize::new(0));
+        let db = FlakyDb {
+            inner: TemporaryDB::new(),
+            failures: Arc::clone(&failures),
+        };
+        let mut blockchain = create_initialized_blockchain(
+            db,
+            vec![Box::new(super::TestService) as Box<dyn Service>],
+        );
+        blockchain.set_commit_retries(2, Duration::from_millis(1));
+
+        // Transient errors are retried.
+        failures.store(2, Ordering::SeqCst);
+        let (block_hash, patch) = blockchain.create_patch(ValidatorId::zero(), Height(1), &[]);
+        blockchain
+            .commit(&patch, block_hash, iter::empty())
+            .unwrap();
+        assert_eq!(Schema::new(&blockchain.snapshot()).height(), Height(1));
+
+        // Persistent errors are returned after the retries are exhausted.
+        failures.store(3, Ordering::SeqCst);
+        let (block_hash, patch) = blockchain.create_patch(ValidatorId::zero(), Height(2), &[]);
+        assert!(blockchain
+            .commit(&patch, block_hash, iter::empty())
+            .is_err());
+        assert_eq!(Schema::new(&blockchain.snapshot()).height(), Height(1));
+    }
+
+    fn state_hash_spy_blockchain(
+        index_prefixes: Option<Vec<String>>,
+    ) -> (Blockchain, Arc<AtomicUsize>, Arc<AtomicBool>) {
+        let state_hash_calls = Arc::new(AtomicUsize::new(0));
+        let write_state = Arc::new(AtomicBool::new(false));
+        let service = StateHashSpyService {
+            state_hash_calls: Arc::clone(&state_hash_calls),
+            write_state: Arc::clone(&write_state),
+            index_prefixes,
+        };
+        let mut blockchain = create_initialized_blockchain(
+            TemporaryDB::new(),
+            vec![
+                Box::new(super::TestService) as Box<dyn Service>,
+                Box::new(service),
+            ],
+        );
+        blockchain.enable_state_hash_precomputation();
+        blockchain.update_state_hash_cache();
+        (blockchain, state_hash_calls, write_state)
+    }
+
+    #[test]
+    fn state_hash_is_not_recomputed_for_unchanged_service() {
+        let (blockchain, state_hash_calls, write_state) =
+            state_hash_spy_blockchain(Some(vec!["spy.".to_owned()]));
+        let calls = state_hash_calls.load(Ordering::SeqCst);
+
+        // The state of the service is not changed by the block, so the cached hash is used.
+        blockchain.create_patch(ValidatorId::zero(), Height(1), &[]);
+        assert_eq!(state_hash_calls.load(Ordering::SeqCst), calls);
+
+        write_state.store(true, Ordering::SeqCst);
+        blockchain.create_patch(ValidatorId::zero(), Height(1), &[]);
+        assert_eq!(state_hash_calls.load(Ordering::SeqCst), calls + 1);
+    }
+
+    #[test]
+    fn state_hash_is_recomputed_without_opt_in() {
+        let (blockchain, state_hash_calls, _) = state_hash_spy_blockchain(None);
+        let calls = state_hash_calls.load(Ordering::SeqCst);
+
+        // The service does not declare its indices, so its state hash is never cached.
+        blockchain.create_patch(ValidatorId::zero(), Height(1), &[]);
+        assert_eq!(state_hash_calls.load(Ordering::SeqCst), calls + 1);
+    }
+
+    #[test]
+    fn after_commit_is_invoked_in_service_id_order() {
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let services = [(30, "third"), (10, "first"), (20, "second")]
+            .iter()
+            .map(|&(id, name)| {
+                Box::new(AfterCommitSpyService {
+                    id,
+                    name,
+                    calls: Arc::clone(&calls),
+                }) as Box<dyn Service>
+            })
+            .collect();
+        let mut blockchain = create_initialized_blockchain(TemporaryDB::new(), services);
+
+        for height in 1..=3 {
+            calls.lock().unwrap().clear();
+            let (block_hash, patch) =
+                blockchain.create_patch(ValidatorId::zero(), Height(height), &[]);
+            blockchain
+                .commit(&patch, block_hash, iter::empty())
+                .unwrap();
+            assert_eq!(*calls.lock().unwrap(), vec![10, 20, 30]);
+        }
+    }
+
+    #[test]
+    fn blocks_are_pruned_gradually() {
+        let mut blockchain = create_initialized_blockchain(
+            TemporaryDB::new(),
+            vec![Box::new(super::TestService) as Box<dyn Service>],
+        );
+
+        let last_height = MAX_PRUNED_BLOCKS_PER_COMMIT + 5;
+        for height in 1..=last_height {
+            let (block_hash, patch) =
+                blockchain.create_patch(ValidatorId::zero(), Height(height), &[]);
+            blockchain
+                .commit(&patch, block_hash, iter::empty())
+                .unwrap();
+        }
+
+        // Only the latest block is kept, but the history is pruned over several commits.
+        let oldest_available =
+            |blockchain: &Blockchain| Schema::new(&blockchain.snapshot()).oldest_available_height();
+        blockchain.prune_blocks(1);
+        assert_eq!(
+            oldest_available(&blockchain),
+            Height(MAX_PRUNED_BLOCKS_PER_COMMIT)
+        );
+        blockchain.prune_blocks(1);
+        assert_eq!(oldest_available(&blockchain), Height(last_height));
+    }
+
     #[test]
     fn service_execute() {
         let blockchain = create_blockchain_with_service(Box::new(ServiceGood));
@@ -16,12 +16,14 @@
 
 use hex::ToHex;
 use protobuf::Message;
-use serde::{de::DeserializeOwned, Serialize};
+use serde::{de::DeserializeOwned, ser::SerializeStruct, Serialize, Serializer};
 
 use std::{any::Any, borrow::Cow, convert::Into, error::Error, fmt, u8};
 
 use crate::crypto::{CryptoHash, Hash, PublicKey};
-use crate::messages::{HexStringRepresentation, RawTransaction, Signed, SignedMessage};
+use crate::messages::{
+    to_hex_string, HexStringRepresentation, RawTransaction, Signed, SignedMessage,
+};
 use crate::proto::{self, ProtobufConvert};
 use exonum_merkledb::{BinaryValue, Fork, ObjectHash};
 
@@ -45,7 +47,12 @@ pub struct TransactionResult(pub Result<(), TransactionError>);
 /// Data transfer object for transaction.
 /// This structure is used to send api info about transaction,
 /// and take some new transaction into pool from user input.
-#[derive(Serialize, Deserialize)]
+///
+/// Besides the hex-encoded `message` and its `debug` representation, the serialized
+/// transaction contains the `hash` of the signed message, which identifies
+/// the transaction, and the `payload_hash` of the message without its author
+/// and signature.
+#[derive(Deserialize)]
 pub struct TransactionMessage {
     #[serde(skip_deserializing)]
     #[serde(rename = "debug")]
@@ -70,7 +77,31 @@ impl ::std::fmt::Debug for TransactionMessage {
     }
 }
 
+impl Serialize for TransactionMessage {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut state = serializer.serialize_struct("TransactionMessage", 4)?;
+        state.serialize_field("debug", &self.transaction)?;
+        state.serialize_field("message", &to_hex_string(&self.message))?;
+        state.serialize_field("hash", &self.hash())?;
+        state.serialize_field("payload_hash", &self.payload_hash())?;
+        state.end()
+    }
+}
+
 impl TransactionMessage {
+    /// Returns the hash of the signed message, which is used as the transaction identifier.
+    pub fn hash(&self) -> Hash {
+        self.message.hash()
+    }
+
+    /// Returns the hash of the transaction payload without the author and the signature.
+    pub fn payload_hash(&self) -> Hash {
+        self.message.payload().payload_hash()
+    }
+
     /// Returns `SignedMessage`.
     pub fn signed_message(&self) -> &SignedMessage {
         self.message.signed_message()
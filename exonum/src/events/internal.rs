@@ -20,15 +20,23 @@ use futures::{
 
 use tokio_core::reactor::{Handle, Timeout};
 
-use std::time::{Duration, SystemTime};
+use std::{
+    collections::BinaryHeap,
+    sync::mpsc as std_mpsc,
+    thread,
+    time::{Duration, SystemTime},
+};
 
-use super::{InternalEvent, InternalRequest, TimeoutRequest};
-use crate::messages::{Message, SignedMessage};
+use super::{InternalEvent, InternalRequest, RejectionReason, TimeoutRequest};
+use crate::crypto::{PublicKey, PUBLIC_KEY_LENGTH};
+use crate::messages::{Message, SignedMessage, EMPTY_SIGNED_MESSAGE_SIZE};
 
 #[derive(Debug)]
 pub struct InternalPart {
     pub internal_tx: mpsc::Sender<InternalEvent>,
     pub internal_requests_rx: mpsc::Receiver<InternalRequest>,
+    /// Whether timeouts are fired by a dedicated timer thread instead of the event loop.
+    pub high_resolution_timeouts: bool,
 }
 
 impl InternalPart {
@@ -50,12 +58,79 @@ impl InternalPart {
         raw: Vec<u8>,
         internal_tx: mpsc::Sender<InternalEvent>,
     ) -> impl Future<Item = (), Error = ()> {
-        future::lazy(|| SignedMessage::from_raw_buffer(raw).and_then(Message::deserialize))
-            .map_err(drop)
-            .and_then(|protocol| {
-                let event = future::ok(InternalEvent::MessageVerified(Box::new(protocol)));
-                Self::send_event(event, internal_tx)
+        let event = future::lazy(|| Ok(Self::verify_raw_message(raw)));
+        Self::send_event(event, internal_tx)
+    }
+
+    /// Verifies the raw message, returning either `MessageVerified` or `MessageRejected` event.
+    pub(crate) fn verify_raw_message(raw: Vec<u8>) -> InternalEvent {
+        match Self::classify_message(raw) {
+            Ok(protocol) => InternalEvent::MessageVerified(Box::new(protocol)),
+            Err((author, reason)) => InternalEvent::MessageRejected(author, reason),
+        }
+    }
+
+    /// Deserializes the raw message and verifies its signature. If the message is rejected,
+    /// returns its author (if the buffer is long enough to contain one) and the reason.
+    fn classify_message(raw: Vec<u8>) -> Result<Message, (Option<PublicKey>, RejectionReason)> {
+        let author = raw.get(..PUBLIC_KEY_LENGTH).and_then(PublicKey::from_slice);
+        let reason = if raw.len() > EMPTY_SIGNED_MESSAGE_SIZE {
+            RejectionReason::BadSignature
+        } else {
+            RejectionReason::Malformed
+        };
+        let signed = SignedMessage::from_raw_buffer(raw).map_err(|_| (author, reason))?;
+        if !Message::has_known_type(&signed) {
+            return Err((author, RejectionReason::UnknownType));
+        }
+        Message::deserialize(signed).map_err(|_| (author, RejectionReason::Malformed))
+    }
+
+    /// Starts a thread that fires the timeouts sent to the returned sender. Unlike the event
+    /// loop timer with millisecond granularity, the thread sleeps until the exact time
+    /// of the nearest timeout. The thread stops once the sender or the receiver
+    /// of the internal events is dropped.
+    fn start_precise_timer(
+        internal_tx: mpsc::Sender<InternalEvent>,
+    ) -> std_mpsc::Sender<TimeoutRequest> {
+        let (timer_tx, timer_rx) = std_mpsc::channel();
+        thread::Builder::new()
+            .name("timeouts".to_owned())
+            .spawn(move || {
+                let mut internal_tx = internal_tx.wait();
+                let mut timers = BinaryHeap::new();
+                loop {
+                    let now = SystemTime::now();
+                    while timers
+                        .peek()
+                        .map_or(false, |TimeoutRequest(time, _)| *time <= now)
+                    {
+                        let request = timers.pop().expect("Expected scheduled timeout");
+                        if internal_tx.send(InternalEvent::Timeout(request)).is_err() {
+                            return;
+                        }
+                    }
+
+                    let received = match timers.peek() {
+                        Some(TimeoutRequest(time, _)) => {
+                            let duration = time
+                                .duration_since(now)
+                                .unwrap_or_else(|_| Duration::from_millis(0));
+                            timer_rx.recv_timeout(duration)
+                        }
+                        None => timer_rx
+                            .recv()
+                            .map_err(|_| std_mpsc::RecvTimeoutError::Disconnected),
+                    };
+                    match received {
+                        Ok(request) => timers.push(request),
+                        Err(std_mpsc::RecvTimeoutError::Timeout) => {}
+                        Err(std_mpsc::RecvTimeoutError::Disconnected) => return,
+                    }
+                }
             })
+            .expect("Unable to start the timer thread");
+        timer_tx
     }
 
     /// Represents a task that processes Internal Requests and produces Internal Events.
@@ -66,6 +141,11 @@ impl InternalPart {
         E: Executor<Box<dyn Future<Item = (), Error = ()> + Send>>,
     {
         let internal_tx = self.internal_tx;
+        let precise_timer = if self.high_resolution_timeouts {
+            Some(Self::start_precise_timer(internal_tx.clone()))
+        } else {
+            None
+        };
 
         self.internal_requests_rx
             .map(move |request| {
@@ -78,14 +158,24 @@ impl InternalPart {
                         return;
                     }
 
-                    InternalRequest::Timeout(TimeoutRequest(time, timeout)) => {
-                        let duration = time
+                    InternalRequest::Timeout(request) => {
+                        if let Some(ref timer) = precise_timer {
+                            // The timer thread stops once the receiver of the internal
+                            // events is dropped on shutdown, so the timeout is not needed.
+                            if timer.send(request).is_err() {
+                                trace!("Timer thread has stopped, timeout is dropped");
+                            }
+                            return;
+                        }
+
+                        let duration = request
+                            .0
                             .duration_since(SystemTime::now())
                             .unwrap_or_else(|_| Duration::from_millis(0));
 
                         let fut = Timeout::new(duration, &handle)
                             .expect("Unable to create timeout")
-                            .map(|()| InternalEvent::Timeout(timeout))
+                            .map(|()| InternalEvent::Timeout(request))
                             .map_err(|e| panic!("Cannot execute timeout: {:?}", e));
 
                         Either::A(fut)
@@ -125,6 +215,7 @@ mod tests {
         let internal_part = InternalPart {
             internal_tx,
             internal_requests_rx,
+            high_resolution_timeouts: false,
         };
 
         let thread = thread::spawn(|| {
@@ -162,6 +253,12 @@ mod tests {
         let tx = SignedMessage::new_with_signature(0, 0, &[0; 200], pk, Signature::zero());
 
         let event = verify_message(tx.raw().to_vec());
-        assert_eq!(event, None);
+        assert_eq!(
+            event,
+            Some(InternalEvent::MessageRejected(
+                Some(pk),
+                RejectionReason::BadSignature
+            ))
+        );
     }
 }
@@ -15,7 +15,9 @@
 #![allow(missing_debug_implementations, missing_docs)]
 
 pub use self::internal::InternalPart;
-pub use self::network::{NetworkConfiguration, NetworkEvent, NetworkPart, NetworkRequest};
+pub use self::network::{
+    ExternalAddressFallback, NetworkConfiguration, NetworkEvent, NetworkPart, NetworkRequest,
+};
 
 pub mod codec;
 pub mod error;
@@ -29,10 +31,11 @@ use futures::{
     Async, Future, Poll, Stream,
 };
 
-use std::{cmp::Ordering, time::SystemTime};
+use std::{cmp::Ordering, collections::VecDeque, fmt, time::SystemTime};
 
+use crate::crypto::PublicKey;
 use crate::helpers::{Height, Round};
-use crate::messages::Message;
+use crate::messages::{Message, Service};
 use crate::node::{ExternalMessage, NodeTimeout};
 use futures::sync::mpsc::UnboundedSender;
 
@@ -44,19 +47,64 @@ mod tests;
 pub type SyncSender<T> = Wait<Sender<T>>;
 pub type UnboundedSyncSender<T> = Wait<UnboundedSender<T>>;
 
+/// Maximum number of internal events buffered by `EventsAggregator` in order to handle
+/// consensus messages ahead of the transactions received before them.
+pub const INTERNAL_EVENTS_LOOKAHEAD: usize = 1_024;
+
 /// This kind of events is used to schedule execution in next event-loop ticks
 /// Usable to make flat logic and remove recursions.
 #[derive(Debug, PartialEq)]
 pub enum InternalEvent {
     /// Round update event.
     JumpToRound(Height, Round),
-    /// Timeout event.
-    Timeout(NodeTimeout),
+    /// Timeout event, together with the time the timeout was scheduled for.
+    Timeout(TimeoutRequest),
     /// Shutdown the node.
     Shutdown,
     /// Message has been successfully verified.
     /// Message is boxed here so that enum variants have similar size.
     MessageVerified(Box<Message>),
+    /// Message has been rejected. Contains the message author, if it could be determined.
+    MessageRejected(Option<PublicKey>, RejectionReason),
+}
+
+impl InternalEvent {
+    /// Returns `true` if the event is a verified transaction. Such events are handled
+    /// after the other internal events, e.g., consensus messages.
+    fn is_transaction(&self) -> bool {
+        match self {
+            InternalEvent::MessageVerified(message) => match **message {
+                Message::Service(Service::RawTransaction(_)) => true,
+                _ => false,
+            },
+            _ => false,
+        }
+    }
+}
+
+/// Reason of rejection of an incoming message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RejectionReason {
+    /// The message is too short or its payload cannot be decoded.
+    Malformed,
+    /// The message signature is not valid.
+    BadSignature,
+    /// The message class or type is unknown.
+    UnknownType,
+    /// The consensus message belongs to another height.
+    WrongHeight,
+}
+
+impl fmt::Display for RejectionReason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let reason = match self {
+            RejectionReason::Malformed => "malformed",
+            RejectionReason::BadSignature => "bad signature",
+            RejectionReason::UnknownType => "unknown type",
+            RejectionReason::WrongHeight => "wrong height",
+        };
+        f.write_str(reason)
+    }
 }
 
 #[derive(Debug)]
@@ -130,7 +178,7 @@ impl Into<Event> for NetworkEvent {
     }
 }
 
-impl Into<Event> for NodeTimeout {
+impl Into<Event> for TimeoutRequest {
     fn into(self) -> Event {
         Event::Internal(InternalEvent::Timeout(self))
     }
@@ -149,7 +197,12 @@ impl Into<Event> for InternalEvent {
 }
 
 /// Receives timeout, network and api events and invokes `handle_event` method of handler.
-/// If one of these streams closes, the aggregator stream completes immediately.
+/// If one of these streams closes, the aggregator stream completes immediately
+/// after yielding the buffered internal events.
+///
+/// Ready internal events are buffered (up to `INTERNAL_EVENTS_LOOKAHEAD` events),
+/// and transactions are yielded after the other buffered events. Thus, consensus
+/// messages are not delayed by a backlog of transactions.
 #[derive(Debug)]
 pub struct EventsAggregator<S1, S2, S3>
 where
@@ -158,9 +211,12 @@ where
     S3: Stream,
 {
     done: bool,
+    internal_done: bool,
     internal: S1,
     network: S2,
     api: S3,
+    prioritized: VecDeque<InternalEvent>,
+    transactions: VecDeque<InternalEvent>,
 }
 
 impl<S1, S2, S3> EventsAggregator<S1, S2, S3>
@@ -172,13 +228,45 @@ where
     pub fn new(internal: S1, network: S2, api: S3) -> Self {
         Self {
             done: false,
+            internal_done: false,
             network,
             internal,
             api,
+            prioritized: VecDeque::new(),
+            transactions: VecDeque::new(),
         }
     }
 }
 
+impl<S1, S2, S3> EventsAggregator<S1, S2, S3>
+where
+    S1: Stream<Item = InternalEvent>,
+    S2: Stream,
+    S3: Stream,
+{
+    /// Moves the ready internal events into the buffers.
+    fn buffer_internal_events(&mut self) -> Result<(), S1::Error> {
+        while !self.internal_done
+            && self.prioritized.len() + self.transactions.len() < INTERNAL_EVENTS_LOOKAHEAD
+        {
+            match self.internal.poll()? {
+                Async::Ready(None) | Async::Ready(Some(InternalEvent::Shutdown)) => {
+                    self.internal_done = true;
+                }
+                Async::Ready(Some(event)) => {
+                    if event.is_transaction() {
+                        self.transactions.push_back(event);
+                    } else {
+                        self.prioritized.push_back(event);
+                    }
+                }
+                Async::NotReady => break,
+            }
+        }
+        Ok(())
+    }
+}
+
 impl<S1, S2, S3> Stream for EventsAggregator<S1, S2, S3>
 where
     S1: Stream<Item = InternalEvent>,
@@ -192,16 +280,18 @@ where
         if self.done {
             Ok(Async::Ready(None))
         } else {
-            match self.internal.poll()? {
-                Async::Ready(None) | Async::Ready(Some(InternalEvent::Shutdown)) => {
-                    self.done = true;
-                    return Ok(Async::Ready(None));
-                }
-                Async::Ready(Some(item)) => {
-                    return Ok(Async::Ready(Some(Event::Internal(item))));
-                }
-                Async::NotReady => {}
+            self.buffer_internal_events()?;
+            let internal_event = match self.prioritized.pop_front() {
+                Some(event) => Some(event),
+                None => self.transactions.pop_front(),
             };
+            if let Some(event) = internal_event {
+                return Ok(Async::Ready(Some(Event::Internal(event))));
+            }
+            if self.internal_done {
+                self.done = true;
+                return Ok(Async::Ready(None));
+            }
             match self.network.poll()? {
                 Async::Ready(Some(item)) => {
                     return Ok(Async::Ready(Some(Event::Network(item))));
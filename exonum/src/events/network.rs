@@ -83,6 +83,51 @@ pub struct NetworkConfiguration {
     pub tcp_keep_alive: Option<u64>,
     pub tcp_connect_retry_timeout: Milliseconds,
     pub tcp_connect_max_retries: u64,
+    /// Maximum random delay added to the peer exchange timeout, in percent of `peers_timeout`.
+    /// Desynchronizes peer exchange requests of different nodes. Jitter is disabled if zero.
+    #[serde(default)]
+    pub peer_exchange_jitter_percent: u64,
+    /// Number of additional attempts to resolve the external address of the node
+    /// if its resolution fails, e.g. because of a temporary DNS failure.
+    #[serde(default = "default_resolve_retries")]
+    pub external_address_resolve_retries: u64,
+    /// Delay between the attempts to resolve the external address.
+    #[serde(default = "default_resolve_retry_timeout")]
+    pub external_address_resolve_retry_timeout: Milliseconds,
+    /// Behavior of the node if the external address cannot be resolved after all attempts.
+    #[serde(default)]
+    pub external_address_fallback: ExternalAddressFallback,
+    /// Maximum number of peers shared in response to a single peer exchange request.
+    /// If the node knows more peers, a random subset is shared. The number of new peers
+    /// accepted by the node from the responses to its own request is limited as well.
+    /// Peer exchange is not limited if the value is not set.
+    #[serde(default)]
+    pub max_peers_in_exchange: Option<usize>,
+}
+
+/// Behavior of the node if its external address cannot be resolved.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ExternalAddressFallback {
+    /// The node refuses to start.
+    FailFast,
+    /// The node advertises the last known form of the external address, i.e.,
+    /// the address as specified in the configuration, leaving its resolution to peers.
+    AdvertiseLastKnown,
+}
+
+impl Default for ExternalAddressFallback {
+    fn default() -> Self {
+        ExternalAddressFallback::AdvertiseLastKnown
+    }
+}
+
+fn default_resolve_retries() -> u64 {
+    3
+}
+
+fn default_resolve_retry_timeout() -> Milliseconds {
+    1_000
 }
 
 impl Default for NetworkConfiguration {
@@ -94,6 +139,11 @@ impl Default for NetworkConfiguration {
             tcp_nodelay: true,
             tcp_connect_retry_timeout: 15_000,
             tcp_connect_max_retries: 10,
+            peer_exchange_jitter_percent: 0,
+            external_address_resolve_retries: default_resolve_retries(),
+            external_address_resolve_retry_timeout: default_resolve_retry_timeout(),
+            external_address_fallback: ExternalAddressFallback::default(),
+            max_peers_in_exchange: None,
         }
     }
 }
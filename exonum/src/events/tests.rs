@@ -23,17 +23,22 @@ use std::{
 };
 
 use crate::blockchain::ConsensusConfig;
-use crate::crypto::{gen_keypair, gen_keypair_from_seed, PublicKey, SecretKey, Seed, SEED_LENGTH};
+use crate::crypto::{
+    gen_keypair, gen_keypair_from_seed, Hash, PublicKey, SecretKey, Seed, SEED_LENGTH,
+};
 use crate::events::{
     error::log_error,
     network::{NetworkConfiguration, NetworkPart},
     noise::HandshakeParams,
-    NetworkEvent, NetworkRequest,
+    Event, EventsAggregator, InternalEvent, NetworkEvent, NetworkRequest,
+};
+use crate::helpers::{user_agent, Height};
+use crate::messages::{
+    Connect, Message, RawTransaction, Service, ServiceTransaction, Signed, SignedMessage, Status,
 };
-use crate::helpers::user_agent;
-use crate::messages::{Connect, Message, Signed, SignedMessage};
 use crate::node::{
-    state::SharedConnectList, ConnectInfo, ConnectList, EventsPoolCapacity, NodeChannel,
+    state::SharedConnectList, ConnectInfo, ConnectList, EventsPoolCapacity, ExternalMessage,
+    NodeChannel,
 };
 
 #[derive(Debug)]
@@ -198,7 +203,12 @@ pub fn connect_message(
 ) -> Signed<Connect> {
     let time = time::UNIX_EPOCH;
     Message::concrete(
-        Connect::new(&addr.to_string(), time.into(), &user_agent::get()),
+        Connect::new(
+            &addr.to_string(),
+            time.into(),
+            &user_agent::get(),
+            &Hash::zero(),
+        ),
         *public_key,
         secret_key,
     )
@@ -228,7 +238,12 @@ impl HandshakeParams {
         let address = "127.0.0.1:8000";
 
         let connect = Message::concrete(
-            Connect::new(address, SystemTime::now().into(), &user_agent::get()),
+            Connect::new(
+                address,
+                SystemTime::now().into(),
+                &user_agent::get(),
+                &Hash::zero(),
+            ),
             public_key,
             &secret_key,
         );
@@ -596,3 +611,45 @@ fn test_connect_list_ignore_when_listening() {
     e1.wait_for_connect();
     e2.wait_for_connect();
 }
+
+#[test]
+fn test_consensus_messages_prioritized_over_transactions() {
+    const TRANSACTIONS_COUNT: usize = 100;
+
+    let (public_key, secret_key) = gen_keypair();
+    let (mut internal_tx, internal_rx) = mpsc::channel(TRANSACTIONS_COUNT + 1);
+    // The senders are kept alive, so that the aggregator does not complete.
+    let (_network_tx, network_rx) = mpsc::channel::<NetworkEvent>(0);
+    let (_api_tx, api_rx) = mpsc::unbounded::<ExternalMessage>();
+
+    for i in 0..TRANSACTIONS_COUNT {
+        let set = ServiceTransaction::from_raw_unchecked(0, vec![i as u8]);
+        let tx = Message::new(RawTransaction::new(0, set), public_key, &secret_key);
+        internal_tx
+            .try_send(InternalEvent::MessageVerified(Box::new(tx)))
+            .unwrap();
+    }
+    let status = Message::new(
+        Status::new(Height(1), &Hash::zero()),
+        public_key,
+        &secret_key,
+    );
+    internal_tx
+        .try_send(InternalEvent::MessageVerified(Box::new(status.clone())))
+        .unwrap();
+
+    let mut events = EventsAggregator::new(internal_rx, network_rx, api_rx).wait();
+    let mut next_message = || match events.next() {
+        Some(Ok(Event::Internal(InternalEvent::MessageVerified(message)))) => *message,
+        other => panic!("Unexpected event: {:?}", other),
+    };
+
+    // The status enqueued after the transactions is handled ahead of them.
+    assert_eq!(next_message(), status);
+    for _ in 0..TRANSACTIONS_COUNT {
+        match next_message() {
+            Message::Service(Service::RawTransaction(_)) => {}
+            other => panic!("Unexpected message: {:?}", other),
+        }
+    }
+}
@@ -17,19 +17,22 @@
 //!
 //! See the `explorer` example in the crate for examples of usage.
 
+use linked_hash_map::LinkedHashMap;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
 use std::{
+    borrow::Cow,
     cell::{Ref, RefCell},
     collections::Bound,
     fmt,
     ops::{Index, RangeBounds},
     slice,
+    sync::{Mutex, MutexGuard},
 };
 
 use crate::blockchain::{
-    Block, Blockchain, Schema, TransactionError, TransactionErrorType, TransactionMessage,
-    TransactionResult, TxLocation,
+    Block, Blockchain, ConsensusConfig, Schema, TransactionError, TransactionErrorType,
+    TransactionMessage, TransactionResult, TxLocation,
 };
 use crate::crypto::{CryptoHash, Hash};
 use crate::helpers::Height;
@@ -125,6 +128,19 @@ impl<'a> BlockInfo<'a> {
         self.len() == 0
     }
 
+    /// Returns `true` if the transaction bodies of this block have been pruned by the node.
+    /// Only the header, precommits and transaction hashes are available for such blocks.
+    pub fn is_pruned(&self) -> bool {
+        self.height() < self.explorer.oldest_available()
+    }
+
+    /// Returns the consensus configuration which was actual at the height of this block.
+    pub fn consensus_config(&self) -> ConsensusConfig {
+        Schema::new(&self.explorer.snapshot)
+            .configuration_by_height(self.height())
+            .consensus
+    }
+
     /// Returns a list of precommits for this block.
     pub fn precommits(&self) -> Ref<[Signed<Precommit>]> {
         if self.precommits.borrow().is_none() {
@@ -147,24 +163,33 @@ impl<'a> BlockInfo<'a> {
         Ref::map(self.txs.borrow(), |cache| cache.as_ref().unwrap().as_ref())
     }
 
-    /// Returns a transaction with the specified index in the block.
+    /// Returns a transaction with the specified index in the block. Returns `None`
+    /// if the block has been pruned.
     pub fn transaction(&self, index: usize) -> Option<CommittedTransaction> {
+        if self.is_pruned() {
+            return None;
+        }
         self.transaction_hashes()
             .get(index)
             .map(|hash| self.explorer.committed_transaction(hash, None))
     }
 
-    /// Iterates over transactions in the block.
+    /// Iterates over transactions in the block. No transactions are returned
+    /// if the block has been pruned.
     pub fn iter(&self) -> Transactions {
         Transactions {
             block: self,
             ptr: 0,
-            len: self.len(),
+            len: if self.is_pruned() { 0 } else { self.len() },
         }
     }
 
-    /// Loads transactions and precommits for the block.
-    pub fn with_transactions(self) -> BlockWithTransactions {
+    /// Loads transactions and precommits for the block. Returns `None` if the block
+    /// has been pruned.
+    pub fn with_transactions(self) -> Option<BlockWithTransactions> {
+        if self.is_pruned() {
+            return None;
+        }
         let (explorer, header, precommits, transactions) =
             (self.explorer, self.header, self.precommits, self.txs);
 
@@ -178,11 +203,11 @@ impl<'a> BlockInfo<'a> {
             .map(|tx_hash| explorer.committed_transaction(tx_hash, None))
             .collect();
 
-        BlockWithTransactions {
+        Some(BlockWithTransactions {
             header,
             precommits,
             transactions,
-        }
+        })
     }
 }
 
@@ -410,8 +435,8 @@ pub struct CommittedTransaction {
 #[derive(Debug, Serialize, Deserialize)]
 pub(crate) enum TxStatus<'a> {
     Success,
-    Panic { description: &'a str },
-    Error { code: u8, description: &'a str },
+    Panic { description: Cow<'a, str> },
+    Error { code: u8, description: Cow<'a, str> },
 }
 
 impl<'a> TxStatus<'a> {
@@ -439,7 +464,7 @@ impl<'a> From<&'a TransactionResult> for TxStatus<'a> {
         match (*result).0 {
             Ok(()) => TxStatus::Success,
             Err(ref e) => {
-                let description = e.description().unwrap_or_default();
+                let description = Cow::Borrowed(e.description().unwrap_or_default());
                 match e.error_type() {
                     Panic => TxStatus::Panic { description },
                     Code(code) => TxStatus::Error { code, description },
@@ -451,11 +476,11 @@ impl<'a> From<&'a TransactionResult> for TxStatus<'a> {
 
 impl<'a> From<TxStatus<'a>> for TransactionResult {
     fn from(status: TxStatus<'a>) -> Self {
-        fn to_option(s: &str) -> Option<String> {
+        fn to_option(s: Cow<str>) -> Option<String> {
             if s.is_empty() {
                 None
             } else {
-                Some(s.to_owned())
+                Some(s.into_owned())
             }
         };
 
@@ -615,6 +640,126 @@ impl TransactionInfo {
     }
 }
 
+/// Default number of the committed transactions cached by the explorer API.
+pub const TRANSACTION_CACHE_SIZE: usize = 1_000;
+
+/// Bounded cache of the committed transactions, which evicts the least recently used entries.
+///
+/// Committed transactions are immutable, so the cached entries never become stale
+/// unless the bodies of the transactions are pruned, in which case the entries are dropped.
+/// Transactions in the memory pool are never cached, since their status changes
+/// once they are committed.
+#[derive(Debug)]
+pub struct TransactionCache {
+    capacity: usize,
+    // Cached transactions, from the least to the most recently used one.
+    entries: Mutex<LinkedHashMap<Hash, CachedTransaction>>,
+}
+
+/// Committed transaction in the form which does not depend on the service parsing it.
+#[derive(Debug, Clone)]
+struct CachedTransaction {
+    message: Signed<RawTransaction>,
+    location: TxLocation,
+    location_proof: ListProof<Hash>,
+    status: TransactionResult,
+}
+
+impl TransactionCache {
+    /// Creates a cache holding up to `capacity` transactions. Zero capacity disables caching.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: Mutex::new(LinkedHashMap::new()),
+        }
+    }
+
+    /// Returns the maximal number of the cached transactions.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Returns the number of the cached transactions.
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    /// Returns `true` if there are no cached transactions.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns information about the transaction identified by the hash. Committed transactions
+    /// are taken from the cache if possible. Otherwise, the transaction is loaded with `load`
+    /// and cached if it is committed. Cached transactions from the pruned blocks are dropped
+    /// and loaded again.
+    pub fn get_or_load<F>(
+        &self,
+        blockchain: &Blockchain,
+        tx_hash: &Hash,
+        load: F,
+    ) -> Option<TransactionInfo>
+    where
+        F: FnOnce(&Hash) -> Option<TransactionInfo>,
+    {
+        if let Some(cached) = self.get(tx_hash) {
+            let oldest_available = Schema::new(&blockchain.snapshot()).oldest_available_height();
+            if cached.location.block_height() < oldest_available {
+                self.remove(tx_hash);
+            } else if let Ok(tx) = blockchain.tx_from_raw(cached.message.payload().clone()) {
+                return Some(TransactionInfo::Committed(CommittedTransaction {
+                    content: TransactionMessage::new(cached.message, tx),
+                    location: cached.location,
+                    location_proof: cached.location_proof,
+                    status: cached.status,
+                }));
+            }
+        }
+
+        let info = load(tx_hash)?;
+        if let TransactionInfo::Committed(ref tx) = info {
+            self.insert(
+                *tx_hash,
+                CachedTransaction {
+                    message: tx.content.message().clone(),
+                    location: tx.location,
+                    location_proof: tx.location_proof.clone(),
+                    status: tx.status.clone(),
+                },
+            );
+        }
+        Some(info)
+    }
+
+    fn lock(&self) -> MutexGuard<LinkedHashMap<Hash, CachedTransaction>> {
+        self.entries
+            .lock()
+            .expect("Expected lock on the transaction cache")
+    }
+
+    fn get(&self, tx_hash: &Hash) -> Option<CachedTransaction> {
+        // The transaction becomes the most recently used one.
+        self.lock().get_refresh(tx_hash).cloned()
+    }
+
+    fn remove(&self, tx_hash: &Hash) {
+        self.lock().remove(tx_hash);
+    }
+
+    fn insert(&self, tx_hash: Hash, tx: CachedTransaction) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        let mut entries = self.lock();
+        // An existing entry is moved to the end of the map, as well as a new one.
+        entries.insert(tx_hash, tx);
+        if entries.len() > self.capacity {
+            entries.pop_front();
+        }
+    }
+}
+
 /// Blockchain explorer.
 ///
 /// # Notes
@@ -726,6 +871,12 @@ impl<'a> BlockchainExplorer<'a> {
         schema.height()
     }
 
+    /// Returns the height of the oldest block which transactions are available. Transaction
+    /// bodies of the preceding blocks have been pruned by the node.
+    pub fn oldest_available(&self) -> Height {
+        Schema::new(&self.snapshot).oldest_available_height()
+    }
+
     /// Returns block information for the specified height or `None` if there is no such block.
     pub fn block(&self, height: Height) -> Option<BlockInfo> {
         if self.height() >= height {
@@ -736,8 +887,11 @@ impl<'a> BlockchainExplorer<'a> {
     }
 
     /// Returns block together with its transactions for the specified height, or `None`
-    /// if there is no such block.
+    /// if there is no such block or it has been pruned.
     pub fn block_with_txs(&self, height: Height) -> Option<BlockWithTransactions> {
+        if height < self.oldest_available() {
+            return None;
+        }
         let schema = Schema::new(&self.snapshot);
         let txs_table = schema.block_transactions(height);
         let block_proof = schema.block_and_precommits(height);
@@ -44,6 +44,20 @@ impl ConfigFile {
         Ok(res)
     }
 
+    /// Loads TOML-encoded file, failing if it contains top-level keys unknown to the type
+    /// of the configuration. Unlike `load`, which ignores such keys, this allows to detect
+    /// misspelled configuration parameters.
+    pub fn load_strict<P, T>(path: P) -> Result<T, Error>
+    where
+        T: DeserializeOwned + Serialize,
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref();
+        let res =
+            do_load_strict(path).context(format!("loading config from {}", path.display()))?;
+        Ok(res)
+    }
+
     /// Saves TOML-encoded file.
     pub fn save<P, T>(value: &T, path: P) -> Result<(), Error>
     where
@@ -63,6 +77,28 @@ fn do_load<T: DeserializeOwned>(path: &Path) -> Result<T, Error> {
     Ok(toml::de::from_str(&toml)?)
 }
 
+fn do_load_strict<T: DeserializeOwned + Serialize>(path: &Path) -> Result<T, Error> {
+    let raw: toml::Value = do_load(path)?;
+    let value: T = raw.clone().try_into()?;
+
+    // Keys known to the configuration type are preserved by the serialization round trip.
+    let known = toml::Value::try_from(&value)?;
+    let unknown_keys = match (raw.as_table(), known.as_table()) {
+        (Some(raw), Some(known)) => raw
+            .keys()
+            .filter(|key| !known.contains_key(key.as_str()))
+            .cloned()
+            .collect::<Vec<_>>(),
+        _ => Vec::new(),
+    };
+    ensure!(
+        unknown_keys.is_empty(),
+        "unknown configuration keys: {}",
+        unknown_keys.join(", ")
+    );
+    Ok(value)
+}
+
 fn do_save<T: Serialize>(value: &T, path: &Path) -> Result<(), Error> {
     if let Some(dir) = path.parent() {
         fs::create_dir_all(dir)?;
@@ -35,10 +35,13 @@ use super::{
     Argument, CommandName, Context, DEFAULT_EXONUM_LISTEN_PORT,
 };
 use crate::api::backends::actix::AllowOrigin;
-use crate::blockchain::{config::ValidatorKeys, GenesisConfig};
+use crate::blockchain::{
+    config::{ValidatorKeys, DEFAULT_VALIDATOR_WEIGHT},
+    GenesisConfig,
+};
 use crate::crypto::{generate_keys_file, PublicKey};
 use crate::helpers::{config::ConfigFile, ZeroizeOnDrop};
-use crate::node::{ConnectListConfig, NodeApiConfig, NodeConfig};
+use crate::node::{ConnectListConfig, NodeApiConfig, NodeConfig, DEFAULT_CONSENSUS_LOG_SIZE};
 use exonum_merkledb::{Database, DbOptions, RocksDB};
 
 const CONSENSUS_KEY_PASS_METHOD: &str = "CONSENSUS_KEY_PASS_METHOD";
@@ -52,6 +55,7 @@ const PRIVATE_API_ADDRESS: &str = "PRIVATE_API_ADDRESS";
 const PUBLIC_ALLOW_ORIGIN: &str = "PUBLIC_ALLOW_ORIGIN";
 const PUBLIC_API_ADDRESS: &str = "PUBLIC_API_ADDRESS";
 const SERVICE_KEY_PASS_METHOD: &str = "SERVICE_KEY_PASS_METHOD";
+const STRICT_CONFIG: &str = "STRICT_CONFIG";
 
 /// Run command.
 pub struct Run;
@@ -70,8 +74,12 @@ impl Run {
             .unwrap_or_else(|_| panic!("{} not found.", NODE_CONFIG_PATH))
     }
 
-    fn node_config(path: String) -> NodeConfig<PathBuf> {
-        ConfigFile::load(path).expect("Can't load node config file")
+    fn node_config(path: String, strict: bool) -> NodeConfig<PathBuf> {
+        if strict {
+            ConfigFile::load_strict(path).expect("Can't load node config file")
+        } else {
+            ConfigFile::load(path).expect("Can't load node config file")
+        }
     }
 
     fn public_api_address(ctx: &Context) -> Option<SocketAddr> {
@@ -146,6 +154,13 @@ impl Command for Run {
                 "service-key-pass",
                 false,
             ),
+            Argument::new_flag(
+                STRICT_CONFIG,
+                "Refuse to start if the node configuration file contains unknown keys",
+                None,
+                "strict-config",
+                false,
+            ),
         ]
     }
 
@@ -165,7 +180,8 @@ impl Command for Run {
     ) -> Feedback {
         let config_path = Self::node_config_path(&context);
 
-        let config = Self::node_config(config_path.clone());
+        let strict = context.get_flag_occurrences(STRICT_CONFIG).is_some();
+        let config = Self::node_config(config_path.clone(), strict);
         let public_addr = Self::public_api_address(&context);
         let private_addr = Self::private_api_address(&context);
 
@@ -602,6 +618,7 @@ impl Command for GenerateNodeConfig {
         let validator_keys = ValidatorKeys {
             consensus_key: consensus_public_key,
             service_key: service_public_key,
+            weight: DEFAULT_VALIDATOR_WEIGHT,
         };
         let node_pub_config = NodePublicConfig {
             address: addresses.0.clone(),
@@ -821,6 +838,29 @@ impl Command for Finalize {
                 database: Default::default(),
                 connect_list,
                 thread_pool_size: Default::default(),
+                record_consensus_messages: false,
+                consensus_log_size: DEFAULT_CONSENSUS_LOG_SIZE,
+                node_label: None,
+                consensus_cache_flush_interval: 0,
+                db_backend: None,
+                log_rejected_messages: false,
+                broadcast_target: Default::default(),
+                status_webhook: None,
+                accepted_services: None,
+                tx_broadcast_batch_interval: 0,
+                precompute_state_hashes: false,
+                max_tx_time_skew: None,
+                nonce_buffer_size: 0,
+                connect_list_source: None,
+                high_resolution_timeouts: false,
+                verify_execution_determinism: false,
+                halt_on_nondeterministic_execution: false,
+                expected_validators: None,
+                strict_expected_validators: false,
+                metrics_sink: None,
+                genesis_conflict_policy: Default::default(),
+                block_publisher: None,
+                prune_blocks_before: None,
             }
         };
 
@@ -0,0 +1,124 @@
+// Copyright 2019 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Logger adapter prefixing log lines with the label of the node.
+
+use log::{Log, Metadata, Record};
+
+use std::cell::RefCell;
+
+thread_local! {
+    static NODE_LABEL: RefCell<Option<String>> = RefCell::new(None);
+}
+
+/// Sets the node label for log lines emitted from the current thread.
+///
+/// The node sets the label from `NodeConfig::node_label` for its own threads,
+/// so this function is useful mostly for custom threads spawned by services.
+pub fn set_node_label(label: Option<String>) {
+    NODE_LABEL.with(|cell| *cell.borrow_mut() = label);
+}
+
+/// Returns the node label for the current thread, if any.
+pub fn node_label() -> Option<String> {
+    NODE_LABEL.with(|cell| cell.borrow().clone())
+}
+
+/// Logger adapter that prefixes every log line with the node label
+/// set for the current thread.
+#[derive(Debug)]
+pub struct LabeledLogger<L> {
+    inner: L,
+}
+
+impl<L: Log> LabeledLogger<L> {
+    /// Wraps the given logger.
+    pub fn new(inner: L) -> Self {
+        Self { inner }
+    }
+}
+
+impl<L: Log> Log for LabeledLogger<L> {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        self.inner.enabled(metadata)
+    }
+
+    fn log(&self, record: &Record) {
+        NODE_LABEL.with(|cell| match *cell.borrow() {
+            Some(ref label) => self.inner.log(
+                &Record::builder()
+                    .args(format_args!("[{}] {}", label, record.args()))
+                    .metadata(record.metadata().clone())
+                    .module_path(record.module_path())
+                    .file(record.file())
+                    .line(record.line())
+                    .build(),
+            ),
+            None => self.inner.log(record),
+        })
+    }
+
+    fn flush(&self) {
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use log::{Level, Log, Metadata, Record};
+
+    use std::sync::Mutex;
+
+    use super::{set_node_label, LabeledLogger};
+
+    #[derive(Debug, Default)]
+    struct CapturingLogger {
+        lines: Mutex<Vec<String>>,
+    }
+
+    impl Log for CapturingLogger {
+        fn enabled(&self, _metadata: &Metadata) -> bool {
+            true
+        }
+
+        fn log(&self, record: &Record) {
+            self.lines.lock().unwrap().push(record.args().to_string());
+        }
+
+        fn flush(&self) {}
+    }
+
+    fn log_line(logger: &dyn Log, message: &str) {
+        logger.log(
+            &Record::builder()
+                .args(format_args!("{}", message))
+                .level(Level::Info)
+                .target("exonum")
+                .build(),
+        );
+    }
+
+    #[test]
+    fn test_labeled_logger() {
+        let logger = LabeledLogger::new(CapturingLogger::default());
+
+        log_line(&logger, "unlabeled");
+        set_node_label(Some("node-1".to_owned()));
+        log_line(&logger, "labeled");
+        set_node_label(None);
+
+        let lines = logger.inner.lines.lock().unwrap();
+        assert_eq!(*lines, vec!["unlabeled", "[node-1] labeled"]);
+    }
+}
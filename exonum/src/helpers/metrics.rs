@@ -13,8 +13,32 @@
 // limitations under the License.
 
 //! Utilities for collecting metrics.
+//!
+//! Metrics recorded with the `metric!` macro are passed to all registered metric sinks.
+//! Sinks are registered process-wide with `add_metric_sink` until the returned registration
+//! is dropped; the node registers the sink set in `NodeConfig::metrics_sink` for its lifetime.
 
-use chrono::offset::Utc;
+use chrono::{DateTime, Utc};
+use failure::Error;
+
+use std::{
+    fmt,
+    net::{SocketAddr, ToSocketAddrs, UdpSocket},
+    sync::{Arc, RwLock},
+    time::Duration,
+};
+
+#[cfg(feature = "http-client")]
+pub use self::otlp::OtlpSink;
+
+/// Interval between the exports of the collected metrics to the OTLP endpoint.
+pub const OTLP_EXPORT_INTERVAL: Duration = Duration::from_secs(10);
+/// Timeout of a single request to the OTLP endpoint.
+pub const OTLP_EXPORT_TIMEOUT: Duration = Duration::from_secs(5);
+
+lazy_static! {
+    static ref METRIC_SINKS: RwLock<Vec<Arc<dyn MetricSink>>> = RwLock::new(Vec::new());
+}
 
 /// Adds given metric with given value.
 ///
@@ -45,12 +69,252 @@ macro_rules! metric {
 
 // Do not use directly, use `metric!` macro instead.
 #[doc(hidden)]
-#[allow(unused_variables)]
 pub fn add_metric(metric_name: &str, value: i64) {
-    let time = format!("{:?}", Utc::now());
+    let time = Utc::now();
 
     #[cfg(feature = "metrics-log")]
     {
-        trace!("{} {} {}", metric_name, value, time);
+        trace!("{} {} {:?}", metric_name, value, time);
+    }
+
+    for sink in METRIC_SINKS.read().expect("Expected read lock.").iter() {
+        sink.record(metric_name, value, time);
+    }
+}
+
+/// Destination of the metrics recorded with the `metric!` macro.
+pub trait MetricSink: fmt::Debug + Send + Sync {
+    /// Records the value of the metric at the given time. The method is called from
+    /// the thread recording the metric, so it should not block.
+    fn record(&self, name: &str, value: i64, time: DateTime<Utc>);
+}
+
+/// Registers the sink receiving all metrics recorded in the process. The sink is unregistered
+/// once the returned registration is dropped.
+pub fn add_metric_sink(sink: Arc<dyn MetricSink>) -> MetricSinkRegistration {
+    METRIC_SINKS
+        .write()
+        .expect("Expected write lock.")
+        .push(Arc::clone(&sink));
+    MetricSinkRegistration { sink }
+}
+
+/// Registration of a metric sink returned by `add_metric_sink`.
+#[derive(Debug)]
+#[must_use = "the sink is unregistered once the registration is dropped"]
+pub struct MetricSinkRegistration {
+    sink: Arc<dyn MetricSink>,
+}
+
+impl Drop for MetricSinkRegistration {
+    fn drop(&mut self) {
+        // The lock is poisoned only if a sink panicked, in which case it is not unregistered.
+        if let Ok(mut sinks) = METRIC_SINKS.write() {
+            sinks.retain(|registered| !Arc::ptr_eq(registered, &self.sink));
+        }
+    }
+}
+
+/// Metric sink set in the node configuration.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum MetricSinkConfig {
+    /// Metrics are sent as statsd gauges over UDP.
+    Statsd {
+        /// Address of the statsd server in the `host:port` format.
+        address: String,
+        /// Prefix prepended to the names of the metrics.
+        #[serde(default)]
+        prefix: String,
+    },
+    /// Metrics are exported as OpenTelemetry gauges over HTTP in the JSON encoding.
+    /// Requires the `http-client` feature.
+    Otlp {
+        /// URL of the OTLP metrics endpoint, e.g., `http://127.0.0.1:4318/v1/metrics`.
+        endpoint: String,
+    },
+}
+
+impl MetricSinkConfig {
+    /// Creates the sink described by the configuration.
+    pub fn build(&self) -> Result<Arc<dyn MetricSink>, Error> {
+        Ok(match self {
+            MetricSinkConfig::Statsd { address, prefix } => {
+                Arc::new(StatsdSink::new(address, prefix.clone())?)
+            }
+            #[cfg(feature = "http-client")]
+            MetricSinkConfig::Otlp { endpoint } => Arc::new(OtlpSink::new(endpoint.clone())?),
+            #[cfg(not(feature = "http-client"))]
+            MetricSinkConfig::Otlp { .. } => {
+                bail!("OTLP metric sink requires the `http-client` feature")
+            }
+        })
+    }
+}
+
+/// Sends metrics to a statsd server as gauges.
+#[derive(Debug)]
+pub struct StatsdSink {
+    socket: UdpSocket,
+    address: SocketAddr,
+    prefix: String,
+}
+
+impl StatsdSink {
+    /// Creates a sink sending metrics to the given address, with the names of the metrics
+    /// prefixed by `prefix`.
+    pub fn new(address: &str, prefix: String) -> Result<Self, Error> {
+        let address = address
+            .to_socket_addrs()?
+            .next()
+            .ok_or_else(|| format_err!("Unable to resolve statsd address {}", address))?;
+        let local_address = if address.is_ipv4() {
+            "0.0.0.0:0"
+        } else {
+            "[::]:0"
+        };
+        let socket = UdpSocket::bind(local_address)?;
+        socket.set_nonblocking(true)?;
+        Ok(Self {
+            socket,
+            address,
+            prefix,
+        })
+    }
+}
+
+impl MetricSink for StatsdSink {
+    fn record(&self, name: &str, value: i64, _time: DateTime<Utc>) {
+        // Negative values are interpreted by statsd as decrements, so the gauge is reset first.
+        let message = if value < 0 {
+            format!("{0}{1}:0|g\n{0}{1}:{2}|g", self.prefix, name, value)
+        } else {
+            format!("{}{}:{}|g", self.prefix, name, value)
+        };
+        if let Err(e) = self.socket.send_to(message.as_bytes(), self.address) {
+            warn!("Unable to send metric {} to statsd: {}", name, e);
+        }
+    }
+}
+
+#[cfg(feature = "http-client")]
+mod otlp {
+    use chrono::{DateTime, Utc};
+    use failure::Error;
+    use reqwest::Client;
+
+    use std::{
+        collections::HashMap,
+        sync::{
+            mpsc::{channel, RecvTimeoutError, Sender},
+            Mutex,
+        },
+        thread,
+        time::Instant,
+    };
+
+    use super::{MetricSink, OTLP_EXPORT_INTERVAL, OTLP_EXPORT_TIMEOUT};
+
+    /// Exports metrics as OpenTelemetry gauges to an OTLP/HTTP endpoint. Metrics are collected
+    /// and exported every `OTLP_EXPORT_INTERVAL` from a separate thread; only the latest value
+    /// of each metric within the interval is exported. The thread exports the remaining metrics
+    /// and stops once the sink is dropped.
+    #[derive(Debug)]
+    pub struct OtlpSink {
+        sender: Mutex<Sender<(String, i64, DateTime<Utc>)>>,
+    }
+
+    impl OtlpSink {
+        /// Creates a sink exporting metrics to the given URL and starts the exporting thread.
+        pub fn new(endpoint: String) -> Result<Self, Error> {
+            let client = Client::builder().timeout(OTLP_EXPORT_TIMEOUT).build()?;
+            let (sender, receiver) = channel();
+            thread::Builder::new()
+                .name("otlp-metrics".to_owned())
+                .spawn(move || {
+                    let mut metrics = HashMap::new();
+                    let mut deadline = Instant::now() + OTLP_EXPORT_INTERVAL;
+                    loop {
+                        // The deadline is checked before receiving, so that the metrics
+                        // are exported even if new ones keep arriving.
+                        let now = Instant::now();
+                        if now >= deadline {
+                            export_otlp_metrics(&client, &endpoint, &mut metrics);
+                            deadline = now + OTLP_EXPORT_INTERVAL;
+                            continue;
+                        }
+                        match receiver.recv_timeout(deadline - now) {
+                            Ok((name, value, time)) => {
+                                metrics.insert(name, (value, time));
+                            }
+                            Err(RecvTimeoutError::Timeout) => {}
+                            Err(RecvTimeoutError::Disconnected) => {
+                                export_otlp_metrics(&client, &endpoint, &mut metrics);
+                                break;
+                            }
+                        }
+                    }
+                })?;
+            Ok(Self {
+                sender: Mutex::new(sender),
+            })
+        }
+    }
+
+    impl MetricSink for OtlpSink {
+        fn record(&self, name: &str, value: i64, time: DateTime<Utc>) {
+            let sender = self.sender.lock().expect("Expected lock.");
+            if sender.send((name.to_owned(), value, time)).is_err() {
+                error!("OTLP metrics thread has stopped");
+            }
+        }
+    }
+
+    /// Exports the collected metrics, if any, and clears them.
+    fn export_otlp_metrics(
+        client: &Client,
+        endpoint: &str,
+        metrics: &mut HashMap<String, (i64, DateTime<Utc>)>,
+    ) {
+        if metrics.is_empty() {
+            return;
+        }
+        let metrics = metrics
+            .drain()
+            .map(|(name, (value, time))| {
+                json!({
+                    "name": name,
+                    "gauge": {
+                        "dataPoints": [{
+                            "asInt": value.to_string(),
+                            "timeUnixNano": time.timestamp_nanos().to_string(),
+                        }],
+                    },
+                })
+            })
+            .collect::<Vec<_>>();
+        let body = json!({
+            "resourceMetrics": [{
+                "resource": {
+                    "attributes": [{
+                        "key": "service.name",
+                        "value": { "stringValue": "exonum" },
+                    }],
+                },
+                "scopeMetrics": [{
+                    "scope": { "name": "exonum" },
+                    "metrics": metrics,
+                }],
+            }],
+        });
+
+        let response = client
+            .post(endpoint)
+            .json(&body)
+            .send()
+            .and_then(|response| response.error_for_status());
+        if let Err(e) = response {
+            warn!("Unable to export metrics to {}: {}", endpoint, e);
+        }
     }
 }
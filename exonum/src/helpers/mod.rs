@@ -18,6 +18,7 @@ pub use self::types::{Height, Milliseconds, Round, ValidatorId, ZeroizeOnDrop};
 
 pub mod config;
 pub mod fabric;
+pub mod logger;
 pub mod user_agent;
 #[macro_use]
 pub mod metrics;
@@ -27,17 +28,23 @@ use log::SetLoggerError;
 
 use std::path::{Component, Path, PathBuf};
 
-use crate::blockchain::{GenesisConfig, ValidatorKeys};
+use self::logger::LabeledLogger;
+use crate::blockchain::{GenesisConfig, ValidatorKeys, DEFAULT_VALIDATOR_WEIGHT};
 use crate::crypto::gen_keypair;
-use crate::node::{ConnectListConfig, NodeConfig};
+use crate::node::{ConnectListConfig, NodeConfig, DEFAULT_CONSENSUS_LOG_SIZE};
 
 mod types;
 
-/// Performs the logger initialization.
+/// Performs the logger initialization. Log lines emitted by a node with
+/// the `node_label` set are prefixed with this label.
 pub fn init_logger() -> Result<(), SetLoggerError> {
-    Builder::from_default_env()
+    let logger = Builder::from_default_env()
         .default_format_timestamp_nanos(true)
-        .try_init()
+        .build();
+    let max_level = logger.filter();
+    log::set_boxed_logger(Box::new(LabeledLogger::new(logger)))?;
+    log::set_max_level(max_level);
+    Ok(())
 }
 
 /// Generates testnet configuration.
@@ -53,6 +60,7 @@ pub fn generate_testnet_config(count: u16, start_port: u16) -> Vec<NodeConfig> {
                 .map(|x| ValidatorKeys {
                     consensus_key: (x.0).0,
                     service_key: (x.1).0,
+                    weight: DEFAULT_VALIDATOR_WEIGHT,
                 }),
         );
     let peers = (0..validators.len())
@@ -78,6 +86,29 @@ pub fn generate_testnet_config(count: u16, start_port: u16) -> Vec<NodeConfig> {
             services_configs: Default::default(),
             database: Default::default(),
             thread_pool_size: Default::default(),
+            record_consensus_messages: false,
+            consensus_log_size: DEFAULT_CONSENSUS_LOG_SIZE,
+            node_label: None,
+            consensus_cache_flush_interval: 0,
+            db_backend: None,
+            log_rejected_messages: false,
+            broadcast_target: Default::default(),
+            status_webhook: None,
+            accepted_services: None,
+            tx_broadcast_batch_interval: 0,
+            precompute_state_hashes: false,
+            max_tx_time_skew: None,
+            nonce_buffer_size: 0,
+            connect_list_source: None,
+            high_resolution_timeouts: false,
+            verify_execution_determinism: false,
+            halt_on_nondeterministic_execution: false,
+            expected_validators: None,
+            strict_expected_validators: false,
+            metrics_sink: None,
+            genesis_conflict_policy: Default::default(),
+            block_publisher: None,
+            prune_blocks_before: None,
         })
         .collect::<Vec<_>>()
 }
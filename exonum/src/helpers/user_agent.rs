@@ -31,6 +31,19 @@ pub fn get() -> String {
     format!("{}/{}", USER_AGENT, os)
 }
 
+/// Returns the version of the `exonum` crate, which is also embedded in the user agent string.
+pub fn exonum_version() -> &'static str {
+    env!("CARGO_PKG_VERSION")
+}
+
+/// Returns the hash of the git commit the `exonum` crate was built from. The hash is taken
+/// from the `EXONUM_GIT_HASH` environment variable at build time, or obtained from
+/// the git repository if the variable is not set and the crate is built from the Exonum
+/// repository. Returns `None` if neither is available.
+pub fn git_hash() -> Option<&'static str> {
+    option_env!("EXONUM_GIT_HASH")
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -36,12 +36,29 @@ impl SignedMessage {
         author: PublicKey,
         secret_key: &SecretKey,
     ) -> SignedMessage {
+        Self::new_with_signer(class, tag, value, author, |buffer| {
+            Self::sign(buffer, secret_key).expect("Couldn't form signature")
+        })
+    }
+
+    /// Creates `SignedMessage` from parts, obtaining the signature of the message
+    /// bytes from `signer`.
+    pub(crate) fn new_with_signer<F>(
+        class: u8,
+        tag: u8,
+        value: &[u8],
+        author: PublicKey,
+        signer: F,
+    ) -> SignedMessage
+    where
+        F: FnOnce(&[u8]) -> Signature,
+    {
         let mut buffer = Vec::with_capacity(2 + value.len() + PUBLIC_KEY_LENGTH + SIGNATURE_LENGTH);
         buffer.extend_from_slice(author.as_ref());
         buffer.push(class);
         buffer.push(tag);
         buffer.extend_from_slice(value);
-        let signature = Self::sign(&buffer, secret_key).expect("Couldn't form signature");
+        let signature = signer(&buffer);
         buffer.extend_from_slice(signature.as_ref());
         SignedMessage { raw: buffer }
     }
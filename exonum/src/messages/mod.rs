@@ -52,7 +52,7 @@ mod protocol;
 mod tests;
 
 /// Version of the protocol. Different versions are incompatible.
-pub const PROTOCOL_MAJOR_VERSION: u8 = 1;
+pub const PROTOCOL_MAJOR_VERSION: u8 = 2;
 pub(crate) const RAW_TRANSACTION_HEADER: usize = mem::size_of::<u16>() * 2;
 
 /// Transaction raw buffer.
@@ -114,6 +114,13 @@ impl RawTransaction {
     pub(crate) fn transaction_id(&self) -> u16 {
         self.service_transaction.transaction_id()
     }
+
+    /// Returns the hash of the transaction payload. Unlike the hash of the signed message,
+    /// which is used as the transaction identifier, it covers neither the transaction
+    /// author nor the signature.
+    pub fn payload_hash(&self) -> Hash {
+        hash(&self.to_bytes())
+    }
 }
 
 impl BinaryValue for RawTransaction {
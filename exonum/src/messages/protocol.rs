@@ -33,7 +33,9 @@ use std::{borrow::Cow, fmt::Debug, mem};
 
 use super::{RawTransaction, ServiceTransaction, Signed, SignedMessage};
 use crate::blockchain;
-use crate::crypto::{CryptoHash, Hash, PublicKey, SecretKey, PUBLIC_KEY_LENGTH, SIGNATURE_LENGTH};
+use crate::crypto::{
+    self, CryptoHash, Hash, PublicKey, SecretKey, Signature, PUBLIC_KEY_LENGTH, SIGNATURE_LENGTH,
+};
 use crate::helpers::{Height, Round, ValidatorId};
 use crate::proto;
 use exonum_merkledb::{BinaryValue, HashTag};
@@ -55,7 +57,8 @@ pub const RAW_TRANSACTION_EMPTY_SIZE: usize = EMPTY_SIGNED_MESSAGE_SIZE + mem::s
 ///
 /// ### Validation
 /// The message is ignored if its time is earlier than in the previous
-/// `Connect` message received from the same peer.
+/// `Connect` message received from the same peer or if its genesis block hash
+/// differs from the one of the node.
 ///
 /// ### Processing
 /// Connect to the peer.
@@ -73,15 +76,18 @@ pub struct Connect {
     time: DateTime<Utc>,
     /// String containing information about this node including Exonum, Rust and OS versions.
     user_agent: String,
+    /// Hash of the genesis block of the node's blockchain.
+    genesis_hash: Hash,
 }
 
 impl Connect {
     /// Create new `Connect` message.
-    pub fn new(addr: &str, time: DateTime<Utc>, user_agent: &str) -> Self {
+    pub fn new(addr: &str, time: DateTime<Utc>, user_agent: &str, genesis_hash: &Hash) -> Self {
         Connect {
             pub_addr: addr.to_owned(),
             time,
             user_agent: user_agent.to_owned(),
+            genesis_hash: *genesis_hash,
         }
     }
 
@@ -99,6 +105,11 @@ impl Connect {
     pub fn user_agent(&self) -> &str {
         &self.user_agent
     }
+
+    /// Hash of the genesis block of the node's blockchain.
+    pub fn genesis_hash(&self) -> &Hash {
+        &self.genesis_hash
+    }
 }
 
 /// Current node status.
@@ -440,7 +451,9 @@ impl BlockResponse {
 /// Returns information about the transactions requested by the hash.
 ///
 /// ### Generation
-/// The message is sent as response to `TransactionsRequest`.
+/// The message is sent as response to `TransactionsRequest`. Additionally, it is used
+/// to broadcast the transactions received by the node in batches if
+/// `NodeConfig::tx_broadcast_batch_interval` is set.
 #[derive(Clone, PartialEq, Eq, Ord, PartialOrd, Debug, ProtobufConvert)]
 #[exonum(pb = "proto::TransactionsResponse", crate = "crate")]
 pub struct TransactionsResponse {
@@ -796,6 +809,19 @@ macro_rules! impl_protocol {
         }
 
         impl $protocol_name {
+            /// Checks whether the class and the type of the `SignedMessage` are known.
+            pub(crate) fn has_known_type(message: &SignedMessage) -> bool {
+                match message.message_class() {
+                    $($class_num =>
+                        match message.message_type() {
+                            $($type_num)|+ => true,
+                            _ => false,
+                        }
+                    ),+
+                    _ => false,
+                }
+            }
+
             /// Converts raw `SignedMessage` into concrete `Message` message.
             /// Returns error if fails.
             pub fn deserialize(message: SignedMessage) -> Result<Self, failure::Error> {
@@ -905,9 +931,24 @@ impl Message {
         author: PublicKey,
         secret_key: &SecretKey,
     ) -> Signed<T> {
+        Self::concrete_with_signer(message, author, |buffer| crypto::sign(buffer, secret_key))
+    }
+
+    /// Creates new protocol message signed by an external signer, e.g., a hardware
+    /// security module holding the secret key. `signer` receives the message bytes
+    /// and must return their Ed25519 signature made with the key corresponding to `author`.
+    ///
+    /// # Panics
+    ///
+    /// This method can panic on serialization failure.
+    pub fn concrete_with_signer<T, F>(message: T, author: PublicKey, signer: F) -> Signed<T>
+    where
+        T: ProtocolMessage,
+        F: FnOnce(&[u8]) -> Signature,
+    {
         let value = message.to_bytes();
         let (cls, typ) = T::message_type();
-        let signed = SignedMessage::new(cls, typ, &value, author, secret_key);
+        let signed = SignedMessage::new_with_signer(cls, typ, &value, author, signer);
         T::into_message_from_parts(message, signed)
     }
 
@@ -64,6 +64,37 @@ fn test_known_transaction() {
     assert_eq!(res, hex::encode(msg.signed_message().raw()));
 }
 
+#[test]
+fn test_concrete_with_external_signer() {
+    use crate::crypto;
+
+    let (pk, sk) = gen_keypair();
+    let data = CreateWallet::new(&pk, "test_wallet");
+    let set = ServiceTransaction::from_raw_unchecked(0, data.to_bytes());
+    let tx = RawTransaction::new(128, set);
+
+    // The signer only sees the message bytes, as an external signing device would.
+    let signer_key = sk.clone();
+    let signed = Message::concrete_with_signer(tx.clone(), pk, move |buffer| {
+        crypto::sign(buffer, &signer_key)
+    });
+    let expected = Message::concrete(tx, pk, &sk);
+    assert_eq!(signed, expected);
+
+    let raw = signed.signed_message().raw().to_vec();
+    SignedMessage::from_raw_buffer(raw).unwrap();
+
+    // Signature made with a key not matching the author is rejected.
+    let (_, other_sk) = gen_keypair();
+    let data = CreateWallet::new(&pk, "test_wallet");
+    let set = ServiceTransaction::from_raw_unchecked(0, data.to_bytes());
+    let forged = Message::concrete_with_signer(RawTransaction::new(128, set), pk, |buffer| {
+        crypto::sign(buffer, &other_sk)
+    });
+    let raw = forged.signed_message().raw().to_vec();
+    assert!(SignedMessage::from_raw_buffer(raw).is_err());
+}
+
 #[test]
 fn test_empty_tx_size() {
     use crate::crypto::{gen_keypair_from_seed, Seed};
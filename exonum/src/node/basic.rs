@@ -12,26 +12,42 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use rand::Rng;
+use rand::{seq::SliceRandom, Rng};
 
 use super::{NodeHandler, NodeRole, RequestData};
+#[cfg(feature = "consensus-log")]
+use crate::blockchain::ConsensusLogEntry;
 use crate::crypto::PublicKey;
 use crate::events::error::LogError;
 use crate::events::network::ConnectedPeerAddr;
+use crate::events::{NetworkRequest, RejectionReason};
 use crate::helpers::Height;
+#[cfg(feature = "consensus-log")]
+use crate::messages::{to_hex_string, Consensus};
 use crate::messages::{Connect, Message, PeersRequest, Responses, Service, Signed, Status};
 
 impl NodeHandler {
     /// Redirects message to the corresponding `handle_...` function.
     pub fn handle_message(&mut self, msg: Message) {
+        #[cfg(feature = "consensus-log")]
+        {
+            if self.api_state.is_consensus_log_enabled() {
+                self.record_consensus_message(&msg);
+            }
+        }
+
         match msg {
             Message::Consensus(msg) => self.handle_consensus(msg),
             Message::Requests(ref msg) => self.handle_request(msg),
 
-            Message::Service(Service::Connect(msg)) => self.handle_connect(msg),
+            Message::Service(Service::Connect(msg)) => self.handle_exchanged_connect(msg),
             Message::Service(Service::Status(msg)) => self.handle_status(&msg),
             // ignore tx duplication error,
-            Message::Service(Service::RawTransaction(msg)) => drop(self.handle_tx(msg)),
+            Message::Service(Service::RawTransaction(msg)) => {
+                for tx in self.release_by_nonce(msg) {
+                    drop(self.handle_tx(tx));
+                }
+            }
             Message::Responses(Responses::BlockResponse(msg)) => {
                 self.handle_block(&msg).log_error()
             }
@@ -41,10 +57,65 @@ impl NodeHandler {
         }
     }
 
+    /// Logs the rejected incoming message if logging of rejected messages is enabled
+    /// and the rate limit is not exceeded.
+    pub(crate) fn log_rejected_message(
+        &mut self,
+        author: Option<PublicKey>,
+        reason: RejectionReason,
+    ) {
+        let now = self.system_state.current_time();
+        let allowed = match self.rejected_messages_log {
+            Some(ref mut log) => log.allow(now),
+            None => false,
+        };
+        if allowed {
+            match author {
+                Some(author) => warn!("Rejected message from {}: {}", author.to_hex(), reason),
+                None => warn!("Rejected message from unknown author: {}", reason),
+            }
+        }
+    }
+
+    /// Appends consensus and status messages to the consensus log.
+    #[cfg(feature = "consensus-log")]
+    fn record_consensus_message(&self, msg: &Message) {
+        let entry = match *msg {
+            Message::Consensus(ref msg) => {
+                let (message_type, message) = match *msg {
+                    Consensus::Propose(ref msg) => ("propose", to_hex_string(msg)),
+                    Consensus::Prevote(ref msg) => ("prevote", to_hex_string(msg)),
+                    Consensus::Precommit(ref msg) => ("precommit", to_hex_string(msg)),
+                };
+                ConsensusLogEntry {
+                    message_type: message_type.to_owned(),
+                    height: msg.height(),
+                    round: Some(msg.round()),
+                    author: msg.author(),
+                    message,
+                }
+            }
+            Message::Service(Service::Status(ref msg)) => ConsensusLogEntry {
+                message_type: "status".to_owned(),
+                height: msg.height(),
+                round: None,
+                author: msg.author(),
+                message: to_hex_string(msg),
+            },
+            _ => return,
+        };
+        self.api_state.record_consensus_message(entry);
+    }
+
     /// Handles the `Connected` event. Node's `Connect` message is sent as response
     /// if received `Connect` message is correct.
     pub fn handle_connected(&mut self, address: &ConnectedPeerAddr, connect: Signed<Connect>) {
         info!("Received Connect message from peer: {:?}", address);
+        if !self.is_genesis_hash_matched(&connect) {
+            // The peer belongs to another network, so the connection is useless.
+            self.send_network_request(NetworkRequest::DisconnectWithPeer(connect.author()));
+            return;
+        }
         // TODO: use `ConnectInfo` instead of connect-messages. (ECR-1452)
         self.state.add_connection(connect.author(), address.clone());
         self.handle_connect(connect);
@@ -69,6 +140,7 @@ impl NodeHandler {
     fn remove_peer_with_addr(&mut self, key: PublicKey) {
         self.state.remove_peer_with_pubkey(&key);
         self.blockchain.remove_peer_with_pubkey(&key);
+        self.check_online_validators();
         let is_validator = self.state.peer_is_validator(&key);
         let in_connect_list = self.state.peer_in_connect_list(&key);
         if is_validator && in_connect_list {
@@ -76,6 +148,24 @@ impl NodeHandler {
         }
     }
 
+    /// Checks that the peer shares the genesis block with this node. Peers with a different
+    /// genesis block belong to another network, so the node refuses to interact with them.
+    fn is_genesis_hash_matched(&self, connect: &Signed<Connect>) -> bool {
+        let our_genesis_hash = self.state.our_connect_message().genesis_hash();
+        if connect.genesis_hash() != our_genesis_hash {
+            error!(
+                "Refusing to peer with {} at {}: its genesis block hash {:?} differs from ours {:?}. \
+                 Check that both nodes are configured with the same genesis configuration.",
+                connect.author(),
+                connect.pub_addr(),
+                connect.genesis_hash(),
+                our_genesis_hash,
+            );
+            return false;
+        }
+        true
+    }
+
     /// Handles the `Connect` message and connects to a peer as result.
     pub fn handle_connect(&mut self, message: Signed<Connect>) {
         // TODO Add spam protection (ECR-170)
@@ -100,6 +190,10 @@ impl NodeHandler {
             return;
         }
 
+        if !self.is_genesis_hash_matched(&message) {
+            return;
+        }
+
         // Check if we have another connect message from peer with the given public_key.
         let mut need_connect = true;
         if let Some(saved_message) = self.state.peers().get(&public_key) {
@@ -126,6 +220,7 @@ impl NodeHandler {
             }
         }
         self.state.add_peer(public_key, message.clone());
+        self.check_online_validators();
         info!(
             "Received Connect message from {}. Need to connect: {}",
             address, need_connect,
@@ -170,12 +265,43 @@ impl NodeHandler {
             // Request block
             self.request(RequestData::Block(height), peer);
         }
+
+        if let Some(ref mut warmup) = self.api_warmup {
+            warmup.peer_status_received = true;
+        }
+        self.update_api_readiness();
+    }
+
+    /// Handles the `Connect` message of a peer received from another peer, usually
+    /// in response to the `PeersRequest`. Messages introducing new peers are ignored
+    /// once `max_peers_in_exchange` new peers have been accepted since the last request.
+    fn handle_exchanged_connect(&mut self, message: Signed<Connect>) {
+        if !self.state.peers().contains_key(&message.author()) {
+            if let Some(ref mut peers_left) = self.exchanged_peers_left {
+                if *peers_left == 0 {
+                    warn!(
+                        "Ignoring Connect message of {} exceeding the peer exchange limit",
+                        message.author()
+                    );
+                    return;
+                }
+                *peers_left -= 1;
+            }
+        }
+        self.handle_connect(message);
     }
 
     /// Handles the `PeersRequest` message. Node sends `Connect` messages of other peers as result.
+    /// If `max_peers_in_exchange` is set, a random subset of the peers is sent.
     pub fn handle_request_peers(&mut self, msg: &Signed<PeersRequest>) {
-        let peers: Vec<Signed<Connect>> =
+        let mut peers: Vec<Signed<Connect>> =
             self.state.peers().iter().map(|(_, b)| b.clone()).collect();
+        if let Some(max_peers) = self.max_peers_in_exchange {
+            if peers.len() > max_peers {
+                peers.shuffle(&mut self.rng);
+                peers.truncate(max_peers);
+            }
+        }
         trace!(
             "HANDLE REQUEST PEERS: Sending {:?} peers to {:?}",
             peers,
@@ -198,21 +324,17 @@ impl NodeHandler {
     /// Handles `NodeTimeout::PeerExchange`. Node sends the `PeersRequest` to a random peer.
     pub fn handle_peer_exchange_timeout(&mut self) {
         if !self.state.peers().is_empty() {
-            let to = self.state.peers().len();
-            let gen_peer_id = || -> usize {
-                let mut rng = rand::thread_rng();
-                rng.gen_range(0, to)
-            };
-
+            let peer_id = self.rng.gen_range(0, self.state.peers().len());
             let peer = self
                 .state
                 .peers()
                 .iter()
-                .nth(gen_peer_id())
+                .nth(peer_id)
                 .map(|x| x.1.clone())
                 .unwrap();
             let msg = PeersRequest::new(&peer.author());
             trace!("Request peers from peer with addr {:?}", peer.pub_addr());
+            self.exchanged_peers_left = self.max_peers_in_exchange;
             let message = self.sign_message(msg);
             self.send_to_peer(peer.author(), message);
         }
@@ -222,7 +344,10 @@ impl NodeHandler {
     /// Node update internal `ApiState` and `NodeRole`.
     pub fn handle_update_api_state_timeout(&mut self) {
         self.api_state.update_node_state(&self.state);
+        self.api_state.set_round_schedule(self.round_schedule());
         self.node_role = NodeRole::new(self.state.validator_id());
+        self.update_status_webhook();
+        self.update_api_readiness();
         self.add_update_api_state_timeout();
     }
 
@@ -0,0 +1,194 @@
+// Copyright 2019 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Notifications about the committed blocks published to a message queue.
+//!
+//! The publisher is set in `NodeConfig::block_publisher`. The blocks are published from
+//! a separate thread through a bounded buffer, so a slow or unavailable message queue
+//! does not affect the consensus. If the buffer is full, the block is not published.
+
+use failure::Error;
+
+use std::{
+    fmt,
+    sync::mpsc::{sync_channel, Receiver, SyncSender, TrySendError},
+    thread,
+};
+
+use crate::blockchain::Block;
+use crate::crypto::Hash;
+
+/// Maximal number of committed blocks waiting to be published.
+pub const BLOCK_PUBLISHER_BUFFER_SIZE: usize = 1_024;
+
+/// Committed block as published to a message queue.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PublishedBlock {
+    /// Header of the committed block.
+    pub block: Block,
+    /// Hashes of the transactions in the block, in the order of their execution.
+    pub transactions: Vec<Hash>,
+}
+
+/// Destination of the committed blocks, such as a message queue.
+pub trait BlockPublisher: Send + 'static {
+    /// Publishes the committed block. The method is invoked from a separate thread in the
+    /// order the blocks are committed. Errors are logged and do not stop the publisher.
+    fn publish(&mut self, block: &PublishedBlock) -> Result<(), Error>;
+}
+
+/// Configuration of the publisher of the committed blocks.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum PublisherConfig {
+    /// Publishes the blocks serialized as JSON to a Kafka topic. Requires the
+    /// `kafka-publisher` feature.
+    Kafka {
+        /// Addresses of the Kafka brokers, e.g., `localhost:9092`.
+        brokers: Vec<String>,
+        /// Topic the blocks are published to.
+        topic: String,
+    },
+}
+
+impl PublisherConfig {
+    /// Checks the configuration without connecting to the message queue.
+    pub fn check(&self) -> Result<(), Error> {
+        match *self {
+            PublisherConfig::Kafka {
+                ref brokers,
+                ref topic,
+            } => kafka_publisher::check(brokers, topic),
+        }
+    }
+
+    /// Creates the publisher described by the configuration.
+    pub fn build(&self) -> Result<Box<dyn BlockPublisher>, Error> {
+        match *self {
+            PublisherConfig::Kafka {
+                ref brokers,
+                ref topic,
+            } => kafka_publisher::build(brokers, topic),
+        }
+    }
+}
+
+/// Passes the committed blocks to the publisher running in a separate thread.
+pub(crate) struct BlockPublisherHandle {
+    sender: SyncSender<PublishedBlock>,
+}
+
+impl BlockPublisherHandle {
+    /// Starts the thread publishing the blocks with the given publisher.
+    pub fn new(publisher: Box<dyn BlockPublisher>) -> Result<Self, Error> {
+        let (sender, receiver) = sync_channel(BLOCK_PUBLISHER_BUFFER_SIZE);
+        thread::Builder::new()
+            .name("block-publisher".to_owned())
+            .spawn(move || run_publisher(publisher, &receiver))?;
+        Ok(Self { sender })
+    }
+
+    /// Queues the committed block for publishing without blocking.
+    pub fn publish(&self, block: PublishedBlock) {
+        match self.sender.try_send(block) {
+            Ok(()) => {}
+            Err(TrySendError::Full(block)) => warn!(
+                "Block publisher buffer is full, block at height {} is not published",
+                block.block.height()
+            ),
+            Err(TrySendError::Disconnected(_)) => error!("Block publisher thread has stopped"),
+        }
+    }
+}
+
+impl fmt::Debug for BlockPublisherHandle {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("BlockPublisherHandle").finish()
+    }
+}
+
+fn run_publisher(mut publisher: Box<dyn BlockPublisher>, receiver: &Receiver<PublishedBlock>) {
+    for block in receiver {
+        if let Err(e) = publisher.publish(&block) {
+            error!(
+                "Failed to publish block at height {}: {}",
+                block.block.height(),
+                e
+            );
+        }
+    }
+}
+
+#[cfg(feature = "kafka-publisher")]
+mod kafka_publisher {
+    use failure::Error;
+    use kafka::producer::{Producer, Record, RequiredAcks};
+
+    use std::time::Duration;
+
+    use super::{BlockPublisher, PublishedBlock};
+
+    /// Timeout of the acknowledgement of a published block by the Kafka brokers.
+    const KAFKA_ACK_TIMEOUT: Duration = Duration::from_secs(5);
+
+    struct KafkaPublisher {
+        producer: Producer,
+        topic: String,
+    }
+
+    impl BlockPublisher for KafkaPublisher {
+        fn publish(&mut self, block: &PublishedBlock) -> Result<(), Error> {
+            let payload = serde_json::to_vec(block)?;
+            self.producer
+                .send(&Record::from_value(&self.topic, payload.as_slice()))
+                .map_err(|e| format_err!("{}", e))
+        }
+    }
+
+    pub fn check(brokers: &[String], topic: &str) -> Result<(), Error> {
+        ensure!(
+            !brokers.is_empty(),
+            "Kafka block publisher requires at least one broker"
+        );
+        ensure!(!topic.is_empty(), "Kafka block publisher requires a topic");
+        Ok(())
+    }
+
+    pub fn build(brokers: &[String], topic: &str) -> Result<Box<dyn BlockPublisher>, Error> {
+        let producer = Producer::from_hosts(brokers.to_vec())
+            .with_ack_timeout(KAFKA_ACK_TIMEOUT)
+            .with_required_acks(RequiredAcks::One)
+            .create()
+            .map_err(|e| format_err!("Unable to connect to Kafka: {}", e))?;
+        Ok(Box::new(KafkaPublisher {
+            producer,
+            topic: topic.to_owned(),
+        }))
+    }
+}
+
+#[cfg(not(feature = "kafka-publisher"))]
+mod kafka_publisher {
+    use failure::Error;
+
+    use super::BlockPublisher;
+
+    pub fn check(_brokers: &[String], _topic: &str) -> Result<(), Error> {
+        bail!("Kafka block publisher requires the `kafka-publisher` feature")
+    }
+
+    pub fn build(_brokers: &[String], _topic: &str) -> Result<Box<dyn BlockPublisher>, Error> {
+        bail!("Kafka block publisher requires the `kafka-publisher` feature")
+    }
+}
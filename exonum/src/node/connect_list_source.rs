@@ -0,0 +1,157 @@
+// Copyright 2019 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Periodic refresh of the node connect list from an HTTP endpoint.
+//!
+//! The endpoint is set in `NodeConfig::connect_list_source` and should return
+//! a `ConnectListConfig` in the JSON format. The list is fetched from a separate thread
+//! and applied via `ExternalMessage::ReplaceConnectList` only if it is valid, contains
+//! all the current validators and differs from the previously applied one. Fetching
+//! the list requires the `http-client` feature.
+
+use failure::Error;
+#[cfg(feature = "http-client")]
+use reqwest::Client;
+use url::Url;
+
+use std::{sync::mpsc::Sender, time::Duration};
+#[cfg(feature = "http-client")]
+use std::{
+    sync::mpsc::{channel, RecvTimeoutError},
+    thread,
+};
+
+use super::ApiSender;
+#[cfg(feature = "http-client")]
+use super::ConnectListConfig;
+use crate::blockchain::Blockchain;
+#[cfg(feature = "http-client")]
+use crate::blockchain::Schema;
+use crate::crypto::PublicKey;
+
+/// Interval between the requests to the connect list source.
+pub const CONNECT_LIST_REFRESH_INTERVAL: Duration = Duration::from_secs(60);
+/// Timeout of a single request to the connect list source.
+pub const CONNECT_LIST_SOURCE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Handle of the thread refreshing the connect list. The thread stops once the handle
+/// is dropped.
+#[derive(Debug)]
+pub struct ConnectListSource {
+    stop: Sender<()>,
+}
+
+impl Drop for ConnectListSource {
+    fn drop(&mut self) {
+        // The thread may have already stopped.
+        let _ = self.stop.send(());
+    }
+}
+
+/// Starts the thread refreshing the connect list of the node from the given URL.
+/// The lists missing any of the current validators other than the node itself,
+/// identified by `consensus_key`, are ignored.
+#[cfg(feature = "http-client")]
+pub(crate) fn start(
+    url: Url,
+    interval: Duration,
+    blockchain: Blockchain,
+    consensus_key: PublicKey,
+    api_sender: ApiSender,
+) -> Result<ConnectListSource, Error> {
+    let client = Client::builder()
+        .timeout(CONNECT_LIST_SOURCE_TIMEOUT)
+        .build()?;
+    let (stop, stop_rx) = channel();
+    thread::Builder::new()
+        .name("connect-list-source".to_owned())
+        .spawn(move || {
+            let mut applied = None;
+            loop {
+                let list = fetch_connect_list(&client, &url).and_then(|list| {
+                    check_connect_list(&list, &blockchain, &consensus_key)?;
+                    Ok(list)
+                });
+                match list {
+                    Ok(ref list) if applied.as_ref() == Some(list) => {}
+                    Ok(list) => {
+                        info!("Refreshed connect list from {}", url);
+                        if api_sender.replace_connect_list(list.clone()).is_err() {
+                            break;
+                        }
+                        applied = Some(list);
+                    }
+                    Err(e) => warn!("Failed to refresh connect list from {}: {}", url, e),
+                }
+                match stop_rx.recv_timeout(interval) {
+                    Err(RecvTimeoutError::Timeout) => {}
+                    Ok(()) | Err(RecvTimeoutError::Disconnected) => break,
+                }
+            }
+        })?;
+    Ok(ConnectListSource { stop })
+}
+
+#[cfg(not(feature = "http-client"))]
+pub(crate) fn start(
+    _url: Url,
+    _interval: Duration,
+    _blockchain: Blockchain,
+    _consensus_key: PublicKey,
+    _api_sender: ApiSender,
+) -> Result<ConnectListSource, Error> {
+    bail!("Connect list source requires the `http-client` feature")
+}
+
+/// Checks that the URL of the connect list source is an HTTP one.
+pub(crate) fn check_url(url: &Url) -> Result<(), Error> {
+    ensure!(
+        url.scheme() == "http" || url.scheme() == "https",
+        "Connect list source should be an HTTP or HTTPS URL, got {}",
+        url
+    );
+    Ok(())
+}
+
+#[cfg(feature = "http-client")]
+fn fetch_connect_list(client: &Client, url: &Url) -> Result<ConnectListConfig, Error> {
+    let list: ConnectListConfig = client.get(url.clone()).send()?.error_for_status()?.json()?;
+    list.validate()?;
+    Ok(list)
+}
+
+/// Checks that the fetched connect list is not empty and contains all the current
+/// validators except the node itself.
+#[cfg(feature = "http-client")]
+pub(crate) fn check_connect_list(
+    list: &ConnectListConfig,
+    blockchain: &Blockchain,
+    consensus_key: &PublicKey,
+) -> Result<(), Error> {
+    ensure!(!list.peers.is_empty(), "Connect list is empty");
+    let snapshot = blockchain.snapshot();
+    let validators = Schema::new(&snapshot).actual_configuration().validator_keys;
+    for key in validators
+        .iter()
+        .map(|validator| validator.consensus_key)
+        .filter(|key| key != consensus_key)
+    {
+        ensure!(
+            list.peers.iter().any(|peer| peer.public_key == key),
+            "Connect list misses validator {:?}",
+            key
+        );
+    }
+    Ok(())
+}
@@ -12,18 +12,18 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::collections::HashSet;
+use std::{collections::HashSet, iter};
 
-use crate::blockchain::Schema;
+use crate::blockchain::{RejectedTransaction, Schema};
 use crate::crypto::{CryptoHash, Hash, PublicKey};
-use crate::events::InternalRequest;
+use crate::events::{InternalRequest, RejectionReason};
 use crate::helpers::{Height, Round, ValidatorId};
 use crate::messages::{
     BlockRequest, BlockResponse, Consensus as ConsensusMessage, Precommit, Prevote,
     PrevotesRequest, Propose, ProposeRequest, RawTransaction, Signed, SignedMessage,
     TransactionsRequest, TransactionsResponse,
 };
-use crate::node::{NodeHandler, RequestData};
+use crate::node::{NodeHandler, PublishedBlock, RequestData};
 use exonum_merkledb::Patch;
 
 // TODO Reduce view invocations. (ECR-171)
@@ -51,6 +51,7 @@ impl NodeHandler {
 
         // Ignore messages from previous and future height
         if msg.height() < self.state.height() || msg.height() > self.state.height().next() {
+            self.log_rejected_message(Some(msg.author()), RejectionReason::WrongHeight);
             return;
         }
 
@@ -110,6 +111,34 @@ impl NodeHandler {
             return;
         }
 
+        // Check propose size
+        let consensus_config = self.state.consensus_config();
+        let propose_len = msg.signed_message().raw().len();
+        if propose_len > consensus_config.max_message_len as usize {
+            error!(
+                "Received oversized propose: hash={}, size={}, transactions={}, \
+                 max_message_len={}",
+                msg.hash().to_hex(),
+                propose_len,
+                msg.transactions().len(),
+                consensus_config.max_message_len
+            );
+            return;
+        }
+        if let Some(max_propose_txs) = consensus_config.max_propose_txs {
+            if msg.transactions().len() > max_propose_txs as usize {
+                error!(
+                    "Received propose with too many transactions: hash={}, size={}, \
+                     transactions={}, max_propose_txs={}",
+                    msg.hash().to_hex(),
+                    propose_len,
+                    msg.transactions().len(),
+                    max_propose_txs
+                );
+                return;
+            }
+        }
+
         trace!("Handle propose");
 
         let snapshot = self.blockchain.snapshot();
@@ -237,11 +266,20 @@ impl NodeHandler {
     }
 
     /// Executes and commits block. This function is called when node has full propose information.
+    ///
+    /// If the node has not prevoted in the propose round yet, it prevotes for the full propose
+    /// of the round with the lowest hash, which is not necessarily the handled one.
     pub fn handle_full_propose(&mut self, hash: Hash, propose_round: Round) {
         // Send prevote
         if self.state.locked_round() == Round::zero() {
-            if self.state.is_validator() && !self.state.have_prevote(propose_round) {
-                self.broadcast_prevote(propose_round, &hash);
+            if self.is_active_validator() && !self.state.have_prevote(propose_round) {
+                // Several proposes for the round may become full simultaneously,
+                // in which case the one with the lowest hash is prevoted.
+                let prevote_hash = self
+                    .state
+                    .preferred_full_propose(propose_round)
+                    .unwrap_or(hash);
+                self.broadcast_prevote(propose_round, &prevote_hash);
             } else {
                 // TODO: what if we HAVE prevote for the propose round? (ECR-171)
             }
@@ -400,7 +438,7 @@ impl NodeHandler {
         trace!("MAKE LOCK {:?} {:?}", prevote_round, propose_hash);
         for round in prevote_round.iter_to(self.state.round().next()) {
             // Send prevotes
-            if self.state.is_validator() && !self.state.have_prevote(round) {
+            if self.is_active_validator() && !self.state.have_prevote(round) {
                 self.broadcast_prevote(round, &propose_hash);
             }
 
@@ -412,12 +450,13 @@ impl NodeHandler {
                     .state
                     .prevotes(prevote_round, propose_hash)
                     .iter()
-                    .map(|p| p.clone().into());
-                self.blockchain.save_messages(round, raw_messages);
+                    .map(|p| p.clone().into())
+                    .collect::<Vec<_>>();
+                self.save_messages(round, raw_messages);
 
                 self.state.lock(round, propose_hash);
                 // Send precommit
-                if self.state.is_validator() && !self.state.have_incompatible_prevotes() {
+                if self.is_active_validator() && !self.state.have_incompatible_prevotes() {
                     // Execute block and get state hash
                     let block_hash = self.execute(&propose_hash);
                     self.broadcast_precommit(round, &propose_hash, &block_hash);
@@ -475,13 +514,29 @@ impl NodeHandler {
     ) {
         trace!("COMMIT {:?}", block_hash);
 
+        // Consensus messages cache is cleared on commit, so pending messages are not needed.
+        self.pending_consensus_messages.clear();
+
         // Merge changes into storage
         let (committed_txs, proposer) = {
             // FIXME: Avoid of clone here. (ECR-171)
             let block_state = self.state.block(&block_hash).unwrap().clone();
+            // Transactions of the block which have not reached the pool of this node
+            // are not reported as removed from it.
+            let removed_from_pool = {
+                let snapshot = self.blockchain.snapshot();
+                let pool = Schema::new(&snapshot).transactions_pool();
+                block_state
+                    .txs()
+                    .iter()
+                    .filter(|tx_hash| pool.contains(*tx_hash))
+                    .cloned()
+                    .collect::<Vec<_>>()
+            };
             self.blockchain
                 .commit(block_state.patch(), block_hash, precommits)
                 .unwrap();
+            self.api_state.record_pool_removals(removed_from_pool);
             // Update node state.
             self.state
                 .update_config(Schema::new(&self.blockchain.snapshot()).actual_configuration());
@@ -493,14 +548,32 @@ impl NodeHandler {
         };
 
         self.api_state.broadcast(&block_hash);
+        self.update_api_readiness();
+
+        if let Some(keep_blocks) = self.prune_blocks_before {
+            self.blockchain.prune_blocks(keep_blocks);
+        }
 
         let snapshot = self.blockchain.snapshot();
         let schema = Schema::new(&snapshot);
         let pool_len = schema.transactions_pool_len();
 
-        metric!("node.mempool", pool_len);
+        if let Some(ref publisher) = self.block_publisher {
+            let block = schema
+                .blocks()
+                .get(&block_hash)
+                .expect("Committed block not found");
+            let transactions = schema.block_transactions(block.height()).iter().collect();
+            publisher.publish(PublishedBlock {
+                block,
+                transactions,
+            });
+        }
 
         let height = self.state.height();
+        metric!("node.mempool", pool_len);
+        metric!("node.height", height.0);
+        metric!("node.block.transactions", committed_txs);
         info!(
             "COMMIT ====== height={}, proposer={}, round={}, committed={}, pool={}, hash={}",
             height,
@@ -525,6 +598,10 @@ impl NodeHandler {
         for msg in self.state.queued() {
             self.handle_consensus(msg);
         }
+
+        // Release transactions held in the nonce buffer for too long and forget
+        // the inactive authors.
+        self.release_expired_transactions();
     }
 
     /// Checks if the transaction is new and adds it to the pool. This may trigger an expedited
@@ -533,13 +610,37 @@ impl NodeHandler {
         let hash = msg.hash();
 
         let snapshot = self.blockchain.snapshot();
-        if Schema::new(&snapshot).transactions().contains(&hash) {
+        let schema = Schema::new(&snapshot);
+        // Bodies of the transactions from the pruned blocks are removed, but their
+        // locations are kept.
+        if schema.transactions().contains(&hash) || schema.transactions_locations().contains(&hash)
+        {
             bail!("Received already processed transaction, hash {:?}", hash)
         }
 
+        // Transactions required by the consensus are accepted regardless of the service.
+        let service_id = msg.payload().service_id();
+        if !self.is_service_accepted(service_id) && !self.state.is_tx_proposed(&hash) {
+            bail!(
+                "Received transaction of service {} which is not accepted by the node, hash {:?}",
+                service_id,
+                hash
+            )
+        }
+
+        if let Some(time) = self.future_tx_time(msg.payload()) {
+            if !self.state.is_tx_proposed(&hash) {
+                bail!(
+                    "Received transaction created in the future at {}, hash {:?}",
+                    time,
+                    hash
+                )
+            }
+        }
+
         if let Err(e) = self.blockchain.tx_from_raw(msg.payload().clone()) {
             error!("Received invalid transaction {:?}, result: {}", msg, e);
-            bail!("Received malicious transaction.")
+            bail!("Received malicious transaction: {}", e)
         }
 
         let fork = self.blockchain.fork();
@@ -550,6 +651,7 @@ impl NodeHandler {
         self.blockchain
             .merge(fork.into_patch())
             .expect("Unable to save transaction to persistent pool.");
+        self.api_state.record_pool_additions(iter::once(hash));
 
         if self.state.is_leader() && self.state.round() != Round::zero() {
             self.maybe_add_propose_timeout();
@@ -598,12 +700,68 @@ impl NodeHandler {
 
     /// Handles external boxed transaction. Additionally transaction will be broadcast to the
     /// Node's peers.
-    #[cfg_attr(feature = "cargo-clippy", allow(clippy::needless_pass_by_value))]
     pub fn handle_incoming_tx(&mut self, msg: Signed<RawTransaction>) {
         trace!("Handle incoming transaction");
+        for tx in self.release_by_nonce(msg) {
+            self.add_incoming_tx(tx);
+        }
+    }
+
+    /// Passes the transaction through the nonce buffer and returns the transactions ready
+    /// to be added into the pool. Transactions required by the consensus are never held.
+    pub(crate) fn release_by_nonce(
+        &mut self,
+        msg: Signed<RawTransaction>,
+    ) -> Vec<Signed<RawTransaction>> {
+        if !self.nonce_buffer.is_enabled() || self.state.is_tx_proposed(&msg.hash()) {
+            return vec![msg];
+        }
+        match self.blockchain.tx_nonce(&msg) {
+            Some(nonce) => {
+                let now = self.system_state.current_time();
+                let ready = self.nonce_buffer.push(msg, nonce, now);
+                if !self.nonce_buffer_timeout_scheduled {
+                    self.add_nonce_buffer_timeout();
+                }
+                ready
+            }
+            None => vec![msg],
+        }
+    }
+
+    /// Handles `NodeTimeout::NonceBufferExpiry`. Adds the transactions held in the nonce
+    /// buffer for too long into the pool and schedules the timeout for the next ones.
+    pub(crate) fn handle_nonce_buffer_timeout(&mut self) {
+        self.nonce_buffer_timeout_scheduled = false;
+        self.release_expired_transactions();
+        self.add_nonce_buffer_timeout();
+    }
+
+    /// Adds the transactions held in the nonce buffer for too long into the pool.
+    fn release_expired_transactions(&mut self) {
+        let expired = self
+            .nonce_buffer
+            .take_expired(self.system_state.current_time());
+        for tx in expired {
+            self.add_incoming_tx(tx);
+        }
+    }
+
+    /// Adds the transaction into the pool and broadcasts it to the peers, or records it
+    /// as rejected.
+    #[cfg_attr(feature = "cargo-clippy", allow(clippy::needless_pass_by_value))]
+    fn add_incoming_tx(&mut self, msg: Signed<RawTransaction>) {
         match self.handle_tx(msg.clone()) {
-            Ok(_) => self.broadcast(msg),
-            Err(e) => error!("{}", e),
+            Ok(_) => self.broadcast_transaction(msg),
+            Err(e) => {
+                error!("{}", e);
+                let entry = RejectedTransaction {
+                    tx_hash: msg.hash(),
+                    reason: e.to_string(),
+                    time: self.system_state.current_time().into(),
+                };
+                self.api_state.record_rejected_transaction(entry);
+            }
         }
     }
 
@@ -626,7 +784,7 @@ impl NodeHandler {
 
     // Try to process consensus messages from the future round.
     fn process_new_round(&mut self) {
-        if self.state.is_validator() {
+        if self.is_active_validator() {
             // Send prevote if we are locked or propose if we are leader
             if let Some(hash) = self.state.locked_propose() {
                 let round = self.state.round();
@@ -679,20 +837,38 @@ impl NodeHandler {
             return;
         }
         if let Some(validator_id) = self.state.validator_id() {
-            if self.state.have_prevote(round) {
+            if self.state.have_prevote(round) || !self.has_enough_online_validators() {
                 return;
             }
             let snapshot = self.blockchain.snapshot();
-            let schema = Schema::new(&snapshot);
-            let pool = schema.transactions_pool();
+            let schema = Schema::new(snapshot.as_ref());
             let pool_len = schema.transactions_pool_len();
 
             info!("LEADER: pool = {}", pool_len);
 
             let round = self.state.round();
-            let max_count = ::std::cmp::min(u64::from(self.txs_block_limit()), pool_len);
+            let mut max_count = ::std::cmp::min(u64::from(self.txs_block_limit()), pool_len);
+            if let Some(max_propose_txs) = self.state.consensus_config().max_propose_txs {
+                max_count = ::std::cmp::min(u64::from(max_propose_txs), max_count);
+            }
 
-            let txs: Vec<Hash> = pool.iter().take(max_count as usize).collect();
+            let mut txs: Vec<Hash> = self.mempool_strategy.select(&schema, max_count as usize);
+            if let Some(max_block_bytes) = self.state.consensus_config().max_block_bytes {
+                // Transactions that do not fit are skipped rather than end the selection,
+                // so that a single large transaction does not block the rest of the pool.
+                let transactions = schema.transactions();
+                let mut block_bytes = 0;
+                txs.retain(|hash| {
+                    let tx_bytes = transactions
+                        .get(hash)
+                        .map_or(0, |tx| tx.signed_message().raw().len());
+                    if block_bytes + tx_bytes > max_block_bytes as usize {
+                        return false;
+                    }
+                    block_bytes += tx_bytes;
+                    true
+                });
+            }
             let propose = self.sign_message(Propose::new(
                 validator_id,
                 self.state.height(),
@@ -701,7 +877,7 @@ impl NodeHandler {
                 &txs,
             ));
             // Put our propose to the consensus messages cache
-            self.blockchain.save_message(round, propose.clone());
+            self.save_own_message(round, propose.clone());
 
             trace!("Broadcast propose: {:?}", propose);
             self.broadcast(propose.clone());
@@ -771,6 +947,9 @@ impl NodeHandler {
             };
             trace!("Send request {:?} to peer {:?}", data, peer);
             self.send_to_peer(peer, message);
+            let sent_at = self.system_state.current_time();
+            self.state.set_request_sent(data, peer, sent_at);
+            self.api_state.add_peer_request(peer);
         }
     }
 
@@ -784,6 +963,34 @@ impl NodeHandler {
         self.blockchain.create_patch(proposer_id, height, tx_hashes)
     }
 
+    /// Executes the block once more on a fresh fork and checks that the resulting block hash
+    /// is equal to the given one. As the block hash commits to the state hash, a mismatch
+    /// means that the execution of the transactions or the services is not deterministic.
+    /// Returns `false` on a mismatch, unless the node is configured to halt in this case.
+    pub(crate) fn check_execution_determinism(
+        &mut self,
+        proposer_id: ValidatorId,
+        height: Height,
+        tx_hashes: &[Hash],
+        block_hash: Hash,
+    ) -> bool {
+        let (repeated_hash, _) = self.create_block(proposer_id, height, tx_hashes);
+        if repeated_hash == block_hash {
+            return true;
+        }
+
+        let message = format!(
+            "Non-deterministic execution of the block at height {}: \
+             the repeated execution resulted in block {:?} instead of {:?}",
+            height, repeated_hash, block_hash
+        );
+        if self.halt_on_nondeterministic_execution {
+            panic!("{}", message);
+        }
+        error!("{}", message);
+        false
+    }
+
     /// Calls `create_block` with transactions from the corresponding `Propose` and returns the
     /// block hash.
     pub fn execute(&mut self, propose_hash: &Hash) -> Hash {
@@ -797,6 +1004,14 @@ impl NodeHandler {
 
         let (block_hash, patch) =
             self.create_block(propose.validator(), propose.height(), tx_hashes.as_slice());
+        if self.verify_execution_determinism {
+            self.check_execution_determinism(
+                propose.validator(),
+                propose.height(),
+                &tx_hashes,
+                block_hash,
+            );
+        }
         // Save patch
         self.state
             .add_block(block_hash, patch, tx_hashes, propose.validator());
@@ -857,6 +1072,12 @@ impl NodeHandler {
     /// Removes the specified request from the pending request list.
     pub fn remove_request(&mut self, data: &RequestData) -> HashSet<PublicKey> {
         // TODO: Clear timeout. (ECR-171)
+        if let Some((peer, sent_at)) = self.state.request_sent(data) {
+            // The round-trip time is attributed to the peer the request has been last sent to.
+            if let Ok(rtt) = self.system_state.current_time().duration_since(sent_at) {
+                self.api_state.add_peer_latency_sample(peer, rtt);
+            }
+        }
         self.state.remove_request(data)
     }
 
@@ -878,7 +1099,7 @@ impl NodeHandler {
 
         // save outgoing Prevote to the consensus messages cache before broadcast
         self.check_propose_saved(round, propose_hash);
-        self.blockchain.save_message(round, prevote.clone());
+        self.save_own_message(round, prevote.clone());
 
         trace!("Broadcast prevote: {:?}", prevote);
         self.broadcast(prevote);
@@ -903,20 +1124,24 @@ impl NodeHandler {
         self.state.add_precommit(precommit.clone());
 
         // Put our Precommit to the consensus cache before broadcast
-        self.blockchain.save_message(round, precommit.clone());
+        self.save_own_message(round, precommit.clone());
 
         trace!("Broadcast precommit: {:?}", precommit);
         self.broadcast(precommit);
     }
 
-    /// Checks that pre-commits count is correct and calls `verify_precommit` for each of them.
+    /// Checks that pre-commits count and weight are correct and calls `verify_precommit`
+    /// for each of them.
     fn verify_precommits(
         &self,
         precommits: &[Signed<Precommit>],
         block_hash: &Hash,
         block_height: Height,
     ) -> Result<(), failure::Error> {
-        if precommits.len() < self.state.majority_count() {
+        let weight = self
+            .state
+            .validators_weight(precommits.iter().map(|precommit| precommit.validator()));
+        if weight < self.state.majority_weight() {
             bail!("Received block without consensus");
         } else if precommits.len() > self.state.validators().len() {
             bail!("Wrong precommits count in block");
@@ -984,12 +1209,13 @@ impl NodeHandler {
 
     /// Checks whether Propose is saved to the consensus cache and saves it otherwise
     fn check_propose_saved(&mut self, round: Round, propose_hash: &Hash) {
-        if let Some(propose_state) = self.state.propose_mut(propose_hash) {
-            if !propose_state.is_saved() {
-                self.blockchain
-                    .save_message(round, propose_state.message().clone());
+        let propose = match self.state.propose_mut(propose_hash) {
+            Some(ref mut propose_state) if !propose_state.is_saved() => {
                 propose_state.set_saved(true);
+                propose_state.message().clone()
             }
-        }
+            _ => return,
+        };
+        self.save_message(round, propose);
     }
 }
@@ -12,10 +12,17 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use super::{ConnectListConfig, ExternalMessage, NodeHandler, NodeTimeout};
+use std::{
+    iter,
+    time::{Duration, SystemTime},
+};
+
+use super::{ConnectList, ConnectListConfig, ExternalMessage, NodeHandler, NodeTimeout};
 use crate::blockchain::Schema;
+use crate::crypto::Hash;
 use crate::events::{
-    error::LogError, Event, EventHandler, InternalEvent, InternalRequest, NetworkEvent,
+    Event, EventHandler, InternalEvent, InternalRequest, NetworkEvent, NetworkRequest,
+    TimeoutRequest,
 };
 
 impl EventHandler for NodeHandler {
@@ -38,6 +45,9 @@ impl NodeHandler {
             InternalEvent::JumpToRound(height, round) => self.handle_new_round(height, round),
             InternalEvent::Shutdown => panic!("Shutdown should be processed in the event loop"),
             InternalEvent::MessageVerified(msg) => self.handle_message(*msg),
+            InternalEvent::MessageRejected(author, reason) => {
+                self.log_rejected_message(author, reason)
+            }
         }
     }
 
@@ -61,17 +71,9 @@ impl NodeHandler {
                 info!("Send Connect message to {}", info);
                 self.state.add_peer_to_connect_list(info.clone());
                 self.connect(info.public_key);
-
-                if self.config_manager.is_some() {
-                    let connect_list_config =
-                        ConnectListConfig::from_connect_list(&self.state.connect_list());
-
-                    self.config_manager
-                        .as_ref()
-                        .unwrap()
-                        .store_connect_list(connect_list_config);
-                }
+                self.store_connect_list();
             }
+            ExternalMessage::ReplaceConnectList(config) => self.handle_replace_connect_list(config),
             ExternalMessage::Enable(value) => {
                 let s = if value { "enabled" } else { "disabled" };
                 if self.is_enabled == value {
@@ -86,12 +88,20 @@ impl NodeHandler {
                     }
                 }
             }
-            ExternalMessage::Shutdown => self.execute_later(InternalRequest::Shutdown),
+            ExternalMessage::Shutdown => self.handle_shutdown(),
             ExternalMessage::Rebroadcast => self.handle_rebroadcast(),
+            ExternalMessage::CancelTransaction(tx_hash) => self.handle_cancel_transaction(tx_hash),
+            ExternalMessage::SetServiceEnabled {
+                service_id,
+                enabled,
+            } => self.handle_set_service_enabled(service_id, enabled),
         }
     }
 
-    fn handle_timeout(&mut self, timeout: NodeTimeout) {
+    fn handle_timeout(&mut self, request: TimeoutRequest) {
+        let TimeoutRequest(time, timeout) = request;
+        self.record_timeout_drift(time);
+        self.flush_consensus_cache_if_due();
         if !self.is_enabled {
             info!(
                 "Ignoring a timeout {:?} because the node is disabled",
@@ -106,12 +116,26 @@ impl NodeHandler {
             NodeTimeout::PeerExchange => self.handle_peer_exchange_timeout(),
             NodeTimeout::UpdateApiState => self.handle_update_api_state_timeout(),
             NodeTimeout::Propose(height, round) => self.handle_propose_timeout(height, round),
+            NodeTimeout::TxBroadcastBatch => self.broadcast_pending_transactions(),
+            NodeTimeout::NonceBufferExpiry => self.handle_nonce_buffer_timeout(),
         }
     }
 
+    /// Records the delay between the time the timeout was scheduled for and the current time.
+    fn record_timeout_drift(&mut self, scheduled: SystemTime) {
+        let drift = self
+            .system_state
+            .current_time()
+            .duration_since(scheduled)
+            .unwrap_or_else(|_| Duration::from_millis(0));
+        let drift = drift.as_secs() * 1_000_000 + u64::from(drift.subsec_micros());
+        self.api_state.add_timeout_drift(drift);
+        metric!("node.timeout_drift", drift);
+    }
+
     /// Schedule execution for later time
     pub(crate) fn execute_later(&mut self, event: InternalRequest) {
-        self.channel.internal_requests.send(event).log_error();
+        self.send_internal_request(event);
     }
 
     /// Broadcasts all transactions from the pool to other validators.
@@ -120,7 +144,7 @@ impl NodeHandler {
         let schema = Schema::new(&snapshot);
         let pool = schema.transactions_pool();
         for tx_hash in pool.iter() {
-            self.broadcast(
+            self.broadcast_transaction(
                 schema
                     .transactions()
                     .get(&tx_hash)
@@ -128,4 +152,84 @@ impl NodeHandler {
             )
         }
     }
+
+    /// Installs the new connect list, connects to the added peers and disconnects
+    /// from the removed ones.
+    pub(crate) fn handle_replace_connect_list(&mut self, config: ConnectListConfig) {
+        info!("Replacing connect list with {} peers", config.peers.len());
+        let old_list = self
+            .state
+            .replace_connect_list(ConnectList::from_config(config.clone()));
+
+        for peer in old_list.peers.keys() {
+            if !config.peers.iter().any(|info| info.public_key == *peer) {
+                self.send_network_request(NetworkRequest::DisconnectWithPeer(*peer));
+            }
+        }
+        for info in &config.peers {
+            let is_new = old_list
+                .find_address_by_pubkey(&info.public_key)
+                .map_or(true, |address| address.address != info.address);
+            if is_new {
+                self.connect(info.public_key);
+            }
+        }
+        self.store_connect_list();
+    }
+
+    /// Saves the current connect list to the node configuration file, if any.
+    fn store_connect_list(&self) {
+        if let Some(ref config_manager) = self.config_manager {
+            let connect_list_config =
+                ConnectListConfig::from_connect_list(&self.state.connect_list());
+            config_manager.store_connect_list(connect_list_config);
+        }
+    }
+
+    /// Lets services flush their state and schedules the node shutdown.
+    pub(crate) fn handle_shutdown(&mut self) {
+        info!("Shutting down the node");
+        // Stops the refresh of the connect list.
+        self.connect_list_source = None;
+        self.flush_consensus_cache();
+        self.blockchain.before_shutdown();
+        self.execute_later(InternalRequest::Shutdown);
+    }
+
+    /// Removes the transaction from the pool, unless it is already included into a propose.
+    pub(crate) fn handle_cancel_transaction(&mut self, tx_hash: Hash) {
+        if self.state.is_tx_proposed(&tx_hash) {
+            warn!(
+                "Unable to cancel transaction {:?} already included into a propose",
+                tx_hash
+            );
+            return;
+        }
+
+        let fork = self.blockchain.fork();
+        if Schema::new(&fork).reject_transaction(&tx_hash).is_err() {
+            info!("Transaction {:?} to cancel is not in the pool", tx_hash);
+            return;
+        }
+        self.blockchain
+            .merge(fork.into_patch())
+            .expect("Unable to remove transaction from persistent pool.");
+        self.api_state.record_pool_removals(iter::once(tx_hash));
+        info!("Transaction {:?} has been cancelled", tx_hash);
+    }
+
+    /// Handles request to enable or disable accepting transactions of the service.
+    pub(crate) fn handle_set_service_enabled(&mut self, service_id: u16, enabled: bool) {
+        let changed = if enabled {
+            self.disabled_services.remove(&service_id)
+        } else {
+            self.disabled_services.insert(service_id)
+        };
+        let s = if enabled { "enabled" } else { "disabled" };
+        if changed {
+            info!("Transactions of service {} are {} now", service_id, s);
+        } else {
+            info!("Transactions of service {} are already {}", service_id, s);
+        }
+    }
 }
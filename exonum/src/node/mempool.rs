@@ -0,0 +1,232 @@
+// Copyright 2019 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Strategies selecting uncommitted transactions for the proposed blocks.
+//!
+//! The strategy used by the node is chosen in `MemoryPoolConfig::strategy`;
+//! a custom strategy can be set with `Node::set_mempool_strategy`.
+
+use exonum_merkledb::Snapshot;
+
+use std::{collections::HashSet, fmt};
+
+use super::MemoryPoolConfig;
+use crate::blockchain::Schema;
+use crate::crypto::Hash;
+
+/// Built-in strategy of selecting transactions, as specified in the node configuration.
+/// Unknown strategy names are rejected when the configuration is loaded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MempoolStrategyKind {
+    /// `HashOrder` strategy.
+    Hash,
+    /// `ArrivalOrder` strategy.
+    Arrival,
+    /// `PriorityOrder` strategy.
+    Priority,
+}
+
+impl Default for MempoolStrategyKind {
+    fn default() -> Self {
+        MempoolStrategyKind::Hash
+    }
+}
+
+impl MempoolStrategyKind {
+    /// Creates the strategy of this kind.
+    pub fn build(self, config: &MemoryPoolConfig) -> Box<dyn MempoolStrategy> {
+        match self {
+            MempoolStrategyKind::Hash => Box::new(HashOrder),
+            MempoolStrategyKind::Arrival => Box::new(ArrivalOrder),
+            MempoolStrategyKind::Priority => {
+                Box::new(PriorityOrder::new(config.priority_services.clone()))
+            }
+        }
+    }
+}
+
+/// Strategy of selecting transactions from the pool into a new `Propose`.
+pub trait MempoolStrategy: fmt::Debug + Send + 'static {
+    /// Returns hashes of at most `limit` transactions from the pool in the order
+    /// they should be included into the block.
+    fn select(&self, schema: &Schema<&dyn Snapshot>, limit: usize) -> Vec<Hash>;
+}
+
+/// Selects transactions in the ascending order of their hashes. This is the default strategy.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct HashOrder;
+
+impl MempoolStrategy for HashOrder {
+    fn select(&self, schema: &Schema<&dyn Snapshot>, limit: usize) -> Vec<Hash> {
+        schema.transactions_pool().iter().take(limit).collect()
+    }
+}
+
+/// Selects transactions in the order they were added to the pool.
+/// Unlike the other built-in strategies, it does not scan the whole pool.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ArrivalOrder;
+
+impl MempoolStrategy for ArrivalOrder {
+    fn select(&self, schema: &Schema<&dyn Snapshot>, limit: usize) -> Vec<Hash> {
+        schema
+            .transactions_pool_by_arrival()
+            .values()
+            .take(limit)
+            .collect()
+    }
+}
+
+/// Selects transactions of the prioritized services first, in the order the services
+/// are listed. Transactions with the same priority are selected in the order they were
+/// added to the pool.
+#[derive(Debug, Clone, Default)]
+pub struct PriorityOrder {
+    priority_services: Vec<u16>,
+}
+
+impl PriorityOrder {
+    /// Creates a strategy prioritizing transactions of the given services.
+    /// Repeated services are ignored.
+    pub fn new(mut priority_services: Vec<u16>) -> Self {
+        let mut seen = HashSet::new();
+        priority_services.retain(|service_id| seen.insert(*service_id));
+        Self { priority_services }
+    }
+
+    fn is_prioritized(&self, service_id: u16) -> bool {
+        self.priority_services.contains(&service_id)
+    }
+}
+
+impl MempoolStrategy for PriorityOrder {
+    fn select(&self, schema: &Schema<&dyn Snapshot>, limit: usize) -> Vec<Hash> {
+        let mut txs = Vec::with_capacity(limit);
+        for service_id in &self.priority_services {
+            if txs.len() == limit {
+                return txs;
+            }
+            let remaining = limit - txs.len();
+            txs.extend(
+                schema
+                    .service_transactions_pool_by_arrival(*service_id)
+                    .values()
+                    .take(remaining),
+            );
+        }
+
+        // All transactions of the prioritized services are already selected, so at most
+        // `limit` of them are skipped here.
+        let transactions = schema.transactions();
+        let remaining = limit - txs.len();
+        txs.extend(
+            schema
+                .transactions_pool_by_arrival()
+                .values()
+                .filter(|hash| {
+                    transactions
+                        .get(hash)
+                        .map_or(true, |tx| !self.is_prioritized(tx.payload().service_id()))
+                })
+                .take(remaining),
+        );
+        txs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use exonum_merkledb::{Database, TemporaryDB};
+
+    use super::*;
+    use crate::crypto::gen_keypair;
+    use crate::messages::{Message, RawTransaction, Signed};
+    use crate::proto::{schema::tests::TxSimple, ProtobufConvert};
+
+    fn create_tx(service_id: u16, nonce: u64) -> Signed<RawTransaction> {
+        let (p_key, s_key) = gen_keypair();
+        let mut msg = TxSimple::new();
+        msg.set_public_key(p_key.to_pb());
+        msg.set_msg(nonce.to_string());
+        Message::sign_transaction(msg, service_id, p_key, &s_key)
+    }
+
+    /// Adds the transactions into the pool in the given order.
+    fn create_pool(txs: &[Signed<RawTransaction>]) -> TemporaryDB {
+        let db = TemporaryDB::new();
+        let fork = db.fork();
+        {
+            let mut schema = Schema::new(&fork);
+            for tx in txs {
+                schema.add_transaction_into_pool(tx.clone());
+            }
+        }
+        db.merge(fork.into_patch()).unwrap();
+        db
+    }
+
+    #[test]
+    fn arrival_order() {
+        let txs = (0..4).map(|i| create_tx(0, i)).collect::<Vec<_>>();
+        let db = create_pool(&txs);
+        let fork = db.fork();
+        Schema::new(&fork).commit_transaction(&txs[1].hash());
+        db.merge(fork.into_patch()).unwrap();
+
+        let snapshot = db.snapshot();
+        let selected = ArrivalOrder.select(&Schema::new(snapshot.as_ref()), 2);
+        assert_eq!(selected, vec![txs[0].hash(), txs[2].hash()]);
+    }
+
+    #[test]
+    fn priority_order() {
+        let txs = vec![
+            create_tx(0, 0),
+            create_tx(1, 1),
+            create_tx(2, 2),
+            create_tx(1, 3),
+            create_tx(0, 4),
+        ];
+        let db = create_pool(&txs);
+        let snapshot = db.snapshot();
+        let schema = Schema::new(snapshot.as_ref());
+
+        let strategy = PriorityOrder::new(vec![2, 1, 2]);
+        let selected = strategy.select(&schema, 4);
+        let expected = [2, 1, 3, 0]
+            .iter()
+            .map(|&i| txs[i].hash())
+            .collect::<Vec<_>>();
+        assert_eq!(selected, expected);
+        assert_eq!(strategy.select(&schema, 10).len(), txs.len());
+    }
+
+    #[test]
+    fn unknown_strategy_is_rejected() {
+        let config_with_strategy = |strategy: &str| {
+            let mut config = toml::Value::try_from(MemoryPoolConfig::default()).unwrap();
+            config.as_table_mut().unwrap().insert(
+                "strategy".to_owned(),
+                toml::Value::String(strategy.to_owned()),
+            );
+            config.try_into::<MemoryPoolConfig>()
+        };
+
+        let config = config_with_strategy("arrival").unwrap();
+        assert_eq!(config.strategy, MempoolStrategyKind::Arrival);
+        let err = config_with_strategy("random").unwrap_err();
+        assert!(err.to_string().contains("unknown variant"));
+    }
+}
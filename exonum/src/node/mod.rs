@@ -18,23 +18,34 @@
 // spell-checker:ignore cors
 
 pub use self::{
+    block_publisher::{BlockPublisher, PublishedBlock, PublisherConfig},
     connect_list::{ConnectList, PeerAddress},
+    mempool::{MempoolStrategy, MempoolStrategyKind},
     state::{RequestData, State, ValidatorState},
+    status_webhook::{NodeHealth, StatusWebhookPayload},
 };
 
+pub mod block_publisher;
+pub mod connect_list_source;
+pub mod mempool;
 // TODO: Temporary solution to get access to WAIT constants. (ECR-167)
 pub mod state;
+pub mod status_webhook;
 
+use chrono::{DateTime, Utc};
 use failure::Error;
 use futures::{sync::mpsc, Sink};
+use rand::{rngs::StdRng, FromEntropy, Rng, SeedableRng};
 use tokio_core::reactor::Core;
 use tokio_threadpool::Builder as ThreadPoolBuilder;
 use toml::Value;
+use url::Url;
 
 use std::{
+    cmp,
     collections::{BTreeMap, HashSet},
-    fmt,
-    net::SocketAddr,
+    fmt, io, iter, mem,
+    net::{SocketAddr, ToSocketAddrs},
     path::{Path, PathBuf},
     sync::Arc,
     thread,
@@ -42,33 +53,52 @@ use std::{
 };
 
 use crate::api::{
-    backends::actix::{AllowOrigin, ApiRuntimeConfig, App, AppConfig, Cors, SystemRuntimeConfig},
+    backends::actix::{
+        AllowOrigin, ApiRuntimeConfig, App, AppConfig, Cors, RateLimiter, ResponseSigner,
+        SystemRuntimeConfig, TlsConfig,
+    },
+    node::public::explorer::{API_WORKER_THREADS, MAX_PENDING_API_REQUESTS},
     ApiAccess, ApiAggregator,
 };
 use crate::blockchain::{
-    Blockchain, ConsensusConfig, GenesisConfig, Schema, Service, SharedNodeState, ValidatorKeys,
+    Blockchain, ConsensusConfig, GenesisConfig, RoundSchedule, RoundTiming, Schema, Service,
+    SharedNodeState, StoredConfiguration, ValidatorKeys, ROUND_SCHEDULE_LENGTH,
+};
+use crate::crypto::{
+    self, read_keys_from_file, CryptoHash, Hash, PublicKey, SecretKey, Seed, SEED_LENGTH,
 };
-use crate::crypto::{self, read_keys_from_file, CryptoHash, Hash, PublicKey, SecretKey};
 use crate::events::{
-    error::{into_failure, LogError},
-    noise::HandshakeParams,
-    HandlerPart, InternalEvent, InternalPart, InternalRequest, NetworkConfiguration, NetworkEvent,
-    NetworkPart, NetworkRequest, SyncSender, TimeoutRequest, UnboundedSyncSender,
+    error::into_failure, noise::HandshakeParams, ExternalAddressFallback, HandlerPart,
+    InternalEvent, InternalPart, InternalRequest, NetworkConfiguration, NetworkEvent, NetworkPart,
+    NetworkRequest, SyncSender, TimeoutRequest, UnboundedSyncSender,
 };
+use crate::explorer::TRANSACTION_CACHE_SIZE;
 use crate::helpers::{
     config::ConfigManager,
     fabric::{NodePrivateConfig, NodePublicConfig},
-    user_agent, Height, Milliseconds, Round, ValidatorId,
+    logger,
+    metrics::{self, MetricSink, MetricSinkConfig, MetricSinkRegistration},
+    user_agent, Height, Milliseconds, Round, ValidatorId, ZeroizeOnDrop,
+};
+use crate::messages::{
+    Connect, Message, ProtocolMessage, RawTransaction, Signed, SignedMessage, TransactionsResponse,
+    RAW_TRANSACTION_HEADER, TRANSACTION_RESPONSE_EMPTY_SIZE,
 };
-use crate::messages::{Connect, Message, ProtocolMessage, RawTransaction, Signed, SignedMessage};
+use crate::node::block_publisher::BlockPublisherHandle;
+use crate::node::connect_list_source::ConnectListSource;
+use crate::node::nonce_buffer::NonceBuffer;
 use crate::node::state::SharedConnectList;
-use exonum_merkledb::{Database, DbOptions};
+use crate::node::status_webhook::StatusWebhook;
+use exonum_merkledb::{Database, DbOptions, IndexAccess, RocksDB, TemporaryDB};
 
 mod basic;
 mod connect_list;
 mod consensus;
 mod events;
+mod nonce_buffer;
 mod requests;
+#[cfg(test)]
+mod tests;
 
 /// External messages.
 #[derive(Debug)]
@@ -83,6 +113,17 @@ pub enum ExternalMessage {
     Shutdown,
     /// Rebroadcast transactions from the pool.
     Rebroadcast,
+    /// Remove the transaction with the given hash from the pool.
+    CancelTransaction(Hash),
+    /// Replace the whole connect list of the node.
+    ReplaceConnectList(ConnectListConfig),
+    /// Enable or disable accepting new transactions of the service.
+    SetServiceEnabled {
+        /// Identifier of the service.
+        service_id: u16,
+        /// Whether the transactions of the service are accepted.
+        enabled: bool,
+    },
 }
 
 /// Node timeout types.
@@ -100,6 +141,10 @@ pub enum NodeTimeout {
     UpdateApiState,
     /// Exchange peers timeout.
     PeerExchange,
+    /// Broadcast of the batched transactions timeout.
+    TxBroadcastBatch,
+    /// Release of the transactions held in the nonce buffer for too long.
+    NonceBufferExpiry,
 }
 
 /// A helper trait that provides the node with information about the state of the system such
@@ -137,6 +182,117 @@ pub struct NodeHandler {
     config_manager: Option<ConfigManager>,
     /// Can we speed up Propose with transaction pressure?
     allow_expedited_propose: bool,
+    /// Maximum jitter of the peer exchange timeout, in percent of `peers_timeout`.
+    peer_exchange_jitter_percent: u64,
+    /// Random number generator used for peer exchange.
+    rng: StdRng,
+    /// Maximum number of peers shared or accepted per peer exchange, if limited.
+    max_peers_in_exchange: Option<usize>,
+    /// Number of new peers which can still be accepted from the current peer exchange.
+    exchanged_peers_left: Option<usize>,
+    /// Strategy selecting transactions from the pool into a new `Propose`.
+    mempool_strategy: Box<dyn MempoolStrategy>,
+    /// Minimal interval between persisting the consensus messages cache.
+    consensus_cache_flush_interval: Milliseconds,
+    /// Consensus messages not yet persisted to the consensus messages cache.
+    pending_consensus_messages: Vec<Message>,
+    /// Round of the latest pending consensus message.
+    pending_consensus_round: Round,
+    /// Time of the latest consensus messages cache flush.
+    last_consensus_cache_flush: SystemTime,
+    /// Log of the rejected incoming messages, if enabled.
+    rejected_messages_log: Option<RejectedMessagesLog>,
+    /// Peers which the transactions are broadcast to.
+    broadcast_target: BroadcastTarget,
+    /// Notifier of the node health transitions, if enabled.
+    status_webhook: Option<StatusWebhook>,
+    /// Publisher of the committed blocks, if enabled.
+    block_publisher: Option<BlockPublisherHandle>,
+    /// Refresh of the connect list from an HTTP endpoint, if enabled.
+    connect_list_source: Option<ConnectListSource>,
+    /// Registration of the metric sink of the node, if any.
+    metric_sink: Option<MetricSinkRegistration>,
+    /// Services which transactions are accepted by the node, if restricted.
+    accepted_services: Option<HashSet<u16>>,
+    /// Services which transactions are temporarily not accepted by the node.
+    disabled_services: HashSet<u16>,
+    /// Warmup of the API, if the node is not ready yet.
+    api_warmup: Option<ApiWarmup>,
+    /// Interval of the batched transaction broadcasting. Zero value disables batching.
+    tx_broadcast_batch_interval: Milliseconds,
+    /// Transactions not yet broadcast to the peers.
+    pending_broadcast_txs: Vec<Signed<RawTransaction>>,
+    /// Maximal allowed difference between the creation time of an incoming transaction
+    /// and the current time, if limited.
+    max_tx_time_skew: Option<Milliseconds>,
+    /// Buffer of the incoming transactions with out-of-order nonces.
+    nonce_buffer: NonceBuffer,
+    /// Whether the timeout releasing the expired transactions from the nonce buffer
+    /// is scheduled.
+    nonce_buffer_timeout_scheduled: bool,
+    /// Whether each executed propose is executed once more to check the determinism.
+    verify_execution_determinism: bool,
+    /// Whether the node halts if the execution is found to be non-deterministic.
+    halt_on_nondeterministic_execution: bool,
+    /// Number of the latest blocks which transaction bodies are kept, if pruning is enabled.
+    prune_blocks_before: Option<u32>,
+    /// Whether the node has warned that fewer than `min_online_validators` or validators
+    /// with less than `min_online_weight` are online.
+    lacks_online_validators: bool,
+}
+
+/// Warmup of the API after the start of the node.
+#[derive(Debug)]
+struct ApiWarmup {
+    /// Time after which the node is ready regardless of the heights of its peers.
+    deadline: SystemTime,
+    /// Whether a `Status` message has been received from any peer.
+    peer_status_received: bool,
+}
+
+/// Maximum number of rejected incoming messages logged per second.
+pub const REJECTED_MESSAGES_LOG_LIMIT: usize = 10;
+
+/// Rate limiter of the rejected messages log.
+#[derive(Debug, Default)]
+struct RejectedMessagesLog {
+    /// Start of the current one-second window.
+    window_start: Option<SystemTime>,
+    /// Number of messages logged in the current window.
+    logged: usize,
+    /// Number of messages not logged in the current window because of the limit.
+    suppressed: usize,
+}
+
+impl RejectedMessagesLog {
+    /// Returns `true` if a rejected message received at `now` should be logged.
+    fn allow(&mut self, now: SystemTime) -> bool {
+        let window_expired = self.window_start.map_or(true, |start| {
+            now.duration_since(start)
+                .map(|elapsed| elapsed >= Duration::from_secs(1))
+                .unwrap_or(false)
+        });
+        if window_expired {
+            if self.suppressed > 0 {
+                warn!(
+                    "{} rejected messages were not logged due to the rate limit",
+                    self.suppressed
+                );
+            }
+            *self = Self {
+                window_start: Some(now),
+                ..Self::default()
+            };
+        }
+
+        if self.logged < REJECTED_MESSAGES_LOG_LIMIT {
+            self.logged += 1;
+            true
+        } else {
+            self.suppressed += 1;
+            false
+        }
+    }
 }
 
 /// Service configuration.
@@ -180,6 +336,92 @@ pub struct NodeApiConfig {
     ///
     /// [cors]: https://developer.mozilla.org/en-US/docs/Web/HTTP/CORS
     pub private_allow_origin: Option<AllowOrigin>,
+    /// Number of additional attempts to bind the public and private API addresses
+    /// if they are in use, e.g. during a node restart.
+    #[serde(default)]
+    pub api_bind_retries: u32,
+    /// Delay between the attempts to bind the API addresses.
+    #[serde(default = "default_api_bind_retry_delay")]
+    pub api_bind_retry_delay: Milliseconds,
+    /// Maximum number of requests per second to the public API from a single IP
+    /// address. Requests above the limit are rejected with `429 Too Many Requests`.
+    /// The private API is not limited.
+    #[serde(default)]
+    pub public_api_rate_limit: Option<u32>,
+    /// Time given to the in-flight API requests to complete when the node is stopped.
+    /// New connections are not accepted during this time. The value is rounded up
+    /// to whole seconds.
+    #[serde(default = "default_api_shutdown_timeout")]
+    pub api_shutdown_timeout: Milliseconds,
+    /// TLS configuration for the public API. If it is set, the public API is served
+    /// over HTTPS only. Requires the `tls` feature.
+    #[serde(default)]
+    pub public_api_tls: Option<TlsConfig>,
+    /// TLS configuration for the private API. If it is set, the private API is served
+    /// over HTTPS only. Requires the `tls` feature.
+    #[serde(default)]
+    pub private_api_tls: Option<TlsConfig>,
+    /// Maximal time of handling a request by the explorer API. Requests exceeding it
+    /// are aborted with `503 Service Unavailable`. If not set, the requests are not
+    /// limited in time.
+    #[serde(default)]
+    pub api_request_timeout: Option<Milliseconds>,
+    /// Number of threads executing the explorer requests if `api_request_timeout` is set.
+    #[serde(default = "default_api_worker_threads")]
+    pub api_worker_threads: usize,
+    /// Maximal number of the explorer requests which are executed or wait for a worker
+    /// thread if `api_request_timeout` is set. Further requests are rejected with
+    /// `503 Service Unavailable`.
+    #[serde(default = "default_max_pending_api_requests")]
+    pub max_pending_api_requests: usize,
+    /// Maximal time after the start of the node during which it is reported as not ready
+    /// by the healthcheck endpoint, unless it catches up with the heights of its peers.
+    /// If not set, the node is ready immediately.
+    #[serde(default)]
+    pub api_warmup_timeout: Option<Milliseconds>,
+    /// If `true`, the bodies of the API responses are signed with the service key
+    /// of the node. The signature and the public key are passed in the `X-Exonum-Signature`
+    /// and `X-Exonum-Public-Key` headers respectively.
+    #[serde(default)]
+    pub sign_responses: bool,
+    /// If `true`, the API is served over HTTP/2 in addition to HTTP/1.1. HTTP/2 is
+    /// negotiated via ALPN if the API is served over HTTPS; otherwise, clients should
+    /// use cleartext HTTP/2 with prior knowledge (h2c).
+    #[serde(default)]
+    pub http2: bool,
+    /// If `false`, the explorer endpoints, including the websocket ones, are not served
+    /// by the public API. The endpoints of the services are served regardless of this option.
+    #[serde(default = "default_enable_explorer")]
+    pub enable_explorer: bool,
+    /// Number of the committed transactions cached by the explorer API in order to
+    /// avoid reading them from the database on repeated requests. Zero value disables
+    /// the cache.
+    #[serde(default = "default_transaction_cache_size")]
+    pub transaction_cache_size: usize,
+}
+
+fn default_api_bind_retry_delay() -> Milliseconds {
+    1_000
+}
+
+fn default_api_shutdown_timeout() -> Milliseconds {
+    30_000
+}
+
+fn default_api_worker_threads() -> usize {
+    API_WORKER_THREADS
+}
+
+fn default_max_pending_api_requests() -> usize {
+    MAX_PENDING_API_REQUESTS
+}
+
+fn default_enable_explorer() -> bool {
+    true
+}
+
+fn default_transaction_cache_size() -> usize {
+    TRANSACTION_CACHE_SIZE
 }
 
 impl Default for NodeApiConfig {
@@ -190,6 +432,20 @@ impl Default for NodeApiConfig {
             private_api_address: None,
             public_allow_origin: None,
             private_allow_origin: None,
+            api_bind_retries: 0,
+            api_bind_retry_delay: default_api_bind_retry_delay(),
+            public_api_rate_limit: None,
+            api_shutdown_timeout: default_api_shutdown_timeout(),
+            public_api_tls: None,
+            private_api_tls: None,
+            api_request_timeout: None,
+            api_worker_threads: default_api_worker_threads(),
+            max_pending_api_requests: default_max_pending_api_requests(),
+            api_warmup_timeout: None,
+            sign_responses: false,
+            http2: false,
+            enable_explorer: default_enable_explorer(),
+            transaction_cache_size: default_transaction_cache_size(),
         }
     }
 }
@@ -224,16 +480,38 @@ pub struct MemoryPoolConfig {
     /// Sets the maximum number of messages that can be buffered on the event loop's
     /// notification channel before a send will fail.
     pub events_pool_capacity: EventsPoolCapacity,
+    /// Strategy selecting transactions from the pool into a new block:
+    /// `hash`, `arrival` or `priority`. See the `mempool` module for details.
+    #[serde(default)]
+    pub strategy: MempoolStrategyKind,
+    /// Identifiers of the services whose transactions are selected first
+    /// by the `priority` strategy, from the highest priority to the lowest.
+    #[serde(default)]
+    pub priority_services: Vec<u16>,
 }
 
 impl Default for MemoryPoolConfig {
     fn default() -> Self {
         Self {
             events_pool_capacity: EventsPoolCapacity::default(),
+            strategy: MempoolStrategyKind::default(),
+            priority_services: Vec::new(),
         }
     }
 }
 
+/// Effective configuration of the running node, as returned by the private
+/// `v1/config/effective` endpoint. Contains no secret keys.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct EffectiveConfig {
+    /// Api configuration options.
+    pub api: NodeApiConfig,
+    /// Memory pool configuration.
+    pub mempool: MemoryPoolConfig,
+    /// Network configuration.
+    pub network: NetworkConfiguration,
+}
+
 /// Configuration for the `Node`.
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
 pub struct NodeConfig<T = SecretKey> {
@@ -267,6 +545,175 @@ pub struct NodeConfig<T = SecretKey> {
     pub connect_list: ConnectListConfig,
     /// Transaction Verification Thread Pool size.
     pub thread_pool_size: Option<u8>,
+    /// Record consensus messages processed by the node, so that they can be retrieved
+    /// via the private `v1/debug/consensus_log` endpoint. Requires the `consensus-log`
+    /// feature; the option is ignored otherwise.
+    #[serde(default)]
+    pub record_consensus_messages: bool,
+    /// Maximum number of the latest consensus messages kept in the consensus log.
+    #[serde(default = "default_consensus_log_size")]
+    pub consensus_log_size: usize,
+    /// Optional human-readable label of the node. If set, it prefixes the log lines
+    /// of the node and is returned by the healthcheck and peers endpoints.
+    #[serde(default)]
+    pub node_label: Option<String>,
+    /// Minimal interval between persisting the processed consensus messages to
+    /// the consensus messages cache, which is used to recover the node state after
+    /// a restart. A smaller interval improves recovery fidelity at the cost of more
+    /// writes to the database. Zero value means persisting every message immediately.
+    /// The node's own messages are always persisted before they are broadcast.
+    #[serde(default)]
+    pub consensus_cache_flush_interval: Milliseconds,
+    /// Optional database backend used by `Node::from_config`. If not set, the database
+    /// should be passed to `Node::new` explicitly.
+    #[serde(default)]
+    pub db_backend: Option<DbBackend>,
+    /// Log rejected incoming messages together with their authors and the reasons of
+    /// rejection (e.g., a bad signature or a wrong height). The number of logged messages
+    /// is limited by `REJECTED_MESSAGES_LOG_LIMIT` per second.
+    #[serde(default)]
+    pub log_rejected_messages: bool,
+    /// Peers which the transactions received by the node are broadcast to.
+    /// Consensus messages are broadcast to all peers regardless of this option.
+    #[serde(default)]
+    pub broadcast_target: BroadcastTarget,
+    /// URL of the HTTP endpoint which is notified with a POST request when the node
+    /// becomes healthy, unhealthy or starts catching up with its peers.
+    #[serde(default)]
+    pub status_webhook: Option<String>,
+    /// Identifiers of the services which transactions are accepted by the node from
+    /// the clients and peers. Transactions of other services are rejected before
+    /// adding them into the pool, unless they are included into a known propose or block.
+    /// If not set, transactions of all services are accepted.
+    #[serde(default)]
+    pub accepted_services: Option<HashSet<u16>>,
+    /// Interval during which the transactions received by the node are accumulated
+    /// before being broadcast to the peers in a single `TransactionsResponse` message
+    /// per peer. Batching reduces the number of network messages under load at the cost
+    /// of a delay in the transaction propagation. Zero value means broadcasting every
+    /// transaction immediately.
+    #[serde(default)]
+    pub tx_broadcast_batch_interval: Milliseconds,
+    /// Precompute the state hashes of the services on a background thread after each block,
+    /// so that the next block reuses the hashes of the services with unchanged state.
+    /// Only the services opted in via `Service::state_hash_index_prefixes` are affected.
+    #[serde(default)]
+    pub precompute_state_hashes: bool,
+    /// Maximal allowed skew between the creation time of an incoming transaction, as reported
+    /// by `Service::tx_timestamp`, and the current time of the node, in milliseconds.
+    /// Transactions created later than the current time plus the skew are rejected before
+    /// adding them into the pool, unless they are included into a known propose or block.
+    /// Transaction times are not checked if the value is not set.
+    #[serde(default)]
+    pub max_tx_time_skew: Option<Milliseconds>,
+    /// Maximal number of incoming transactions of a single author held by the node because
+    /// their nonces, as reported by `Service::tx_nonce`, are ahead of the expected ones.
+    /// Held transactions are added into the pool once the preceding transactions arrive,
+    /// or after a short timeout. Zero value disables holding.
+    #[serde(default)]
+    pub nonce_buffer_size: usize,
+    /// URL of the HTTP endpoint returning the connect list of the node in the JSON format.
+    /// If set, the node periodically fetches the list and replaces its connect list with it,
+    /// which is useful for the networks with dynamic membership. Invalid and empty lists,
+    /// as well as the lists missing any of the current validators, are ignored.
+    #[serde(default)]
+    pub connect_list_source: Option<Url>,
+    /// Whether timeouts are scheduled on a dedicated timer thread instead of the event loop.
+    /// The dedicated thread fires timeouts with sub-millisecond precision and is not delayed
+    /// by the network load, at the cost of an additional thread.
+    #[serde(default)]
+    pub high_resolution_timeouts: bool,
+    /// Whether each executed propose is executed once more on a fresh fork in order
+    /// to check that the execution is deterministic. The check doubles the execution
+    /// time of the blocks and is intended for debugging.
+    #[serde(default)]
+    pub verify_execution_determinism: bool,
+    /// Whether the node halts if the execution is found to be non-deterministic.
+    /// Otherwise, an error is logged. Ignored if `verify_execution_determinism` is disabled.
+    #[serde(default)]
+    pub halt_on_nondeterministic_execution: bool,
+    /// Number of validators the network is expected to consist of. If set, the node compares
+    /// it with the number of validators in the actual configuration on start and warns
+    /// about a mismatch.
+    #[serde(default)]
+    pub expected_validators: Option<usize>,
+    /// Whether the node refuses to start if the number of validators differs from
+    /// `expected_validators`.
+    #[serde(default)]
+    pub strict_expected_validators: bool,
+    /// Sink receiving the metrics of the node, such as the height and the pool size,
+    /// in addition to the `metrics-log` output. Metrics are not pushed anywhere if not set.
+    #[serde(default)]
+    pub metrics_sink: Option<MetricSinkConfig>,
+    /// Behavior of the node if `genesis` differs from the genesis configuration
+    /// the database was initialized with.
+    #[serde(default)]
+    pub genesis_conflict_policy: GenesisConflictPolicy,
+    /// Message queue each committed block is published to, together with the hashes
+    /// of its transactions. Blocks are not published if not set.
+    #[serde(default)]
+    pub block_publisher: Option<PublisherConfig>,
+    /// Number of the latest blocks which transaction bodies are kept by the node. Bodies
+    /// of the transactions in the older blocks are removed after each commit, while the block
+    /// headers, precommits and transaction results are retained. The node does not serve
+    /// the pruned blocks to its peers. Blocks are not pruned if not set.
+    #[serde(default)]
+    pub prune_blocks_before: Option<u32>,
+}
+
+/// Database backend of the node.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum DbBackend {
+    /// RocksDB database stored at the given path.
+    #[serde(rename = "rocksdb")]
+    RocksDb {
+        /// Path to the database directory.
+        path: PathBuf,
+    },
+    /// In-memory database, which is discarded when the node stops. Useful for
+    /// ephemeral and test deployments.
+    Temporary,
+}
+
+/// Behavior of the node if the configured genesis differs from the one stored in the database.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum GenesisConflictPolicy {
+    /// The node refuses to start.
+    Error,
+    /// The node logs a warning and keeps the genesis stored in the database,
+    /// ignoring the configured one. The ignored configuration is not validated.
+    KeepDatabase,
+}
+
+impl Default for GenesisConflictPolicy {
+    fn default() -> Self {
+        GenesisConflictPolicy::Error
+    }
+}
+
+/// Peers which the transactions are broadcast to.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum BroadcastTarget {
+    /// All connected peers allowed by the connect list.
+    All,
+    /// Only the peers which are validators in the current configuration.
+    ValidatorsOnly,
+}
+
+impl Default for BroadcastTarget {
+    fn default() -> Self {
+        BroadcastTarget::All
+    }
+}
+
+/// Default maximum number of messages kept in the consensus log.
+pub const DEFAULT_CONSENSUS_LOG_SIZE: usize = 1_000;
+
+fn default_consensus_log_size() -> usize {
+    DEFAULT_CONSENSUS_LOG_SIZE
 }
 
 impl NodeConfig<PathBuf> {
@@ -310,10 +757,86 @@ impl NodeConfig<PathBuf> {
             database: self.database,
             connect_list: self.connect_list,
             thread_pool_size: self.thread_pool_size,
+            record_consensus_messages: self.record_consensus_messages,
+            consensus_log_size: self.consensus_log_size,
+            node_label: self.node_label,
+            consensus_cache_flush_interval: self.consensus_cache_flush_interval,
+            db_backend: self.db_backend,
+            log_rejected_messages: self.log_rejected_messages,
+            broadcast_target: self.broadcast_target,
+            status_webhook: self.status_webhook,
+            accepted_services: self.accepted_services,
+            tx_broadcast_batch_interval: self.tx_broadcast_batch_interval,
+            precompute_state_hashes: self.precompute_state_hashes,
+            max_tx_time_skew: self.max_tx_time_skew,
+            nonce_buffer_size: self.nonce_buffer_size,
+            connect_list_source: self.connect_list_source,
+            high_resolution_timeouts: self.high_resolution_timeouts,
+            verify_execution_determinism: self.verify_execution_determinism,
+            halt_on_nondeterministic_execution: self.halt_on_nondeterministic_execution,
+            expected_validators: self.expected_validators,
+            strict_expected_validators: self.strict_expected_validators,
+            metrics_sink: self.metrics_sink,
+            genesis_conflict_policy: self.genesis_conflict_policy,
+            block_publisher: self.block_publisher,
+            prune_blocks_before: self.prune_blocks_before,
         }
     }
 }
 
+/// Length of the secret part of the blob exported by `NodeConfig::export_encrypted`,
+/// i.e., of the seeds of the consensus and service keys.
+const EXPORTED_SEEDS_LENGTH: usize = 2 * SEED_LENGTH;
+
+impl NodeConfig {
+    /// Exports the consensus and service keys together with the connect list of the node
+    /// into a single blob encrypted with the given pass phrase. The blob is encrypted
+    /// with the same scheme as the key files and can be imported on another machine with
+    /// `import_encrypted`, e.g., to migrate the node.
+    pub fn export_encrypted(&self, passphrase: &[u8]) -> Result<Vec<u8>, Error> {
+        // The seeds of the keys are followed by the connect list serialized as JSON.
+        // The buffer is allocated once, so that no copies of the seeds are left
+        // in memory after it is zeroized.
+        let connect_list = serde_json::to_vec(&self.connect_list)?;
+        let mut plaintext = ZeroizeOnDrop(Vec::with_capacity(
+            EXPORTED_SEEDS_LENGTH + connect_list.len(),
+        ));
+        plaintext.extend_from_slice(&self.consensus_secret_key[..SEED_LENGTH]);
+        plaintext.extend_from_slice(&self.service_secret_key[..SEED_LENGTH]);
+        plaintext.extend_from_slice(&connect_list);
+        let encrypted = crypto::encrypt_with_passphrase(&*plaintext, passphrase)?;
+        Ok(encrypted)
+    }
+
+    /// Replaces the keys and the connect list of the node with the ones exported
+    /// by `export_encrypted`. Returns an error if the pass phrase is wrong or the blob
+    /// is corrupted; in this case, the configuration is not changed.
+    pub fn import_encrypted(&mut self, bytes: &[u8], passphrase: &[u8]) -> Result<(), Error> {
+        // The decrypted data is zeroized on drop.
+        let decrypted = crypto::decrypt_with_passphrase(bytes, passphrase)?;
+        ensure!(
+            decrypted.len() >= EXPORTED_SEEDS_LENGTH,
+            "Exported node secrets are truncated"
+        );
+        let (seeds, connect_list) = decrypted.split_at(EXPORTED_SEEDS_LENGTH);
+        let connect_list: ConnectListConfig = serde_json::from_slice(connect_list)?;
+        let consensus_seed = Seed::from_slice(&seeds[..SEED_LENGTH])
+            .ok_or_else(|| format_err!("Invalid consensus key seed"))?;
+        let service_seed = Seed::from_slice(&seeds[SEED_LENGTH..])
+            .ok_or_else(|| format_err!("Invalid service key seed"))?;
+
+        let (consensus_public_key, consensus_secret_key) =
+            crypto::gen_keypair_from_seed(&consensus_seed);
+        let (service_public_key, service_secret_key) = crypto::gen_keypair_from_seed(&service_seed);
+        self.consensus_public_key = consensus_public_key;
+        self.consensus_secret_key = consensus_secret_key;
+        self.service_public_key = service_public_key;
+        self.service_secret_key = service_secret_key;
+        self.connect_list = connect_list;
+        Ok(())
+    }
+}
+
 /// Configuration for the `NodeHandler`.
 #[derive(Debug, Clone)]
 pub struct Configuration {
@@ -417,6 +940,33 @@ impl ConnectListConfig {
         ConnectListConfig { peers }
     }
 
+    /// Checks that the connect list contains unique peers with addresses in the `host:port`
+    /// format. The same check is applied to the connect list of the node configuration
+    /// and to the lists fetched from the connect list source.
+    pub fn validate(&self) -> Result<(), Error> {
+        let mut keys = HashSet::with_capacity(self.peers.len());
+        for peer in &self.peers {
+            ensure!(
+                keys.insert(peer.public_key),
+                "Connect list contains several peers with the public key {}",
+                peer.public_key.to_hex()
+            );
+            let is_valid_address = match peer.address.rfind(':') {
+                Some(position) => {
+                    position > 0 && peer.address[position + 1..].parse::<u16>().is_ok()
+                }
+                None => false,
+            };
+            ensure!(
+                is_valid_address,
+                "Address `{}` of the peer {} in the connect list is not in the `host:port` format",
+                peer.address,
+                peer.public_key.to_hex()
+            );
+        }
+        Ok(())
+    }
+
     /// Creates `ConnectListConfig` from `ConnectList`.
     pub fn from_connect_list(connect_list: &SharedConnectList) -> Self {
         ConnectListConfig {
@@ -424,6 +974,28 @@ impl ConnectListConfig {
         }
     }
 
+    /// Creates `ConnectListConfig` from the peers the node is currently connected to
+    /// and which are allowed by the connect list. Peer addresses are taken from
+    /// their `Connect` messages.
+    pub fn from_connected_peers(state: &State) -> Self {
+        let connect_list = state.connect_list();
+        let mut peers: Vec<_> = state
+            .peers()
+            .iter()
+            .filter(|(public_key, _)| {
+                state.connections().contains_key(public_key)
+                    && connect_list.is_peer_allowed(public_key)
+            })
+            .map(|(public_key, connect)| ConnectInfo {
+                address: connect.pub_addr().to_owned(),
+                public_key: *public_key,
+            })
+            .collect();
+        peers.sort_by(|a, b| a.public_key.cmp(&b.public_key));
+
+        ConnectListConfig { peers }
+    }
+
     /// `ConnectListConfig` peers addresses.
     pub fn addresses(&self) -> Vec<String> {
         self.peers.iter().map(|p| p.address.clone()).collect()
@@ -448,8 +1020,12 @@ impl NodeHandler {
 
         let snapshot = blockchain.snapshot();
 
-        let stored = Schema::new(&snapshot).actual_configuration();
+        let schema = Schema::new(&snapshot);
+        let stored = schema.actual_configuration();
         info!("Creating a node with config: {:#?}", stored);
+        let genesis_hash = schema
+            .block_hash_by_height(Height::zero())
+            .expect("Genesis block is not found");
 
         let validator_id = stored
             .validator_keys
@@ -462,6 +1038,7 @@ impl NodeHandler {
                 external_address,
                 system_state.current_time().into(),
                 &user_agent::get(),
+                &genesis_hash,
             ),
             config.listener.consensus_public_key,
             &config.listener.consensus_secret_key,
@@ -483,6 +1060,8 @@ impl NodeHandler {
             system_state.current_time(),
         );
 
+        let mempool_strategy = config.mempool.strategy.build(&config.mempool);
+
         let node_role = NodeRole::new(validator_id);
         let is_enabled = api_state.is_enabled();
         api_state.set_node_role(node_role);
@@ -492,6 +1071,7 @@ impl NodeHandler {
             None => None,
         };
 
+        let last_consensus_cache_flush = system_state.current_time();
         Self {
             blockchain,
             api_state,
@@ -503,6 +1083,33 @@ impl NodeHandler {
             node_role,
             config_manager,
             allow_expedited_propose: true,
+            peer_exchange_jitter_percent: config.network.peer_exchange_jitter_percent,
+            rng: StdRng::from_entropy(),
+            max_peers_in_exchange: config.network.max_peers_in_exchange,
+            exchanged_peers_left: config.network.max_peers_in_exchange,
+            mempool_strategy,
+            consensus_cache_flush_interval: 0,
+            pending_consensus_messages: Vec::new(),
+            pending_consensus_round: Round::zero(),
+            last_consensus_cache_flush,
+            rejected_messages_log: None,
+            broadcast_target: BroadcastTarget::All,
+            status_webhook: None,
+            block_publisher: None,
+            connect_list_source: None,
+            metric_sink: None,
+            accepted_services: None,
+            disabled_services: HashSet::new(),
+            api_warmup: None,
+            tx_broadcast_batch_interval: 0,
+            pending_broadcast_txs: Vec::new(),
+            max_tx_time_skew: None,
+            nonce_buffer: NonceBuffer::new(0),
+            nonce_buffer_timeout_scheduled: false,
+            verify_execution_determinism: false,
+            halt_on_nondeterministic_execution: false,
+            prune_blocks_before: None,
+            lacks_online_validators: false,
         }
     }
 
@@ -541,6 +1148,229 @@ impl NodeHandler {
         self.state().consensus_config().peers_timeout
     }
 
+    /// Returns the maximum random delay added to the peer exchange timeout.
+    pub fn max_peer_exchange_jitter(&self) -> Milliseconds {
+        self.peers_timeout() * self.peer_exchange_jitter_percent.min(100) / 100
+    }
+
+    /// Reseeds the random number generator used for peer exchange. Allows to make
+    /// peer exchange timeouts deterministic, e.g., in tests.
+    pub fn set_rng_seed(&mut self, seed: u64) {
+        self.rng = StdRng::seed_from_u64(seed);
+    }
+
+    /// Replaces the strategy selecting transactions from the pool into a new `Propose`.
+    pub fn set_mempool_strategy(&mut self, strategy: Box<dyn MempoolStrategy>) {
+        self.mempool_strategy = strategy;
+    }
+
+    /// Sets the minimal interval between persisting the consensus messages cache.
+    pub fn set_consensus_cache_flush_interval(&mut self, interval: Milliseconds) {
+        self.consensus_cache_flush_interval = interval;
+    }
+
+    /// Enables or disables logging of the rejected incoming messages.
+    pub fn set_log_rejected_messages(&mut self, enabled: bool) {
+        self.rejected_messages_log = if enabled {
+            Some(RejectedMessagesLog::default())
+        } else {
+            None
+        };
+    }
+
+    /// Sets the peers which the transactions are broadcast to.
+    pub fn set_broadcast_target(&mut self, target: BroadcastTarget) {
+        self.broadcast_target = target;
+    }
+
+    /// Sets the interval during which the transactions are accumulated before being
+    /// broadcast to the peers in a batch. Zero value disables batching.
+    pub fn set_tx_broadcast_batch_interval(&mut self, interval: Milliseconds) {
+        self.tx_broadcast_batch_interval = interval;
+    }
+
+    /// Sets the URL notified about the node health transitions. If `None`,
+    /// the notifications are disabled.
+    pub fn set_status_webhook(&mut self, url: Option<String>) -> Result<(), Error> {
+        self.status_webhook = match url {
+            Some(url) => Some(StatusWebhook::new(url)?),
+            None => None,
+        };
+        Ok(())
+    }
+
+    /// Sets the publisher of the committed blocks. If `None`, the blocks are not published.
+    pub fn set_block_publisher(
+        &mut self,
+        publisher: Option<Box<dyn BlockPublisher>>,
+    ) -> Result<(), Error> {
+        self.block_publisher = match publisher {
+            Some(publisher) => Some(BlockPublisherHandle::new(publisher)?),
+            None => None,
+        };
+        Ok(())
+    }
+
+    /// Sets the refresh of the connect list from an HTTP endpoint. The refresh stops
+    /// once the node shuts down.
+    pub(crate) fn set_connect_list_source(&mut self, source: Option<ConnectListSource>) {
+        self.connect_list_source = source;
+    }
+
+    /// Registers the metric sink of the node. The sink is unregistered once the node
+    /// is dropped or another sink is set.
+    pub fn set_metric_sink(&mut self, sink: Option<Arc<dyn MetricSink>>) {
+        self.metric_sink = sink.map(metrics::add_metric_sink);
+    }
+
+    /// Restricts the services which transactions are accepted by the node.
+    /// If `None`, transactions of all services are accepted.
+    pub fn set_accepted_services(&mut self, services: Option<HashSet<u16>>) {
+        self.accepted_services = services;
+    }
+
+    /// Sets the maximal allowed skew between the creation time of an incoming transaction
+    /// and the current time. If `None`, transaction times are not checked.
+    pub fn set_max_tx_time_skew(&mut self, skew: Option<Milliseconds>) {
+        self.max_tx_time_skew = skew;
+    }
+
+    /// Sets the maximal number of transactions of a single author held until the transactions
+    /// with the preceding nonces arrive. Zero value disables holding.
+    pub fn set_nonce_buffer_size(&mut self, size: usize) {
+        self.nonce_buffer = NonceBuffer::new(size);
+    }
+
+    /// Enables pruning of the transaction bodies of the blocks older than the latest
+    /// `keep_blocks` blocks after each commit. If `None`, the blocks are not pruned.
+    pub fn set_prune_blocks_before(&mut self, keep_blocks: Option<u32>) {
+        self.prune_blocks_before = keep_blocks;
+    }
+
+    /// Enables or disables the repeated execution of the proposes checking that
+    /// the execution is deterministic. If `halt` is set, the node panics once
+    /// a non-deterministic execution is detected.
+    pub fn set_execution_determinism_check(&mut self, verify: bool, halt: bool) {
+        self.verify_execution_determinism = verify;
+        self.halt_on_nondeterministic_execution = halt;
+    }
+
+    /// Sets the time given to the node to catch up with its peers before it is reported
+    /// as ready via the API. If `None`, the node is ready immediately.
+    pub fn set_api_warmup_timeout(&mut self, timeout: Option<Duration>) {
+        self.api_warmup = timeout.map(|timeout| ApiWarmup {
+            deadline: self.system_state.current_time() + timeout,
+            peer_status_received: false,
+        });
+        self.api_state.set_ready(self.api_warmup.is_none());
+    }
+
+    /// Finishes the API warmup if the node has caught up with the heights of its peers
+    /// known from the `Status` messages or the warmup timeout has elapsed.
+    fn update_api_readiness(&mut self) {
+        let is_ready = match self.api_warmup {
+            Some(ref warmup) => {
+                let caught_up =
+                    warmup.peer_status_received && self.state.nodes_with_bigger_height().is_empty();
+                caught_up || self.system_state.current_time() >= warmup.deadline
+            }
+            None => return,
+        };
+        if is_ready {
+            info!("Node is ready at height {}", self.state.height());
+            self.api_warmup = None;
+            self.api_state.set_ready(true);
+        }
+    }
+
+    /// Returns `true` if the node accepts transactions of the given service.
+    fn is_service_accepted(&self, service_id: u16) -> bool {
+        !self.disabled_services.contains(&service_id)
+            && self
+                .accepted_services
+                .as_ref()
+                .map_or(true, |services| services.contains(&service_id))
+    }
+
+    /// Returns the creation time of the transaction if it exceeds the current time
+    /// by more than `max_tx_time_skew`.
+    fn future_tx_time(&self, tx: &RawTransaction) -> Option<DateTime<Utc>> {
+        let max_skew = self.max_tx_time_skew?;
+        let time = self.blockchain.tx_timestamp(tx)?;
+        let now: DateTime<Utc> = self.system_state.current_time().into();
+        if time > now + chrono::Duration::milliseconds(max_skew as i64) {
+            Some(time)
+        } else {
+            None
+        }
+    }
+
+    /// Notifies the status webhook, if any, about the current health of the node.
+    fn update_status_webhook(&mut self) {
+        if let Some(ref mut webhook) = self.status_webhook {
+            let health = NodeHealth::new(&self.state, &self.api_state);
+            webhook.update(health, self.state.height());
+        }
+    }
+
+    /// Saves the given message to the consensus messages cache.
+    pub(crate) fn save_message<T: ProtocolMessage>(&mut self, round: Round, message: Signed<T>) {
+        self.save_messages(round, iter::once(message.into()));
+    }
+
+    /// Saves the message created by the node itself to the consensus messages cache.
+    /// Unlike the messages of other validators, it is persisted immediately, together
+    /// with the pending messages, so that the node never broadcasts a vote it could
+    /// forget after a restart.
+    pub(crate) fn save_own_message<T: ProtocolMessage>(
+        &mut self,
+        round: Round,
+        message: Signed<T>,
+    ) {
+        self.pending_consensus_messages.push(message.into());
+        self.pending_consensus_round = round;
+        self.flush_consensus_cache();
+    }
+
+    /// Saves the given messages of other validators to the consensus messages cache.
+    /// Messages are persisted at most once per `consensus_cache_flush_interval`, the rest
+    /// are kept in memory until the next flush.
+    pub(crate) fn save_messages<I>(&mut self, round: Round, messages: I)
+    where
+        I: IntoIterator<Item = Message>,
+    {
+        if self.consensus_cache_flush_interval == 0 {
+            self.blockchain.save_messages(round, messages);
+            return;
+        }
+        self.pending_consensus_messages.extend(messages);
+        self.pending_consensus_round = round;
+        self.flush_consensus_cache_if_due();
+    }
+
+    /// Persists the pending consensus messages if `consensus_cache_flush_interval`
+    /// has passed since the previous flush.
+    pub(crate) fn flush_consensus_cache_if_due(&mut self) {
+        if self.pending_consensus_messages.is_empty() {
+            return;
+        }
+        let flush_time = self.last_consensus_cache_flush
+            + Duration::from_millis(self.consensus_cache_flush_interval);
+        if self.system_state.current_time() >= flush_time {
+            self.flush_consensus_cache();
+        }
+    }
+
+    /// Persists the pending consensus messages to the consensus messages cache.
+    pub fn flush_consensus_cache(&mut self) {
+        if !self.pending_consensus_messages.is_empty() {
+            let messages = mem::replace(&mut self.pending_consensus_messages, Vec::new());
+            self.blockchain
+                .save_messages(self.pending_consensus_round, messages);
+        }
+        self.last_consensus_cache_flush = self.system_state.current_time();
+    }
+
     /// Returns value of the `txs_block_limit` field from the current `ConsensusConfig`.
     pub fn txs_block_limit(&self) -> u32 {
         self.state().consensus_config().txs_block_limit
@@ -556,11 +1386,84 @@ impl NodeHandler {
         self.state().consensus_config().max_propose_timeout
     }
 
+    /// Returns the minimal interval between blocks implied by the block rate limit.
+    pub fn min_block_interval(&self) -> Option<Milliseconds> {
+        self.state().consensus_config().min_block_interval()
+    }
+
     /// Returns threshold starting from which the minimal propose timeout value is used.
     pub fn propose_timeout_threshold(&self) -> u32 {
         self.state().consensus_config().propose_timeout_threshold
     }
 
+    /// Returns `true` if the node is a validator and enough validators are connected
+    /// to it to take part in the consensus, as specified by `min_online_validators`
+    /// and `min_online_weight`.
+    pub fn is_active_validator(&self) -> bool {
+        self.state.is_validator() && self.has_enough_online_validators()
+    }
+
+    /// Returns `true` if the number of validators connected to the node (including
+    /// the node itself) is not less than `min_online_validators`, and their total weight
+    /// is not less than `min_online_weight` from the consensus configuration.
+    pub fn has_enough_online_validators(&self) -> bool {
+        let consensus_config = self.state.consensus_config();
+        let enough_validators = consensus_config
+            .min_online_validators
+            .map_or(true, |min_count| self.online_validators() >= min_count);
+        let enough_weight = consensus_config
+            .min_online_weight
+            .map_or(true, |min_weight| self.online_weight() >= min_weight);
+        enough_validators && enough_weight
+    }
+
+    /// Returns the number of validators connected to the node, including the node itself.
+    fn online_validators(&self) -> usize {
+        let connected_validators = self
+            .state
+            .peers()
+            .keys()
+            .filter(|key| self.state.peer_is_validator(key))
+            .count();
+        connected_validators + usize::from(self.state.is_validator())
+    }
+
+    /// Returns the total weight of the validators connected to the node, including
+    /// the node itself.
+    fn online_weight(&self) -> u64 {
+        let online_ids = self
+            .state
+            .peers()
+            .keys()
+            .filter_map(|key| self.state.find_validator(*key))
+            .chain(self.state.validator_id());
+        self.state.validators_weight(online_ids)
+    }
+
+    /// Logs the transitions between having enough and too few validators online.
+    /// Should be called whenever the set of the connected peers changes.
+    pub(crate) fn check_online_validators(&mut self) {
+        let min_online_validators = self.state.consensus_config().min_online_validators;
+        let min_online_weight = self.state.consensus_config().min_online_weight;
+        if min_online_validators.is_none() && min_online_weight.is_none() {
+            return;
+        }
+        let has_enough = self.has_enough_online_validators();
+        if !has_enough && !self.lacks_online_validators {
+            warn!(
+                "Only {} validators with the total weight {} are online, while {} validators \
+                 with the total weight {} are required to take part in the consensus",
+                self.online_validators(),
+                self.online_weight(),
+                min_online_validators.unwrap_or(0),
+                min_online_weight.unwrap_or(0)
+            );
+        } else if has_enough && self.lacks_online_validators {
+            info!("Enough validators are online, resuming consensus");
+        }
+        self.lacks_online_validators = !has_enough;
+    }
+
     /// Returns `State` of the node.
     pub fn state(&self) -> &State {
         &self.state
@@ -570,6 +1473,7 @@ impl NodeHandler {
     pub fn initialize(&mut self) {
         let listen_address = self.system_state.listen_address();
         info!("Start listening address={}", listen_address);
+        self.check_online_validators();
 
         let peers: HashSet<_> = {
             let it = self.state.peers().values().map(Signed::author);
@@ -601,45 +1505,167 @@ impl NodeHandler {
 
         // Recover cached consensus messages if any. We do this after main initialization and before
         // the start of event processing.
-        let messages = schema.consensus_messages_cache();
-        for msg in messages.iter() {
+        let messages = schema.consensus_messages_cache().iter().collect::<Vec<_>>();
+        if let Err(e) = self.check_consensus_messages_cache(&messages, round) {
+            error!(
+                "Consensus messages cache is inconsistent with the recovered round {}, \
+                 skipping its replay: {}",
+                round, e
+            );
+            return;
+        }
+        for msg in messages {
             self.handle_message(msg);
         }
     }
 
+    /// Checks that the cached consensus messages belong to the current height and
+    /// do not exceed the recovered consensus round.
+    fn check_consensus_messages_cache(
+        &self,
+        messages: &[Message],
+        round: Round,
+    ) -> Result<(), Error> {
+        for message in messages {
+            let msg = match *message {
+                Message::Consensus(ref msg) => msg,
+                _ => bail!("Cached message is not a consensus one: {:?}", message),
+            };
+            ensure!(
+                msg.height() == self.state.height(),
+                "Cached message has height {}, while the node height is {}",
+                msg.height(),
+                self.state.height()
+            );
+            ensure!(
+                msg.round() <= round,
+                "Cached message has round {}, which is after the recovered round",
+                msg.round()
+            );
+        }
+        Ok(())
+    }
+
     /// Runs the node's basic timers.
     fn add_timeouts(&mut self) {
         self.add_round_timeout();
         self.add_status_timeout();
         self.add_peer_exchange_timeout();
         self.add_update_api_state_timeout();
+        // The timeout of the pending broadcast batch is ignored while the node is disabled.
+        if !self.pending_broadcast_txs.is_empty() {
+            self.add_tx_broadcast_batch_timeout();
+        }
+        if self.nonce_buffer.held_len() > 0 {
+            self.add_nonce_buffer_timeout();
+        }
     }
 
     /// Sends the given message to a peer by its public key.
     pub fn send_to_peer<T: Into<SignedMessage>>(&mut self, public_key: PublicKey, message: T) {
         let message = message.into();
         let request = NetworkRequest::SendMessage(public_key, message);
-        self.channel.network_requests.send(request).log_error();
+        self.send_network_request(request);
+    }
+
+    /// Sends the request to the network thread. If the channel is full, the request
+    /// is dropped instead of blocking the consensus, and is counted in `ChannelStats`.
+    pub(crate) fn send_network_request(&mut self, request: NetworkRequest) {
+        if let Err(e) = self.channel.network_requests.get_mut().try_send(request) {
+            let dropped = self.api_state.add_dropped_network_request();
+            metric!("node.channel.network_requests.dropped", dropped);
+            error!("Network request dropped: {}", e);
+        }
+    }
+
+    /// Sends the request to the internal thread. If sending fails, the request is
+    /// counted as dropped in `ChannelStats`.
+    pub(crate) fn send_internal_request(&mut self, request: InternalRequest) {
+        if let Err(e) = self.channel.internal_requests.send(request) {
+            let dropped = self.api_state.add_dropped_internal_request();
+            metric!("node.channel.internal_requests.dropped", dropped);
+            error!("Internal request dropped: {}", e);
+        }
     }
 
     /// Broadcasts given message to all peers.
     pub(crate) fn broadcast<M: Into<SignedMessage>>(&mut self, message: M) {
-        let peers: Vec<PublicKey> = self
-            .state
+        self.broadcast_to_peers(message, false);
+    }
+
+    /// Broadcasts the transaction to the peers selected by the broadcast target.
+    /// If batching is enabled, the transaction is broadcast after the batch interval
+    /// together with the other transactions received during it.
+    pub(crate) fn broadcast_transaction(&mut self, tx: Signed<RawTransaction>) {
+        if self.tx_broadcast_batch_interval == 0 {
+            let validators_only = self.broadcast_target == BroadcastTarget::ValidatorsOnly;
+            self.broadcast_to_peers(tx, validators_only);
+            return;
+        }
+
+        self.pending_broadcast_txs.push(tx);
+        if self.pending_broadcast_txs.len() == 1 {
+            self.add_tx_broadcast_batch_timeout();
+        }
+    }
+
+    /// Broadcasts the accumulated transactions to the peers selected by the broadcast target.
+    /// A single transaction is broadcast as is; several transactions are sent to each peer
+    /// in `TransactionsResponse` messages not exceeding the maximum message length.
+    pub(crate) fn broadcast_pending_transactions(&mut self) {
+        let mut txs = mem::replace(&mut self.pending_broadcast_txs, Vec::new());
+        let validators_only = self.broadcast_target == BroadcastTarget::ValidatorsOnly;
+        if txs.len() <= 1 {
+            if let Some(tx) = txs.pop() {
+                self.broadcast_to_peers(tx, validators_only);
+            }
+            return;
+        }
+
+        let unoccupied_message_size = self.state.config().consensus.max_message_len as usize
+            - TRANSACTION_RESPONSE_EMPTY_SIZE;
+        let mut batches = vec![Vec::new()];
+        let mut batch_size = 0;
+        for tx in txs {
+            let raw = tx.signed_message().raw().to_vec();
+            let tx_size = raw.len() + RAW_TRANSACTION_HEADER;
+            if batch_size + tx_size > unoccupied_message_size && batch_size > 0 {
+                batches.push(Vec::new());
+                batch_size = 0;
+            }
+            batch_size += tx_size;
+            batches.last_mut().unwrap().push(raw);
+        }
+
+        for peer in self.broadcast_peers(validators_only) {
+            for batch in &batches {
+                let message = self.sign_message(TransactionsResponse::new(&peer, batch.clone()));
+                self.send_to_peer(peer, message);
+            }
+        }
+    }
+
+    fn broadcast_to_peers<M: Into<SignedMessage>>(&mut self, message: M, validators_only: bool) {
+        let message = message.into();
+        for address in self.broadcast_peers(validators_only) {
+            self.send_to_peer(address, message.clone());
+        }
+    }
+
+    fn broadcast_peers(&self, validators_only: bool) -> Vec<PublicKey> {
+        self.state
             .peers()
             .iter()
             .filter_map(|(pubkey, _)| {
-                if self.state.connect_list().is_peer_allowed(pubkey) {
+                if self.state.connect_list().is_peer_allowed(pubkey)
+                    && (!validators_only || self.state.peer_is_validator(pubkey))
+                {
                     Some(*pubkey)
                 } else {
                     None
                 }
             })
-            .collect();
-        let message = message.into();
-        for address in peers {
-            self.send_to_peer(address, message.clone());
-        }
+            .collect()
     }
 
     /// Performs connection to the specified network address.
@@ -649,12 +1675,20 @@ impl NodeHandler {
     }
 
     /// Add timeout request.
+    ///
+    /// Sending blocks while the internal requests channel is full, so it fails only
+    /// if the internal thread is gone. A lost timeout (e.g., a round one) can freeze
+    /// the node forever, thus the failure is fatal instead of dropping the timeout.
     pub fn add_timeout(&mut self, timeout: NodeTimeout, time: SystemTime) {
         let request = TimeoutRequest(time, timeout);
-        self.channel
-            .internal_requests
-            .send(request.into())
-            .log_error();
+        if let Err(e) = self.channel.internal_requests.send(request.into()) {
+            let dropped = self.api_state.add_dropped_internal_request();
+            metric!("node.channel.internal_requests.dropped", dropped);
+            panic!(
+                "Unable to schedule {:?}, the node cannot proceed without it",
+                e.into_inner()
+            );
+        }
     }
 
     /// Adds request timeout if it isn't already requested.
@@ -685,8 +1719,12 @@ impl NodeHandler {
         } else {
             self.max_propose_timeout()
         };
-
-        let time = self.round_start_time(self.state.round()) + Duration::from_millis(timeout);
+        let mut time = self.round_start_time(self.state.round()) + Duration::from_millis(timeout);
+        // Blocks must not be produced faster than `max_blocks_per_second` allows.
+        if let Some(interval) = self.min_block_interval() {
+            let earliest = self.state.height_start_time() + Duration::from_millis(interval);
+            time = cmp::max(time, earliest);
+        }
 
         trace!(
             "ADD PROPOSE TIMEOUT: time={:?}, height={}, round={}",
@@ -729,7 +1767,14 @@ impl NodeHandler {
     /// Adds `NodeTimeout::PeerExchange` timeout to the channel.
     pub fn add_peer_exchange_timeout(&mut self) {
         trace!("ADD PEER EXCHANGE TIMEOUT");
-        let time = self.system_state.current_time() + Duration::from_millis(self.peers_timeout());
+        let max_jitter = self.max_peer_exchange_jitter();
+        let jitter = if max_jitter > 0 {
+            self.rng.gen_range(0, max_jitter + 1)
+        } else {
+            0
+        };
+        let time =
+            self.system_state.current_time() + Duration::from_millis(self.peers_timeout() + jitter);
         self.add_timeout(NodeTimeout::PeerExchange, time);
     }
 
@@ -740,6 +1785,22 @@ impl NodeHandler {
         self.add_timeout(NodeTimeout::UpdateApiState, time);
     }
 
+    /// Adds `NodeTimeout::TxBroadcastBatch` timeout to the channel.
+    pub fn add_tx_broadcast_batch_timeout(&mut self) {
+        let time = self.system_state.current_time()
+            + Duration::from_millis(self.tx_broadcast_batch_interval);
+        self.add_timeout(NodeTimeout::TxBroadcastBatch, time);
+    }
+
+    /// Adds `NodeTimeout::NonceBufferExpiry` timeout for the earliest transaction held
+    /// in the nonce buffer to the channel.
+    pub(crate) fn add_nonce_buffer_timeout(&mut self) {
+        if let Some(time) = self.nonce_buffer.next_expiry() {
+            self.add_timeout(NodeTimeout::NonceBufferExpiry, time);
+            self.nonce_buffer_timeout_scheduled = true;
+        }
+    }
+
     /// Returns hash of the last block.
     pub fn last_block_hash(&self) -> Hash {
         self.blockchain.last_block().hash()
@@ -758,6 +1819,29 @@ impl NodeHandler {
                 * self.round_timeout_increase();
         self.state.height_start_time() + Duration::from_millis(ms)
     }
+
+    /// Returns start times and timeouts of the upcoming rounds at the current height,
+    /// starting from the current round.
+    pub fn round_schedule(&self) -> RoundSchedule {
+        // The round is zero until the node starts the first round at the height.
+        let first_round = cmp::max(self.state.round(), Round::first());
+        let rounds = first_round
+            .iter_to(Round(first_round.0 + ROUND_SCHEDULE_LENGTH as u32))
+            .map(|round| {
+                let previous_round: u64 = round.previous().into();
+                RoundTiming {
+                    round,
+                    start_time: DateTime::from(self.round_start_time(round)),
+                    timeout: self.first_round_timeout()
+                        + previous_round * self.round_timeout_increase(),
+                }
+            })
+            .collect();
+        RoundSchedule {
+            height: self.state.height(),
+            rounds,
+        }
+    }
 }
 
 impl fmt::Debug for NodeHandler {
@@ -782,6 +1866,23 @@ impl ApiSender {
         self.send_external_message(msg)
     }
 
+    /// Atomically replaces the connect list of the node. The node connects to
+    /// the newly added peers and disconnects from the removed ones.
+    pub fn replace_connect_list(&self, connect_list: ConnectListConfig) -> Result<(), Error> {
+        let msg = ExternalMessage::ReplaceConnectList(connect_list);
+        self.send_external_message(msg)
+    }
+
+    /// Enables or disables accepting new transactions of the service. Transactions
+    /// of a disabled service are still executed when they are committed.
+    pub fn set_service_enabled(&self, service_id: u16, enabled: bool) -> Result<(), Error> {
+        let msg = ExternalMessage::SetServiceEnabled {
+            service_id,
+            enabled,
+        };
+        self.send_external_message(msg)
+    }
+
     /// Sends an external message.
     pub fn send_external_message(&self, message: ExternalMessage) -> Result<(), Error> {
         self.0
@@ -863,6 +1964,8 @@ pub struct Node {
     channel: NodeChannel,
     max_message_len: u32,
     thread_pool_size: Option<u8>,
+    db_compaction_interval: Option<Milliseconds>,
+    high_resolution_timeouts: bool,
 }
 
 impl NodeChannel {
@@ -896,6 +1999,7 @@ impl Node {
         config_file_path: Option<String>,
     ) -> Self {
         crypto::init();
+        check_node_config(&node_cfg).unwrap_or_else(|e| panic!("{}", e));
 
         let channel = NodeChannel::new(&node_cfg.mempool.events_pool_capacity);
         let mut blockchain = Blockchain::new(
@@ -905,9 +2009,42 @@ impl Node {
             node_cfg.service_secret_key.clone(),
             ApiSender::new(channel.api_requests.0.clone()),
         );
+        blockchain.set_commit_retries(
+            node_cfg.database.commit_retries,
+            Duration::from_millis(node_cfg.database.commit_retry_delay),
+        );
+        check_genesis(
+            &Schema::new(&blockchain.snapshot()),
+            &node_cfg.genesis,
+            node_cfg.genesis_conflict_policy,
+        )
+        .unwrap_or_else(|e| panic!("{}", e));
         blockchain.initialize(node_cfg.genesis.clone()).unwrap();
+        if node_cfg.precompute_state_hashes {
+            blockchain.enable_state_hash_precomputation();
+        }
+
+        let start_time = SystemTime::now();
+        let fork = blockchain.fork();
+        Schema::new(&fork).record_node_start(start_time.into());
+        blockchain
+            .merge(fork.into_patch())
+            .expect("Unable to record the node start");
+        // The configured genesis is ignored if the database was initialized with another one.
+        let actual_config = Schema::new(&blockchain.snapshot()).actual_configuration();
+        if let Some(expected) = node_cfg.expected_validators {
+            let actual = actual_config.validator_keys.len();
+            check_validators_count(expected, actual, node_cfg.strict_expected_validators)
+                .unwrap_or_else(|e| panic!("{}", e));
+        }
 
         let peers = node_cfg.connect_list.addresses();
+        let external_address = resolve_external_address(
+            &node_cfg.external_address,
+            &node_cfg.network,
+            resolve_socket_address,
+        )
+        .unwrap_or_else(|e| panic!("{}", e));
 
         let config = Configuration {
             listener: ListenerConfig {
@@ -926,37 +2063,156 @@ impl Node {
         };
 
         let api_state = SharedNodeState::new(node_cfg.api.state_update_timeout as u64);
+        if node_cfg.record_consensus_messages {
+            if cfg!(feature = "consensus-log") {
+                api_state.enable_consensus_log(node_cfg.consensus_log_size);
+            } else {
+                warn!("Recording of consensus messages requires the `consensus-log` feature");
+            }
+        }
+        api_state.set_node_label(node_cfg.node_label);
+        api_state.set_start_time(start_time);
+        api_state
+            .set_api_request_timeout(node_cfg.api.api_request_timeout.map(Duration::from_millis));
+        api_state.set_api_workers(
+            node_cfg.api.api_worker_threads,
+            node_cfg.api.max_pending_api_requests,
+        );
+        api_state.set_transaction_cache_size(node_cfg.api.transaction_cache_size);
+        api_state.set_effective_config(EffectiveConfig {
+            api: node_cfg.api.clone(),
+            mempool: config.mempool.clone(),
+            network: config.network,
+        });
         let system_state = Box::new(DefaultSystemState(node_cfg.listen_address));
         let network_config = config.network;
-        let handler = NodeHandler::new(
+        let mut handler = NodeHandler::new(
             blockchain,
-            &node_cfg.external_address,
+            &external_address,
             channel.node_sender(),
             system_state,
             config,
             api_state,
             config_file_path,
         );
+        handler.set_consensus_cache_flush_interval(node_cfg.consensus_cache_flush_interval);
+        handler.set_log_rejected_messages(node_cfg.log_rejected_messages);
+        handler.set_broadcast_target(node_cfg.broadcast_target);
+        handler.set_tx_broadcast_batch_interval(node_cfg.tx_broadcast_batch_interval);
+        handler
+            .set_status_webhook(node_cfg.status_webhook)
+            .expect("Unable to start the status webhook");
+        let block_publisher = node_cfg.block_publisher.as_ref().map(|config| {
+            config
+                .build()
+                .expect("Unable to create the block publisher")
+        });
+        handler
+            .set_block_publisher(block_publisher)
+            .expect("Unable to start the block publisher");
+        handler.set_accepted_services(node_cfg.accepted_services);
+        handler.set_max_tx_time_skew(node_cfg.max_tx_time_skew);
+        handler.set_nonce_buffer_size(node_cfg.nonce_buffer_size);
+        handler.set_prune_blocks_before(node_cfg.prune_blocks_before);
+        let metric_sink = node_cfg
+            .metrics_sink
+            .as_ref()
+            .map(|config| config.build().expect("Unable to start the metric sink"));
+        handler.set_metric_sink(metric_sink);
+        handler.set_execution_determinism_check(
+            node_cfg.verify_execution_determinism,
+            node_cfg.halt_on_nondeterministic_execution,
+        );
+        let connect_list_source = node_cfg.connect_list_source.map(|url| {
+            connect_list_source::start(
+                url,
+                connect_list_source::CONNECT_LIST_REFRESH_INTERVAL,
+                handler.blockchain.clone(),
+                node_cfg.consensus_public_key,
+                ApiSender::new(channel.api_requests.0.clone()),
+            )
+            .expect("Unable to start the connect list refresh")
+        });
+        handler.set_connect_list_source(connect_list_source);
+        handler.set_api_warmup_timeout(node_cfg.api.api_warmup_timeout.map(Duration::from_millis));
         Self {
             api_options: node_cfg.api,
             handler,
             channel,
             network_config,
-            max_message_len: node_cfg.genesis.consensus.max_message_len,
+            max_message_len: actual_config.consensus.max_message_len,
             thread_pool_size: node_cfg.thread_pool_size,
+            db_compaction_interval: node_cfg.database.compaction_interval,
+            high_resolution_timeouts: node_cfg.high_resolution_timeouts,
         }
     }
 
+    /// Checks that the node can be created for the given database, services and node
+    /// configuration, performing the same validation as `new`. Unlike `new`, the method
+    /// neither writes the genesis block into the database nor binds sockets or starts
+    /// threads, so it can be used to check the configuration before the node launch.
+    /// The external address of the node is not resolved.
+    pub fn check<D: Into<Arc<dyn Database>>>(
+        db: D,
+        services: &[Box<dyn Service>],
+        node_cfg: &NodeConfig,
+    ) -> Result<(), Error> {
+        crypto::init();
+        check_services(services)?;
+        check_node_config(node_cfg)?;
+
+        let db: Arc<dyn Database> = db.into();
+        let snapshot = db.snapshot();
+        let schema = Schema::new(&snapshot);
+        check_genesis(&schema, &node_cfg.genesis, node_cfg.genesis_conflict_policy)?;
+        let validators_count = if schema.block_hashes_by_height().is_empty() {
+            node_cfg.genesis.validator_keys.len()
+        } else {
+            schema.actual_configuration().validator_keys.len()
+        };
+        if let Some(expected) = node_cfg.expected_validators {
+            check_validators_count(
+                expected,
+                validators_count,
+                node_cfg.strict_expected_validators,
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Creates node for the given services and node configuration, opening the database
+    /// specified by `NodeConfig::db_backend`.
+    pub fn from_config(
+        services: Vec<Box<dyn Service>>,
+        node_cfg: NodeConfig,
+        config_file_path: Option<String>,
+    ) -> Result<Self, Error> {
+        let db: Box<dyn Database> = match node_cfg.db_backend {
+            Some(DbBackend::RocksDb { ref path }) => {
+                Box::new(RocksDB::open(path, &node_cfg.database)?)
+            }
+            Some(DbBackend::Temporary) => Box::new(TemporaryDB::new()),
+            None => bail!("Database backend is not specified in the node configuration"),
+        };
+        Ok(Self::new(db, services, node_cfg, config_file_path))
+    }
+
     /// Launches only consensus messages handler.
     /// This may be used if you want to customize api with the `ApiContext`.
     pub fn run_handler(mut self, handshake_params: &HandshakeParams) -> Result<(), Error> {
+        let node_label = self.handler.api_state().node_label();
+        logger::set_node_label(node_label.clone());
         self.handler.initialize();
 
         let pool_size = self.thread_pool_size;
+        let db_compaction = self
+            .db_compaction_interval
+            .map(|interval| spawn_db_compaction(self.handler.blockchain.clone(), interval));
         let (handler_part, network_part, internal_part) = self.into_reactor();
         let handshake_params = handshake_params.clone();
 
         let network_thread = thread::spawn(move || {
+            logger::set_node_label(node_label);
             let mut core = Core::new().map_err(into_failure)?;
             let handle = core.handle();
 
@@ -978,17 +2234,28 @@ impl Node {
         let mut core = Core::new().map_err(into_failure)?;
         core.run(handler_part.run())
             .map_err(|_| format_err!("An error in the `Handler` thread occurred"))?;
+        if let Some((stop_tx, compaction_thread)) = db_compaction {
+            drop(stop_tx);
+            compaction_thread.join().unwrap();
+        }
         network_thread.join().unwrap()
     }
 
     /// A generic implementation that launches `Node` and optionally creates threads
     /// for public and private api handlers.
-    /// Explorer api prefix is `/api/explorer`
+    /// Explorer api prefix is `/api/explorer`, unless the explorer is disabled
     /// Public api prefix is `/api/services/{service_name}`
     /// Private api prefix is `/api/services/{service_name}`
     pub fn run(self) -> Result<(), failure::Error> {
         trace!("Running node.");
         let api_state = self.handler.api_state.clone();
+        let mut api_aggregator = ApiAggregator::new(
+            self.handler.blockchain.clone(),
+            self.handler.api_state.clone(),
+        );
+        if !self.api_options.enable_explorer {
+            api_aggregator.remove("explorer");
+        }
         // Runs actix-web api.
         let actix_api_runtime = SystemRuntimeConfig {
             api_runtimes: {
@@ -1000,30 +2267,89 @@ impl Node {
                     Arc::new(app_config)
                 };
 
+                fn with_rate_limit(
+                    app_config: Option<AppConfig>,
+                    requests_per_second: u32,
+                ) -> AppConfig {
+                    let rate_limiter = RateLimiter::new(requests_per_second);
+                    let app_config = move |app: App| -> App {
+                        let app = match app_config {
+                            Some(ref app_config) => app_config(app),
+                            None => app,
+                        };
+                        app.middleware(rate_limiter.clone())
+                    };
+                    Arc::new(app_config)
+                }
+
+                fn with_response_signer(
+                    app_config: Option<AppConfig>,
+                    response_signer: ResponseSigner,
+                ) -> AppConfig {
+                    let app_config = move |app: App| -> App {
+                        let app = match app_config {
+                            Some(ref app_config) => app_config(app),
+                            None => app,
+                        };
+                        app.middleware(response_signer.clone())
+                    };
+                    Arc::new(app_config)
+                }
+
+                let response_signer = if self.api_options.sign_responses {
+                    Some(ResponseSigner::new(
+                        *self.state().service_public_key(),
+                        self.state().service_secret_key().clone(),
+                    ))
+                } else {
+                    None
+                };
+
                 let public_api_handler = self
                     .api_options
                     .public_api_address
-                    .map(|listen_address| ApiRuntimeConfig {
-                        listen_address,
-                        access: ApiAccess::Public,
-                        app_config: self
+                    .map(|listen_address| {
+                        let mut app_config = self
                             .api_options
                             .public_allow_origin
                             .clone()
-                            .map(into_app_config),
+                            .map(into_app_config);
+                        if let Some(limit) = self.api_options.public_api_rate_limit {
+                            app_config = Some(with_rate_limit(app_config, limit));
+                        }
+                        if let Some(ref response_signer) = response_signer {
+                            app_config =
+                                Some(with_response_signer(app_config, response_signer.clone()));
+                        }
+                        ApiRuntimeConfig {
+                            listen_address,
+                            access: ApiAccess::Public,
+                            app_config,
+                            tls: self.api_options.public_api_tls.clone(),
+                            http2: self.api_options.http2,
+                        }
                     })
                     .into_iter();
                 let private_api_handler = self
                     .api_options
                     .private_api_address
-                    .map(|listen_address| ApiRuntimeConfig {
-                        listen_address,
-                        access: ApiAccess::Private,
-                        app_config: self
+                    .map(|listen_address| {
+                        let mut app_config = self
                             .api_options
                             .private_allow_origin
                             .clone()
-                            .map(into_app_config),
+                            .map(into_app_config);
+                        if let Some(ref response_signer) = response_signer {
+                            app_config =
+                                Some(with_response_signer(app_config, response_signer.clone()));
+                        }
+                        ApiRuntimeConfig {
+                            listen_address,
+                            access: ApiAccess::Private,
+                            app_config,
+                            tls: self.api_options.private_api_tls.clone(),
+                            http2: self.api_options.http2,
+                        }
                     })
                     .into_iter();
                 // Collects API handlers.
@@ -1031,10 +2357,10 @@ impl Node {
                     .chain(private_api_handler)
                     .collect::<Vec<_>>()
             },
-            api_aggregator: ApiAggregator::new(
-                self.handler.blockchain.clone(),
-                self.handler.api_state.clone(),
-            ),
+            api_aggregator,
+            bind_retries: self.api_options.api_bind_retries,
+            bind_retry_delay: Duration::from_millis(self.api_options.api_bind_retry_delay),
+            shutdown_timeout: Duration::from_millis(self.api_options.api_shutdown_timeout),
         }
         .start()?;
 
@@ -1051,7 +2377,7 @@ impl Node {
         // Stop ws server.
         api_state.shutdown_broadcast_server();
 
-        // Stops actix web runtime.
+        // Stops actix web runtime, waiting for the in-flight requests.
         actix_api_runtime.stop()?;
 
         info!("Exonum node stopped");
@@ -1084,6 +2410,7 @@ impl Node {
         let internal_part = InternalPart {
             internal_tx,
             internal_requests_rx,
+            high_resolution_timeouts: self.high_resolution_timeouts,
         };
         (handler_part, network_part, internal_part)
     }
@@ -1103,124 +2430,262 @@ impl Node {
         &self.handler
     }
 
+    /// Replaces the strategy selecting transactions from the pool into a new block,
+    /// e.g., with a custom one. By default, the strategy is chosen by
+    /// `MemoryPoolConfig::strategy`.
+    pub fn set_mempool_strategy(&mut self, strategy: Box<dyn MempoolStrategy>) {
+        self.handler.set_mempool_strategy(strategy);
+    }
+
     /// Returns channel.
     pub fn channel(&self) -> ApiSender {
         ApiSender::new(self.channel.api_requests.0.clone())
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use std::borrow::Cow;
-
-    use super::*;
-    use crate::blockchain::{
-        ExecutionResult, Schema, Service, Transaction, TransactionContext, TransactionSet,
-    };
-    use crate::crypto::gen_keypair;
-    use crate::events::EventHandler;
-    use crate::helpers;
-    use crate::proto::{schema::tests::TxSimple, ProtobufConvert};
-    use exonum_merkledb::{
-        impl_binary_value_for_message, BinaryValue, Database, Snapshot, TemporaryDB,
-    };
-    use protobuf::Message as ProtobufMessage;
-
-    const SERVICE_ID: u16 = 0;
-
-    #[derive(Serialize, Deserialize, Clone, Debug, TransactionSet)]
-    #[exonum(crate = "crate")]
-    enum SimpleTransactions {
-        TxSimple(TxSimple),
-    }
-
-    impl_binary_value_for_message! { TxSimple }
-
-    impl Transaction for TxSimple {
-        fn execute(&self, _: TransactionContext) -> ExecutionResult {
-            Ok(())
+/// Spawns a thread compacting the database with the given interval. The compaction is skipped
+/// while there are unconfirmed transactions in the pool, so that it does not compete with
+/// the block processing. The thread stops once the returned sender is dropped.
+fn spawn_db_compaction(
+    blockchain: Blockchain,
+    interval: Milliseconds,
+) -> (std::sync::mpsc::Sender<()>, thread::JoinHandle<()>) {
+    use std::sync::mpsc::{channel, RecvTimeoutError};
+
+    let (stop_tx, stop_rx) = channel::<()>();
+    let node_label = logger::node_label();
+    let compaction_thread = thread::spawn(move || {
+        logger::set_node_label(node_label);
+        while let Err(RecvTimeoutError::Timeout) =
+            stop_rx.recv_timeout(Duration::from_millis(interval))
+        {
+            let pool_len = Schema::new(&blockchain.snapshot()).transactions_pool_len();
+            if pool_len > 0 {
+                trace!(
+                    "Skipping database compaction, {} transactions in pool",
+                    pool_len
+                );
+                continue;
+            }
+            if let Err(e) = blockchain.compact_database() {
+                error!("Database compaction failed: {}", e);
+            }
         }
-    }
+    });
+    (stop_tx, compaction_thread)
+}
 
-    fn create_simple_tx(p_key: PublicKey, s_key: &SecretKey) -> Signed<RawTransaction> {
-        let mut msg = TxSimple::new();
-        msg.set_public_key(p_key.to_pb());
-        msg.set_msg("Hello, World!".to_owned());
-        Message::sign_transaction(msg, SERVICE_ID, p_key, s_key)
+/// Checks that the services have unique identifiers and names.
+fn check_services(services: &[Box<dyn Service>]) -> Result<(), Error> {
+    let mut ids = HashSet::new();
+    let mut names = HashSet::new();
+    for service in services {
+        ensure!(
+            ids.insert(service.service_id()),
+            "Services contain several services with id {}",
+            service.service_id()
+        );
+        ensure!(
+            names.insert(service.service_name()),
+            "Services contain several services with name '{}'",
+            service.service_name()
+        );
     }
+    Ok(())
+}
 
-    struct TestService;
+/// Checks the parts of the node configuration which do not depend on the database:
+/// the node keys, the connect list and its source, the block publisher, the API workers
+/// and the capacities of the events pool.
+fn check_node_config(node_cfg: &NodeConfig) -> Result<(), Error> {
+    check_keypair(
+        &node_cfg.consensus_public_key,
+        &node_cfg.consensus_secret_key,
+        "consensus",
+    )?;
+    check_keypair(
+        &node_cfg.service_public_key,
+        &node_cfg.service_secret_key,
+        "service",
+    )?;
+    node_cfg.connect_list.validate()?;
+    if let Some(ref url) = node_cfg.connect_list_source {
+        connect_list_source::check_url(url)?;
+    }
+    if let Some(ref publisher) = node_cfg.block_publisher {
+        publisher.check()?;
+    }
+    ensure!(
+        node_cfg.api.api_worker_threads > 0,
+        "api_worker_threads must be positive"
+    );
+    check_events_pool_capacity(&node_cfg.mempool.events_pool_capacity)
+}
 
-    impl Service for TestService {
-        fn service_id(&self) -> u16 {
-            SERVICE_ID
-        }
+/// Checks the genesis configuration in the same way as the configurations committed
+/// after it.
+fn check_genesis_config(genesis: &GenesisConfig) -> Result<(), Error> {
+    ensure!(
+        !genesis.validator_keys.is_empty(),
+        "Genesis configuration contains no validators"
+    );
+    let config = StoredConfiguration {
+        previous_cfg_hash: Hash::zero(),
+        actual_from: Height::zero(),
+        validator_keys: genesis.validator_keys.clone(),
+        consensus: genesis.consensus.clone(),
+        services: BTreeMap::new(),
+    };
+    StoredConfiguration::try_deserialize(&config.try_serialize()?)
+        .map_err(|e| format_err!("Invalid genesis configuration: {}", e))?;
+    Ok(())
+}
 
-        fn service_name(&self) -> &'static str {
-            "test service"
-        }
+/// Checks the configured genesis configuration against the database. The configuration
+/// is validated if the database is empty and is going to be initialized with it;
+/// otherwise, it is compared with the genesis the database was initialized with.
+fn check_genesis<T: IndexAccess>(
+    schema: &Schema<T>,
+    genesis: &GenesisConfig,
+    policy: GenesisConflictPolicy,
+) -> Result<(), Error> {
+    if schema.block_hashes_by_height().is_empty() {
+        check_genesis_config(genesis)
+    } else {
+        check_stored_genesis(schema, genesis, policy)
+    }
+}
 
-        fn state_hash(&self, _: &dyn Snapshot) -> Vec<Hash> {
-            vec![]
+/// Checks that the genesis configuration matches the one the database was initialized with.
+/// A mismatch is an error unless the policy is to keep the genesis stored in the database.
+fn check_stored_genesis<T: IndexAccess>(
+    schema: &Schema<T>,
+    genesis: &GenesisConfig,
+    policy: GenesisConflictPolicy,
+) -> Result<(), Error> {
+    // Databases created before the genesis configuration was stored are checked against
+    // the configuration of the genesis block.
+    let stored = schema.genesis_config().unwrap_or_else(|| {
+        let config = schema.configuration_by_height(Height::zero());
+        GenesisConfig {
+            consensus: config.consensus,
+            validator_keys: config.validator_keys,
         }
-
-        fn tx_from_raw(&self, raw: RawTransaction) -> Result<Box<dyn Transaction>, failure::Error> {
-            Ok(SimpleTransactions::tx_from_raw(raw)?.into())
+    });
+    if stored == *genesis {
+        return Ok(());
+    }
+
+    let genesis_block = schema
+        .block_hash_by_height(Height::zero())
+        .map_or_else(|| "unknown".to_owned(), |hash| hash.to_hex());
+    match policy {
+        GenesisConflictPolicy::Error => bail!(
+            "Genesis configuration differs from the one the database with the genesis block {} \
+             was initialized with. Set `genesis_conflict_policy` to `keep_database` to ignore \
+             the configured genesis",
+            genesis_block
+        ),
+        GenesisConflictPolicy::KeepDatabase => {
+            warn!(
+                "Genesis configuration differs from the one the database with the genesis block \
+                 {} was initialized with, the configured genesis is ignored",
+                genesis_block
+            );
+            Ok(())
         }
     }
+}
 
-    #[test]
-    fn test_duplicated_transaction() {
-        let (p_key, s_key) = gen_keypair();
-
-        let db = Arc::from(Box::new(TemporaryDB::new()) as Box<dyn Database>) as Arc<dyn Database>;
-        let services = vec![Box::new(TestService) as Box<dyn Service>];
-        let node_cfg = helpers::generate_testnet_config(1, 16_500)[0].clone();
-
-        let mut node = Node::new(db, services, node_cfg, None);
-
-        let tx = create_simple_tx(p_key, &s_key);
-
-        // Create original transaction.
-        let tx_orig = tx.clone();
-        let event = ExternalMessage::Transaction(tx_orig);
-        node.handler.handle_event(event.into());
+/// Checks that the secret key corresponds to the public one.
+fn check_keypair(public_key: &PublicKey, secret_key: &SecretKey, name: &str) -> Result<(), Error> {
+    const DATA: &[u8] = b"keypair check";
+    ensure!(
+        crypto::verify(&crypto::sign(DATA, secret_key), DATA, public_key),
+        "The {} secret key does not correspond to the {} public key",
+        name,
+        name
+    );
+    Ok(())
+}
 
-        // Initial transaction should be added to the pool.
-        let snapshot = node.blockchain().snapshot();
-        let schema = Schema::new(&snapshot);
-        assert_eq!(schema.transactions_pool_len(), 1);
+/// Checks that the capacities of the events pool are non-zero.
+fn check_events_pool_capacity(capacity: &EventsPoolCapacity) -> Result<(), Error> {
+    ensure!(
+        capacity.network_requests_capacity > 0
+            && capacity.network_events_capacity > 0
+            && capacity.internal_events_capacity > 0
+            && capacity.api_requests_capacity > 0,
+        "Events pool capacities must be positive: {:?}",
+        capacity
+    );
+    Ok(())
+}
 
-        // Create duplicated transaction.
-        let tx_copy = tx.clone();
-        let event = ExternalMessage::Transaction(tx_copy);
-        node.handler.handle_event(event.into());
+/// Compares the number of validators in the actual configuration with the number expected
+/// by the node configuration. A mismatch is logged as a warning, or results in an error
+/// if `strict` is set.
+fn check_validators_count(expected: usize, actual: usize, strict: bool) -> Result<(), Error> {
+    if expected == actual {
+        return Ok(());
+    }
+
+    let message = format!(
+        "The actual configuration contains {} validators, while {} validators are expected \
+         by the node configuration",
+        actual, expected
+    );
+    ensure!(!strict, "{}", message);
+    warn!("{}", message);
+    Ok(())
+}
 
-        // Duplicated transaction shouldn't be added to the pool.
-        let snapshot = node.blockchain().snapshot();
-        let schema = Schema::new(&snapshot);
-        assert_eq!(schema.transactions_pool_len(), 1);
+/// Resolves the external address of the node, making at most `external_address_resolve_retries`
+/// additional attempts if the resolution fails. If the address cannot be resolved, the outcome
+/// depends on `external_address_fallback`.
+fn resolve_external_address<F>(
+    address: &str,
+    config: &NetworkConfiguration,
+    mut resolve: F,
+) -> Result<String, Error>
+where
+    F: FnMut(&str) -> io::Result<SocketAddr>,
+{
+    let retries = config.external_address_resolve_retries;
+    let delay = Duration::from_millis(config.external_address_resolve_retry_timeout);
+    let mut attempt = 0;
+    loop {
+        match resolve(address) {
+            Ok(resolved) => return Ok(resolved.to_string()),
+            Err(ref e) if attempt < retries => {
+                attempt += 1;
+                warn!(
+                    "Unable to resolve external address {}: {}, retrying in {:?} (attempt {} of {})",
+                    address, e, delay, attempt, retries
+                );
+                thread::sleep(delay);
+            }
+            Err(e) => match config.external_address_fallback {
+                ExternalAddressFallback::FailFast => {
+                    bail!("Unable to resolve external address {}: {}", address, e)
+                }
+                ExternalAddressFallback::AdvertiseLastKnown => {
+                    warn!(
+                        "Unable to resolve external address {}: {}, advertising it as is",
+                        address, e
+                    );
+                    return Ok(address.to_owned());
+                }
+            },
+        }
     }
+}
 
-    #[test]
-    fn test_transaction_without_service() {
-        let (p_key, s_key) = gen_keypair();
-
-        let db = Arc::from(Box::new(TemporaryDB::new()) as Box<dyn Database>) as Arc<dyn Database>;
-        let services = vec![];
-        let node_cfg = helpers::generate_testnet_config(1, 16_500)[0].clone();
-
-        let mut node = Node::new(db, services, node_cfg, None);
-
-        let tx = create_simple_tx(p_key, &s_key);
-
-        // Send transaction to node.
-        let event = ExternalMessage::Transaction(tx);
-        node.handler.handle_event(event.into());
-
-        // Service not found for transaction.
-        let snapshot = node.blockchain().snapshot();
-        let schema = Schema::new(&snapshot);
-        assert_eq!(schema.transactions_pool_len(), 0);
-    }
+fn resolve_socket_address(address: &str) -> io::Result<SocketAddr> {
+    address.to_socket_addrs()?.next().ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("no addresses found for {}", address),
+        )
+    })
 }
@@ -0,0 +1,169 @@
+// Copyright 2019 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Buffer holding incoming transactions which nonces are slightly ahead of the expected ones.
+//!
+//! Clients submitting several transactions in a row may have them delivered out of order.
+//! Instead of rejecting such transactions, the node holds them for a short time until
+//! the preceding transactions of the same author arrive. The buffer only affects the order
+//! in which transactions are added into the pool and is not a part of the consensus.
+
+use std::{
+    cmp,
+    collections::{BTreeMap, HashMap},
+    time::{Duration, SystemTime},
+};
+
+use crate::blockchain::TxNonce;
+use crate::crypto::PublicKey;
+use crate::messages::{RawTransaction, Signed};
+
+/// Maximal time a transaction is held in the buffer, in milliseconds. Expired transactions
+/// are released regardless of the missing preceding transactions.
+pub(crate) const NONCE_BUFFER_HOLD_TIME: u64 = 5_000;
+
+/// Maximal number of transactions held in the buffer for all authors together.
+pub(crate) const NONCE_BUFFER_MAX_HELD: usize = 10_000;
+
+/// Maximal number of authors tracked by the buffer. Transactions of new authors are not
+/// held once the limit is reached.
+pub(crate) const NONCE_BUFFER_MAX_AUTHORS: usize = 10_000;
+
+/// Transactions of a single author held in the buffer.
+#[derive(Debug)]
+struct AuthorTransactions {
+    /// Nonce of the next transaction of the author to be released.
+    next_nonce: u64,
+    /// Held transactions indexed by their nonces, with the time they were received.
+    held: BTreeMap<u64, (Signed<RawTransaction>, SystemTime)>,
+    /// Time the last transaction of the author was received.
+    last_seen: SystemTime,
+}
+
+/// Buffer of transactions with out-of-order nonces, bounded both per author and in total.
+#[derive(Debug)]
+pub(crate) struct NonceBuffer {
+    capacity: usize,
+    authors: HashMap<(u16, PublicKey), AuthorTransactions>,
+    held_len: usize,
+}
+
+impl NonceBuffer {
+    /// Creates a buffer holding at most `capacity` transactions per author. Zero capacity
+    /// disables the buffer.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            authors: HashMap::new(),
+            held_len: 0,
+        }
+    }
+
+    /// Returns `true` if the buffer is enabled.
+    pub fn is_enabled(&self) -> bool {
+        self.capacity > 0
+    }
+
+    /// Returns the number of transactions held in the buffer.
+    pub fn held_len(&self) -> usize {
+        self.held_len
+    }
+
+    /// Returns the time the earliest held transaction expires at, if any.
+    pub fn next_expiry(&self) -> Option<SystemTime> {
+        self.authors
+            .values()
+            .flat_map(|author| author.held.values().map(|(_, received)| *received))
+            .min()
+            .map(|received| received + Duration::from_millis(NONCE_BUFFER_HOLD_TIME))
+    }
+
+    /// Puts the transaction with the given nonce into the buffer and returns the transactions
+    /// ready to be added into the pool, in the order of their nonces. The transaction is held
+    /// if its nonce is ahead of the next expected one by no more than the buffer capacity
+    /// and the global limits of the buffer are not reached.
+    pub fn push(
+        &mut self,
+        tx: Signed<RawTransaction>,
+        nonce: TxNonce,
+        now: SystemTime,
+    ) -> Vec<Signed<RawTransaction>> {
+        let key = (tx.payload().service_id(), tx.author());
+        if !self.authors.contains_key(&key) && self.authors.len() >= NONCE_BUFFER_MAX_AUTHORS {
+            return vec![tx];
+        }
+
+        let capacity = self.capacity;
+        let can_hold = self.held_len < NONCE_BUFFER_MAX_HELD;
+        let author = self
+            .authors
+            .entry(key)
+            .or_insert_with(|| AuthorTransactions {
+                next_nonce: nonce.expected,
+                held: BTreeMap::new(),
+                last_seen: now,
+            });
+        author.last_seen = now;
+
+        // Transactions may be committed by other nodes before this node sees them.
+        if nonce.expected > author.next_nonce {
+            author.next_nonce = nonce.expected;
+            let held_before = author.held.len();
+            author.held = author.held.split_off(&nonce.expected);
+            self.held_len -= held_before - author.held.len();
+        }
+
+        let distance = nonce.actual.saturating_sub(author.next_nonce);
+        if can_hold && distance > 0 && distance <= capacity as u64 && author.held.len() < capacity {
+            if author.held.insert(nonce.actual, (tx, now)).is_none() {
+                self.held_len += 1;
+            }
+            return Vec::new();
+        }
+
+        let mut ready = vec![tx];
+        if nonce.actual == author.next_nonce {
+            author.next_nonce += 1;
+            while let Some((tx, _)) = author.held.remove(&author.next_nonce) {
+                ready.push(tx);
+                author.next_nonce += 1;
+            }
+        }
+        self.held_len -= ready.len() - 1;
+        ready
+    }
+
+    /// Removes the transactions held longer than `NONCE_BUFFER_HOLD_TIME` and returns them.
+    /// Authors without held transactions are forgotten after the same time.
+    pub fn take_expired(&mut self, now: SystemTime) -> Vec<Signed<RawTransaction>> {
+        let deadline = now - Duration::from_millis(NONCE_BUFFER_HOLD_TIME);
+        let mut expired = Vec::new();
+        self.authors.retain(|_, author| {
+            let nonces = author
+                .held
+                .iter()
+                .filter(|(_, (_, received))| *received <= deadline)
+                .map(|(nonce, _)| *nonce)
+                .collect::<Vec<_>>();
+            for nonce in nonces {
+                let (tx, _) = author.held.remove(&nonce).unwrap();
+                author.next_nonce = cmp::max(author.next_nonce, nonce + 1);
+                expired.push(tx);
+            }
+            !author.held.is_empty() || author.last_seen > deadline
+        });
+        self.held_len -= expired.len();
+        expired
+    }
+}
@@ -141,6 +141,11 @@ impl NodeHandler {
         let schema = Schema::new(&snapshot);
 
         let height = msg.height();
+        // Transactions of the pruned blocks cannot be provided, so the peer should
+        // request the block from another node.
+        if height < schema.oldest_available_height() {
+            return;
+        }
         let block_hash = schema.block_hash_by_height(height).unwrap();
 
         let block = schema.blocks().get(&block_hash).unwrap();
@@ -18,7 +18,9 @@ use bit_vec::BitVec;
 use serde_json::Value;
 
 use std::{
+    cmp,
     collections::{hash_map::Entry, BTreeMap, HashMap, HashSet},
+    mem,
     ops::Deref,
     sync::{Arc, RwLock},
     time::{Duration, SystemTime},
@@ -69,6 +71,8 @@ pub struct State {
     height: Height,
 
     round: Round,
+    /// Maximal round reached at a single height since the node start.
+    max_round: Round,
     locked_round: Round,
     locked_propose: Option<Hash>,
     last_hash: Hash,
@@ -125,6 +129,8 @@ struct RequestState {
     retries: u16,
     // Nodes that have the required information.
     known_nodes: HashSet<PublicKey>,
+    // Node the request was last sent to, and the time of sending.
+    sent: Option<(PublicKey, SystemTime)>,
 }
 
 /// `ProposeState` represents the state of some propose and is used for tracking of unknown
@@ -246,6 +252,16 @@ where
         self.count
     }
 
+    /// Returns the total voting weight of the validators that sent the contained messages.
+    pub fn weight(&self, validators: &[ValidatorKeys]) -> u64 {
+        validators
+            .iter()
+            .zip(self.validators.iter())
+            .filter(|&(_, voted)| voted)
+            .map(|(keys, _)| keys.weight)
+            .sum()
+    }
+
     /// Returns messages.
     pub fn messages(&self) -> &Vec<T> {
         &self.messages
@@ -272,6 +288,7 @@ impl RequestState {
         Self {
             retries: 0,
             known_nodes: HashSet::new(),
+            sent: None,
         }
     }
 
@@ -460,6 +477,7 @@ impl State {
             height: last_height,
             height_start_time,
             round: Round::zero(),
+            max_round: Round::zero(),
             locked_round: Round::zero(),
             locked_propose: None,
             last_hash,
@@ -726,6 +744,42 @@ impl State {
         total * 2 / 3 + 1
     }
 
+    /// Returns the total voting weight of the current validators.
+    ///
+    /// # Panics
+    ///
+    /// If the total weight overflows `u64`, which is prevented by the validation
+    /// of the configuration.
+    pub fn total_weight(&self) -> u64 {
+        self.validators()
+            .iter()
+            .try_fold(0_u64, |total, keys| total.checked_add(keys.weight))
+            .expect("Total weight of validators overflows u64")
+    }
+
+    /// Returns sufficient voting weight of votes for the current validators.
+    pub fn majority_weight(&self) -> u64 {
+        Self::byzantine_majority_weight(self.total_weight())
+    }
+
+    /// Returns sufficient voting weight of votes for the given total weight of validators.
+    pub fn byzantine_majority_weight(total: u64) -> u64 {
+        // Equals to `total * 2 / 3 + 1`, but does not overflow for large weights.
+        total / 3 * 2 + total % 3 * 2 / 3 + 1
+    }
+
+    /// Returns the total voting weight of the validators with the specified ids.
+    /// Unknown ids are ignored.
+    pub fn validators_weight<I>(&self, ids: I) -> u64
+    where
+        I: IntoIterator<Item = ValidatorId>,
+    {
+        ids.into_iter()
+            .filter_map(|id| self.validators().get(id.0 as usize))
+            .map(|keys| keys.weight)
+            .sum()
+    }
+
     /// Returns current height.
     pub fn height(&self) -> Height {
         self.height
@@ -741,6 +795,11 @@ impl State {
         self.round
     }
 
+    /// Returns the maximal round reached at a single height since the node start.
+    pub fn max_round(&self) -> Round {
+        self.max_round
+    }
+
     /// Returns a hash of the last block.
     pub fn last_hash(&self) -> &Hash {
         &self.last_hash
@@ -778,6 +837,18 @@ impl State {
         self.proposes.get(hash)
     }
 
+    /// Returns the hash of the propose to be prevoted among the proposes for the given round
+    /// that contain no unknown transactions. If there are several such proposes, the one
+    /// with the lowest hash is chosen, so that validators agree regardless of the order
+    /// in which the proposes have been received.
+    pub fn preferred_full_propose(&self, round: Round) -> Option<Hash> {
+        self.proposes
+            .iter()
+            .filter(|(_, state)| state.message().round() == round && !state.has_unknown_txs())
+            .map(|(hash, _)| *hash)
+            .min()
+    }
+
     /// Returns a block with the specified hash.
     pub fn block(&self, hash: &Hash) -> Option<&BlockState> {
         self.blocks.get(hash)
@@ -786,11 +857,13 @@ impl State {
     /// Updates mode's round.
     pub fn jump_round(&mut self, round: Round) {
         self.round = round;
+        self.max_round = cmp::max(self.max_round, round);
     }
 
     /// Increments node's round by one.
     pub fn new_round(&mut self) {
         self.round.increment();
+        self.max_round = cmp::max(self.max_round, self.round);
     }
 
     /// Return incomplete block.
@@ -803,6 +876,7 @@ impl State {
         self.height.increment();
         self.height_start_time = height_start_time;
         self.round = Round::first();
+        self.max_round = cmp::max(self.max_round, self.round);
         self.locked_round = Round::zero();
         self.locked_propose = None;
         self.last_hash = *block_hash;
@@ -851,6 +925,20 @@ impl State {
         full_proposes
     }
 
+    /// Returns `true` if the transaction is included into a known propose
+    /// or into the incomplete block for the current height.
+    pub fn is_tx_proposed(&self, tx_hash: &Hash) -> bool {
+        let in_proposes = self
+            .proposes
+            .values()
+            .any(|state| state.message().transactions().contains(tx_hash));
+        let in_incomplete_block = self
+            .incomplete_block
+            .as_ref()
+            .map_or(false, |block| block.msg.transactions().contains(tx_hash));
+        in_proposes || in_incomplete_block
+    }
+
     /// Checks if there is an incomplete block that waits for this transaction.
     /// Returns a block that don't contain unknown transactions.
     ///
@@ -1015,13 +1103,13 @@ impl State {
         self.incomplete_block().unwrap()
     }
 
-    /// Adds pre-vote. Returns `true` there are +2/3 pre-votes.
+    /// Adds pre-vote. Returns `true` if there are pre-votes with +2/3 of the validators weight.
     ///
     /// # Panics
     ///
     /// A node panics if it has already sent a different `Prevote` for the same round.
     pub fn add_prevote(&mut self, msg: Signed<Prevote>) -> bool {
-        let majority_count = self.majority_count();
+        let majority_weight = self.majority_weight();
         if let Some(ref mut validator_state) = self.validator_state {
             if validator_state.id == msg.validator() {
                 if let Some(other) = validator_state
@@ -1046,13 +1134,14 @@ impl State {
             .entry(key)
             .or_insert_with(|| Votes::new(validators_len));
         votes.insert(msg);
-        votes.count() >= majority_count
+        votes.weight(&self.config.validator_keys) >= majority_weight
     }
 
-    /// Returns `true` if there are +2/3 pre-votes for the specified round and hash.
+    /// Returns `true` if there are pre-votes with +2/3 of the validators weight for the
+    /// specified round and hash.
     pub fn has_majority_prevotes(&self, round: Round, propose_hash: Hash) -> bool {
         match self.prevotes.get(&(round, propose_hash)) {
-            Some(votes) => votes.count() >= self.majority_count(),
+            Some(votes) => votes.weight(self.validators()) >= self.majority_weight(),
             None => false,
         }
     }
@@ -1073,13 +1162,13 @@ impl State {
             .map_or_else(|| BitVec::from_elem(len, false), |x| x.validators().clone())
     }
 
-    /// Adds pre-commit. Returns `true` there are +2/3 pre-commits.
+    /// Adds pre-commit. Returns `true` if there are pre-commits with +2/3 of the validators weight.
     ///
     /// # Panics
     ///
     /// A node panics if it has already sent a different `Precommit` for the same round.
     pub fn add_precommit(&mut self, msg: Signed<Precommit>) -> bool {
-        let majority_count = self.majority_count();
+        let majority_weight = self.majority_weight();
         if let Some(ref mut validator_state) = self.validator_state {
             if validator_state.id == msg.validator() {
                 if let Some(other) = validator_state
@@ -1104,7 +1193,7 @@ impl State {
             .entry(key)
             .or_insert_with(|| Votes::new(validators_len));
         votes.insert(msg);
-        votes.count() >= majority_count
+        votes.weight(&self.config.validator_keys) >= majority_weight
     }
 
     /// Adds unknown (for this node) propose.
@@ -1130,10 +1219,11 @@ impl State {
             .unwrap_or_default()
     }
 
-    /// Returns true if the node has +2/3 pre-commits for the specified round and block hash.
+    /// Returns true if the node has pre-commits with +2/3 of the validators weight for the
+    /// specified round and block hash.
     pub fn has_majority_precommits(&self, round: Round, block_hash: Hash) -> bool {
         match self.precommits.get(&(round, block_hash)) {
-            Some(votes) => votes.count() >= self.majority_count(),
+            Some(votes) => votes.weight(self.validators()) >= self.majority_weight(),
             None => false,
         }
     }
@@ -1185,6 +1275,18 @@ impl State {
         next
     }
 
+    /// Records that the request has been sent to the specified peer at the given time.
+    pub fn set_request_sent(&mut self, data: &RequestData, peer: PublicKey, time: SystemTime) {
+        if let Some(state) = self.requests.get_mut(data) {
+            state.sent = Some((peer, time));
+        }
+    }
+
+    /// Returns the peer the request has been last sent to, and the time of sending.
+    pub fn request_sent(&self, data: &RequestData) -> Option<(PublicKey, SystemTime)> {
+        self.requests.get(data).and_then(|state| state.sent)
+    }
+
     /// Removes the specified request from the pending request list.
     pub fn remove_request(&mut self, data: &RequestData) -> HashSet<PublicKey> {
         let state = self.requests.remove(data);
@@ -1210,4 +1312,14 @@ impl State {
             .expect("ConnectList write lock");
         list.add(peer);
     }
+
+    /// Replaces node's `ConnectList` with the given one. Returns the previous list.
+    pub fn replace_connect_list(&mut self, connect_list: ConnectList) -> ConnectList {
+        let mut list = self
+            .connect_list
+            .inner
+            .write()
+            .expect("ConnectList write lock");
+        mem::replace(&mut *list, connect_list)
+    }
 }
@@ -0,0 +1,252 @@
+// Copyright 2019 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Notifications about the node health changes sent to an HTTP endpoint.
+//!
+//! The endpoint is set in `NodeConfig::status_webhook`. The requests are sent from
+//! a separate thread, so a slow or unavailable endpoint does not affect the consensus.
+//! Sending the requests requires the `http-client` feature.
+
+use failure::Error;
+
+use std::{
+    sync::mpsc::{channel, Sender},
+    time::Duration,
+};
+
+use super::State;
+use crate::blockchain::SharedNodeState;
+use crate::helpers::Height;
+
+/// Number of attempts to deliver a status webhook notification.
+pub const STATUS_WEBHOOK_ATTEMPTS: usize = 3;
+/// Timeout of a single status webhook request.
+pub const STATUS_WEBHOOK_TIMEOUT: Duration = Duration::from_secs(5);
+/// Delay between the attempts to deliver a status webhook notification.
+pub const STATUS_WEBHOOK_RETRY_DELAY: Duration = Duration::from_secs(1);
+
+/// Health of the node as reported by the status webhook.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NodeHealth {
+    /// The node is connected to enough validators to achieve the consensus
+    /// and is not behind its peers.
+    Healthy,
+    /// The node is not connected to enough validators to achieve the consensus.
+    Unhealthy,
+    /// Some peers of the node are at a bigger height, so the node is synchronizing.
+    CatchingUp,
+}
+
+impl NodeHealth {
+    /// Determines the health of the node from its state.
+    pub fn new(state: &State, api_state: &SharedNodeState) -> Self {
+        if !state.nodes_with_bigger_height().is_empty() {
+            NodeHealth::CatchingUp
+        } else if api_state.consensus_status() {
+            NodeHealth::Healthy
+        } else {
+            NodeHealth::Unhealthy
+        }
+    }
+}
+
+/// Payload of the status webhook request.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StatusWebhookPayload {
+    /// The current health of the node.
+    pub status: NodeHealth,
+    /// The health of the node before the transition.
+    pub previous_status: NodeHealth,
+    /// The current height of the node.
+    pub height: Height,
+}
+
+/// Sends notifications about the node health transitions to an HTTP endpoint.
+#[derive(Debug)]
+pub(crate) struct StatusWebhook {
+    last_status: Option<NodeHealth>,
+    sender: Sender<StatusWebhookPayload>,
+}
+
+impl StatusWebhook {
+    /// Creates a webhook posting to the given URL and starts the thread sending requests.
+    pub fn new(url: String) -> Result<Self, Error> {
+        let (sender, receiver) = channel();
+        http_client::spawn(url, receiver)?;
+        Ok(Self {
+            last_status: None,
+            sender,
+        })
+    }
+
+    /// Records the current health of the node and notifies the endpoint if it differs
+    /// from the previously recorded one. The first recorded health is not reported.
+    pub fn update(&mut self, status: NodeHealth, height: Height) {
+        let previous_status = match self.last_status.replace(status) {
+            Some(previous_status) if previous_status != status => previous_status,
+            _ => return,
+        };
+
+        let payload = StatusWebhookPayload {
+            status,
+            previous_status,
+            height,
+        };
+        if self.sender.send(payload).is_err() {
+            error!("Status webhook thread has stopped");
+        }
+    }
+}
+
+#[cfg(feature = "http-client")]
+mod http_client {
+    use failure::Error;
+    use reqwest::Client;
+
+    use std::{sync::mpsc::Receiver, thread};
+
+    use super::{
+        StatusWebhookPayload, STATUS_WEBHOOK_ATTEMPTS, STATUS_WEBHOOK_RETRY_DELAY,
+        STATUS_WEBHOOK_TIMEOUT,
+    };
+
+    /// Starts the thread posting the received payloads to the given URL.
+    pub fn spawn(url: String, receiver: Receiver<StatusWebhookPayload>) -> Result<(), Error> {
+        let client = Client::builder().timeout(STATUS_WEBHOOK_TIMEOUT).build()?;
+        thread::Builder::new()
+            .name("status-webhook".to_owned())
+            .spawn(move || run_webhook(&client, &url, &receiver))?;
+        Ok(())
+    }
+
+    fn run_webhook(client: &Client, url: &str, receiver: &Receiver<StatusWebhookPayload>) {
+        for payload in receiver {
+            for attempt in 1..=STATUS_WEBHOOK_ATTEMPTS {
+                let response = client
+                    .post(url)
+                    .json(&payload)
+                    .send()
+                    .and_then(|response| response.error_for_status());
+                match response {
+                    Ok(_) => break,
+                    Err(e) if attempt < STATUS_WEBHOOK_ATTEMPTS => {
+                        warn!("Failed to send status webhook to {}, retrying: {}", url, e);
+                        thread::sleep(STATUS_WEBHOOK_RETRY_DELAY);
+                    }
+                    Err(e) => error!("Failed to send status webhook to {}: {}", url, e),
+                }
+            }
+        }
+    }
+}
+
+#[cfg(not(feature = "http-client"))]
+mod http_client {
+    use failure::Error;
+
+    use std::sync::mpsc::Receiver;
+
+    use super::StatusWebhookPayload;
+
+    pub fn spawn(_url: String, _receiver: Receiver<StatusWebhookPayload>) -> Result<(), Error> {
+        bail!("Status webhook requires the `http-client` feature")
+    }
+}
+
+#[cfg(all(test, feature = "http-client"))]
+mod tests {
+    use std::{
+        io::{BufRead, BufReader, Read, Write},
+        net::TcpListener,
+        sync::mpsc,
+        thread,
+    };
+
+    use super::*;
+
+    /// Starts an HTTP server which replies `200 OK` and passes the received request bodies
+    /// to the returned channel.
+    fn start_mock_server() -> (String, mpsc::Receiver<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/status", listener.local_addr().unwrap());
+        let (sender, receiver) = mpsc::channel();
+
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut content_length = 0;
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    let line = line.trim_end().to_lowercase();
+                    if line.is_empty() {
+                        break;
+                    }
+                    if line.starts_with("content-length:") {
+                        content_length = line["content-length:".len()..].trim().parse().unwrap();
+                    }
+                }
+                let mut body = vec![0; content_length];
+                reader.read_exact(&mut body).unwrap();
+                stream
+                    .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\nConnection: close\r\n\r\n")
+                    .unwrap();
+                if sender.send(String::from_utf8(body).unwrap()).is_err() {
+                    break;
+                }
+            }
+        });
+        (url, receiver)
+    }
+
+    fn receive_payload(receiver: &mpsc::Receiver<String>) -> StatusWebhookPayload {
+        let body = receiver.recv_timeout(Duration::from_secs(10)).unwrap();
+        serde_json::from_str(&body).unwrap()
+    }
+
+    #[test]
+    fn test_status_webhook_fires_on_transitions() {
+        let (url, receiver) = start_mock_server();
+        let mut webhook = StatusWebhook::new(url).unwrap();
+
+        webhook.update(NodeHealth::Healthy, Height(1));
+        webhook.update(NodeHealth::Healthy, Height(2));
+        webhook.update(NodeHealth::Unhealthy, Height(3));
+        webhook.update(NodeHealth::Unhealthy, Height(3));
+        webhook.update(NodeHealth::Unhealthy, Height(3));
+        webhook.update(NodeHealth::Healthy, Height(4));
+
+        assert_eq!(
+            receive_payload(&receiver),
+            StatusWebhookPayload {
+                status: NodeHealth::Unhealthy,
+                previous_status: NodeHealth::Healthy,
+                height: Height(3),
+            }
+        );
+        // Repeated unhealthy states are not reported, so the next notification
+        // is about the recovery.
+        assert_eq!(
+            receive_payload(&receiver),
+            StatusWebhookPayload {
+                status: NodeHealth::Healthy,
+                previous_status: NodeHealth::Unhealthy,
+                height: Height(4),
+            }
+        );
+        assert!(receiver.recv_timeout(Duration::from_millis(500)).is_err());
+    }
+}
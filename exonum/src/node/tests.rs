@@ -0,0 +1,1195 @@
+// Copyright 2019 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use log::{LevelFilter, Log, Metadata, Record};
+
+use std::borrow::Cow;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+use super::*;
+use crate::blockchain::{
+    ChannelStats, ExecutionResult, Schema, Service, ServiceContext, Transaction,
+    TransactionContext, TransactionSet, TxNonce,
+};
+use crate::crypto::gen_keypair;
+use crate::events::EventHandler;
+use crate::helpers;
+use crate::proto::{schema::tests::TxSimple, ProtobufConvert};
+use exonum_merkledb::{
+    impl_binary_value_for_message, BinaryValue, Database, Entry, Snapshot, TemporaryDB,
+};
+use futures::Stream;
+use protobuf::Message as ProtobufMessage;
+
+const SERVICE_ID: u16 = 0;
+
+#[derive(Serialize, Deserialize, Clone, Debug, TransactionSet)]
+#[exonum(crate = "crate")]
+enum SimpleTransactions {
+    TxSimple(TxSimple),
+}
+
+impl_binary_value_for_message! { TxSimple }
+
+impl Transaction for TxSimple {
+    fn execute(&self, _: TransactionContext) -> ExecutionResult {
+        Ok(())
+    }
+}
+
+/// Returns the configuration of the only validator of the test network.
+fn node_config() -> NodeConfig {
+    helpers::generate_testnet_config(1, 16_500)[0].clone()
+}
+
+fn temporary_db() -> Arc<dyn Database> {
+    Arc::new(TemporaryDB::new())
+}
+
+/// Creates a node with the given services and configuration on a temporary database.
+fn create_node(services: Vec<Box<dyn Service>>, node_cfg: NodeConfig) -> Node {
+    Node::new(temporary_db(), services, node_cfg, None)
+}
+
+fn create_simple_tx(p_key: PublicKey, s_key: &SecretKey) -> Signed<RawTransaction> {
+    let mut msg = TxSimple::new();
+    msg.set_public_key(p_key.to_pb());
+    msg.set_msg("Hello, World!".to_owned());
+    Message::sign_transaction(msg, SERVICE_ID, p_key, s_key)
+}
+
+struct TestService;
+
+impl Service for TestService {
+    fn service_id(&self) -> u16 {
+        SERVICE_ID
+    }
+
+    fn service_name(&self) -> &'static str {
+        "test service"
+    }
+
+    fn state_hash(&self, _: &dyn Snapshot) -> Vec<Hash> {
+        vec![]
+    }
+
+    fn tx_from_raw(&self, raw: RawTransaction) -> Result<Box<dyn Transaction>, failure::Error> {
+        Ok(SimpleTransactions::tx_from_raw(raw)?.into())
+    }
+}
+
+#[test]
+fn test_duplicated_transaction() {
+    let (p_key, s_key) = gen_keypair();
+
+    let services = vec![Box::new(TestService) as Box<dyn Service>];
+    let mut node = create_node(services, node_config());
+
+    let tx = create_simple_tx(p_key, &s_key);
+
+    // Create original transaction.
+    let tx_orig = tx.clone();
+    let event = ExternalMessage::Transaction(tx_orig);
+    node.handler.handle_event(event.into());
+
+    // Initial transaction should be added to the pool.
+    let snapshot = node.blockchain().snapshot();
+    let schema = Schema::new(&snapshot);
+    assert_eq!(schema.transactions_pool_len(), 1);
+
+    // Create duplicated transaction.
+    let tx_copy = tx.clone();
+    let event = ExternalMessage::Transaction(tx_copy);
+    node.handler.handle_event(event.into());
+
+    // Duplicated transaction shouldn't be added to the pool.
+    let snapshot = node.blockchain().snapshot();
+    let schema = Schema::new(&snapshot);
+    assert_eq!(schema.transactions_pool_len(), 1);
+}
+
+/// Service which transactions carry their creation time in the message text.
+struct TimestampedService;
+
+impl Service for TimestampedService {
+    fn service_id(&self) -> u16 {
+        SERVICE_ID
+    }
+
+    fn service_name(&self) -> &'static str {
+        "timestamped service"
+    }
+
+    fn state_hash(&self, _: &dyn Snapshot) -> Vec<Hash> {
+        vec![]
+    }
+
+    fn tx_from_raw(&self, raw: RawTransaction) -> Result<Box<dyn Transaction>, failure::Error> {
+        Ok(SimpleTransactions::tx_from_raw(raw)?.into())
+    }
+
+    fn tx_timestamp(&self, raw: &RawTransaction) -> Option<DateTime<Utc>> {
+        match SimpleTransactions::tx_from_raw(raw.clone()).ok()? {
+            SimpleTransactions::TxSimple(tx) => tx.get_msg().parse().ok(),
+        }
+    }
+}
+
+#[test]
+fn test_future_dated_transactions() {
+    let (p_key, s_key) = gen_keypair();
+    let create_tx = |time: DateTime<Utc>| {
+        let mut msg = TxSimple::new();
+        msg.set_public_key(p_key.to_pb());
+        msg.set_msg(time.to_rfc3339());
+        Message::sign_transaction(msg, SERVICE_ID, p_key, &s_key)
+    };
+
+    let services = vec![Box::new(TimestampedService) as Box<dyn Service>];
+    let mut node_cfg = node_config();
+    node_cfg.max_tx_time_skew = Some(60_000);
+    let mut node = create_node(services, node_cfg);
+
+    let now = Utc::now();
+    let tx_within_skew = create_tx(now + chrono::Duration::seconds(30));
+    let tx_beyond_skew = create_tx(now + chrono::Duration::minutes(10));
+    node.handler.handle_incoming_tx(tx_within_skew.clone());
+    node.handler.handle_incoming_tx(tx_beyond_skew.clone());
+
+    let snapshot = node.blockchain().snapshot();
+    let schema = Schema::new(&snapshot);
+    assert!(schema.transactions_pool().contains(&tx_within_skew.hash()));
+    assert!(!schema.transactions_pool().contains(&tx_beyond_skew.hash()));
+}
+
+/// Service which transactions carry their nonces in the message text.
+/// Nonces of all authors start from 1.
+struct NonceService;
+
+impl Service for NonceService {
+    fn service_id(&self) -> u16 {
+        SERVICE_ID
+    }
+
+    fn service_name(&self) -> &'static str {
+        "nonce service"
+    }
+
+    fn state_hash(&self, _: &dyn Snapshot) -> Vec<Hash> {
+        vec![]
+    }
+
+    fn tx_from_raw(&self, raw: RawTransaction) -> Result<Box<dyn Transaction>, failure::Error> {
+        Ok(SimpleTransactions::tx_from_raw(raw)?.into())
+    }
+
+    fn tx_nonce(&self, _: &dyn Snapshot, tx: &Signed<RawTransaction>) -> Option<TxNonce> {
+        match SimpleTransactions::tx_from_raw(tx.payload().clone()).ok()? {
+            SimpleTransactions::TxSimple(tx) => Some(TxNonce {
+                expected: 1,
+                actual: tx.get_msg().parse().ok()?,
+            }),
+        }
+    }
+}
+
+#[test]
+fn test_out_of_order_nonces() {
+    let (p_key, s_key) = gen_keypair();
+    let create_tx = |nonce: u64| {
+        let mut msg = TxSimple::new();
+        msg.set_public_key(p_key.to_pb());
+        msg.set_msg(nonce.to_string());
+        Message::sign_transaction(msg, SERVICE_ID, p_key, &s_key)
+    };
+
+    let services = vec![Box::new(NonceService) as Box<dyn Service>];
+    let mut node_cfg = node_config();
+    node_cfg.nonce_buffer_size = 4;
+    let mut node = create_node(services, node_cfg);
+
+    let first_tx = create_tx(1);
+    let second_tx = create_tx(2);
+    node.handler.handle_incoming_tx(second_tx.clone());
+    {
+        let snapshot = node.blockchain().snapshot();
+        let schema = Schema::new(&snapshot);
+        assert_eq!(schema.transactions_pool_len(), 0);
+    }
+
+    node.handler.handle_incoming_tx(first_tx.clone());
+    let snapshot = node.blockchain().snapshot();
+    let schema = Schema::new(&snapshot);
+    assert_eq!(schema.transactions_pool_len(), 2);
+    let arrival = schema.transactions_pool_arrival();
+    assert!(arrival.get(&first_tx.hash()).unwrap() < arrival.get(&second_tx.hash()).unwrap());
+}
+
+#[test]
+fn test_held_transactions_expire_by_timeout() {
+    use super::nonce_buffer::NONCE_BUFFER_HOLD_TIME;
+
+    let (p_key, s_key) = gen_keypair();
+    let mut msg = TxSimple::new();
+    msg.set_public_key(p_key.to_pb());
+    msg.set_msg(2.to_string());
+    let tx = Message::sign_transaction(msg, SERVICE_ID, p_key, &s_key);
+
+    let services = vec![Box::new(NonceService) as Box<dyn Service>];
+    let mut node_cfg = node_config();
+    node_cfg.nonce_buffer_size = 4;
+    let mut node = create_node(services, node_cfg);
+
+    let before = SystemTime::now();
+    node.handler.handle_incoming_tx(tx);
+    let after = SystemTime::now();
+    assert_eq!(node.handler.nonce_buffer.held_len(), 1);
+
+    let hold_time = Duration::from_millis(NONCE_BUFFER_HOLD_TIME);
+    let request = node.channel.internal_requests.1.by_ref().wait().next();
+    match request {
+        Some(Ok(InternalRequest::Timeout(TimeoutRequest(
+            time,
+            NodeTimeout::NonceBufferExpiry,
+        )))) => {
+            assert!(time >= before + hold_time);
+            assert!(time <= after + hold_time);
+        }
+        other => panic!("Unexpected internal request: {:?}", other),
+    }
+}
+
+#[test]
+fn test_transaction_without_service() {
+    let (p_key, s_key) = gen_keypair();
+
+    let mut node = create_node(vec![], node_config());
+
+    let tx = create_simple_tx(p_key, &s_key);
+
+    // Send transaction to node.
+    let event = ExternalMessage::Transaction(tx);
+    node.handler.handle_event(event.into());
+
+    // Service not found for transaction.
+    let snapshot = node.blockchain().snapshot();
+    let schema = Schema::new(&snapshot);
+    assert_eq!(schema.transactions_pool_len(), 0);
+}
+
+#[test]
+fn test_peer_exchange_timeout_jitter() {
+    const SEED: u64 = 42;
+
+    let mut node_cfg = node_config();
+    node_cfg.network.peer_exchange_jitter_percent = 20;
+
+    let mut node = create_node(vec![], node_cfg);
+    let peers_timeout = node.handler.peers_timeout();
+    let max_jitter = node.handler.max_peer_exchange_jitter();
+    assert_eq!(max_jitter, peers_timeout / 5);
+
+    node.handler.set_rng_seed(SEED);
+    let before = SystemTime::now();
+    node.handler.add_peer_exchange_timeout();
+    let after = SystemTime::now();
+
+    let expected_jitter = StdRng::seed_from_u64(SEED).gen_range(0, max_jitter + 1);
+    assert!(expected_jitter <= max_jitter);
+    let delay = Duration::from_millis(peers_timeout + expected_jitter);
+
+    let request = node.channel.internal_requests.1.by_ref().wait().next();
+    match request {
+        Some(Ok(InternalRequest::Timeout(TimeoutRequest(time, NodeTimeout::PeerExchange)))) => {
+            assert!(time >= before + delay);
+            assert!(time <= after + delay);
+        }
+        other => panic!("Unexpected internal request: {:?}", other),
+    }
+}
+
+#[test]
+fn test_max_peers_in_exchange() {
+    use crate::messages::PeersRequest;
+
+    let mut node_cfg = node_config();
+    node_cfg.network.max_peers_in_exchange = Some(2);
+    let mut node = create_node(vec![], node_cfg);
+
+    for port in 17_000..17_005 {
+        let (p_key, s_key) = gen_keypair();
+        let address = format!("127.0.0.1:{}", port);
+        let connect = Connect::new(&address, Utc::now(), &user_agent::get(), &Hash::zero());
+        node.handler
+            .state
+            .add_peer(p_key, Message::concrete(connect, p_key, &s_key));
+    }
+
+    let (requester, requester_key) = gen_keypair();
+    let our_key = *node.handler.state().consensus_public_key();
+    let request = Message::concrete(PeersRequest::new(&our_key), requester, &requester_key);
+    node.handler.handle_request_peers(&request);
+    // Marks the end of the messages sent in response to the request.
+    node.handler
+        .send_network_request(NetworkRequest::DisconnectWithPeer(requester));
+
+    let sent_peers = node
+        .channel
+        .network_requests
+        .1
+        .by_ref()
+        .wait()
+        .map(Result::unwrap)
+        .take_while(|request| match request {
+            NetworkRequest::DisconnectWithPeer(_) => false,
+            _ => true,
+        })
+        .filter(|request| match request {
+            NetworkRequest::SendMessage(to, _) => *to == requester,
+            _ => false,
+        })
+        .count();
+    assert_eq!(sent_peers, 2);
+}
+
+#[test]
+fn test_disconnect_on_genesis_mismatch() {
+    use crate::events::network::ConnectedPeerAddr;
+
+    let mut node = create_node(vec![], node_config());
+
+    let (p_key, s_key) = gen_keypair();
+    let address = "127.0.0.1:17000";
+    let connect = Connect::new(address, Utc::now(), &user_agent::get(), &Hash::zero());
+    node.handler.handle_connected(
+        &ConnectedPeerAddr::In(address.parse().unwrap()),
+        Message::concrete(connect, p_key, &s_key),
+    );
+
+    assert!(!node.handler.state().peers().contains_key(&p_key));
+    let request = node
+        .channel
+        .network_requests
+        .1
+        .by_ref()
+        .wait()
+        .next()
+        .unwrap()
+        .unwrap();
+    match request {
+        NetworkRequest::DisconnectWithPeer(peer) => assert_eq!(peer, p_key),
+        other => panic!("Unexpected network request: {:?}", other),
+    }
+}
+
+struct ShutdownService {
+    shutdowns: Arc<AtomicUsize>,
+}
+
+impl Service for ShutdownService {
+    fn service_id(&self) -> u16 {
+        SERVICE_ID
+    }
+
+    fn service_name(&self) -> &'static str {
+        "shutdown service"
+    }
+
+    fn state_hash(&self, _: &dyn Snapshot) -> Vec<Hash> {
+        vec![]
+    }
+
+    fn tx_from_raw(&self, raw: RawTransaction) -> Result<Box<dyn Transaction>, failure::Error> {
+        Ok(SimpleTransactions::tx_from_raw(raw)?.into())
+    }
+
+    fn before_shutdown(&self, context: &ServiceContext) {
+        self.shutdowns.fetch_add(1, Ordering::SeqCst);
+        Entry::new("shutdown_service.flushed", context.fork()).set(true);
+    }
+}
+
+#[test]
+fn test_before_shutdown() {
+    let shutdowns = Arc::new(AtomicUsize::new(0));
+    let services = vec![Box::new(ShutdownService {
+        shutdowns: Arc::clone(&shutdowns),
+    }) as Box<dyn Service>];
+    let mut node = create_node(services, node_config());
+    assert_eq!(shutdowns.load(Ordering::SeqCst), 0);
+
+    node.handler.handle_event(ExternalMessage::Shutdown.into());
+    assert_eq!(shutdowns.load(Ordering::SeqCst), 1);
+    // The changes made by the service at shutdown are persisted.
+    let snapshot = node.handler.blockchain.snapshot();
+    let flushed = Entry::new("shutdown_service.flushed", snapshot.as_ref());
+    assert_eq!(flushed.get(), Some(true));
+
+    let request = node.channel.internal_requests.1.by_ref().wait().next();
+    match request {
+        Some(Ok(InternalRequest::Shutdown)) => {}
+        other => panic!("Unexpected internal request: {:?}", other),
+    }
+}
+
+#[test]
+fn test_replace_connect_list() {
+    let node_cfg = helpers::generate_testnet_config(2, 16_500)[0].clone();
+    let kept_peer = node_cfg.connect_list.peers[0].clone();
+    let removed_peer = node_cfg.connect_list.peers[1].public_key;
+    let new_peer = ConnectInfo {
+        public_key: gen_keypair().0,
+        address: "127.0.0.1:16600".to_owned(),
+    };
+
+    let mut node = create_node(vec![], node_cfg);
+    let new_list = ConnectListConfig {
+        peers: vec![kept_peer.clone(), new_peer.clone()],
+    };
+    node.handler
+        .handle_event(ExternalMessage::ReplaceConnectList(new_list).into());
+
+    let connect_list = node.handler.state().connect_list();
+    assert!(connect_list.is_peer_allowed(&kept_peer.public_key));
+    assert!(connect_list.is_peer_allowed(&new_peer.public_key));
+    assert!(!connect_list.is_peer_allowed(&removed_peer));
+
+    let requests = node
+        .channel
+        .network_requests
+        .1
+        .by_ref()
+        .wait()
+        .take(2)
+        .map(Result::unwrap)
+        .collect::<Vec<_>>();
+    match requests.as_slice() {
+        [NetworkRequest::DisconnectWithPeer(disconnected), NetworkRequest::SendMessage(connected, _)] =>
+        {
+            assert_eq!(*disconnected, removed_peer);
+            assert_eq!(*connected, new_peer.public_key);
+        }
+        other => panic!("Unexpected network requests: {:?}", other),
+    }
+}
+
+#[test]
+fn test_connect_list_config_validation() {
+    let peer = |address: &str| ConnectInfo {
+        public_key: gen_keypair().0,
+        address: address.to_owned(),
+    };
+    let valid = ConnectListConfig {
+        peers: vec![peer("127.0.0.1:6333"), peer("node.example.com:6333")],
+    };
+    assert!(valid.validate().is_ok());
+
+    for address in &["127.0.0.1", "127.0.0.1:port", ":6333", ""] {
+        let config = ConnectListConfig {
+            peers: vec![peer(address)],
+        };
+        assert!(config.validate().is_err(), "{}", address);
+    }
+
+    let duplicate = valid.peers[0].clone();
+    let config = ConnectListConfig {
+        peers: vec![duplicate.clone(), duplicate],
+    };
+    assert!(config.validate().is_err());
+}
+
+#[cfg(feature = "http-client")]
+#[test]
+fn test_connect_list_source() {
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+
+    let mut node_cfg = node_config();
+    // The auditor is removed from the connect list, while the validator is kept.
+    let removed_peer = ConnectInfo {
+        public_key: gen_keypair().0,
+        address: "127.0.0.1:16601".to_owned(),
+    };
+    node_cfg.connect_list.peers.push(removed_peer.clone());
+    let new_peer = ConnectInfo {
+        public_key: gen_keypair().0,
+        address: "127.0.0.1:16600".to_owned(),
+    };
+    let new_list = ConnectListConfig {
+        peers: vec![node_cfg.connect_list.peers[0].clone(), new_peer.clone()],
+    };
+    let removed_peer = removed_peer.public_key;
+
+    // Mock service discovery endpoint serving the updated connect list.
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    node_cfg.connect_list_source = Some(
+        format!("http://{}/peers", listener.local_addr().unwrap())
+            .parse()
+            .unwrap(),
+    );
+    let body = serde_json::to_string(&new_list).unwrap();
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            // Skip the request headers.
+            let mut line = String::new();
+            while reader.read_line(&mut line).unwrap() > 2 {
+                line.clear();
+            }
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\n\
+                 Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            )
+            .unwrap();
+        }
+    });
+
+    let mut node = create_node(vec![], node_cfg);
+    let message = node
+        .channel
+        .api_requests
+        .1
+        .by_ref()
+        .wait()
+        .next()
+        .unwrap()
+        .unwrap();
+    match message {
+        ExternalMessage::ReplaceConnectList(ref config) => assert_eq!(*config, new_list),
+        ref other => panic!("Unexpected external message: {:?}", other),
+    }
+    node.handler.handle_event(message.into());
+
+    let connect_list = node.handler.state().connect_list();
+    assert!(connect_list.is_peer_allowed(&new_peer.public_key));
+    assert!(!connect_list.is_peer_allowed(&removed_peer));
+}
+
+#[cfg(feature = "http-client")]
+#[test]
+fn test_connect_list_source_rejects_incomplete_lists() {
+    let configs = helpers::generate_testnet_config(2, 16_500);
+    let node_cfg = configs[0].clone();
+    let other_validator = node_cfg.connect_list.peers[1].clone();
+    let node = create_node(vec![], node_cfg.clone());
+    let check = |peers: Vec<ConnectInfo>| {
+        connect_list_source::check_connect_list(
+            &ConnectListConfig { peers },
+            &node.handler.blockchain,
+            &node_cfg.consensus_public_key,
+        )
+    };
+
+    let auditor = ConnectInfo {
+        public_key: gen_keypair().0,
+        address: "127.0.0.1:16600".to_owned(),
+    };
+    assert!(check(vec![]).is_err());
+    assert!(check(vec![auditor.clone()]).is_err());
+    // The node itself may be missing from the list.
+    assert!(check(vec![other_validator, auditor]).is_ok());
+}
+
+#[test]
+fn test_connect_list_source_should_be_http_url() {
+    let mut node_cfg = node_config();
+    node_cfg.connect_list_source = Some("ftp://127.0.0.1/peers".parse().unwrap());
+    let err = Node::check(temporary_db(), &[], &node_cfg).unwrap_err();
+    assert!(err.to_string().contains("HTTP or HTTPS URL"));
+}
+
+#[test]
+fn test_effective_config() {
+    let mut node_cfg = node_config();
+    node_cfg.api.state_update_timeout = 1_234;
+    node_cfg.network.tcp_connect_max_retries = 3;
+
+    let node = create_node(vec![], node_cfg.clone());
+    let config = node.handler.api_state().effective_config().unwrap();
+    assert_eq!(config.api.state_update_timeout, 1_234);
+    assert_eq!(config.api, node_cfg.api);
+    assert_eq!(config.mempool, node_cfg.mempool);
+    assert_eq!(config.network, node_cfg.network);
+}
+
+#[test]
+fn test_node_restarts_are_counted() {
+    let db = temporary_db();
+    let node_cfg = node_config();
+
+    let node = Node::new(Arc::clone(&db), vec![], node_cfg.clone(), None);
+    assert!(node.handler.api_state().uptime().is_some());
+    let (first_start, restarts) = {
+        let snapshot = db.snapshot();
+        let schema = Schema::new(&snapshot);
+        (schema.node_first_start(), schema.node_restarts())
+    };
+    assert!(first_start.is_some());
+    assert_eq!(restarts, 0);
+    drop(node);
+
+    let _node = Node::new(Arc::clone(&db), vec![], node_cfg, None);
+    let snapshot = db.snapshot();
+    let schema = Schema::new(&snapshot);
+    assert_eq!(schema.node_first_start(), first_start);
+    assert_eq!(schema.node_restarts(), 1);
+}
+
+#[test]
+fn test_export_import_encrypted_secrets() {
+    let configs = helpers::generate_testnet_config(2, 16_600);
+    let (exported, mut imported) = (configs[0].clone(), configs[1].clone());
+
+    let passphrase = b"passphrase";
+    let bytes = exported.export_encrypted(passphrase).unwrap();
+    assert!(imported
+        .import_encrypted(&bytes, b"wrong passphrase")
+        .is_err());
+    assert_eq!(imported, configs[1]);
+
+    imported.import_encrypted(&bytes, passphrase).unwrap();
+    assert_eq!(imported.consensus_public_key, exported.consensus_public_key);
+    assert_eq!(imported.consensus_secret_key, exported.consensus_secret_key);
+    assert_eq!(imported.service_public_key, exported.service_public_key);
+    assert_eq!(imported.service_secret_key, exported.service_secret_key);
+    assert_eq!(imported.connect_list, exported.connect_list);
+}
+
+#[test]
+fn test_dropped_requests_are_counted() {
+    let mut node_cfg = node_config();
+    let capacity = 4;
+    node_cfg
+        .mempool
+        .events_pool_capacity
+        .network_requests_capacity = capacity;
+
+    let mut node = create_node(vec![], node_cfg);
+    let stats = node.handler.api_state().channel_stats();
+    assert_eq!(stats, ChannelStats::default());
+
+    // The node is not running, so nobody reads the network requests and the channel
+    // is saturated after `capacity` requests.
+    let (peer, _) = gen_keypair();
+    let extra_requests = 10;
+    for _ in 0..capacity + extra_requests {
+        node.handler.connect(peer);
+    }
+    let stats = node.handler.api_state().channel_stats();
+    assert!(stats.network_requests_dropped > 0);
+    assert!(stats.network_requests_dropped <= extra_requests as u64);
+    assert_eq!(stats.internal_requests_dropped, 0);
+
+    // Internal requests are only lost if the receiving side of the channel is gone.
+    drop(node.channel.internal_requests.1);
+    node.handler
+        .execute_later(InternalRequest::JumpToRound(Height(1), Round(2)));
+    let stats = node.handler.api_state().channel_stats();
+    assert_eq!(stats.internal_requests_dropped, 1);
+}
+
+#[test]
+#[should_panic(expected = "the node cannot proceed without it")]
+fn test_lost_timeout_is_fatal() {
+    let mut node = create_node(vec![], node_config());
+    // Saturate the channel and stop the receiving side, so the timeout can never
+    // be delivered.
+    let sender = node.handler.channel.internal_requests.get_mut();
+    while sender.try_send(InternalRequest::Shutdown).is_ok() {}
+    drop(node.channel.internal_requests.1);
+    node.handler.add_round_timeout();
+}
+
+#[test]
+fn test_consensus_messages_cache_pruned_on_commit() {
+    use crate::messages::Prevote;
+
+    let node = create_node(vec![], node_config());
+    let prevote = |height, round| -> Message {
+        node.handler
+            .sign_message(Prevote::new(
+                ValidatorId(0),
+                height,
+                round,
+                &crypto::hash(&[]),
+                Round::zero(),
+            ))
+            .into()
+    };
+    let next_height_prevote = prevote(Height(2), Round(1));
+
+    let mut blockchain = node.blockchain();
+    blockchain.save_messages(
+        Round(2),
+        vec![
+            prevote(Height(1), Round(1)),
+            prevote(Height(1), Round(2)),
+            next_height_prevote.clone(),
+        ],
+    );
+    let (block_hash, patch) = blockchain.create_patch(ValidatorId(0), Height(1), &[]);
+    blockchain
+        .commit(&patch, block_hash, iter::empty())
+        .unwrap();
+
+    let snapshot = blockchain.snapshot();
+    let schema = Schema::new(&snapshot);
+    assert_eq!(
+        schema.consensus_messages_cache().iter().collect::<Vec<_>>(),
+        vec![next_height_prevote]
+    );
+    assert_eq!(schema.consensus_round(), Round(1));
+}
+
+#[test]
+fn test_skip_inconsistent_consensus_messages_cache() {
+    use crate::messages::Prevote;
+
+    let node_cfg = helpers::generate_testnet_config(4, 16_500)[0].clone();
+    let mut node = create_node(vec![], node_cfg);
+
+    // The cached prevote is from a round after the saved one, which is possible
+    // only if the cache is corrupted.
+    let prevote = node.handler.sign_message(Prevote::new(
+        ValidatorId(0),
+        Height(1),
+        Round(3),
+        &crypto::hash(&[]),
+        Round::zero(),
+    ));
+    node.handler
+        .blockchain
+        .save_messages(Round(2), iter::once(prevote.into()));
+
+    node.handler.initialize();
+    assert_eq!(node.handler.state().round(), Round(2));
+    // The prevote would be queued as a message from the future round if replayed.
+    assert!(node.handler.state.queued().is_empty());
+}
+
+#[test]
+fn test_node_check() {
+    let db = temporary_db();
+    let node_cfg = node_config();
+
+    Node::check(db.clone(), &[], &node_cfg).unwrap();
+    // The genesis block is not created by the check.
+    assert!(Schema::new(&db.snapshot())
+        .block_hashes_by_height()
+        .is_empty());
+
+    let mut invalid_cfg = node_cfg.clone();
+    invalid_cfg.genesis.consensus.txs_block_limit = 0;
+    let err = Node::check(db.clone(), &[], &invalid_cfg).unwrap_err();
+    assert!(err.to_string().contains("txs_block_limit"));
+
+    let mut invalid_cfg = node_cfg.clone();
+    invalid_cfg.service_secret_key = gen_keypair().1;
+    let err = Node::check(db.clone(), &[], &invalid_cfg).unwrap_err();
+    assert!(err.to_string().contains("service secret key"));
+
+    let mut invalid_cfg = node_cfg.clone();
+    invalid_cfg.api.api_worker_threads = 0;
+    let err = Node::check(db.clone(), &[], &invalid_cfg).unwrap_err();
+    assert!(err.to_string().contains("api_worker_threads"));
+
+    // The connect list is checked in the same way as the lists of the connect list source.
+    let mut invalid_cfg = node_cfg.clone();
+    invalid_cfg.connect_list.peers.push(ConnectInfo {
+        public_key: gen_keypair().0,
+        address: ":6333".to_owned(),
+    });
+    assert!(invalid_cfg.connect_list.validate().is_err());
+    let err = Node::check(db.clone(), &[], &invalid_cfg).unwrap_err();
+    assert!(err.to_string().contains("`host:port` format"));
+
+    // The block publisher is checked without connecting to the brokers.
+    let mut invalid_cfg = node_cfg.clone();
+    invalid_cfg.block_publisher = Some(PublisherConfig::Kafka {
+        brokers: vec![],
+        topic: "blocks".to_owned(),
+    });
+    let err = Node::check(db.clone(), &[], &invalid_cfg).unwrap_err();
+    assert!(err.to_string().contains("Kafka block publisher"));
+
+    // Once the database is initialized, the genesis configuration cannot be changed.
+    Node::new(db.clone(), vec![], node_cfg.clone(), None);
+    Node::check(db.clone(), &[], &node_cfg).unwrap();
+    let mut other_cfg = node_cfg.clone();
+    other_cfg.genesis.consensus.txs_block_limit += 1;
+    let err = Node::check(db, &[], &other_cfg).unwrap_err();
+    assert!(err.to_string().contains("Genesis configuration differs"));
+}
+
+#[test]
+#[should_panic(expected = "Genesis configuration differs")]
+fn test_restart_with_changed_genesis() {
+    let db = temporary_db();
+    let node_cfg = node_config();
+    Node::new(db.clone(), vec![], node_cfg.clone(), None);
+
+    let mut changed_cfg = node_cfg;
+    changed_cfg.genesis.consensus.txs_block_limit += 1;
+    Node::new(db, vec![], changed_cfg, None);
+}
+
+#[test]
+fn test_restart_with_changed_genesis_keeping_database() {
+    let db = temporary_db();
+    let node_cfg = node_config();
+    let genesis_hash = Node::new(db.clone(), vec![], node_cfg.clone(), None)
+        .blockchain()
+        .last_hash();
+
+    // The ignored genesis is not validated.
+    let mut changed_cfg = node_cfg.clone();
+    changed_cfg.genesis.validator_keys.clear();
+    changed_cfg.genesis.consensus.max_message_len += 1;
+    changed_cfg.genesis_conflict_policy = GenesisConflictPolicy::KeepDatabase;
+    Node::check(db.clone(), &[], &changed_cfg).unwrap();
+    let node = Node::new(db, vec![], changed_cfg, None);
+    assert_eq!(
+        node.max_message_len,
+        node_cfg.genesis.consensus.max_message_len
+    );
+
+    let blockchain = node.blockchain();
+    assert_eq!(blockchain.last_hash(), genesis_hash);
+    let schema = Schema::new(blockchain.snapshot());
+    assert_eq!(
+        schema.actual_configuration().consensus,
+        node_cfg.genesis.consensus
+    );
+}
+
+#[test]
+#[should_panic(expected = "Genesis configuration differs")]
+fn test_restart_with_changed_genesis_without_stored_genesis() {
+    let db = temporary_db();
+    let node_cfg = node_config();
+    let mut blockchain = Node::new(db.clone(), vec![], node_cfg.clone(), None).blockchain();
+
+    // Emulates a database created before the genesis configuration was stored.
+    let fork = blockchain.fork();
+    Schema::new(&fork).genesis_config_entry().remove();
+    blockchain.merge(fork.into_patch()).unwrap();
+
+    let mut changed_cfg = node_cfg;
+    changed_cfg.genesis.consensus.txs_block_limit += 1;
+    Node::new(db, vec![], changed_cfg, None);
+}
+
+#[test]
+fn test_node_from_config_with_temporary_db() {
+    let mut node_cfg = node_config();
+    assert!(Node::from_config(vec![], node_cfg.clone(), None).is_err());
+
+    node_cfg.db_backend = Some(DbBackend::Temporary);
+    let node = Node::from_config(vec![], node_cfg, None).unwrap();
+
+    let mut blockchain = node.blockchain();
+    let (block_hash, patch) = blockchain.create_patch(ValidatorId(0), Height(1), &[]);
+    blockchain
+        .commit(&patch, block_hash, iter::empty())
+        .unwrap();
+    assert_eq!(blockchain.last_block().height(), Height(1));
+    assert_eq!(node.blockchain().last_hash(), block_hash);
+}
+
+#[test]
+fn test_resolve_external_address_after_failures() {
+    let resolved: SocketAddr = "127.0.0.1:6333".parse().unwrap();
+    let config = NetworkConfiguration {
+        external_address_resolve_retries: 3,
+        external_address_resolve_retry_timeout: 10,
+        external_address_fallback: ExternalAddressFallback::FailFast,
+        ..NetworkConfiguration::default()
+    };
+
+    // Resolution fails twice and then succeeds.
+    let mut attempts = 0;
+    let address = resolve_external_address("node.example:6333", &config, |address| {
+        assert_eq!(address, "node.example:6333");
+        attempts += 1;
+        if attempts <= 2 {
+            Err(io::Error::new(io::ErrorKind::Other, "temporary failure"))
+        } else {
+            Ok(resolved)
+        }
+    })
+    .unwrap();
+    assert_eq!(attempts, 3);
+    assert_eq!(address, resolved.to_string());
+
+    // The resolved address is advertised by the node.
+    let mut node_cfg = node_config();
+    node_cfg.external_address = format!("localhost:{}", node_cfg.listen_address.port());
+    let expected = resolve_socket_address(&node_cfg.external_address).unwrap();
+    let node = create_node(vec![], node_cfg);
+    assert_eq!(
+        node.handler.state().our_connect_message().pub_addr(),
+        expected.to_string()
+    );
+}
+
+#[test]
+fn test_resolve_external_address_fallback() {
+    let mut config = NetworkConfiguration {
+        external_address_resolve_retries: 1,
+        external_address_resolve_retry_timeout: 10,
+        external_address_fallback: ExternalAddressFallback::FailFast,
+        ..NetworkConfiguration::default()
+    };
+    let fail = |_: &str| Err(io::Error::new(io::ErrorKind::Other, "persistent failure"));
+
+    assert!(resolve_external_address("node.example:6333", &config, fail).is_err());
+
+    config.external_address_fallback = ExternalAddressFallback::AdvertiseLastKnown;
+    let address = resolve_external_address("node.example:6333", &config, fail).unwrap();
+    assert_eq!(address, "node.example:6333");
+}
+
+/// Logger capturing the logged messages, so that the tests can check them.
+#[derive(Debug, Default)]
+struct CapturingLogger {
+    lines: Mutex<Vec<String>>,
+}
+
+impl CapturingLogger {
+    /// Returns the logged messages containing the given pattern.
+    fn lines_containing(&self, pattern: &str) -> Vec<String> {
+        let lines = self.lines.lock().unwrap();
+        lines
+            .iter()
+            .filter(|line| line.contains(pattern))
+            .cloned()
+            .collect()
+    }
+}
+
+impl Log for CapturingLogger {
+    fn enabled(&self, _metadata: &Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &Record) {
+        self.lines.lock().unwrap().push(record.args().to_string());
+    }
+
+    fn flush(&self) {}
+}
+
+/// Returns the logger capturing the messages of the warning level and above.
+///
+/// The logger is installed once for the test binary on the first call. Other tests may
+/// install their own logger first (e.g., with `env_logger::try_init`); in this case
+/// `None` is returned and the logged messages cannot be checked.
+fn capturing_logger() -> Option<&'static CapturingLogger> {
+    lazy_static! {
+        static ref LOGGER: CapturingLogger = CapturingLogger::default();
+        static ref INSTALLED: bool = match log::set_logger(&*LOGGER) {
+            Ok(()) => {
+                log::set_max_level(LevelFilter::Warn);
+                true
+            }
+            Err(_) => false,
+        };
+    }
+
+    if *INSTALLED {
+        Some(&LOGGER)
+    } else {
+        None
+    }
+}
+
+#[test]
+fn test_log_rejected_messages() {
+    use crate::crypto::Signature;
+    use crate::events::{Event, InternalEvent, InternalPart, RejectionReason};
+    use crate::messages::Prevote;
+
+    let logger = capturing_logger();
+    let logged_lines =
+        |author: &PublicKey| logger.map(|logger| logger.lines_containing(&author.to_hex()));
+
+    let mut node_cfg = node_config();
+    node_cfg.log_rejected_messages = true;
+    let (consensus_key, consensus_secret_key) = (
+        node_cfg.consensus_public_key,
+        node_cfg.consensus_secret_key.clone(),
+    );
+    let mut node = create_node(vec![], node_cfg);
+
+    // Message with an invalid signature.
+    let (p_key, _) = gen_keypair();
+    let malformed = SignedMessage::new_with_signature(0, 0, &[0; 200], p_key, Signature::zero());
+    let event = InternalPart::verify_raw_message(malformed.raw().to_vec());
+    assert_eq!(
+        event,
+        InternalEvent::MessageRejected(Some(p_key), RejectionReason::BadSignature)
+    );
+    node.handler.handle_event(event.into());
+    if let Some(lines) = logged_lines(&p_key) {
+        assert_eq!(
+            lines,
+            vec![format!(
+                "Rejected message from {}: bad signature",
+                p_key.to_hex()
+            )]
+        );
+    }
+
+    // Consensus message from a far height.
+    let prevote = Message::concrete(
+        Prevote::new(
+            ValidatorId(0),
+            Height(10),
+            Round(1),
+            &crypto::hash(&[]),
+            Round::zero(),
+        ),
+        consensus_key,
+        &consensus_secret_key,
+    );
+    node.handler.handle_message(prevote.into());
+    if let Some(lines) = logged_lines(&consensus_key) {
+        assert_eq!(
+            lines,
+            vec![format!(
+                "Rejected message from {}: wrong height",
+                consensus_key.to_hex()
+            )]
+        );
+    }
+
+    // The number of logged messages is limited.
+    let (p_key, _) = gen_keypair();
+    let rejected = || InternalEvent::MessageRejected(Some(p_key), RejectionReason::UnknownType);
+    for _ in 0..REJECTED_MESSAGES_LOG_LIMIT * 2 {
+        node.handler.handle_event(Event::Internal(rejected()));
+    }
+    if let Some(lines) = logged_lines(&p_key) {
+        assert!(!lines.is_empty() && lines.len() <= REJECTED_MESSAGES_LOG_LIMIT);
+    }
+}
+
+/// Service with a random state hash, which makes the block execution non-deterministic.
+struct NondeterministicService;
+
+impl Service for NondeterministicService {
+    fn service_id(&self) -> u16 {
+        SERVICE_ID
+    }
+
+    fn service_name(&self) -> &'static str {
+        "nondeterministic service"
+    }
+
+    fn state_hash(&self, _: &dyn Snapshot) -> Vec<Hash> {
+        vec![crypto::hash(gen_keypair().0.as_ref())]
+    }
+
+    fn tx_from_raw(&self, raw: RawTransaction) -> Result<Box<dyn Transaction>, failure::Error> {
+        Ok(SimpleTransactions::tx_from_raw(raw)?.into())
+    }
+}
+
+/// Executes a propose with a transaction of the service on the node configured
+/// to verify the determinism of the execution if `verify` is set.
+fn execute_propose(service: Box<dyn Service>, verify: bool, halt: bool) {
+    use crate::messages::Propose;
+
+    let mut node_cfg = node_config();
+    node_cfg.verify_execution_determinism = verify;
+    node_cfg.halt_on_nondeterministic_execution = halt;
+    let mut node = create_node(vec![service], node_cfg);
+
+    let (p_key, s_key) = gen_keypair();
+    let tx = create_simple_tx(p_key, &s_key);
+    node.handler
+        .handle_event(ExternalMessage::Transaction(tx.clone()).into());
+
+    let propose = node.handler.sign_message(Propose::new(
+        ValidatorId(0),
+        Height(1),
+        Round(1),
+        &node.handler.blockchain.last_hash(),
+        &[tx.hash()],
+    ));
+    let propose_hash = node.handler.state.add_self_propose(propose);
+    node.handler.execute(&propose_hash);
+}
+
+#[test]
+fn test_deterministic_execution() {
+    execute_propose(Box::new(TestService), true, true);
+}
+
+#[test]
+fn test_nondeterministic_execution() {
+    let logger = capturing_logger();
+    execute_propose(Box::new(NondeterministicService), true, false);
+    if let Some(logger) = logger {
+        assert!(!logger
+            .lines_containing("Non-deterministic execution of the block at height 1")
+            .is_empty());
+    }
+}
+
+#[test]
+#[should_panic(expected = "Non-deterministic execution of the block at height 1")]
+fn test_nondeterministic_execution_halts_node() {
+    execute_propose(Box::new(NondeterministicService), true, true);
+}
+
+#[test]
+fn test_nondeterministic_execution_without_verification() {
+    // The block is executed once, so the non-determinism is not detected.
+    execute_propose(Box::new(NondeterministicService), false, true);
+}
+
+#[test]
+fn test_expected_validators_mismatch() {
+    let logger = capturing_logger();
+    let mut node_cfg = node_config();
+    node_cfg.expected_validators = Some(4);
+    let _node = create_node(vec![], node_cfg);
+
+    if let Some(logger) = logger {
+        assert_eq!(
+            logger.lines_containing("validators are expected"),
+            vec![
+                "The actual configuration contains 1 validators, while 4 validators are \
+                 expected by the node configuration"
+                    .to_owned()
+            ]
+        );
+    }
+    assert!(check_validators_count(1, 1, true).is_ok());
+}
+
+#[test]
+#[should_panic(expected = "while 4 validators are expected")]
+fn test_expected_validators_mismatch_strict() {
+    let mut node_cfg = node_config();
+    node_cfg.expected_validators = Some(4);
+    node_cfg.strict_expected_validators = true;
+    create_node(vec![], node_cfg);
+}
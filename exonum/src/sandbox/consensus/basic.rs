@@ -16,14 +16,34 @@
 //! related to consensus protocol handling, such as ability of the node
 //! to add block after receiving correct consensus messages.
 
+use chrono::{DateTime, Utc};
 use rand::{thread_rng, Rng};
+use tokio::runtime::current_thread::Runtime;
 
-use std::collections::BTreeMap;
+use std::{
+    collections::BTreeMap,
+    sync::{mpsc, Arc, Mutex},
+    thread,
+    time::Duration,
+};
 
-use crate::blockchain::{Blockchain, Schema, CORE_SERVICE};
-use crate::crypto::{gen_keypair_from_seed, CryptoHash, Hash, Seed, HASH_SIZE, SEED_LENGTH};
-use crate::helpers::{Height, Round, ValidatorId};
-use crate::messages::{Precommit, Signed};
+use crate::api::{
+    node::public::explorer::{
+        ApiWorkers, BlockByTimeQuery, BlockEtaQuery, BlockExists, BlockExistsQuery, BlockQuery,
+        BlockRound, BlockStateHashQuery, BlockVotesQuery, BlocksRoundsQuery, ExplorerApi,
+        RawBlockQuery, TransactionQuery, TransactionStatus, TransactionStatusesQuery,
+    },
+    Error as ApiError, ServiceApiState,
+};
+use crate::blockchain::{Blockchain, ConsensusState, Schema, CORE_SERVICE, ROUND_SCHEDULE_LENGTH};
+use crate::crypto::{self, gen_keypair_from_seed, CryptoHash, Hash, Seed, HASH_SIZE, SEED_LENGTH};
+use crate::explorer::{BlockchainExplorer, TransactionCache};
+use crate::helpers::{
+    metrics::{self, MetricSink},
+    Height, Round, ValidatorId,
+};
+use crate::messages::{to_hex_string, Precommit, Signed};
+use crate::node::{BlockPublisher, PublishedBlock};
 use crate::sandbox::{
     self,
     sandbox_tests_helper::*,
@@ -279,3 +299,562 @@ fn test_store_txs_positions() {
         assert_eq!(committed_height, location.block_height());
     }
 }
+
+/// Idea of the test is to check that the API reports the number of rounds spent
+/// at the current height and the maximal number of rounds needed for a height.
+#[test]
+fn test_consensus_state_round_count() {
+    let sandbox = timestamping_sandbox();
+    let consensus_state = || {
+        let handler = sandbox.node_handler_mut();
+        handler.api_state().update_node_state(&handler.state);
+        handler.api_state().consensus_state().unwrap()
+    };
+
+    assert_eq!(
+        consensus_state(),
+        ConsensusState {
+            height: Height(1),
+            current_height_round_count: 1,
+            max_height_round_count: 1,
+            validators_count: 4,
+        }
+    );
+
+    // Validator 0 becomes the leader at round 3, so the block is committed in this round.
+    add_one_height(&sandbox, &SandboxState::new());
+    assert_eq!(
+        consensus_state(),
+        ConsensusState {
+            height: Height(2),
+            current_height_round_count: 1,
+            max_height_round_count: 3,
+            validators_count: 4,
+        }
+    );
+
+    sandbox
+        .node_handler_mut()
+        .handle_new_round(Height(2), Round(2));
+    assert_eq!(
+        consensus_state(),
+        ConsensusState {
+            height: Height(2),
+            current_height_round_count: 2,
+            max_height_round_count: 3,
+            validators_count: 4,
+        }
+    );
+}
+
+/// Idea of the test is to check that an explorer request is aborted if it does not
+/// complete within the request timeout.
+#[test]
+fn test_explorer_request_timeout() {
+    let sandbox = timestamping_sandbox();
+    let api_state = ServiceApiState::new(sandbox.blockchain_ref().clone());
+    let node_state = sandbox.node_handler_mut().api_state().clone();
+    // The workers are not created unless the timeout is set.
+    assert!(ApiWorkers::from_node_state(&node_state).is_none());
+    node_state.set_api_request_timeout(Some(Duration::from_millis(100)));
+    node_state.set_api_workers(2, 2);
+    let api_workers = ApiWorkers::from_node_state(&node_state).map(Arc::new);
+    assert!(api_workers.is_some());
+
+    let slow_block = ExplorerApi::with_request_timeout(
+        &api_workers,
+        |state: &ServiceApiState, query: BlockQuery| {
+            thread::sleep(Duration::from_secs(1));
+            ExplorerApi::block(state, query)
+        },
+    );
+    let block = ExplorerApi::with_request_timeout(&api_workers, ExplorerApi::block);
+
+    let mut runtime = Runtime::new().unwrap();
+    match runtime.block_on(slow_block(&api_state, BlockQuery::new(Height(0)))) {
+        Err(ApiError::ServiceUnavailable(_)) => {}
+        Err(e) => panic!("Unexpected error: {}", e),
+        Ok(_) => panic!("Request has not timed out"),
+    }
+    let info = runtime
+        .block_on(block(&api_state, BlockQuery::new(Height(0))))
+        .unwrap();
+    assert_eq!(info.block.height(), Height(0));
+}
+
+/// Idea of the test is to check that the consensus log records processed consensus messages
+/// in order and evicts the oldest ones when it is full.
+#[cfg(feature = "consensus-log")]
+#[test]
+fn test_consensus_log() {
+    let sandbox = timestamping_sandbox();
+    sandbox
+        .node_handler_mut()
+        .api_state()
+        .enable_consensus_log(2);
+
+    let propose_hash = Hash::zero();
+    // Messages from the future round are queued, so the node does not respond to them.
+    let prevote = sandbox.create_prevote(
+        ValidatorId(2),
+        Height(1),
+        Round(2),
+        &propose_hash,
+        NOT_LOCKED,
+        sandbox.secret_key(ValidatorId(2)),
+    );
+    let precommit = sandbox.create_precommit(
+        ValidatorId(2),
+        Height(1),
+        Round(2),
+        &propose_hash,
+        &propose_hash,
+        sandbox.time().into(),
+        sandbox.secret_key(ValidatorId(2)),
+    );
+    let status = sandbox.create_status(
+        &sandbox.public_key(ValidatorId(1)),
+        Height(1),
+        &sandbox.last_hash(),
+        sandbox.secret_key(ValidatorId(1)),
+    );
+
+    sandbox.recv(&prevote);
+    sandbox.recv(&precommit);
+    {
+        let log = sandbox.node_handler_mut().api_state().consensus_log();
+        assert_eq!(log.len(), 2);
+        assert_eq!(log[0].message_type, "prevote");
+        assert_eq!(log[0].message, to_hex_string(&prevote));
+        assert_eq!(log[1].message_type, "precommit");
+        assert_eq!(log[1].message, to_hex_string(&precommit));
+        assert_eq!(log[1].round, Some(Round(2)));
+    }
+
+    sandbox.recv(&status);
+    let log = sandbox.node_handler_mut().api_state().consensus_log();
+    assert_eq!(log.len(), 2);
+    assert_eq!(log[0].message_type, "precommit");
+    assert_eq!(log[1].message_type, "status");
+    assert_eq!(log[1].height, Height(1));
+    assert_eq!(log[1].round, None);
+    assert_eq!(log[1].author, sandbox.public_key(ValidatorId(1)));
+}
+
+/// Checks that the `v1/blocks/rounds` endpoint reports the rounds at which blocks were committed.
+#[test]
+fn test_blocks_rounds() {
+    let sandbox = timestamping_sandbox();
+    let sandbox_state = SandboxState::new();
+
+    // Validator 0 becomes the leader at rounds 3, 2 and 1 for heights 1, 2 and 3 respectively.
+    for _ in 0..3 {
+        add_one_height(&sandbox, &sandbox_state);
+    }
+
+    let state = ServiceApiState::new(sandbox.blockchain_ref().clone());
+    let rounds = ExplorerApi::blocks_rounds(&state, BlocksRoundsQuery { count: 10 }).unwrap();
+    assert_eq!(
+        rounds,
+        vec![
+            BlockRound {
+                height: Height(3),
+                round: Round(1),
+            },
+            BlockRound {
+                height: Height(2),
+                round: Round(2),
+            },
+            BlockRound {
+                height: Height(1),
+                round: Round(3),
+            },
+        ]
+    );
+
+    let rounds = ExplorerApi::blocks_rounds(&state, BlocksRoundsQuery { count: 1 }).unwrap();
+    assert_eq!(rounds.len(), 1);
+    assert_eq!(rounds[0].height, Height(3));
+}
+
+/// Checks that the `v1/block/by_time` endpoint finds the first block committed at or after
+/// the given instant.
+#[test]
+fn test_block_by_time() {
+    let sandbox = timestamping_sandbox();
+    let sandbox_state = SandboxState::new();
+    for _ in 0..3 {
+        add_one_height(&sandbox, &sandbox_state);
+    }
+
+    let state = ServiceApiState::new(sandbox.blockchain_ref().clone());
+    let block_time = |height| {
+        ExplorerApi::block(&state, BlockQuery::new(height))
+            .unwrap()
+            .time
+            .unwrap()
+    };
+    let block_by_time = |at| {
+        ExplorerApi::block_by_time(&state, BlockByTimeQuery { at })
+            .unwrap()
+            .block
+            .height()
+    };
+
+    let (first_time, second_time) = (block_time(Height(1)), block_time(Height(2)));
+    assert!(first_time < second_time);
+    let between = first_time + (second_time - first_time) / 2;
+    assert_eq!(block_by_time(between), Height(2));
+    assert_eq!(block_by_time(first_time), Height(1));
+    assert_eq!(block_by_time(second_time), Height(2));
+
+    let genesis_time = block_time(Height(0));
+    assert_eq!(
+        block_by_time(genesis_time - chrono::Duration::days(1)),
+        Height(0)
+    );
+    let latest_time = block_time(Height(3));
+    assert_eq!(
+        block_by_time(latest_time + chrono::Duration::days(1)),
+        Height(3)
+    );
+}
+
+/// Checks that the `v1/blocks/eta` endpoint returns the actual time of the committed blocks
+/// and extrapolates the regular block intervals for the future blocks.
+#[test]
+fn test_block_eta() {
+    let sandbox = timestamping_sandbox();
+    let sandbox_state = SandboxState::new();
+    let state = ServiceApiState::new(sandbox.blockchain_ref().clone());
+    let block_eta = |height| ExplorerApi::block_eta(&state, BlockEtaQuery { height });
+
+    add_one_height(&sandbox, &sandbox_state);
+    assert!(block_eta(Height(5)).is_err());
+
+    for _ in 0..4 {
+        add_one_height(&sandbox, &sandbox_state);
+    }
+    let block_time = |height| {
+        ExplorerApi::block(&state, BlockQuery::new(height))
+            .unwrap()
+            .time
+            .unwrap()
+    };
+
+    for height in 0..=5 {
+        let eta = block_eta(Height(height)).unwrap();
+        assert!(!eta.estimated);
+        assert_eq!(eta.time, block_time(Height(height)));
+    }
+
+    let interval = block_time(Height(5)) - block_time(Height(4));
+    let tolerance = chrono::Duration::milliseconds(interval.num_milliseconds() / 10 + 1);
+    for remaining in &[1, 10, 100] {
+        let eta = block_eta(Height(5 + remaining)).unwrap();
+        assert!(eta.estimated);
+        assert_eq!(eta.height, Height(5 + remaining));
+        let expected = block_time(Height(5)) + interval * *remaining as i32;
+        let deviation = (eta.time - expected).num_milliseconds().abs();
+        assert!(
+            deviation <= tolerance.num_milliseconds() * *remaining as i64,
+            "ETA {} deviates from {} by {} ms",
+            eta.time,
+            expected,
+            deviation
+        );
+    }
+
+    assert!(block_eta(Height(u64::max_value())).is_err());
+}
+
+/// Metric sink recording the metrics reported from the thread it was created on,
+/// so that the metrics of the concurrently running tests are ignored.
+#[derive(Debug)]
+struct MockMetricSink {
+    thread: thread::ThreadId,
+    metrics: Mutex<Vec<(String, i64)>>,
+}
+
+impl MetricSink for MockMetricSink {
+    fn record(&self, name: &str, value: i64, _time: DateTime<Utc>) {
+        if thread::current().id() == self.thread {
+            self.metrics.lock().unwrap().push((name.to_owned(), value));
+        }
+    }
+}
+
+/// Checks that the metrics of the committed block are passed to the registered metric sinks.
+#[test]
+fn test_metric_sink_on_commit() {
+    let sandbox = timestamping_sandbox();
+    let sandbox_state = SandboxState::new();
+    let sink = Arc::new(MockMetricSink {
+        thread: thread::current().id(),
+        metrics: Mutex::new(Vec::new()),
+    });
+    let registration = metrics::add_metric_sink(sink.clone());
+    add_one_height(&sandbox, &sandbox_state);
+    drop(registration);
+
+    let metrics = sink.metrics.lock().unwrap();
+    let expected = vec![
+        ("node.mempool".to_owned(), 0),
+        ("node.height".to_owned(), sandbox.current_height().0 as i64),
+        ("node.block.transactions".to_owned(), 1),
+    ];
+    for metric in &expected {
+        assert!(metrics.contains(metric), "Metric {:?} not recorded", metric);
+    }
+}
+
+/// Block publisher passing the published blocks to a channel.
+struct MockBlockPublisher {
+    sender: mpsc::Sender<PublishedBlock>,
+}
+
+impl BlockPublisher for MockBlockPublisher {
+    fn publish(&mut self, block: &PublishedBlock) -> Result<(), failure::Error> {
+        self.sender.send(block.clone())?;
+        Ok(())
+    }
+}
+
+/// Checks that the committed block is published exactly once with its transactions.
+#[test]
+fn test_block_publisher_on_commit() {
+    let sandbox = timestamping_sandbox();
+    let sandbox_state = SandboxState::new();
+    let (sender, receiver) = mpsc::channel();
+    sandbox
+        .node_handler_mut()
+        .set_block_publisher(Some(Box::new(MockBlockPublisher { sender })))
+        .unwrap();
+    add_one_height(&sandbox, &sandbox_state);
+
+    let published = receiver.recv_timeout(Duration::from_secs(10)).unwrap();
+    assert_eq!(published.block.height(), Height(1));
+    assert_eq!(published.block.hash(), sandbox.last_hash());
+    assert_eq!(
+        published.transactions,
+        *sandbox_state.committed_transaction_hashes.borrow()
+    );
+    assert!(receiver.recv_timeout(Duration::from_millis(100)).is_err());
+}
+
+/// Checks that the transaction bodies of the blocks outside of the pruning window are removed
+/// after commits, while the block headers and transaction hashes remain available.
+#[test]
+fn test_prune_blocks() {
+    let sandbox = timestamping_sandbox();
+    let sandbox_state = SandboxState::new();
+    sandbox.node_handler_mut().set_prune_blocks_before(Some(2));
+    let state = ServiceApiState::new(sandbox.blockchain_ref().clone());
+    let oldest_available = || ExplorerApi::oldest_available(&state, ()).unwrap().height;
+    let cache = TransactionCache::new(10);
+
+    let mut committed_txs = Vec::new();
+    for _ in 0..4 {
+        add_one_height(&sandbox, &sandbox_state);
+        let tx_hash = sandbox_state.committed_transaction_hashes.borrow()[0];
+        committed_txs.push(tx_hash);
+        let expected = sandbox.current_height().0.saturating_sub(2);
+        assert_eq!(oldest_available(), Height(expected));
+        ExplorerApi::cached_transaction_info(&cache, &state, TransactionQuery::new(tx_hash))
+            .unwrap();
+    }
+    assert_eq!(oldest_available(), Height(3));
+    assert_eq!(cache.len(), 4);
+
+    let snapshot = sandbox.blockchain_ref().snapshot();
+    let schema = Schema::new(&snapshot);
+    let explorer = BlockchainExplorer::new(sandbox.blockchain_ref());
+    for (i, tx_hash) in committed_txs.iter().enumerate() {
+        let height = Height(i as u64 + 1);
+        let pruned = height < Height(3);
+        assert_eq!(schema.transactions().contains(tx_hash), !pruned);
+        assert!(schema.transactions_locations().contains(tx_hash));
+
+        let block = explorer.block(height).unwrap();
+        assert_eq!(block.is_pruned(), pruned);
+        assert_eq!(&*block.transaction_hashes(), &[*tx_hash]);
+        assert_eq!(block.transaction(0).is_some(), !pruned);
+        assert_eq!(block.with_transactions().is_some(), !pruned);
+        assert_eq!(explorer.block_with_txs(height).is_some(), !pruned);
+
+        let info = ExplorerApi::block(&state, BlockQuery::new(height).with_tx_bodies()).unwrap();
+        assert_eq!(info.header_only, pruned);
+        assert_eq!(info.block, *explorer.block(height).unwrap().header());
+        assert_eq!(info.tx_bodies.is_some(), !pruned);
+
+        // Pruned transactions are still reported as committed.
+        let query = TransactionStatusesQuery {
+            hashes: vec![*tx_hash],
+        };
+        let statuses = ExplorerApi::transaction_statuses(&state, query).unwrap();
+        assert_eq!(statuses[tx_hash], TransactionStatus::Committed { height });
+
+        let proof =
+            ExplorerApi::transaction_proof(&state, TransactionQuery::new(*tx_hash)).unwrap();
+        assert_eq!(proof.height, height);
+        assert_eq!(proof.transaction.is_some(), !pruned);
+
+        // Cached transactions from the pruned blocks are not served either.
+        for result in &[
+            ExplorerApi::transaction_info(&state, TransactionQuery::new(*tx_hash)),
+            ExplorerApi::cached_transaction_info(&cache, &state, TransactionQuery::new(*tx_hash)),
+        ] {
+            match result {
+                Ok(info) => assert!(!pruned && info.is_committed()),
+                Err(ApiError::NotFound(description)) => {
+                    assert!(pruned && description.contains("pruned"))
+                }
+                Err(e) => panic!("Unexpected error: {}", e),
+            }
+        }
+    }
+    assert_eq!(cache.len(), 2);
+}
+
+/// Checks that the `v1/block/votes` endpoint reports the precommits of the block
+/// committed after round changes.
+#[test]
+fn test_block_votes() {
+    let sandbox = timestamping_sandbox();
+    let sandbox_state = SandboxState::new();
+    // Validator 0 becomes the leader at round 3 for height 1.
+    add_one_height(&sandbox, &sandbox_state);
+
+    let state = ServiceApiState::new(sandbox.blockchain_ref().clone());
+    let votes = ExplorerApi::block_votes(&state, BlockVotesQuery { height: Height(1) }).unwrap();
+    assert_eq!(
+        votes.len(),
+        sandbox.majority_count(sandbox.validators().len())
+    );
+    for (i, vote) in votes.iter().enumerate() {
+        let validator = ValidatorId(i as u16);
+        assert_eq!(vote.validator, validator);
+        assert_eq!(vote.author, sandbox.public_key(validator));
+        assert_eq!(vote.round, Round(3));
+    }
+
+    let snapshot = sandbox.blockchain_ref().snapshot();
+    let schema = Schema::new(&snapshot);
+    let block_hash = schema.block_hash_by_height(Height(1)).unwrap();
+    let mut expected_times: Vec<_> = schema
+        .precommits(&block_hash)
+        .iter()
+        .map(|precommit| precommit.time())
+        .collect();
+    let mut times: Vec<_> = votes.iter().map(|vote| vote.time).collect();
+    expected_times.sort();
+    times.sort();
+    assert_eq!(times, expected_times);
+
+    assert!(ExplorerApi::block_votes(&state, BlockVotesQuery { height: Height(5) }).is_err());
+}
+
+/// Checks that the `v1/blocks/state_hash` endpoint returns the state hashes recorded
+/// in the headers of the committed blocks.
+#[test]
+fn test_block_state_hash() {
+    let sandbox = timestamping_sandbox();
+    let sandbox_state = SandboxState::new();
+    for _ in 0..2 {
+        add_one_height(&sandbox, &sandbox_state);
+    }
+
+    let state = ServiceApiState::new(sandbox.blockchain_ref().clone());
+    let explorer = BlockchainExplorer::new(sandbox.blockchain_ref());
+    for height in 0..=2 {
+        let height = Height(height);
+        let response =
+            ExplorerApi::block_state_hash(&state, BlockStateHashQuery { height }).unwrap();
+        assert_eq!(response.height, height);
+        assert_eq!(
+            response.state_hash,
+            *explorer.block(height).unwrap().header().state_hash()
+        );
+    }
+
+    let query = BlockStateHashQuery { height: Height(3) };
+    assert!(ExplorerApi::block_state_hash(&state, query).is_err());
+}
+
+/// Checks that the `v1/block/raw` endpoint returns the block bytes hashing to the block hash
+/// referenced by the precommits.
+#[test]
+fn test_raw_block() {
+    let sandbox = timestamping_sandbox();
+    let sandbox_state = SandboxState::new();
+    add_one_height(&sandbox, &sandbox_state);
+
+    let state = ServiceApiState::new(sandbox.blockchain_ref().clone());
+    let explorer = BlockchainExplorer::new(sandbox.blockchain_ref());
+    let block = explorer.block(Height(1)).unwrap();
+    let bytes = ExplorerApi::raw_block(&state, RawBlockQuery { height: Height(1) }).unwrap();
+    assert_eq!(crypto::hash(&bytes), block.header().hash());
+    for precommit in block.precommits() {
+        assert_eq!(crypto::hash(&bytes), *precommit.block_hash());
+    }
+
+    let query = RawBlockQuery { height: Height(2) };
+    assert!(ExplorerApi::raw_block(&state, query).is_err());
+}
+
+/// Idea of the test is to check that the API reports the heights of the known blocks
+/// and does not find the unknown ones.
+#[test]
+fn test_block_exists() {
+    let sandbox = timestamping_sandbox();
+    let sandbox_state = SandboxState::new();
+    add_one_height(&sandbox, &sandbox_state);
+
+    let state = ServiceApiState::new(sandbox.blockchain_ref().clone());
+    let hash = sandbox.last_hash();
+    let response = ExplorerApi::block_exists(&state, BlockExistsQuery { hash }).unwrap();
+    assert_eq!(
+        response,
+        BlockExists {
+            exists: true,
+            height: Some(Height(1)),
+        }
+    );
+
+    let query = BlockExistsQuery {
+        hash: crypto::hash(&[1, 2, 3]),
+    };
+    let response = ExplorerApi::block_exists(&state, query).unwrap();
+    assert_eq!(
+        response,
+        BlockExists {
+            exists: false,
+            height: None,
+        }
+    );
+}
+
+/// Idea of the test is to check that the API reports the start times and timeouts
+/// of the upcoming rounds calculated by the node.
+#[test]
+fn test_round_schedule() {
+    let sandbox = timestamping_sandbox();
+    let handler = sandbox.node_handler_mut();
+    handler.handle_update_api_state_timeout();
+
+    let schedule = handler.api_state().round_schedule().unwrap();
+    assert_eq!(schedule.height, Height(1));
+    assert_eq!(schedule.rounds.len(), ROUND_SCHEDULE_LENGTH);
+    for (timing, round) in schedule.rounds.iter().zip(Round(1).iter_to(Round(6))) {
+        assert_eq!(timing.round, round);
+        assert_eq!(
+            timing.start_time,
+            DateTime::<Utc>::from(handler.round_start_time(round))
+        );
+        let next_start_time = DateTime::<Utc>::from(handler.round_start_time(round.next()));
+        assert_eq!(
+            (next_start_time - timing.start_time).num_milliseconds() as u64,
+            timing.timeout
+        );
+    }
+}
@@ -943,3 +943,72 @@ fn transactions_request_to_multiple_nodes() {
         sandbox.secret_key(ValidatorId(0)),
     ));
 }
+
+/// - the node with API warmup enabled is not ready until it catches up with its peers
+#[test]
+fn api_not_ready_until_caught_up() {
+    let sandbox = timestamping_sandbox();
+    sandbox
+        .node_handler_mut()
+        .set_api_warmup_timeout(Some(Duration::from_secs(60)));
+    let api_state = sandbox.node_handler_mut().api_state().clone();
+    assert!(!api_state.is_ready());
+
+    let tx = gen_timestamping_tx();
+    let propose = ProposeBuilder::new(&sandbox).build();
+    let block = BlockBuilder::new(&sandbox)
+        .with_tx_hash(&compute_tx_hash(&[tx.clone()]))
+        .with_state_hash(&sandbox.compute_state_hash(&[tx.clone()]))
+        .build();
+    let precommits = (1..4).map(|validator| {
+        sandbox.create_precommit(
+            ValidatorId(validator),
+            Height(1),
+            Round(1),
+            &propose.hash(),
+            &block.hash(),
+            sandbox.time().into(),
+            sandbox.secret_key(ValidatorId(validator)),
+        )
+    });
+    let precommits = precommits.collect::<Vec<_>>();
+
+    sandbox.recv(&sandbox.create_status(
+        &sandbox.public_key(ValidatorId(3)),
+        Height(2),
+        &block.hash(),
+        sandbox.secret_key(ValidatorId(3)),
+    ));
+    assert!(!api_state.is_ready());
+
+    sandbox.add_time(Duration::from_millis(BLOCK_REQUEST_TIMEOUT));
+    sandbox.send(
+        sandbox.public_key(ValidatorId(3)),
+        &sandbox.create_block_request(
+            &sandbox.public_key(ValidatorId(0)),
+            &sandbox.public_key(ValidatorId(3)),
+            Height(1),
+            sandbox.secret_key(ValidatorId(0)),
+        ),
+    );
+    sandbox.recv(&tx);
+    assert!(!api_state.is_ready());
+
+    sandbox.recv(&sandbox.create_block_response(
+        &sandbox.public_key(ValidatorId(3)),
+        &sandbox.public_key(ValidatorId(0)),
+        block.clone(),
+        precommits,
+        &[tx.hash()],
+        sandbox.secret_key(ValidatorId(3)),
+    ));
+
+    sandbox.assert_state(Height(2), Round(1));
+    sandbox.broadcast(&sandbox.create_status(
+        &sandbox.public_key(ValidatorId(0)),
+        Height(2),
+        &block.hash(),
+        sandbox.secret_key(ValidatorId(0)),
+    ));
+    assert!(api_state.is_ready());
+}
@@ -15,12 +15,14 @@
 //! Tests in this module are designed to test ability of the node to recover
 //! state after restart/stop.
 
-use std::time::Duration;
+use std::{iter, time::Duration};
 
+use crate::blockchain::Schema;
 use crate::crypto::CryptoHash;
 use crate::helpers::{user_agent, Height, Round, ValidatorId};
+use crate::messages::Message;
 use crate::node;
-use crate::sandbox::{sandbox_tests_helper::*, timestamping_sandbox, SandboxBuilder};
+use crate::sandbox::{sandbox_tests_helper::*, timestamping_sandbox, Sandbox, SandboxBuilder};
 
 #[test]
 fn test_disable_and_enable() {
@@ -456,3 +458,57 @@ fn should_restore_peers_after_restart() {
     sandbox_restarted.recv(&peers_request);
     sandbox_restarted.send(public_key1, &connect_from_1);
 }
+
+/// Idea: with `consensus_cache_flush_interval` set, messages of other validators are
+/// persisted in batches, while the node's own messages are persisted before they are
+/// broadcast, so that they are recovered even if the node crashes before the next flush.
+#[test]
+fn test_consensus_cache_flush_interval() {
+    const FLUSH_INTERVAL: u64 = 100;
+
+    let sandbox = timestamping_sandbox();
+    sandbox
+        .node_handler_mut()
+        .set_consensus_cache_flush_interval(FLUSH_INTERVAL);
+    let cached_messages = |sandbox: &Sandbox| {
+        let snapshot = sandbox.blockchain_ref().snapshot();
+        let schema = Schema::new(&snapshot);
+        schema
+            .consensus_messages_cache()
+            .iter()
+            .collect::<Vec<Message>>()
+    };
+
+    let propose = ProposeBuilder::new(&sandbox).build();
+    let prevote = make_prevote_from_propose(&sandbox, &propose);
+    let peer_prevote = sandbox.create_prevote(
+        ValidatorId(1),
+        Height(1),
+        Round(1),
+        &propose.hash(),
+        NOT_LOCKED,
+        sandbox.secret_key(ValidatorId(1)),
+    );
+
+    // Messages of other validators are kept in memory until the flush interval passes.
+    sandbox
+        .node_handler_mut()
+        .save_messages(Round(1), iter::once(peer_prevote.clone().into()));
+    assert!(cached_messages(&sandbox).is_empty());
+
+    sandbox.add_time(Duration::from_millis(FLUSH_INTERVAL));
+    sandbox.node_handler_mut().flush_consensus_cache_if_due();
+    assert_eq!(cached_messages(&sandbox), vec![Message::from(peer_prevote)]);
+
+    // Our prevote is persisted before the broadcast, although the flush interval
+    // has not passed yet.
+    sandbox.recv(&propose);
+    sandbox.broadcast(&prevote);
+    let messages = cached_messages(&sandbox);
+    assert!(messages.contains(&Message::from(propose.clone())));
+    assert!(messages.contains(&Message::from(prevote.clone())));
+
+    // The node is restarted without flushing the cache and recovers its prevote.
+    let sandbox_restarted = sandbox.restart();
+    sandbox_restarted.broadcast(&prevote);
+}
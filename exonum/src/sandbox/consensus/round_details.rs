@@ -20,16 +20,25 @@
 use std::collections::HashSet;
 
 use bit_vec::BitVec;
+use chrono::{DateTime, Utc};
 
 use std::time::Duration;
 
+use crate::api::{
+    node::public::explorer::{BlockQuery, ExplorerApi},
+    ServiceApiState,
+};
+use crate::blockchain::PeerLatency;
 use crate::crypto::CryptoHash;
-use crate::helpers::{Height, Round, ValidatorId};
+use crate::helpers::{Height, Milliseconds, Round, ValidatorId};
 use crate::messages::{PrevotesRequest, ProtocolMessage, TransactionsRequest};
 use crate::node::state::{
     PREVOTES_REQUEST_TIMEOUT, PROPOSE_REQUEST_TIMEOUT, TRANSACTIONS_REQUEST_TIMEOUT,
 };
-use crate::sandbox::{self, compute_tx_hash, sandbox_tests_helper::*, timestamping_sandbox};
+use crate::sandbox::{
+    self, compute_tx_hash, sandbox_tests_helper::*, timestamping_sandbox,
+    timestamping_sandbox_builder,
+};
 
 /// check scenario:
 /// HANDLE FULL PROPOSE
@@ -99,6 +108,52 @@ fn request_propose_when_get_prevote() {
     sandbox.add_time(Duration::from_millis(0));
 }
 
+/// - record the round-trip time of the propose request
+#[test]
+fn request_propose_records_peer_latency() {
+    let sandbox = timestamping_sandbox();
+    let peer = sandbox.public_key(ValidatorId(2));
+    let propose = ProposeBuilder::new(&sandbox).build();
+
+    sandbox.recv(&sandbox.create_prevote(
+        ValidatorId(2),
+        Height(1),
+        Round(1),
+        &propose.hash(),
+        NOT_LOCKED,
+        sandbox.secret_key(ValidatorId(2)),
+    ));
+    sandbox.add_time(Duration::from_millis(PROPOSE_REQUEST_TIMEOUT));
+    sandbox.send(
+        peer,
+        &sandbox.create_propose_request(
+            &sandbox.public_key(ValidatorId(0)),
+            &peer,
+            Height(1),
+            &propose.hash(),
+            sandbox.secret_key(ValidatorId(0)),
+        ),
+    );
+
+    // The peer has not responded yet, so its latency is not measured.
+    let latencies = sandbox.node_handler_mut().api_state().peer_latencies();
+    assert_eq!(latencies.len(), 1);
+    assert_eq!(latencies[&peer].average, None);
+
+    sandbox.add_time(Duration::from_millis(30));
+    sandbox.recv(&propose);
+    sandbox.broadcast(&make_prevote_from_propose(&sandbox, &propose));
+
+    let latencies = sandbox.node_handler_mut().api_state().peer_latencies();
+    assert_eq!(
+        latencies[&peer],
+        PeerLatency {
+            average: Some(30),
+            samples: 1,
+        }
+    );
+}
+
 /// - request prevotes when get prevote message
 #[test]
 fn request_prevotes_when_get_prevote_message() {
@@ -231,6 +286,136 @@ fn lock_to_propose_when_get_2_3_prevote_positive() {
     sandbox.add_time(Duration::from_millis(0));
 }
 
+/// - prevote and lock to the propose with the lowest hash if several proposes
+///   for the round become full simultaneously
+#[test]
+fn lock_to_propose_with_lowest_hash() {
+    let sandbox = timestamping_sandbox();
+
+    let tx1 = gen_timestamping_tx();
+    let tx2 = gen_timestamping_tx();
+    let first_txs = vec![tx1.clone()];
+    let second_txs = vec![tx1.clone(), tx2.clone()];
+
+    let first_propose = ProposeBuilder::new(&sandbox)
+        .with_tx_hashes(&[tx1.hash()])
+        .build();
+    let second_propose = ProposeBuilder::new(&sandbox)
+        .with_tx_hashes(&[tx1.hash(), tx2.hash()])
+        .build();
+    let (propose, txs) = if first_propose.hash() < second_propose.hash() {
+        (&first_propose, &first_txs)
+    } else {
+        (&second_propose, &second_txs)
+    };
+    let block = BlockBuilder::new(&sandbox)
+        .with_txs_hashes(&txs.iter().map(|tx| tx.hash()).collect::<Vec<_>>())
+        .with_state_hash(&sandbox.compute_state_hash(txs))
+        .build();
+
+    // Both proposes wait for `tx1` and become full when it is received.
+    sandbox.recv(&first_propose);
+    sandbox.recv(&second_propose);
+    sandbox.recv(&tx2);
+    sandbox.recv(&tx1);
+
+    sandbox.broadcast(&sandbox.create_prevote(
+        ValidatorId(0),
+        Height(1),
+        Round(1),
+        &propose.hash(),
+        NOT_LOCKED,
+        sandbox.secret_key(ValidatorId(0)),
+    ));
+
+    sandbox.recv(&sandbox.create_prevote(
+        ValidatorId(1),
+        Height(1),
+        Round(1),
+        &propose.hash(),
+        NOT_LOCKED,
+        sandbox.secret_key(ValidatorId(1)),
+    ));
+    sandbox.recv(&sandbox.create_prevote(
+        ValidatorId(2),
+        Height(1),
+        Round(1),
+        &propose.hash(),
+        NOT_LOCKED,
+        sandbox.secret_key(ValidatorId(2)),
+    ));
+    sandbox.assert_lock(Round(1), Some(propose.hash()));
+
+    sandbox.broadcast(&sandbox.create_precommit(
+        ValidatorId(0),
+        Height(1),
+        Round(1),
+        &propose.hash(),
+        &block.hash(),
+        sandbox.time().into(),
+        sandbox.secret_key(ValidatorId(0)),
+    ));
+    sandbox.add_time(Duration::from_millis(0));
+}
+
+/// - clamp future-dated precommit times when reporting the block time
+#[test]
+fn block_time_with_future_precommits_is_clamped() {
+    const MAX_CLOCK_SKEW: Milliseconds = 1000;
+
+    let sandbox = timestamping_sandbox_builder()
+        .with_consensus(|config| config.max_clock_skew = Some(MAX_CLOCK_SKEW))
+        .build();
+
+    let propose = ProposeBuilder::new(&sandbox).build();
+    let block = BlockBuilder::new(&sandbox).build();
+
+    sandbox.recv(&propose);
+    sandbox.broadcast(&make_prevote_from_propose(&sandbox, &propose));
+    for validator in 1..3 {
+        sandbox.recv(&sandbox.create_prevote(
+            ValidatorId(validator),
+            Height(1),
+            Round(1),
+            &propose.hash(),
+            NOT_LOCKED,
+            sandbox.secret_key(ValidatorId(validator)),
+        ));
+    }
+    let our_time: DateTime<Utc> = sandbox.time().into();
+    sandbox.broadcast(&sandbox.create_precommit(
+        ValidatorId(0),
+        Height(1),
+        Round(1),
+        &propose.hash(),
+        &block.hash(),
+        our_time,
+        sandbox.secret_key(ValidatorId(0)),
+    ));
+
+    // Clocks of the other validators are a year ahead, so they define the median time.
+    let future_time = our_time + chrono::Duration::days(365);
+    for validator in 1..3 {
+        sandbox.recv(&sandbox.create_precommit(
+            ValidatorId(validator),
+            Height(1),
+            Round(1),
+            &propose.hash(),
+            &block.hash(),
+            future_time,
+            sandbox.secret_key(ValidatorId(validator)),
+        ));
+    }
+    sandbox.assert_state(Height(2), Round(1));
+
+    // The time is clamped against the precommits of the block rather than the current
+    // time, so it does not change between the queries.
+    let state = ServiceApiState::new(sandbox.blockchain_ref().clone());
+    let block_info = ExplorerApi::block(&state, BlockQuery::new(Height(1))).unwrap();
+    let skew = chrono::Duration::milliseconds(MAX_CLOCK_SKEW as i64);
+    assert_eq!(block_info.time, Some(our_time + skew));
+}
+
 /// idea: lock to propose from past round and observe broadcast Prevote
 /// LOCK
 /// - Send prevote
@@ -702,6 +887,81 @@ fn handle_precommit_positive_scenario_commit() {
     sandbox.add_time(Duration::from_millis(0));
 }
 
+/// idea of the scenario:
+/// - validators have weights 1, 1, 1 and 4, so the majority weight is 5
+/// - votes from 3 of 4 validators with the total weight 3 are not enough for a lock or a commit
+/// - the node locks and commits only after receiving votes from the heaviest validator
+#[test]
+fn commit_with_weighted_majority_of_precommits() {
+    let sandbox = timestamping_sandbox_builder()
+        .with_validator_weights(&[1, 1, 1, 4])
+        .build();
+    assert_eq!(sandbox.node_state().majority_weight(), 5);
+
+    let propose = ProposeBuilder::new(&sandbox).build();
+    let block = BlockBuilder::new(&sandbox).build();
+
+    sandbox.recv(&propose);
+    sandbox.broadcast(&make_prevote_from_propose(&sandbox, &propose));
+    for i in 1..3 {
+        sandbox.recv(&sandbox.create_prevote(
+            ValidatorId(i),
+            Height(1),
+            Round(1),
+            &propose.hash(),
+            NOT_LOCKED,
+            sandbox.secret_key(ValidatorId(i)),
+        ));
+    }
+    // 3 of 4 validators, but only 3 of 7 weight units.
+    sandbox.assert_lock(NOT_LOCKED, None);
+
+    sandbox.recv(&sandbox.create_prevote(
+        ValidatorId(3),
+        Height(1),
+        Round(1),
+        &propose.hash(),
+        NOT_LOCKED,
+        sandbox.secret_key(ValidatorId(3)),
+    ));
+    sandbox.assert_lock(Round(1), Some(propose.hash()));
+    sandbox.broadcast(&sandbox.create_precommit(
+        ValidatorId(0),
+        Height(1),
+        Round(1),
+        &propose.hash(),
+        &block.hash(),
+        sandbox.time().into(),
+        sandbox.secret_key(ValidatorId(0)),
+    ));
+
+    for i in 1..3 {
+        sandbox.recv(&sandbox.create_precommit(
+            ValidatorId(i),
+            Height(1),
+            Round(1),
+            &propose.hash(),
+            &block.hash(),
+            sandbox.time().into(),
+            sandbox.secret_key(ValidatorId(i)),
+        ));
+    }
+    sandbox.assert_state(Height(1), Round(1));
+
+    sandbox.recv(&sandbox.create_precommit(
+        ValidatorId(3),
+        Height(1),
+        Round(1),
+        &propose.hash(),
+        &block.hash(),
+        sandbox.time().into(),
+        sandbox.secret_key(ValidatorId(3)),
+    ));
+    sandbox.assert_state(Height(2), Round(1));
+    sandbox.check_broadcast_status(Height(2), &block.hash());
+    sandbox.add_time(Duration::from_millis(0));
+}
+
 /// LOCK
 /// - Send precommit during the lock   // covered in `lock_to_propose_when_get_2_3_prevote_positive`
 /// - if there aren't incompatible prevotes
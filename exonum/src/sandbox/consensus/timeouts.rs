@@ -16,10 +16,13 @@
 
 use std::time::Duration;
 
+use crate::blockchain::DEFAULT_VALIDATOR_WEIGHT;
 use crate::crypto::CryptoHash;
-use crate::helpers::{Height, Round, ValidatorId};
+use crate::helpers::{user_agent, Height, Round, ValidatorId};
 use crate::node::state::PROPOSE_REQUEST_TIMEOUT;
-use crate::sandbox::{compute_tx_hash, sandbox_tests_helper::*, timestamping_sandbox};
+use crate::sandbox::{
+    compute_tx_hash, sandbox_tests_helper::*, timestamping_sandbox, timestamping_sandbox_builder,
+};
 
 /// HANDLE ROUND TIMEOUT:
 /// - Ignore if height and round are not the same
@@ -156,6 +159,75 @@ fn test_send_propose_and_prevote_when_we_are_leader() {
     sandbox.add_time(Duration::from_millis(0));
 }
 
+/// Idea of the test is to verify that the node does not send `Propose` when it is a leader
+/// but fewer validators than `min_online_validators` are connected, and resumes proposing
+/// after the validators reconnect.
+#[test]
+fn test_no_propose_if_not_enough_validators_online() {
+    let sandbox = timestamping_sandbox_builder()
+        .with_consensus(|config| config.min_online_validators = Some(4))
+        .build();
+    let connect = sandbox.connect().unwrap().clone();
+    let offline_validator = ValidatorId(3);
+
+    // The node tries to reconnect to the disconnected validator.
+    sandbox.disconnect(sandbox.public_key(offline_validator));
+    sandbox.send(sandbox.public_key(offline_validator), &connect);
+
+    sandbox.add_time(Duration::from_millis(sandbox.current_round_timeout()));
+    sandbox.add_time(Duration::from_millis(
+        sandbox.current_round_timeout() + PROPOSE_TIMEOUT,
+    ));
+    sandbox.assert_state(Height(1), Round(3));
+    assert!(sandbox.is_leader());
+    // No `Propose` is broadcast.
+    sandbox.add_time(Duration::from_millis(0));
+
+    // The validator reconnects.
+    sandbox.recv(&sandbox.create_connect(
+        &sandbox.public_key(offline_validator),
+        sandbox.address(offline_validator),
+        sandbox.time().into(),
+        &user_agent::get(),
+        sandbox.secret_key(offline_validator),
+    ));
+    sandbox.send(sandbox.public_key(offline_validator), &connect);
+
+    for _ in 0..4 {
+        sandbox.add_time(Duration::from_millis(sandbox.current_round_timeout()));
+    }
+    sandbox.assert_state(Height(1), Round(7));
+    assert!(sandbox.is_leader());
+
+    let propose = ProposeBuilder::new(&sandbox).build();
+    sandbox.broadcast(&propose);
+    sandbox.broadcast(&make_prevote_from_propose(&sandbox, &propose));
+    sandbox.add_time(Duration::from_millis(0));
+}
+
+/// Idea of the test is to verify that the node does not send `Propose` when it is a leader
+/// but the connected validators have a smaller total weight than `min_online_weight`.
+#[test]
+fn test_no_propose_if_not_enough_weight_online() {
+    let sandbox = timestamping_sandbox_builder()
+        .with_consensus(|config| config.min_online_weight = Some(4 * DEFAULT_VALIDATOR_WEIGHT))
+        .build();
+    let connect = sandbox.connect().unwrap().clone();
+    let offline_validator = ValidatorId(3);
+
+    sandbox.disconnect(sandbox.public_key(offline_validator));
+    sandbox.send(sandbox.public_key(offline_validator), &connect);
+
+    sandbox.add_time(Duration::from_millis(sandbox.current_round_timeout()));
+    sandbox.add_time(Duration::from_millis(
+        sandbox.current_round_timeout() + PROPOSE_TIMEOUT,
+    ));
+    sandbox.assert_state(Height(1), Round(3));
+    assert!(sandbox.is_leader());
+    // No `Propose` is broadcast.
+    sandbox.add_time(Duration::from_millis(0));
+}
+
 /// HANDLE ROUND TIMEOUT:
 /// - send prevote if locked to propose
 /// idea:
@@ -284,3 +356,27 @@ fn test_round_timeout_increase() {
     sandbox.add_time(Duration::from_millis(1));
     sandbox.assert_state(Height(1), Round(5));
 }
+
+/// Check that the delay between the scheduled time of a timeout and its handling is recorded.
+#[test]
+fn test_round_timeout_drift() {
+    const DRIFT: u64 = 7;
+
+    let sandbox = timestamping_sandbox();
+    sandbox.add_time(Duration::from_millis(sandbox.first_round_timeout() - 1));
+    sandbox.assert_state(Height(1), Round(1));
+    let timeouts = sandbox
+        .node_handler_mut()
+        .api_state()
+        .timeout_drift()
+        .timeouts;
+
+    // The round timeout is handled `DRIFT` milliseconds after the time it was scheduled for.
+    sandbox.add_time(Duration::from_millis(1 + DRIFT));
+    sandbox.assert_state(Height(1), Round(2));
+
+    let drift = sandbox.node_handler_mut().api_state().timeout_drift();
+    assert_eq!(drift.timeouts, timeouts + 1);
+    assert_eq!(drift.last, DRIFT * 1000);
+    assert!(drift.max >= DRIFT * 1000);
+}
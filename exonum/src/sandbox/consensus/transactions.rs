@@ -15,18 +15,34 @@
 //! Tests in this module are designed to test details of transaction handling.
 
 use bit_vec::BitVec;
+use chrono::{DateTime, Utc};
+use exonum_merkledb::Snapshot;
 
-use std::time::Duration;
+use std::{
+    collections::{BTreeMap, HashSet},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
 
+use crate::api::{
+    node::public::explorer::{
+        ExplorerApi, PoolByServiceCache, PoolDeltaQuery, POOL_BY_SERVICE_CACHE_TIME,
+    },
+    ServiceApiState,
+};
+use crate::blockchain::{PoolDelta, Schema};
 use crate::crypto::{gen_keypair, CryptoHash, Hash};
-use crate::helpers::{Height, Milliseconds, Round, ValidatorId};
-use crate::messages::{RawTransaction, Signed};
-use crate::node::state::TRANSACTIONS_REQUEST_TIMEOUT;
+use crate::helpers::{user_agent, Height, Milliseconds, Round, ValidatorId};
+use crate::messages::{Message, RawTransaction, Signed};
+use crate::node::{
+    state::TRANSACTIONS_REQUEST_TIMEOUT, BroadcastTarget, ConnectInfo, MempoolStrategy,
+};
 use crate::sandbox::{
     compute_tx_hash,
-    config_updater::TxConfig,
+    config_updater::{TxConfig, CONFIG_SERVICE},
+    gen_primitive_socket_addr,
     sandbox_tests_helper::*,
-    timestamping::{TimestampingTxGenerator, DATA_SIZE},
+    timestamping::{TimestampingTxGenerator, DATA_SIZE, TIMESTAMPING_SERVICE},
     timestamping_sandbox, timestamping_sandbox_builder, Sandbox,
 };
 
@@ -260,6 +276,193 @@ fn rebroadcast_transactions() {
     }
 }
 
+#[test]
+fn broadcast_transactions_to_validators_only() {
+    let sandbox = timestamping_sandbox();
+
+    // Connect an auditor, which is not a validator.
+    let (auditor, auditor_secret_key) = gen_keypair();
+    let auditor_address = gen_primitive_socket_addr(10).to_string();
+    sandbox
+        .node_handler_mut()
+        .state
+        .add_peer_to_connect_list(ConnectInfo {
+            address: auditor_address.clone(),
+            public_key: auditor,
+        });
+    sandbox.recv(&sandbox.create_connect(
+        &auditor,
+        auditor_address,
+        sandbox.time().into(),
+        &user_agent::get(),
+        &auditor_secret_key,
+    ));
+    sandbox.send(
+        auditor,
+        &sandbox.create_connect(
+            &sandbox.public_key(ValidatorId(0)),
+            sandbox.address(ValidatorId(0)),
+            sandbox.time().into(),
+            &user_agent::get(),
+            sandbox.secret_key(ValidatorId(0)),
+        ),
+    );
+
+    let validators = sandbox.validators()[1..].to_vec();
+    let mut all_peers = validators.clone();
+    all_peers.push(auditor);
+
+    let mut transactions: Vec<_> = TimestampingTxGenerator::new(DATA_SIZE).take(2).collect();
+    transactions.sort_by(|tx1, tx2| tx1.hash().cmp(&tx2.hash()));
+
+    sandbox
+        .node_handler_mut()
+        .handle_incoming_tx(transactions[0].clone());
+    sandbox.broadcast_to_addrs(&transactions[0], &all_peers);
+
+    sandbox
+        .node_handler_mut()
+        .set_broadcast_target(BroadcastTarget::ValidatorsOnly);
+    sandbox
+        .node_handler_mut()
+        .handle_incoming_tx(transactions[1].clone());
+    sandbox.broadcast_to_addrs(&transactions[1], &validators);
+
+    // Rebroadcasting is limited to validators as well.
+    sandbox.recv_rebroadcast();
+    for tx in &transactions {
+        sandbox.broadcast_to_addrs(tx, &validators);
+    }
+}
+
+#[test]
+fn broadcast_transactions_in_batches() {
+    let sandbox = timestamping_sandbox();
+    sandbox
+        .node_handler_mut()
+        .set_tx_broadcast_batch_interval(100);
+
+    let transactions: Vec<_> = TimestampingTxGenerator::new(DATA_SIZE).take(3).collect();
+    for tx in &transactions {
+        sandbox.node_handler_mut().handle_incoming_tx(tx.clone());
+    }
+
+    // Transactions are not broadcast until the batch interval elapses.
+    sandbox.add_time(Duration::from_millis(99));
+    sandbox.add_time(Duration::from_millis(1));
+
+    // Each peer receives a single message containing all the transactions.
+    let mut peers: HashSet<_> = sandbox.validators()[1..].iter().cloned().collect();
+    sandbox.process_events();
+    while let Some((peer, message)) = sandbox.pop_sent_message() {
+        let expected = sandbox.create_transactions_response(
+            &sandbox.public_key(ValidatorId(0)),
+            &peer,
+            transactions.clone(),
+            sandbox.secret_key(ValidatorId(0)),
+        );
+        assert_eq!(message, Message::from(expected));
+        assert!(peers.remove(&peer), "Unexpected recipient {}", peer);
+    }
+    assert!(peers.is_empty());
+}
+
+#[test]
+fn reject_transactions_of_not_accepted_services() {
+    let sandbox = timestamping_sandbox();
+    sandbox
+        .node_handler_mut()
+        .set_accepted_services(Some(vec![TIMESTAMPING_SERVICE + 1].into_iter().collect()));
+
+    let mut tx_gen = TimestampingTxGenerator::new(DATA_SIZE);
+    let tx1 = tx_gen.next().unwrap();
+    let tx2 = tx_gen.next().unwrap();
+
+    // The transaction is neither added into the pool nor broadcast.
+    sandbox.node_handler_mut().handle_incoming_tx(tx1.clone());
+    sandbox.recv(&tx1);
+    assert!(sandbox.transactions_hashes().is_empty());
+
+    // Transactions included into a propose are accepted regardless of the service.
+    let propose = ProposeBuilder::new(&sandbox)
+        .with_tx_hashes(&[tx2.hash()])
+        .build();
+    sandbox.recv(&propose);
+    sandbox.add_time(Duration::from_millis(TRANSACTIONS_REQUEST_TIMEOUT));
+    sandbox.send(
+        sandbox.public_key(ValidatorId(2)),
+        &sandbox.create_transactions_request(
+            &sandbox.public_key(ValidatorId(0)),
+            &sandbox.public_key(ValidatorId(2)),
+            &[tx2.hash()],
+            &sandbox.secret_key(ValidatorId(0)),
+        ),
+    );
+    sandbox.recv(&tx2);
+    sandbox.broadcast(&make_prevote_from_propose(&sandbox, &propose));
+    assert_eq!(sandbox.transactions_hashes(), vec![tx2.hash()]);
+}
+
+#[test]
+fn record_rejected_transactions() {
+    let sandbox = timestamping_sandbox();
+    sandbox
+        .node_handler_mut()
+        .set_accepted_services(Some(vec![TIMESTAMPING_SERVICE + 1].into_iter().collect()));
+
+    let tx = gen_timestamping_tx();
+    sandbox.node_handler_mut().handle_incoming_tx(tx.clone());
+
+    let rejected = sandbox
+        .node_handler_mut()
+        .api_state()
+        .rejected_transactions();
+    assert_eq!(rejected.len(), 1);
+    assert_eq!(rejected[0].tx_hash, tx.hash());
+    assert_eq!(
+        rejected[0].reason,
+        format!(
+            "Received transaction of service {} which is not accepted by the node, hash {:?}",
+            TIMESTAMPING_SERVICE,
+            tx.hash()
+        )
+    );
+    assert_eq!(rejected[0].time, DateTime::<Utc>::from(sandbox.time()));
+}
+
+#[test]
+fn reject_transactions_of_disabled_service() {
+    let sandbox = timestamping_sandbox();
+    let api_sender = sandbox.blockchain_ref().api_sender.clone();
+    api_sender
+        .set_service_enabled(TIMESTAMPING_SERVICE, false)
+        .unwrap();
+    sandbox.process_events();
+
+    let tx = TimestampingTxGenerator::new(DATA_SIZE).next().unwrap();
+    let config_tx = TxConfig::create_signed(
+        &sandbox.public_key(ValidatorId(0)),
+        &[],
+        sandbox.current_height().next(),
+        sandbox.secret_key(ValidatorId(0)),
+    );
+
+    // Transactions of the disabled service are rejected, while the other services
+    // are not affected.
+    sandbox.recv(&tx);
+    sandbox.recv(&config_tx);
+    assert_eq!(sandbox.transactions_hashes(), vec![config_tx.hash()]);
+
+    api_sender
+        .set_service_enabled(TIMESTAMPING_SERVICE, true)
+        .unwrap();
+    sandbox.process_events();
+    sandbox.recv(&tx);
+    let mut expected_hashes = vec![tx.hash(), config_tx.hash()];
+    expected_hashes.sort();
+    assert_eq!(sandbox.transactions_hashes(), expected_hashes);
+}
+
 // TODO: transaction verification logic is duplicated,
 // in sandbox so this test is testing sandbox
 #[test]
@@ -728,3 +931,291 @@ fn expedited_propose_on_transaction_pressure() {
     sandbox.broadcast(&propose);
     sandbox.broadcast(&make_prevote_from_propose(&sandbox, &propose));
 }
+
+/// Selects transactions in the descending order of their hashes.
+#[derive(Debug)]
+struct ReverseHashOrder;
+
+impl MempoolStrategy for ReverseHashOrder {
+    fn select(&self, schema: &Schema<&dyn Snapshot>, limit: usize) -> Vec<Hash> {
+        let mut hashes = schema.transactions_pool().iter().collect::<Vec<_>>();
+        hashes.reverse();
+        hashes.truncate(limit);
+        hashes
+    }
+}
+
+#[test]
+fn propose_with_custom_mempool_strategy() {
+    let sandbox = timestamping_sandbox_with_threshold();
+    sandbox
+        .node_handler_mut()
+        .set_mempool_strategy(Box::new(ReverseHashOrder));
+
+    let transactions = TimestampingTxGenerator::new(64)
+        .take(PROPOSE_THRESHOLD as usize - 1)
+        .collect::<Vec<_>>();
+
+    for tx in &transactions {
+        sandbox.recv(tx);
+    }
+
+    sandbox.add_time(Duration::from_millis(MAX_PROPOSE_TIMEOUT));
+
+    let mut hashes = tx_hashes(&transactions);
+    hashes.reverse();
+    let propose = ProposeBuilder::new(&sandbox)
+        .with_tx_hashes(&hashes)
+        .build();
+
+    sandbox.broadcast(&propose);
+    sandbox.broadcast(&make_prevote_from_propose(&sandbox, &propose));
+}
+
+#[test]
+fn propose_with_max_block_bytes() {
+    let transactions = TimestampingTxGenerator::new(DATA_SIZE)
+        .take(PROPOSE_THRESHOLD as usize - 1)
+        .collect::<Vec<_>>();
+    let tx_len = transactions[0].signed_message().raw().len() as u32;
+
+    let sandbox = timestamping_sandbox_builder()
+        .with_consensus(|config| {
+            config.max_propose_timeout = MAX_PROPOSE_TIMEOUT;
+            config.min_propose_timeout = MIN_PROPOSE_TIMEOUT;
+            config.propose_timeout_threshold = PROPOSE_THRESHOLD;
+            config.max_block_bytes = Some(tx_len * 3 / 2);
+        })
+        .build();
+    // Wait for us to become the leader.
+    sandbox.add_time(Duration::from_millis(sandbox.current_round_timeout()));
+    sandbox.add_time(Duration::from_millis(sandbox.current_round_timeout()));
+
+    for tx in &transactions {
+        sandbox.recv(tx);
+    }
+    sandbox.add_time(Duration::from_millis(MAX_PROPOSE_TIMEOUT));
+
+    // Only one transaction fits into the block, although `txs_block_limit` allows more.
+    let hashes = tx_hashes(&transactions);
+    let propose = ProposeBuilder::new(&sandbox)
+        .with_tx_hashes(&hashes[..1])
+        .build();
+
+    sandbox.broadcast(&propose);
+    sandbox.broadcast(&make_prevote_from_propose(&sandbox, &propose));
+}
+
+/// A transaction larger than `max_block_bytes` is skipped, and the transactions
+/// following it still get into the propose.
+#[test]
+fn propose_with_max_block_bytes_skips_oversized_transactions() {
+    let transactions = TimestampingTxGenerator::new(DATA_SIZE)
+        .take(2)
+        .collect::<Vec<_>>();
+    let oversized_tx = TimestampingTxGenerator::new(DATA_SIZE * 10).next().unwrap();
+    let tx_len = transactions[0].signed_message().raw().len() as u32;
+
+    let sandbox = timestamping_sandbox_builder()
+        .with_consensus(|config| {
+            config.max_propose_timeout = MAX_PROPOSE_TIMEOUT;
+            config.min_propose_timeout = MIN_PROPOSE_TIMEOUT;
+            config.propose_timeout_threshold = PROPOSE_THRESHOLD;
+            config.max_block_bytes = Some(tx_len * 5 / 2);
+        })
+        .build();
+    // Wait for us to become the leader.
+    sandbox.add_time(Duration::from_millis(sandbox.current_round_timeout()));
+    sandbox.add_time(Duration::from_millis(sandbox.current_round_timeout()));
+
+    sandbox.recv(&oversized_tx);
+    for tx in &transactions {
+        sandbox.recv(tx);
+    }
+    sandbox.add_time(Duration::from_millis(MAX_PROPOSE_TIMEOUT));
+
+    // Both small transactions fit into the block wherever the oversized one is in the pool.
+    let propose = ProposeBuilder::new(&sandbox)
+        .with_tx_hashes(&tx_hashes(&transactions))
+        .build();
+
+    sandbox.broadcast(&propose);
+    sandbox.broadcast(&make_prevote_from_propose(&sandbox, &propose));
+}
+
+/// A propose with more transactions than `max_propose_txs` is rejected and is not sent
+/// to the peers requesting it, although all its transactions are known.
+#[test]
+fn ignore_propose_exceeding_max_propose_txs() {
+    let sandbox = timestamping_sandbox_builder()
+        .with_consensus(|config| {
+            config.max_propose_txs = Some(2);
+        })
+        .build();
+
+    let transactions = TimestampingTxGenerator::new(DATA_SIZE)
+        .take(3)
+        .collect::<Vec<_>>();
+    for tx in &transactions {
+        sandbox.recv(tx);
+    }
+
+    // Without `max_propose_txs` a prevote would have been broadcast.
+    let propose = ProposeBuilder::new(&sandbox)
+        .with_tx_hashes(&tx_hashes(&transactions))
+        .build();
+    sandbox.recv(&propose);
+
+    sandbox.recv(&sandbox.create_propose_request(
+        &sandbox.public_key(ValidatorId(3)),
+        &sandbox.public_key(ValidatorId(0)),
+        Height(1),
+        &propose.hash(),
+        sandbox.secret_key(ValidatorId(3)),
+    ));
+}
+
+/// With `max_blocks_per_second` set, the proposer does not propose earlier than the minimal
+/// block interval after the previous block, even if the pool is full.
+#[test]
+fn propose_with_max_blocks_per_second() {
+    const MIN_BLOCK_INTERVAL: Milliseconds = 500;
+
+    let sandbox = timestamping_sandbox_builder()
+        .with_consensus(|config| {
+            config.max_propose_timeout = MAX_PROPOSE_TIMEOUT;
+            config.min_propose_timeout = MIN_PROPOSE_TIMEOUT;
+            config.propose_timeout_threshold = PROPOSE_THRESHOLD;
+            config.max_blocks_per_second = Some(1000.0 / MIN_BLOCK_INTERVAL as f64);
+        })
+        .build();
+    let sandbox_state = SandboxState::new();
+    add_one_height(&sandbox, &sandbox_state);
+    add_one_height(&sandbox, &sandbox_state);
+
+    // We are the leader in the first round of the new height.
+    sandbox.assert_state(Height(3), Round(1));
+    assert!(sandbox.is_leader());
+    let block_time = sandbox.time();
+
+    let transactions = TimestampingTxGenerator::new(DATA_SIZE)
+        .take(PROPOSE_THRESHOLD as usize)
+        .collect::<Vec<_>>();
+    for tx in &transactions {
+        sandbox.recv(tx);
+    }
+
+    // The pool is full, but neither the expedited nor the regular propose timeout
+    // triggers the proposal before the minimal block interval elapses.
+    sandbox.add_time(Duration::from_millis(MIN_PROPOSE_TIMEOUT));
+    sandbox.add_time(Duration::from_millis(
+        MIN_BLOCK_INTERVAL - MIN_PROPOSE_TIMEOUT - 1,
+    ));
+    sandbox.add_time(Duration::from_millis(1));
+    assert_eq!(
+        sandbox.time().duration_since(block_time).unwrap(),
+        Duration::from_millis(MIN_BLOCK_INTERVAL)
+    );
+
+    let propose = ProposeBuilder::new(&sandbox)
+        .with_tx_hashes(&tx_hashes(&transactions))
+        .build();
+    sandbox.broadcast(&propose);
+    sandbox.broadcast(&make_prevote_from_propose(&sandbox, &propose));
+}
+
+#[test]
+fn transactions_pool_by_service() {
+    let sandbox = timestamping_sandbox();
+    let state = ServiceApiState::new(sandbox.blockchain_ref().clone());
+    let mut tx_gen = TimestampingTxGenerator::new(DATA_SIZE);
+    let config_tx = TxConfig::create_signed(
+        &sandbox.public_key(ValidatorId(0)),
+        &[],
+        sandbox.current_height().next(),
+        sandbox.secret_key(ValidatorId(0)),
+    );
+    sandbox.recv(&tx_gen.next().unwrap());
+    sandbox.recv(&tx_gen.next().unwrap());
+    sandbox.recv(&config_tx);
+
+    let now = Arc::new(Mutex::new(Instant::now()));
+    let clock = Arc::clone(&now);
+    let cache = PoolByServiceCache::with_clock(move || *clock.lock().unwrap());
+    let breakdown = ExplorerApi::transactions_pool_by_service(&cache, &state, ()).unwrap();
+    let mut expected = BTreeMap::new();
+    expected.insert(CONFIG_SERVICE, 1);
+    expected.insert(TIMESTAMPING_SERVICE, 2);
+    assert_eq!(breakdown, expected);
+
+    // The pool is not rescanned while the cached breakdown is fresh.
+    sandbox.recv(&tx_gen.next().unwrap());
+    *now.lock().unwrap() += Duration::from_millis(POOL_BY_SERVICE_CACHE_TIME - 1);
+    let breakdown = ExplorerApi::transactions_pool_by_service(&cache, &state, ()).unwrap();
+    assert_eq!(breakdown, expected);
+
+    *now.lock().unwrap() += Duration::from_millis(1);
+    let breakdown = ExplorerApi::transactions_pool_by_service(&cache, &state, ()).unwrap();
+    expected.insert(TIMESTAMPING_SERVICE, 3);
+    assert_eq!(breakdown, expected);
+}
+
+#[test]
+fn transactions_pool_delta() {
+    let sandbox = timestamping_sandbox();
+    let node_state = sandbox.node_handler_mut().api_state().clone();
+    let mut tx_gen = TimestampingTxGenerator::new(DATA_SIZE);
+    let (tx1, tx2) = (tx_gen.next().unwrap(), tx_gen.next().unwrap());
+    let epoch = node_state.pool_delta(None, 0).epoch;
+
+    // `tx1` is added into the pool and then committed.
+    add_one_height_with_transactions(&sandbox, &SandboxState::new(), &[tx1.clone()]);
+    sandbox.recv(&tx2);
+
+    let query = PoolDeltaQuery {
+        since: 1,
+        epoch: Some(epoch),
+    };
+    let delta = ExplorerApi::pool_delta(&node_state, query);
+    assert_eq!(
+        delta,
+        PoolDelta {
+            added: vec![tx2.hash()],
+            removed: vec![tx1.hash()],
+            token: 3,
+            epoch,
+            resync_required: false,
+        }
+    );
+
+    // `tx1` was added and removed after the initial revision, so it is not reported.
+    let delta = node_state.pool_delta(None, 0);
+    assert_eq!(delta.added, vec![tx2.hash()]);
+    assert!(delta.removed.is_empty());
+    assert_eq!(delta.token, 3);
+
+    let delta = node_state.pool_delta(Some(epoch), 3);
+    assert!(delta.added.is_empty() && delta.removed.is_empty());
+    assert!(!delta.resync_required);
+
+    // Revisions from the future require resynchronization.
+    assert!(node_state.pool_delta(Some(epoch), 4).resync_required);
+    // So do revisions of another epoch, e.g., issued before a restart of the node,
+    // and non-zero revisions without an epoch.
+    assert!(
+        node_state
+            .pool_delta(Some(epoch.wrapping_add(1)), 3)
+            .resync_required
+    );
+    assert!(node_state.pool_delta(None, 3).resync_required);
+
+    // Revisions which are no longer retained require resynchronization as well.
+    node_state.set_pool_changes_capacity(1);
+    let delta = node_state.pool_delta(Some(epoch), 1);
+    assert!(delta.resync_required);
+    assert_eq!(delta.token, 3);
+    assert_eq!(
+        node_state.pool_delta(Some(epoch), 2).added,
+        vec![tx2.hash()]
+    );
+}
@@ -30,7 +30,7 @@ use exonum_merkledb::{HashTag, MapProof, ObjectHash, TemporaryDB};
 use crate::{
     blockchain::{
         Block, BlockProof, Blockchain, ConsensusConfig, GenesisConfig, Schema, Service,
-        SharedNodeState, StoredConfiguration, Transaction, ValidatorKeys,
+        SharedNodeState, StoredConfiguration, Transaction, ValidatorKeys, DEFAULT_VALIDATOR_WEIGHT,
     },
     crypto::{gen_keypair, gen_keypair_from_seed, Hash, PublicKey, SecretKey, Seed, SEED_LENGTH},
     events::{
@@ -271,7 +271,7 @@ impl Sandbox {
         secret_key: &SecretKey,
     ) -> Signed<Connect> {
         Message::concrete(
-            Connect::new(&addr, time, user_agent),
+            Connect::new(&addr, time, user_agent, &self.genesis_hash()),
             *public_key,
             secret_key,
         )
@@ -466,6 +466,12 @@ impl Sandbox {
         self.inner.borrow_mut().handle_event(event);
     }
 
+    pub fn disconnect(&self, key: PublicKey) {
+        self.check_unexpected_message();
+        let event = NetworkEvent::PeerDisconnected(key);
+        self.inner.borrow_mut().handle_event(event);
+    }
+
     pub fn recv_rebroadcast(&self) {
         self.check_unexpected_message();
         self.inner
@@ -597,7 +603,7 @@ impl Sandbox {
                         timers.push(TimeoutRequest(time, timeout));
                         break;
                     } else {
-                        timeout
+                        TimeoutRequest(time, timeout)
                     }
                 } else {
                     break;
@@ -619,6 +625,13 @@ impl Sandbox {
         self.blockchain_ref().last_block()
     }
 
+    pub fn genesis_hash(&self) -> Hash {
+        let snapshot = self.blockchain_ref().snapshot();
+        Schema::new(&snapshot)
+            .block_hash_by_height(Height::zero())
+            .unwrap()
+    }
+
     pub fn last_hash(&self) -> Hash {
         self.blockchain_ref().last_hash()
     }
@@ -959,6 +972,7 @@ pub struct SandboxBuilder {
     initialize: bool,
     services: Vec<Box<dyn Service>>,
     validators_count: u8,
+    validator_weights: Vec<u64>,
     consensus_config: ConsensusConfig,
 }
 
@@ -968,6 +982,7 @@ impl SandboxBuilder {
             initialize: true,
             services: Vec::new(),
             validators_count: 4,
+            validator_weights: Vec::new(),
             consensus_config: ConsensusConfig {
                 first_round_timeout: 1000,
                 status_timeout: 600_000,
@@ -977,6 +992,12 @@ impl SandboxBuilder {
                 min_propose_timeout: PROPOSE_TIMEOUT,
                 max_propose_timeout: PROPOSE_TIMEOUT,
                 propose_timeout_threshold: std::u32::MAX,
+                min_online_validators: None,
+                min_online_weight: None,
+                max_clock_skew: None,
+                max_block_bytes: None,
+                max_blocks_per_second: None,
+                max_propose_txs: None,
             },
         }
     }
@@ -1001,6 +1022,14 @@ impl SandboxBuilder {
         self
     }
 
+    /// Sets the weights of the validators; the number of validators is set
+    /// to the number of weights.
+    pub fn with_validator_weights(mut self, weights: &[u64]) -> Self {
+        self.validators_count = weights.len() as u8;
+        self.validator_weights = weights.to_vec();
+        self
+    }
+
     pub fn build(self) -> Sandbox {
         let _ = env_logger::Builder::from_default_env()
             .target(env_logger::Target::Stdout)
@@ -1010,6 +1039,7 @@ impl SandboxBuilder {
             self.services,
             self.consensus_config,
             self.validators_count,
+            &self.validator_weights,
         );
 
         sandbox.inner.borrow_mut().sent.clear(); // To clear initial connect messages.
@@ -1032,6 +1062,7 @@ fn sandbox_with_services_uninitialized(
     services: Vec<Box<dyn Service>>,
     consensus: ConsensusConfig,
     validators_count: u8,
+    validator_weights: &[u64],
 ) -> Sandbox {
     let validators = (0..validators_count)
         .map(|i| gen_keypair_from_seed(&Seed::new([i; SEED_LENGTH])))
@@ -1072,9 +1103,14 @@ fn sandbox_with_services_uninitialized(
         validators
             .iter()
             .zip(service_keys.iter())
-            .map(|x| ValidatorKeys {
+            .enumerate()
+            .map(|(i, x)| ValidatorKeys {
                 consensus_key: (x.0).0,
                 service_key: (x.1).0,
+                weight: validator_weights
+                    .get(i)
+                    .cloned()
+                    .unwrap_or(DEFAULT_VALIDATOR_WEIGHT),
             }),
     );
 
@@ -1248,6 +1284,7 @@ mod tests {
         let validator_keys = ValidatorKeys {
             consensus_key: public,
             service_key: service,
+            weight: DEFAULT_VALIDATOR_WEIGHT,
         };
 
         let new_peer_addr = gen_primitive_socket_addr(2);
@@ -1274,6 +1311,77 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_sandbox_ignores_connect_with_different_genesis() {
+        let s = timestamping_sandbox();
+        // Another network with a different genesis configuration.
+        let other = timestamping_sandbox_builder()
+            .with_consensus(|config| config.propose_timeout_threshold += 1)
+            .build();
+        assert_ne!(s.genesis_hash(), other.genesis_hash());
+
+        let (public, secret) = gen_keypair();
+        let (service, _) = gen_keypair();
+        let validator_keys = ValidatorKeys {
+            consensus_key: public,
+            service_key: service,
+            weight: DEFAULT_VALIDATOR_WEIGHT,
+        };
+        let new_peer_addr = gen_primitive_socket_addr(2);
+        s.add_peer_to_connect_list(new_peer_addr, validator_keys);
+
+        // The node does not respond with its own `Connect` message.
+        s.recv(&other.create_connect(
+            &public,
+            new_peer_addr.to_string(),
+            s.time().into(),
+            &user_agent::get(),
+            &secret,
+        ));
+        assert!(!s.node_state().peers().contains_key(&public));
+    }
+
+    #[test]
+    fn test_connected_peers_snapshot() {
+        use crate::events::network::ConnectedPeerAddr;
+
+        let s = timestamping_sandbox();
+        let (unknown_peer, _) = gen_keypair();
+        let connected = vec![
+            s.public_key(ValidatorId(1)),
+            s.public_key(ValidatorId(2)),
+            unknown_peer,
+        ];
+        {
+            let mut handler = s.node_handler_mut();
+            for (i, public_key) in connected.into_iter().enumerate() {
+                let address = gen_primitive_socket_addr(i as u8 + 1);
+                handler
+                    .state
+                    .add_connection(public_key, ConnectedPeerAddr::In(address));
+            }
+            handler.api_state().update_node_state(&handler.state);
+        }
+
+        // Validator 3 is not connected and the unknown peer has not sent
+        // a `Connect` message, so they are not included.
+        let mut peers: Vec<_> = [ValidatorId(1), ValidatorId(2)]
+            .iter()
+            .map(|&id| ConnectInfo {
+                address: s.address(id),
+                public_key: s.public_key(id),
+            })
+            .collect();
+        peers.sort_by(|a, b| a.public_key.cmp(&b.public_key));
+        let expected = ConnectListConfig { peers };
+
+        assert_eq!(
+            ConnectListConfig::from_connected_peers(&s.node_state()),
+            expected
+        );
+        assert_eq!(s.node_handler_mut().api_state().connected_peers(), expected);
+    }
+
     #[test]
     fn test_sandbox_assert_status() {
         let s = timestamping_sandbox();
@@ -1310,6 +1418,7 @@ mod tests {
         let validator_keys = ValidatorKeys {
             consensus_key: public,
             service_key: service,
+            weight: DEFAULT_VALIDATOR_WEIGHT,
         };
         s.add_peer_to_connect_list(gen_primitive_socket_addr(1), validator_keys);
         s.recv(&s.create_connect(
@@ -1341,6 +1450,7 @@ mod tests {
         let validator_keys = ValidatorKeys {
             consensus_key: public,
             service_key: service,
+            weight: DEFAULT_VALIDATOR_WEIGHT,
         };
         s.add_peer_to_connect_list(gen_primitive_socket_addr(1), validator_keys);
         s.recv(&s.create_connect(
@@ -1362,6 +1472,7 @@ mod tests {
         let validator_keys = ValidatorKeys {
             consensus_key: public,
             service_key: service,
+            weight: DEFAULT_VALIDATOR_WEIGHT,
         };
         s.add_peer_to_connect_list(gen_primitive_socket_addr(1), validator_keys);
         s.recv(&s.create_connect(
@@ -1391,6 +1502,7 @@ mod tests {
         let validator_keys = ValidatorKeys {
             consensus_key: public,
             service_key: service,
+            weight: DEFAULT_VALIDATOR_WEIGHT,
         };
         s.add_peer_to_connect_list(gen_primitive_socket_addr(1), validator_keys);
         s.recv(&s.create_connect(
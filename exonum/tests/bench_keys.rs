@@ -0,0 +1,40 @@
+// Copyright 2019 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// This is a regression test for the derivation of the keys used by the benchmarks.
+
+#[path = "../benches/criterion/keys.rs"]
+mod keys;
+
+use crate::keys::gen_keypair_from_bench_seed;
+
+#[test]
+fn same_seed_gives_same_keys() {
+    assert_eq!(
+        gen_keypair_from_bench_seed(42, 0),
+        gen_keypair_from_bench_seed(42, 0)
+    );
+}
+
+#[test]
+fn different_seeds_give_different_keys() {
+    assert_ne!(
+        gen_keypair_from_bench_seed(42, 0).0,
+        gen_keypair_from_bench_seed(43, 0).0
+    );
+    assert_ne!(
+        gen_keypair_from_bench_seed(42, 0).0,
+        gen_keypair_from_bench_seed(42, 1).0
+    );
+}
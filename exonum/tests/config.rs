@@ -498,3 +498,30 @@ fn test_update_config() {
     let new_connect_list = config.connect_list;
     assert_eq!(new_connect_list.peers, connect_list.peers);
 }
+
+#[test]
+fn test_strict_config_loading() {
+    let env = ConfigSpec::new_without_pass();
+    let config_path = env.output_dir().join("node.toml");
+    fs::create_dir(&config_path.parent().unwrap()).unwrap();
+    fs::copy(&env.expected_node_config_file(0), &config_path).unwrap();
+
+    // Unmodified configuration is loaded in both modes.
+    let lenient: NodeConfig<PathBuf> = ConfigFile::load(&config_path).unwrap();
+    let strict: NodeConfig<PathBuf> = ConfigFile::load_strict(&config_path).unwrap();
+    assert_eq!(lenient, strict);
+
+    // Top-level keys precede the tables in TOML, so the misspelled key is prepended.
+    let content = fs::read_to_string(&config_path).unwrap();
+    fs::write(&config_path, format!("thread_pool_siz = 4\n{}", content)).unwrap();
+
+    let lenient: NodeConfig<PathBuf> = ConfigFile::load(&config_path).unwrap();
+    assert_eq!(lenient.thread_pool_size, strict.thread_pool_size);
+    let err = ConfigFile::load_strict::<_, NodeConfig<PathBuf>>(&config_path).unwrap_err();
+    let message = err
+        .iter_chain()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(": ");
+    assert!(message.contains("thread_pool_siz"), "{}", message);
+}
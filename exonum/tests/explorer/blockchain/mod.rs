@@ -122,7 +122,7 @@ pub fn consensus_keys() -> (PublicKey, SecretKey) {
 
 /// Creates a blockchain with no blocks.
 pub fn create_blockchain() -> Blockchain {
-    use exonum::blockchain::{GenesisConfig, ValidatorKeys};
+    use exonum::blockchain::{GenesisConfig, ValidatorKeys, DEFAULT_VALIDATOR_WEIGHT};
 
     let (consensus_key, _) = consensus_keys();
     let service_keys = crypto::gen_keypair();
@@ -139,6 +139,7 @@ pub fn create_blockchain() -> Blockchain {
     let keys = ValidatorKeys {
         consensus_key,
         service_key: service_keys.0,
+        weight: DEFAULT_VALIDATOR_WEIGHT,
     };
     blockchain
         .initialize(GenesisConfig::new(vec![keys].into_iter()))
@@ -24,6 +24,8 @@ extern crate serde_derive;
 #[macro_use]
 extern crate pretty_assertions;
 
+use std::cell::Cell;
+
 use exonum::{
     blockchain::{Schema, TransactionErrorType, TransactionSet, TxLocation},
     crypto::{self, Hash},
@@ -104,7 +106,9 @@ fn test_explorer_basics() {
             json!({
                 "content": {
                     "debug": payload_alice,
-                    "message": messages::to_hex_string(&tx_alice)
+                    "message": messages::to_hex_string(&tx_alice),
+                    "hash": tx_alice.hash(),
+                    "payload_hash": tx_alice.payload().payload_hash(),
                 },
                 "location": {
                     "block_height": 1,
@@ -137,7 +141,9 @@ fn test_explorer_basics() {
         json!({
             "content": {
                     "debug": payload_bob,
-                    "message": messages::to_hex_string(&tx_bob)
+                    "message": messages::to_hex_string(&tx_bob),
+                    "hash": tx_bob.hash(),
+                    "payload_hash": tx_bob.payload().payload_hash(),
             },
             "location": {
                 "block_height": 2,
@@ -161,7 +167,9 @@ fn test_explorer_basics() {
         json!({
             "content": {
                     "debug": payload_transfer,
-                    "message": messages::to_hex_string(&tx_transfer)
+                    "message": messages::to_hex_string(&tx_transfer),
+                    "hash": tx_transfer.hash(),
+                    "payload_hash": tx_transfer.payload().payload_hash(),
             },
             "location": {
                 "block_height": 2,
@@ -208,6 +216,61 @@ fn test_explorer_pool_transaction() {
     assert_eq!(tx_info.content().signed_message(), &tx_alice);
 }
 
+#[test]
+fn test_transaction_cache() {
+    let mut blockchain = create_blockchain();
+    let mut txs = tx_generator();
+    let (committed_tx, pool_tx) = (txs.next().unwrap(), txs.next().unwrap());
+    create_block(&mut blockchain, vec![committed_tx.clone()]);
+
+    let fork = blockchain.fork();
+    {
+        let mut schema = Schema::new(&fork);
+        schema.add_transaction_into_pool(pool_tx.clone());
+    }
+    blockchain.merge(fork.into_patch()).unwrap();
+
+    let cache = TransactionCache::new(TRANSACTION_CACHE_SIZE);
+    let explorer = BlockchainExplorer::new(&blockchain);
+    let loads = Cell::new(0);
+    let load = |hash: &Hash| {
+        loads.set(loads.get() + 1);
+        explorer.transaction(hash)
+    };
+
+    // The committed transaction is loaded from the database only once.
+    let info = cache
+        .get_or_load(&blockchain, &committed_tx.hash(), load)
+        .unwrap();
+    let cached_info = cache
+        .get_or_load(&blockchain, &committed_tx.hash(), load)
+        .unwrap();
+    assert_eq!(loads.get(), 1);
+    assert_eq!(cache.len(), 1);
+    assert!(cached_info.is_committed());
+    assert_eq!(cached_info.content().message(), &committed_tx);
+    assert_eq!(
+        serde_json::to_value(&cached_info).unwrap(),
+        serde_json::to_value(&info).unwrap()
+    );
+
+    // The transaction in the pool is never cached.
+    for _ in 0..2 {
+        let info = cache
+            .get_or_load(&blockchain, &pool_tx.hash(), load)
+            .unwrap();
+        assert!(info.is_in_pool());
+    }
+    assert_eq!(loads.get(), 3);
+    assert_eq!(cache.len(), 1);
+
+    // Unknown transactions are not cached as well.
+    assert!(cache
+        .get_or_load(&blockchain, &crypto::hash(&[]), load)
+        .is_none());
+    assert_eq!(loads.get(), 4);
+}
+
 fn tx_generator() -> Box<dyn Iterator<Item = Signed<RawTransaction>>> {
     Box::new((0..).map(|i| {
         let (pk, key) = crypto::gen_keypair();
@@ -263,7 +326,7 @@ fn test_explorer_block_iter() {
 
     let transactions: Vec<CommittedTransaction> = explorer
         .blocks(..)
-        .flat_map(|info| info.with_transactions().transactions)
+        .flat_map(|info| info.with_transactions().unwrap().transactions)
         .collect();
     assert_eq!(transactions.len(), 12);
     assert!(transactions
@@ -20,14 +20,18 @@ use tokio::util::FutureExt;
 use tokio_core::reactor::Core;
 
 use std::{
-    sync::{Arc, Mutex},
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
     thread::{self, JoinHandle},
     time::Duration,
 };
 
-use exonum_merkledb::{Database, Fork, Snapshot, TemporaryDB};
+use exonum_merkledb::{Database, Fork, Patch, Result as StorageResult, Snapshot, TemporaryDB};
 
 use exonum::{
+    api::{ServiceApiBuilder, ServiceApiState},
     blockchain::{Service, ServiceContext, Transaction},
     crypto::Hash,
     helpers,
@@ -86,11 +90,116 @@ impl Service for InitializeCheckerService {
     }
 }
 
+/// Service with an API endpoint which takes a second to respond.
+struct SlowApiService(pub Arc<AtomicBool>);
+
+impl Service for SlowApiService {
+    fn service_id(&self) -> u16 {
+        257
+    }
+
+    fn service_name(&self) -> &str {
+        "slow_api"
+    }
+
+    fn state_hash(&self, _: &dyn Snapshot) -> Vec<Hash> {
+        Vec::new()
+    }
+
+    fn tx_from_raw(&self, _raw: RawTransaction) -> Result<Box<dyn Transaction>, failure::Error> {
+        unreachable!("An unknown transaction received");
+    }
+
+    fn wire_api(&self, builder: &mut ServiceApiBuilder) {
+        let started = Arc::clone(&self.0);
+        builder
+            .public_scope()
+            .endpoint("v1/slow", move |_state: &ServiceApiState, _query: ()| {
+                started.store(true, Ordering::SeqCst);
+                thread::sleep(Duration::from_secs(1));
+                Ok("done")
+            });
+    }
+}
+
+/// Database counting the explicit compactions.
+struct CompactionSpyDb {
+    inner: TemporaryDB,
+    compactions: Arc<AtomicUsize>,
+}
+
+impl Database for CompactionSpyDb {
+    fn snapshot(&self) -> Box<dyn Snapshot> {
+        self.inner.snapshot()
+    }
+
+    fn merge(&self, patch: Patch) -> StorageResult<()> {
+        self.inner.merge(patch)
+    }
+
+    fn merge_sync(&self, patch: Patch) -> StorageResult<()> {
+        self.inner.merge_sync(patch)
+    }
+
+    fn compact(&self) -> StorageResult<()> {
+        self.compactions.fetch_add(1, Ordering::SeqCst);
+        self.inner.compact()
+    }
+}
+
+fn compaction_spy_db() -> (Arc<dyn Database>, Arc<AtomicUsize>) {
+    let compactions = Arc::new(AtomicUsize::new(0));
+    let db = CompactionSpyDb {
+        inner: TemporaryDB::new(),
+        compactions: Arc::clone(&compactions),
+    };
+    (Arc::new(db), compactions)
+}
+
 struct RunHandle {
     node_thread: JoinHandle<()>,
     api_tx: ApiSender,
 }
 
+impl RunHandle {
+    /// Runs the node in a separate thread.
+    fn start(node: Node) -> Self {
+        let api_tx = node.channel();
+        let node_thread = thread::spawn(move || {
+            node.run().unwrap();
+        });
+        Self {
+            node_thread,
+            api_tx,
+        }
+    }
+
+    /// Stops the node and waits for its thread to finish.
+    fn shutdown(self) {
+        self.api_tx
+            .send_external_message(ExternalMessage::Shutdown)
+            .unwrap();
+        self.node_thread.join().unwrap();
+    }
+}
+
+/// Repeats the request until the API server of the node starts and responds to it.
+fn wait_for_api<F>(mut send: F) -> reqwest::Response
+where
+    F: FnMut() -> reqwest::Result<reqwest::Response>,
+{
+    (0..50)
+        .filter_map(|_| {
+            let response = send().ok();
+            if response.is_none() {
+                thread::sleep(Duration::from_millis(100));
+            }
+            response
+        })
+        .next()
+        .expect("Cannot connect to node")
+}
+
 fn run_nodes(count: u16, start_port: u16) -> (Vec<RunHandle>, Vec<oneshot::Receiver<()>>) {
     let mut node_threads = Vec::new();
     let mut commit_rxs = Vec::new();
@@ -98,13 +207,7 @@ fn run_nodes(count: u16, start_port: u16) -> (Vec<RunHandle>, Vec<oneshot::Recei
         let (commit_tx, commit_rx) = oneshot::channel();
         let service = Box::new(CommitWatcherService(Mutex::new(Some(commit_tx))));
         let node = Node::new(TemporaryDB::new(), vec![service], node_cfg, None);
-        let api_tx = node.channel();
-        node_threads.push(RunHandle {
-            node_thread: thread::spawn(move || {
-                node.run().unwrap();
-            }),
-            api_tx,
-        });
+        node_threads.push(RunHandle::start(node));
         commit_rxs.push(commit_rx);
     }
     (node_threads, commit_rxs)
@@ -123,11 +226,7 @@ fn test_node_run() {
     }
 
     for handle in nodes {
-        handle
-            .api_tx
-            .send_external_message(ExternalMessage::Shutdown)
-            .unwrap();
-        handle.node_thread.join().unwrap();
+        handle.shutdown();
     }
 }
 
@@ -136,15 +235,8 @@ fn test_node_restart_regression() {
     let start_node = |node_cfg, db, init_times| {
         let service = Box::new(InitializeCheckerService(init_times));
         let node = Node::new(db, vec![service], node_cfg, None);
-        let api_tx = node.channel();
-        let node_thread = thread::spawn(move || {
-            node.run().unwrap();
-        });
         // Wait for shutdown
-        api_tx
-            .send_external_message(ExternalMessage::Shutdown)
-            .unwrap();
-        node_thread.join().unwrap();
+        RunHandle::start(node).shutdown();
     };
 
     let db = Arc::from(Box::new(TemporaryDB::new()) as Box<dyn Database>) as Arc<dyn Database>;
@@ -157,3 +249,231 @@ fn test_node_restart_regression() {
     start_node(node_cfg, db, Arc::clone(&init_times));
     assert_eq!(*init_times.lock().unwrap(), 1);
 }
+
+#[test]
+fn test_compact_endpoint() {
+    let (db, compactions) = compaction_spy_db();
+    let mut node_cfg = helpers::generate_testnet_config(1, 3610)[0].clone();
+    node_cfg.api.private_api_address = Some("127.0.0.1:8091".parse().unwrap());
+    let node = RunHandle::start(Node::new(db, vec![], node_cfg, None));
+
+    // Waits for the API server to start.
+    let client = reqwest::Client::new();
+    let url = "http://127.0.0.1:8091/api/system/v1/debug/compact";
+    let response = wait_for_api(|| client.post(url).json(&()).send());
+    assert!(response.status().is_success());
+    assert_eq!(compactions.load(Ordering::SeqCst), 1);
+
+    node.shutdown();
+}
+
+#[test]
+fn test_db_compaction_interval() {
+    let (db, compactions) = compaction_spy_db();
+    let mut node_cfg = helpers::generate_testnet_config(1, 3620)[0].clone();
+    node_cfg.database.compaction_interval = Some(50);
+    let node = RunHandle::start(Node::new(db, vec![], node_cfg, None));
+
+    thread::sleep(Duration::from_millis(500));
+    node.shutdown();
+
+    // The transaction pool is empty, so the node is idle and compacts the database.
+    assert!(compactions.load(Ordering::SeqCst) > 0);
+}
+
+#[test]
+fn test_graceful_api_shutdown() {
+    let started = Arc::new(AtomicBool::new(false));
+    let service = Box::new(SlowApiService(Arc::clone(&started)));
+    let mut node_cfg = helpers::generate_testnet_config(1, 3630)[0].clone();
+    node_cfg.api.public_api_address = Some("127.0.0.1:8092".parse().unwrap());
+    node_cfg.api.api_shutdown_timeout = 5_000;
+    let node = RunHandle::start(Node::new(TemporaryDB::new(), vec![service], node_cfg, None));
+
+    // Waits for the API server to start.
+    let client = reqwest::Client::new();
+    wait_for_api(|| {
+        client
+            .get("http://127.0.0.1:8092/api/system/v1/healthcheck")
+            .send()
+    });
+
+    let request_thread = thread::spawn(move || {
+        reqwest::Client::new()
+            .get("http://127.0.0.1:8092/api/services/slow_api/v1/slow")
+            .send()
+            .and_then(|mut response| response.json::<String>())
+    });
+    while !started.load(Ordering::SeqCst) {
+        thread::sleep(Duration::from_millis(10));
+    }
+
+    // The node is stopped while the request is being processed.
+    node.shutdown();
+
+    let response = request_thread.join().unwrap().expect("Request was dropped");
+    assert_eq!(response, "done");
+}
+
+#[cfg(feature = "tls")]
+#[test]
+fn test_public_api_tls() {
+    use exonum::api::backends::actix::TlsConfig;
+
+    let mut node_cfg = helpers::generate_testnet_config(1, 3640)[0].clone();
+    node_cfg.api.public_api_address = Some("127.0.0.1:8093".parse().unwrap());
+    node_cfg.api.public_api_tls = Some(TlsConfig {
+        tls_cert_path: concat!(env!("CARGO_MANIFEST_DIR"), "/tests/testdata/tls/cert.pem").into(),
+        tls_key_path: concat!(env!("CARGO_MANIFEST_DIR"), "/tests/testdata/tls/key.pem").into(),
+    });
+    let node = RunHandle::start(Node::new(TemporaryDB::new(), vec![], node_cfg, None));
+
+    // The certificate is self-signed, so it cannot be verified by the client.
+    let client = reqwest::Client::builder()
+        .danger_accept_invalid_certs(true)
+        .build()
+        .unwrap();
+    let url = "https://127.0.0.1:8093/api/system/v1/healthcheck";
+    let response = wait_for_api(|| client.get(url).send());
+    assert!(response.status().is_success());
+
+    // Plain HTTP requests are not served.
+    assert!(reqwest::get("http://127.0.0.1:8093/api/system/v1/healthcheck").is_err());
+
+    node.shutdown();
+}
+
+#[test]
+fn test_signed_api_responses() {
+    use exonum::api::backends::actix::{
+        response_signature_payload, PUBLIC_KEY_HEADER, SIGNATURE_HEADER,
+    };
+    use exonum::crypto::{self, PublicKey, Signature};
+    use hex::FromHex;
+
+    let mut node_cfg = helpers::generate_testnet_config(1, 3650)[0].clone();
+    node_cfg.api.public_api_address = Some("127.0.0.1:8094".parse().unwrap());
+    node_cfg.api.sign_responses = true;
+    let service_public_key = node_cfg.service_public_key;
+    let node = RunHandle::start(Node::new(TemporaryDB::new(), vec![], node_cfg, None));
+
+    // Waits for the API server to start.
+    let client = reqwest::Client::new();
+    let url = "http://127.0.0.1:8094/api/explorer/v1/block?height=0";
+    let mut response = wait_for_api(|| client.get(url).send());
+    assert!(response.status().is_success());
+
+    let header = |name: &str| {
+        response.headers()[name]
+            .to_str()
+            .expect("Header is not a string")
+            .to_owned()
+    };
+    let signature = Signature::from_hex(header(SIGNATURE_HEADER)).unwrap();
+    let public_key = PublicKey::from_hex(header(PUBLIC_KEY_HEADER)).unwrap();
+    assert_eq!(public_key, service_public_key);
+
+    let body = response.text().unwrap().into_bytes();
+    let payload = response_signature_payload("GET", "/api/explorer/v1/block?height=0", &body);
+    assert!(crypto::verify(&signature, &payload, &service_public_key));
+    // The bare body, as well as the body of a response to another request, is not signed.
+    assert!(!crypto::verify(&signature, &body, &service_public_key));
+    let payload = response_signature_payload("GET", "/api/explorer/v1/block?height=1", &body);
+    assert!(!crypto::verify(&signature, &payload, &service_public_key));
+
+    node.shutdown();
+}
+
+#[cfg(feature = "tls")]
+#[test]
+fn test_public_api_http2() {
+    let mut node_cfg = helpers::generate_testnet_config(1, 3660)[0].clone();
+    node_cfg.api.public_api_address = Some("127.0.0.1:8095".parse().unwrap());
+    node_cfg.api.http2 = true;
+    let node = RunHandle::start(Node::new(TemporaryDB::new(), vec![], node_cfg, None));
+
+    // The API is served over cleartext HTTP/2, so the client has to know about it in advance.
+    let client = reqwest::Client::builder()
+        .h2_prior_knowledge()
+        .build()
+        .unwrap();
+    let url = "http://127.0.0.1:8095/api/explorer/v1/block?height=0";
+    let mut response = wait_for_api(|| client.get(url).send());
+    assert!(response.status().is_success());
+    assert_eq!(response.version(), reqwest::Version::HTTP_2);
+    let block: Value = response.json().unwrap();
+    assert_eq!(block["height"], 0);
+
+    node.shutdown();
+}
+
+#[test]
+fn test_explorer_disabled() {
+    let service = Box::new(SlowApiService(Arc::new(AtomicBool::new(false))));
+    let mut node_cfg = helpers::generate_testnet_config(1, 3680)[0].clone();
+    node_cfg.api.public_api_address = Some("127.0.0.1:8097".parse().unwrap());
+    node_cfg.api.enable_explorer = false;
+    let node = RunHandle::start(Node::new(TemporaryDB::new(), vec![service], node_cfg, None));
+
+    // Waits for the API server to start.
+    let client = reqwest::Client::new();
+    let url = "http://127.0.0.1:8097/api/explorer/v1/blocks?count=10";
+    let response = wait_for_api(|| client.get(url).send());
+    assert_eq!(response.status(), reqwest::StatusCode::NOT_FOUND);
+
+    // Endpoints of the services are still served.
+    let response = client
+        .get("http://127.0.0.1:8097/api/services/slow_api/v1/slow")
+        .send()
+        .and_then(|mut response| response.json::<String>())
+        .unwrap();
+    assert_eq!(response, "done");
+
+    node.shutdown();
+}
+
+#[cfg(feature = "tls")]
+#[test]
+fn test_public_api_http2_disabled() {
+    let mut node_cfg = helpers::generate_testnet_config(1, 3670)[0].clone();
+    node_cfg.api.public_api_address = Some("127.0.0.1:8096".parse().unwrap());
+    let node = RunHandle::start(Node::new(TemporaryDB::new(), vec![], node_cfg, None));
+
+    let client = reqwest::Client::builder()
+        .h2_prior_knowledge()
+        .build()
+        .unwrap();
+    let url = "http://127.0.0.1:8096/api/explorer/v1/block?height=0";
+    let response = wait_for_api(|| client.get(url).send());
+    assert_eq!(
+        response.status(),
+        reqwest::StatusCode::HTTP_VERSION_NOT_SUPPORTED
+    );
+
+    node.shutdown();
+}
+
+#[test]
+fn test_node_label_in_api() {
+    let mut node_cfg = helpers::generate_testnet_config(1, 3690)[0].clone();
+    node_cfg.api.public_api_address = Some("127.0.0.1:8098".parse().unwrap());
+    node_cfg.api.private_api_address = Some("127.0.0.1:8099".parse().unwrap());
+    node_cfg.node_label = Some("node-1".to_owned());
+    let node = RunHandle::start(Node::new(TemporaryDB::new(), vec![], node_cfg, None));
+
+    // Waits for the API server to start.
+    let client = reqwest::Client::new();
+    let url = "http://127.0.0.1:8098/api/system/v1/healthcheck";
+    let mut response = wait_for_api(|| client.get(url).send());
+    let healthcheck: Value = response.json().unwrap();
+    assert_eq!(healthcheck["node_label"], "node-1");
+
+    let peers: Value = client
+        .get("http://127.0.0.1:8099/api/system/v1/peers")
+        .send()
+        .and_then(|mut response| response.json())
+        .unwrap();
+    assert_eq!(peers["node_label"], "node-1");
+
+    node.shutdown();
+}
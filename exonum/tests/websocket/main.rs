@@ -30,11 +30,15 @@ use websocket::{
 };
 
 use std::{
+    io::{BufRead, BufReader},
     thread::sleep,
     time::{Duration, Instant},
 };
 
-use exonum::{api::websocket::*, crypto::gen_keypair, messages::Message, node::ExternalMessage};
+use exonum::{
+    api::websocket::*, crypto::gen_keypair, explorer::TransactionInfo, messages::Message,
+    node::ExternalMessage,
+};
 
 mod blockchain;
 
@@ -92,7 +96,10 @@ fn test_send_transaction() {
         serde_json::from_str::<serde_json::Value>(&resp_text).unwrap(),
         json!({
             "result": "success",
-            "response": { "tx_hash": tx_hash }
+            "response": {
+                "tx_hash": tx_hash,
+                "payload_hash": tx.payload().payload_hash(),
+            }
         })
     );
 
@@ -179,6 +186,62 @@ fn test_transactions_subscribe() {
     node_handler.node_thread.join().unwrap();
 }
 
+#[test]
+fn test_transactions_stream() {
+    let node_handler = run_node(6335, 8084);
+
+    let http_client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(60))
+        .build()
+        .unwrap();
+    let stream = http_client
+        .get("http://localhost:8084/api/explorer/v1/transactions/stream")
+        .query(&[("service_id", SERVICE_ID)])
+        .send()
+        .unwrap();
+    assert_eq!(
+        stream.headers()[reqwest::header::CONTENT_TYPE],
+        "application/x-ndjson"
+    );
+    let mut lines = BufReader::new(stream).lines();
+
+    // Send transactions.
+    let tx_hashes = ["Alice", "Bob"]
+        .iter()
+        .map(|name| {
+            let (pk, sk) = gen_keypair();
+            let tx = Message::sign_transaction(CreateWallet::new(&pk, name), SERVICE_ID, pk, &sk);
+            http_client
+                .post("http://localhost:8084/api/explorer/v1/transactions")
+                .json(&json!({ "tx_body": tx }))
+                .send()
+                .unwrap();
+            tx.hash()
+        })
+        .collect::<Vec<_>>();
+
+    // Read committed transactions off the stream.
+    let mut committed = (0..tx_hashes.len())
+        .map(|_| {
+            let line = lines.next().unwrap().unwrap();
+            let info = serde_json::from_str::<TransactionInfo>(&line).unwrap();
+            assert!(info.is_committed());
+            info.content().message().hash()
+        })
+        .collect::<Vec<_>>();
+    committed.sort();
+    let mut tx_hashes = tx_hashes;
+    tx_hashes.sort();
+    assert_eq!(committed, tx_hashes);
+
+    // Shutdown node.
+    node_handler
+        .api_tx
+        .send_external_message(ExternalMessage::Shutdown)
+        .unwrap();
+    node_handler.node_thread.join().unwrap();
+}
+
 #[test]
 fn test_subscribe() {
     let node_handler = run_node(6333, 8082);
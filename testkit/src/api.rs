@@ -17,7 +17,7 @@
 pub use exonum::api::ApiAccess;
 
 use actix_web::{test::TestServer, App};
-use reqwest::{Client, Response, StatusCode};
+use reqwest::{header::ACCEPT, Client, RequestBuilder as ReqwestBuilder, Response, StatusCode};
 use serde::{de::DeserializeOwned, Serialize};
 
 use std::fmt::{self, Display};
@@ -131,6 +131,7 @@ where
     access: ApiAccess,
     prefix: String,
     query: Option<&'b Q>,
+    headers: Vec<(String, String)>,
 }
 
 impl<'a, 'b, Q> fmt::Debug for RequestBuilder<'a, 'b, Q>
@@ -142,6 +143,7 @@ where
             .field("access", &self.access)
             .field("prefix", &self.prefix)
             .field("query", &self.query)
+            .field("headers", &self.headers)
             .finish()
     }
 }
@@ -162,9 +164,16 @@ where
             access,
             prefix,
             query: None,
+            headers: Vec::new(),
         }
     }
 
+    /// Adds a header to the current request.
+    pub fn with_header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_owned(), value.to_owned()));
+        self
+    }
+
     /// Sets a query data of the current request.
     pub fn query<T>(&'a self, query: &'b T) -> RequestBuilder<'a, 'b, T> {
         RequestBuilder {
@@ -173,6 +182,7 @@ where
             access: self.access,
             prefix: self.prefix.clone(),
             query: Some(query),
+            headers: self.headers.clone(),
         }
     }
 
@@ -182,6 +192,39 @@ where
     where
         R: DeserializeOwned + 'static,
     {
+        let url = self.get_url(endpoint);
+        trace!("GET {}", url);
+
+        let response = self
+            .with_headers(self.test_client.get(&url))
+            .send()
+            .expect("Unable to send request");
+        Self::response_to_api_result(response)
+    }
+
+    /// Sends a get request accepting newline-delimited JSON to the testing API endpoint
+    /// and decodes each line of the response as the corresponding type.
+    pub fn get_ndjson<R>(&self, endpoint: &str) -> api::Result<Vec<R>>
+    where
+        R: DeserializeOwned + 'static,
+    {
+        let url = self.get_url(endpoint);
+        trace!("GET {} (NDJSON)", url);
+
+        let response = self
+            .with_headers(self.test_client.get(&url))
+            .header(ACCEPT, "application/x-ndjson")
+            .send()
+            .expect("Unable to send request");
+        Self::parse_response(response, |body| {
+            body.lines()
+                .filter(|line| !line.is_empty())
+                .map(|line| serde_json::from_str(line).expect("Unable to deserialize line"))
+                .collect()
+        })
+    }
+
+    fn get_url(&self, endpoint: &str) -> String {
         let params = self
             .query
             .as_ref()
@@ -192,23 +235,14 @@ where
                 )
             })
             .unwrap_or_default();
-        let url = format!(
+        format!(
             "{url}{access}/{prefix}/{endpoint}{query}",
             url = self.test_server_url,
             access = self.access,
             prefix = self.prefix,
             endpoint = endpoint,
             query = params
-        );
-
-        trace!("GET {}", url);
-
-        let response = self
-            .test_client
-            .get(&url)
-            .send()
-            .expect("Unable to send request");
-        Self::response_to_api_result(response)
+        )
     }
 
     /// Sends a post request to the testing API endpoint and decodes response as
@@ -217,17 +251,10 @@ where
     where
         R: DeserializeOwned + 'static,
     {
-        let url = format!(
-            "{url}{access}/{prefix}/{endpoint}",
-            url = self.test_server_url,
-            access = self.access,
-            prefix = self.prefix,
-            endpoint = endpoint
-        );
-
+        let url = self.post_url(endpoint);
         trace!("POST {}", url);
 
-        let builder = self.test_client.post(&url);
+        let builder = self.with_headers(self.test_client.post(&url));
         let builder = if let Some(ref query) = self.query.as_ref() {
             trace!("Body: {}", serde_json::to_string_pretty(&query).unwrap());
             builder.json(query)
@@ -238,10 +265,56 @@ where
         Self::response_to_api_result(response)
     }
 
+    /// Sends a post request with the `application/x-www-form-urlencoded` body
+    /// to the testing API endpoint and decodes response as the corresponding type.
+    pub fn post_form<R>(&self, endpoint: &str) -> api::Result<R>
+    where
+        R: DeserializeOwned + 'static,
+    {
+        let url = self.post_url(endpoint);
+        trace!("POST {} (form)", url);
+
+        let builder = self.with_headers(self.test_client.post(&url));
+        let builder = if let Some(ref query) = self.query.as_ref() {
+            builder.form(query)
+        } else {
+            builder
+        };
+        let response = builder.send().expect("Unable to send request");
+        Self::response_to_api_result(response)
+    }
+
+    fn post_url(&self, endpoint: &str) -> String {
+        format!(
+            "{url}{access}/{prefix}/{endpoint}",
+            url = self.test_server_url,
+            access = self.access,
+            prefix = self.prefix,
+            endpoint = endpoint
+        )
+    }
+
+    fn with_headers(&self, builder: ReqwestBuilder) -> ReqwestBuilder {
+        self.headers.iter().fold(builder, |builder, (name, value)| {
+            builder.header(name.as_str(), value.as_str())
+        })
+    }
+
     /// Converts reqwest Response to api::Result.
-    fn response_to_api_result<R>(mut response: Response) -> api::Result<R>
+    fn response_to_api_result<R>(response: Response) -> api::Result<R>
     where
         R: DeserializeOwned + 'static,
+    {
+        Self::parse_response(response, |body| {
+            serde_json::from_str(body).expect("Unable to deserialize body")
+        })
+    }
+
+    /// Converts reqwest Response to api::Result, parsing the body of a successful
+    /// response with the given function.
+    fn parse_response<R, F>(mut response: Response, parse: F) -> api::Result<R>
+    where
+        F: FnOnce(&str) -> R,
     {
         trace!("Response status: {}", response.status());
 
@@ -265,11 +338,12 @@ where
             StatusCode::OK => Ok({
                 let body = response.text().expect("Unable to get response text");
                 trace!("Body: {}", body);
-                serde_json::from_str(&body).expect("Unable to deserialize body")
+                parse(&body)
             }),
             StatusCode::FORBIDDEN => Err(api::Error::Unauthorized),
             StatusCode::BAD_REQUEST => Err(api::Error::BadRequest(error(response))),
             StatusCode::NOT_FOUND => Err(api::Error::NotFound(error(response))),
+            StatusCode::SERVICE_UNAVAILABLE => Err(api::Error::ServiceUnavailable(error(response))),
             s if s.is_server_error() => Err(api::Error::InternalError(format_err!(
                 "{}",
                 error(response)
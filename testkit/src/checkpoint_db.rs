@@ -61,6 +61,14 @@ impl<T: Database> Database for CheckpointDb<T> {
     fn merge_sync(&self, patch: Patch) -> StorageResult<()> {
         self.merge(patch)
     }
+
+    fn compact(&self) -> StorageResult<()> {
+        self.inner
+            .read()
+            .expect("Cannot lock CheckpointDb for compaction")
+            .db
+            .compact()
+    }
 }
 
 impl<T: Database> From<CheckpointDb<T>> for Arc<dyn Database> {
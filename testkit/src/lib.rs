@@ -453,9 +453,15 @@ impl TestKit {
                             schema.add_transaction_into_pool(tx.clone());
                         }
                     }
+                    ExternalMessage::CancelTransaction(hash) => {
+                        // Result is ignored, since the transaction may be absent in the pool.
+                        let _ = schema.reject_transaction(&hash);
+                    }
                     ExternalMessage::PeerAdd(_)
+                    | ExternalMessage::ReplaceConnectList(_)
                     | ExternalMessage::Enable(_)
                     | ExternalMessage::Rebroadcast
+                    | ExternalMessage::SetServiceEnabled { .. }
                     | ExternalMessage::Shutdown => { /* Ignored */ }
                 }
                 blockchain.merge(fork.into_patch()).unwrap();
@@ -990,6 +996,9 @@ impl TestKit {
                 ApiRuntimeConfig::new(private_api_address, ApiAccess::Private),
             ],
             api_aggregator: server::create_testkit_api_aggregator(&testkit_ref),
+            bind_retries: 0,
+            bind_retry_delay: Default::default(),
+            shutdown_timeout: Default::default(),
         };
         let system_runtime = system_runtime_config.start().unwrap();
         // Run the event stream in a separate thread in order to put transactions to mempool
@@ -15,7 +15,10 @@
 use serde::{Deserialize, Serialize};
 
 use exonum::{
-    blockchain::{ConsensusConfig, GenesisConfig, StoredConfiguration, ValidatorKeys},
+    blockchain::{
+        ConsensusConfig, GenesisConfig, StoredConfiguration, ValidatorKeys,
+        DEFAULT_VALIDATOR_WEIGHT,
+    },
     crypto::{self, CryptoHash, PublicKey, SecretKey},
     helpers::{Height, Round, ValidatorId},
     messages::{Message, Precommit, Propose, Signed},
@@ -212,6 +215,7 @@ impl TestNode {
         ValidatorKeys {
             consensus_key: self.consensus_public_key,
             service_key: self.service_public_key,
+            weight: DEFAULT_VALIDATOR_WEIGHT,
         }
     }
 
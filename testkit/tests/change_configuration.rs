@@ -12,17 +12,27 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+#[macro_use]
+extern crate assert_matches;
 #[macro_use]
 extern crate pretty_assertions;
 #[macro_use]
 extern crate serde_derive;
+#[macro_use]
+extern crate serde_json;
 
 use exonum::{
-    blockchain::Schema,
-    crypto::CryptoHash,
+    api::{
+        node::public::system::{
+            ConfigActivation, ConfigActivationQuery, ConfigDiff, ConfigDiffQuery,
+        },
+        Error as ApiError,
+    },
+    blockchain::{GenesisConfig, Schema},
+    crypto::{CryptoHash, Hash},
     helpers::{Height, ValidatorId},
 };
-use exonum_testkit::TestKitBuilder;
+use exonum_testkit::{ApiKind, TestKitBuilder};
 
 #[test]
 fn test_following_config() {
@@ -83,6 +93,33 @@ fn test_configuration_and_rollbacks() {
     assert_eq!(testkit.actual_configuration(), old_config);
 }
 
+#[test]
+fn test_genesis_config_after_config_change() {
+    let mut testkit = TestKitBuilder::validator().with_validators(2).create();
+    let genesis = testkit.network().genesis_config();
+
+    let cfg_change_height = Height(5);
+    let proposal = {
+        let mut cfg = testkit.configuration_change_proposal();
+        let mut consensus = cfg.consensus_configuration().clone();
+        consensus.txs_block_limit = 500;
+        let validators = cfg.validators()[..1].to_vec();
+        cfg.set_actual_from(cfg_change_height);
+        cfg.set_consensus_configuration(consensus);
+        cfg.set_validators(validators);
+        cfg
+    };
+    testkit.commit_configuration_change(proposal);
+    testkit.create_blocks_until(cfg_change_height);
+    assert_eq!(testkit.actual_configuration().validator_keys.len(), 1);
+
+    let api = testkit.api();
+    let actual_genesis: GenesisConfig = api.public(ApiKind::Explorer).get("v1/genesis").unwrap();
+    assert_eq!(actual_genesis, genesis);
+    assert_eq!(actual_genesis.validator_keys.len(), 2);
+    assert_ne!(actual_genesis.consensus.txs_block_limit, 500);
+}
+
 #[test]
 fn test_add_to_validators() {
     let mut testkit = TestKitBuilder::auditor().with_validators(1).create();
@@ -203,3 +240,117 @@ fn test_another_configuration_change_proposal() {
     };
     testkit.commit_configuration_change(second_proposal);
 }
+
+#[test]
+fn test_config_activation_api() {
+    let mut testkit = TestKitBuilder::validator().create();
+    let genesis_config = testkit.actual_configuration();
+
+    let mut configs = Vec::new();
+    for &(height, value) in &[(Height(5), "first"), (Height(10), "second")] {
+        let proposal = {
+            let mut cfg = testkit.configuration_change_proposal();
+            cfg.set_actual_from(height);
+            cfg.set_service_config("service", value);
+            cfg
+        };
+        let stored = proposal.stored_configuration().clone();
+        testkit.commit_configuration_change(proposal);
+        testkit.create_block();
+
+        // The configuration is committed, but not activated yet.
+        let err = testkit
+            .api()
+            .public(ApiKind::System)
+            .query(&ConfigActivationQuery {
+                hash: stored.hash(),
+            })
+            .get::<ConfigActivation>("v1/config/activation")
+            .unwrap_err();
+        assert_matches!(err, ApiError::NotFound(_));
+
+        testkit.create_blocks_until(height);
+        configs.push(stored);
+    }
+
+    let api = testkit.api();
+    let activation: ConfigActivation = api
+        .public(ApiKind::System)
+        .query(&ConfigActivationQuery {
+            hash: genesis_config.hash(),
+        })
+        .get("v1/config/activation")
+        .unwrap();
+    assert_eq!(activation.height, Height(0));
+    assert_eq!(activation.config, genesis_config);
+
+    for (config, &height) in configs.iter().zip(&[Height(5), Height(10)]) {
+        let activation: ConfigActivation = api
+            .public(ApiKind::System)
+            .query(&ConfigActivationQuery {
+                hash: config.hash(),
+            })
+            .get("v1/config/activation")
+            .unwrap();
+        assert_eq!(activation.height, height);
+        assert_eq!(&activation.config, config);
+    }
+
+    let err = api
+        .public(ApiKind::System)
+        .query(&ConfigActivationQuery { hash: Hash::zero() })
+        .get::<ConfigActivation>("v1/config/activation")
+        .unwrap_err();
+    assert_matches!(err, ApiError::NotFound(_));
+}
+
+#[test]
+fn test_config_diff_api() {
+    let mut testkit = TestKitBuilder::validator().create();
+    let genesis_config = testkit.actual_configuration();
+
+    let proposal = {
+        let mut cfg = testkit.configuration_change_proposal();
+        let mut consensus = cfg.consensus_configuration().clone();
+        consensus.txs_block_limit += 1;
+        cfg.set_consensus_configuration(consensus);
+        cfg.set_actual_from(Height(5));
+        cfg
+    };
+    let stored = proposal.stored_configuration().clone();
+    testkit.commit_configuration_change(proposal);
+    testkit.create_block();
+
+    let api = testkit.api();
+    let diff: ConfigDiff = api
+        .public(ApiKind::System)
+        .query(&ConfigDiffQuery {
+            from: genesis_config.hash(),
+            to: stored.hash(),
+        })
+        .get("v1/config/diff")
+        .unwrap();
+    assert!(diff.added_validators.is_empty());
+    assert!(diff.removed_validators.is_empty());
+    assert!(diff.services.is_empty());
+    assert_eq!(
+        diff.consensus.keys().collect::<Vec<_>>(),
+        vec!["txs_block_limit"]
+    );
+    let change = &diff.consensus["txs_block_limit"];
+    assert_eq!(
+        change.from,
+        Some(json!(genesis_config.consensus.txs_block_limit))
+    );
+    assert_eq!(change.to, Some(json!(stored.consensus.txs_block_limit)));
+
+    let err = api
+        .public(ApiKind::System)
+        .query(&ConfigDiffQuery {
+            from: genesis_config.hash(),
+            to: Hash::zero(),
+        })
+        .get::<ConfigDiff>("v1/config/diff")
+        .unwrap_err();
+    assert_matches!(err, ApiError::NotFound(_));
+}
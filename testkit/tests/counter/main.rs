@@ -674,6 +674,43 @@ fn test_explorer_blocks_bounds() {
     assert!(result.is_err());
 }
 
+#[test]
+fn test_explorer_blocks_ascending_order() {
+    use exonum::api::node::public::explorer::BlocksRange;
+    use exonum::helpers::Height;
+
+    let (mut testkit, api) = init_testkit();
+    for _ in 0..5 {
+        create_sample_block(&mut testkit);
+    }
+
+    let BlocksRange { blocks, range } = api
+        .public(ApiKind::Explorer)
+        .get("v1/blocks?count=2&earliest=1&order=asc")
+        .unwrap();
+    let heights: Vec<_> = blocks.iter().map(|info| info.block.height()).collect();
+    assert_eq!(heights, vec![Height(1), Height(2)]);
+    assert_eq!(range.start, Height(1));
+    assert_eq!(range.end, Height(3));
+
+    // `latest` truncates the blocks in the ascending order.
+    let BlocksRange { blocks, range } = api
+        .public(ApiKind::Explorer)
+        .get("v1/blocks?count=10&earliest=3&latest=4&order=asc")
+        .unwrap();
+    let heights: Vec<_> = blocks.iter().map(|info| info.block.height()).collect();
+    assert_eq!(heights, vec![Height(3), Height(4)]);
+    assert_eq!(range.start, Height(3));
+    assert_eq!(range.end, Height(5));
+
+    // The descending order is used by default.
+    let BlocksRange { blocks, .. } = api
+        .public(ApiKind::Explorer)
+        .get("v1/blocks?count=2&earliest=1")
+        .unwrap();
+    assert_eq!(blocks[0].block.height(), Height(5));
+}
+
 #[test]
 fn test_explorer_blocks_loaded_info() {
     use exonum::api::node::public::explorer::BlocksRange;
@@ -759,6 +796,282 @@ fn test_explorer_single_block() {
     }
 }
 
+#[test]
+fn test_explorer_latest_block() {
+    use exonum::api::node::public::explorer::{BlockInfo, BlockQuery};
+
+    let (mut testkit, api) = init_testkit();
+    create_sample_block(&mut testkit);
+    create_sample_block(&mut testkit);
+
+    let latest: BlockInfo = api
+        .public(ApiKind::Explorer)
+        .get("v1/block/latest?add_precommits=true&add_blocks_time=true")
+        .unwrap();
+    let expected: BlockInfo = api
+        .public(ApiKind::Explorer)
+        .query(&BlockQuery::new(testkit.height()))
+        .get("v1/block")
+        .unwrap();
+    assert_eq!(latest.block.height(), Height(2));
+    assert_eq!(latest, expected);
+
+    // Precommits and block time are omitted by default.
+    let latest: BlockInfo = api
+        .public(ApiKind::Explorer)
+        .get("v1/block/latest")
+        .unwrap();
+    assert_eq!(latest.block, expected.block);
+    assert_eq!(latest.txs, expected.txs);
+    assert!(latest.precommits.is_none());
+    assert!(latest.time.is_none());
+}
+
+#[test]
+fn test_explorer_block_txs_slice() {
+    use exonum::api::node::public::explorer::{BlockInfo, BlockQuery};
+
+    let (mut testkit, api) = init_testkit();
+    let txs = (1..=5).map(|by| {
+        let (pubkey, key) = crypto::gen_keypair();
+        TxIncrement::sign(&pubkey, by, &key)
+    });
+    testkit.create_block_with_transactions(txs);
+
+    let get_block = |query: &BlockQuery| -> BlockInfo {
+        api.public(ApiKind::Explorer)
+            .query(query)
+            .get("v1/block")
+            .unwrap()
+    };
+    let full = get_block(&BlockQuery::new(Height(1)));
+    let all_txs = full.txs.clone().unwrap();
+    assert_eq!(all_txs.len(), 5);
+
+    let block = get_block(&BlockQuery::new(Height(1)).with_txs_slice(1, 2));
+    assert_eq!(block.txs.unwrap(), &all_txs[1..3]);
+    assert_eq!(block.block, full.block);
+    assert_eq!(block.block.tx_count(), 5);
+    assert_eq!(block.precommits, full.precommits);
+
+    let block = get_block(&BlockQuery::new(Height(1)).with_txs_slice(4, 10));
+    assert_eq!(block.txs.unwrap(), &all_txs[4..]);
+
+    let block = get_block(&BlockQuery::new(Height(1)).with_txs_slice(10, 1));
+    assert!(block.txs.unwrap().is_empty());
+    assert_eq!(block.block.tx_count(), 5);
+}
+
+#[test]
+fn test_explorer_signatures_count() {
+    use exonum::api::node::public::explorer::{BlockInfo, BlockQuery, BlocksQuery, BlocksRange};
+
+    let mut testkit = TestKitBuilder::validator()
+        .with_validators(4)
+        .with_service(CounterService)
+        .create();
+    let api = testkit.api();
+    testkit.create_block();
+
+    // All the validators sign the blocks created by the testkit.
+    let block: BlockInfo = api
+        .public(ApiKind::Explorer)
+        .query(&BlockQuery::new(Height(1)))
+        .get("v1/block")
+        .unwrap();
+    assert_eq!(block.signatures_count, Some(4));
+    assert!(block.signatures_count.unwrap() > testkit.majority_count());
+
+    let range: BlocksRange = api
+        .public(ApiKind::Explorer)
+        .query(&BlocksQuery {
+            count: 1,
+            add_signatures_count: true,
+            ..Default::default()
+        })
+        .get("v1/blocks")
+        .unwrap();
+    assert_eq!(range.blocks[0].signatures_count, Some(4));
+    assert!(range.blocks[0].precommits.is_none());
+
+    // The number of signatures is omitted by default.
+    let range: BlocksRange = api
+        .public(ApiKind::Explorer)
+        .query(&BlocksQuery {
+            count: 1,
+            ..Default::default()
+        })
+        .get("v1/blocks")
+        .unwrap();
+    assert!(range.blocks[0].signatures_count.is_none());
+}
+
+#[test]
+fn test_explorer_block_tx_bodies() {
+    use exonum::api::node::public::explorer::{
+        BlockInfo, BlockQuery, BlocksQuery, BlocksRange, TransactionQuery,
+        MAX_BLOCKS_WITH_TX_BODIES_PER_REQUEST,
+    };
+    use exonum::explorer::TransactionInfo;
+
+    let (mut testkit, api) = init_testkit();
+    let txs = (1..=4).map(|by| {
+        let (pubkey, key) = crypto::gen_keypair();
+        TxIncrement::sign(&pubkey, by, &key)
+    });
+    testkit.create_block_with_transactions(txs);
+
+    let lookup = |hash| -> TransactionInfo {
+        api.public(ApiKind::Explorer)
+            .query(&TransactionQuery { hash })
+            .get("v1/transactions")
+            .unwrap()
+    };
+
+    let block: BlockInfo = api
+        .public(ApiKind::Explorer)
+        .query(&BlockQuery::new(Height(1)))
+        .get("v1/block")
+        .unwrap();
+    assert!(block.tx_bodies.is_none());
+
+    let query = BlockQuery::new(Height(1))
+        .with_txs_slice(1, 2)
+        .with_tx_bodies();
+    let block: BlockInfo = api
+        .public(ApiKind::Explorer)
+        .query(&query)
+        .get("v1/block")
+        .unwrap();
+    let hashes = block.txs.unwrap();
+    let bodies = block.tx_bodies.unwrap();
+    assert_eq!(hashes.len(), 2);
+    assert_eq!(bodies.len(), 2);
+    for (hash, body) in hashes.into_iter().zip(&bodies) {
+        assert_eq!(lookup(hash).content().message(), body);
+    }
+
+    let range: BlocksRange = api
+        .public(ApiKind::Explorer)
+        .query(&BlocksQuery {
+            count: 1,
+            add_tx_bodies: true,
+            ..Default::default()
+        })
+        .get("v1/blocks")
+        .unwrap();
+    let bodies = range.blocks[0].tx_bodies.clone().unwrap();
+    assert_eq!(bodies.len(), 4);
+    for body in &bodies {
+        assert_eq!(lookup(body.hash()).content().message(), body);
+    }
+
+    let error = api
+        .public(ApiKind::Explorer)
+        .query(&BlocksQuery {
+            count: MAX_BLOCKS_WITH_TX_BODIES_PER_REQUEST + 1,
+            add_tx_bodies: true,
+            ..Default::default()
+        })
+        .get::<BlocksRange>("v1/blocks")
+        .unwrap_err();
+    assert_matches!(error, ApiError::BadRequest(_));
+}
+
+#[test]
+fn test_explorer_transactions_by_author() {
+    use exonum::api::node::public::explorer::TransactionsByAuthorQuery;
+    use exonum::explorer::TransactionInfo;
+
+    let (mut testkit, api) = init_testkit();
+    let (alice, alice_key) = crypto::gen_keypair();
+    let (bob, bob_key) = crypto::gen_keypair();
+
+    let alice_txs = (1..=3)
+        .map(|by| TxIncrement::sign(&alice, by, &alice_key))
+        .collect::<Vec<_>>();
+    let bob_tx = TxIncrement::sign(&bob, 10, &bob_key);
+    testkit.create_block_with_transactions(txvec![alice_txs[0].clone(), bob_tx.clone()]);
+    testkit.create_block_with_transactions(txvec![alice_txs[1].clone()]);
+    testkit.create_block_with_transactions(txvec![alice_txs[2].clone()]);
+
+    let by_author = |author, earliest, count| -> Vec<TransactionInfo> {
+        api.public(ApiKind::Explorer)
+            .query(&TransactionsByAuthorQuery {
+                author,
+                earliest,
+                latest: None,
+                count,
+            })
+            .get("v1/transactions/by_author")
+            .unwrap()
+    };
+
+    // Transactions are returned starting from the latest one.
+    let txs = by_author(alice, None, None);
+    assert_eq!(txs.len(), 3);
+    for (info, tx) in txs.iter().zip(alice_txs.iter().rev()) {
+        assert!(info.is_committed());
+        assert_eq!(info.content().message(), tx);
+    }
+
+    let txs = by_author(bob, None, None);
+    assert_eq!(txs.len(), 1);
+    assert_eq!(txs[0].content().message(), &bob_tx);
+
+    let txs = by_author(alice, Some(Height(2)), None);
+    assert_eq!(txs.len(), 2);
+    assert_eq!(txs[1].content().message(), &alice_txs[1]);
+
+    let txs = by_author(alice, None, Some(1));
+    assert_eq!(txs.len(), 1);
+    assert_eq!(txs[0].content().message(), &alice_txs[2]);
+
+    let (stranger, _) = crypto::gen_keypair();
+    assert!(by_author(stranger, None, None).is_empty());
+}
+
+#[test]
+fn test_explorer_transaction_statuses() {
+    use exonum::api::node::public::explorer::{
+        TransactionStatus, TransactionStatusesQuery, MAX_TRANSACTIONS_PER_REQUEST,
+    };
+    use exonum::crypto::Hash;
+    use std::collections::HashMap;
+
+    let (mut testkit, api) = init_testkit();
+    let (pubkey, key) = crypto::gen_keypair();
+    let committed_tx = TxIncrement::sign(&pubkey, 1, &key);
+    let pending_tx = TxIncrement::sign(&pubkey, 2, &key);
+    testkit.create_block_with_transactions(txvec![committed_tx.clone()]);
+    testkit.add_tx(pending_tx.clone());
+    let unknown_hash = crypto::hash(&[1, 2, 3]);
+
+    let statuses: HashMap<Hash, TransactionStatus> = api
+        .public(ApiKind::Explorer)
+        .query(&TransactionStatusesQuery {
+            hashes: vec![committed_tx.hash(), pending_tx.hash(), unknown_hash],
+        })
+        .post("v1/transactions/statuses")
+        .unwrap();
+    assert_eq!(statuses.len(), 3);
+    assert_eq!(
+        statuses[&committed_tx.hash()],
+        TransactionStatus::Committed { height: Height(1) }
+    );
+    assert_eq!(statuses[&pending_tx.hash()], TransactionStatus::Pending);
+    assert_eq!(statuses[&unknown_hash], TransactionStatus::Unknown);
+
+    let error = api
+        .public(ApiKind::Explorer)
+        .query(&TransactionStatusesQuery {
+            hashes: vec![unknown_hash; MAX_TRANSACTIONS_PER_REQUEST + 1],
+        })
+        .post::<HashMap<Hash, TransactionStatus>>("v1/transactions/statuses")
+        .unwrap_err();
+    assert_matches!(error, ApiError::BadRequest(_));
+}
+
 #[test]
 fn test_explorer_transaction_info() {
     use exonum::explorer::{BlockchainExplorer, TransactionInfo};
@@ -794,7 +1107,9 @@ fn test_explorer_transaction_info() {
             "type": "in-pool",
             "content": {
                 "debug": TxIncrement::new(5),
-                "message": messages::to_hex_string(&tx)
+                "message": messages::to_hex_string(&tx),
+                "hash": tx.hash(),
+                "payload_hash": tx.payload().payload_hash(),
             },
         })
     );
@@ -820,6 +1135,395 @@ fn test_explorer_transaction_info() {
         .is_ok());
 }
 
+#[test]
+fn test_explorer_transaction_info_with_execution_error() {
+    use exonum::blockchain::TransactionErrorType;
+    use exonum::explorer::TransactionInfo;
+
+    let (mut testkit, api) = init_testkit();
+    let tx = {
+        let (pubkey, key) = crypto::gen_keypair();
+        // Adding zero results in an execution error.
+        TxIncrement::sign(&pubkey, 0, &key)
+    };
+    testkit.create_block_with_transactions(txvec![tx.clone()]);
+
+    let info: Value = api
+        .public(ApiKind::Explorer)
+        .get(&format!("v1/transactions?hash={}", &tx.hash().to_hex()))
+        .unwrap();
+    assert_eq!(info["type"], "committed");
+    assert_eq!(
+        info["status"],
+        json!({
+            "type": "error",
+            "code": 0,
+            "description": "Adding zero does nothing!",
+        })
+    );
+
+    let info: TransactionInfo = serde_json::from_value(info).unwrap();
+    let err = info.as_committed().unwrap().status().unwrap_err();
+    assert_eq!(err.error_type(), TransactionErrorType::Code(0));
+    assert_eq!(err.description(), Some("Adding zero does nothing!"));
+}
+
+#[test]
+fn test_explorer_transaction_proof() {
+    use exonum::api::node::public::explorer::TransactionProof;
+
+    let (mut testkit, api) = init_testkit();
+    let tx = {
+        let (pubkey, key) = crypto::gen_keypair();
+        TxIncrement::sign(&pubkey, 5, &key)
+    };
+    let other_tx = {
+        let (pubkey, key) = crypto::gen_keypair();
+        TxIncrement::sign(&pubkey, 3, &key)
+    };
+    let url = format!("v1/transactions/proof?hash={}", &tx.hash().to_hex());
+
+    // Uncommitted transaction has no proof.
+    api.send(tx.clone());
+    testkit.poll_events();
+    let err = api
+        .public(ApiKind::Explorer)
+        .get::<TransactionProof>(&url)
+        .unwrap_err();
+    assert_matches!(err, ApiError::NotFound(_));
+
+    testkit.create_block_with_transactions(txvec![other_tx, tx.clone()]);
+    let tx_proof: TransactionProof = api.public(ApiKind::Explorer).get(&url).unwrap();
+    assert_eq!(tx_proof.height, Height(1));
+    assert_eq!(tx_proof.transaction, Some(tx.clone()));
+
+    let explorer = BlockchainExplorer::new(testkit.blockchain());
+    let block = explorer.block(Height(1)).unwrap();
+    let proven = tx_proof
+        .proof
+        .validate(
+            *block.header().tx_hash(),
+            u64::from(block.header().tx_count()),
+        )
+        .unwrap();
+    assert_eq!(proven, vec![(1, &tx.hash())]);
+}
+
+#[test]
+fn test_stats_summary() {
+    use exonum::api::node::public::system::StatsSummary;
+    use exonum::explorer::BlockchainExplorer;
+
+    let (mut testkit, api) = init_testkit();
+    let summary: StatsSummary = api.public(ApiKind::System).get("v1/stats/summary").unwrap();
+    assert_eq!(summary.height, Height(0));
+    assert_eq!(summary.tx_count, 0);
+    assert_eq!(summary.average_block_time, None);
+
+    testkit.create_block_with_transactions(txvec![inc_count(&api, 1), inc_count(&api, 2)]);
+    testkit.create_block();
+    testkit.create_block_with_transactions(txvec![inc_count(&api, 3)]);
+
+    let summary: StatsSummary = api.public(ApiKind::System).get("v1/stats/summary").unwrap();
+    assert_eq!(summary.height, Height(3));
+    assert_eq!(summary.services_count, 1);
+    assert_eq!(
+        summary.validators_count,
+        testkit.network().validators().len()
+    );
+    assert!(summary.average_block_time.is_some());
+
+    let explorer = BlockchainExplorer::new(testkit.blockchain());
+    let block_txs: u64 = explorer
+        .blocks(Height(1)..)
+        .map(|block| u64::from(block.header().tx_count()))
+        .sum();
+    assert_eq!(summary.tx_count, block_txs);
+    assert_eq!(summary.tx_count, 3);
+}
+
+#[test]
+fn test_explorer_transactions_pool_size() {
+    use exonum::api::node::public::explorer::TransactionsPoolSize;
+
+    let (mut testkit, api) = init_testkit();
+    let pool_size: TransactionsPoolSize = api
+        .public(ApiKind::Explorer)
+        .get("v1/transactions/pool/size")
+        .unwrap();
+    assert_eq!(pool_size, TransactionsPoolSize { size: 0, bytes: 0 });
+
+    let tx = inc_count(&api, 5);
+    let other_tx = inc_count(&api, 3);
+    testkit.poll_events();
+
+    let pool_size: TransactionsPoolSize = api
+        .public(ApiKind::Explorer)
+        .get("v1/transactions/pool/size")
+        .unwrap();
+    let expected_bytes = (tx.clone().serialize().len() + other_tx.clone().serialize().len()) as u64;
+    assert_eq!(
+        pool_size,
+        TransactionsPoolSize {
+            size: 2,
+            bytes: expected_bytes,
+        }
+    );
+
+    testkit.create_block();
+    let pool_size: TransactionsPoolSize = api
+        .public(ApiKind::Explorer)
+        .get("v1/transactions/pool/size")
+        .unwrap();
+    assert_eq!(pool_size, TransactionsPoolSize { size: 0, bytes: 0 });
+}
+
+#[test]
+fn test_explorer_pending_transaction() {
+    use exonum::api::node::public::explorer::PendingTransaction;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    let (mut testkit, api) = init_testkit();
+    let tx = {
+        let (pubkey, key) = crypto::gen_keypair();
+        TxIncrement::sign(&pubkey, 5, &key)
+    };
+    let url = format!("v1/transactions/pending?hash={}", &tx.hash().to_hex());
+
+    // Unknown transaction.
+    let err = api
+        .public(ApiKind::Explorer)
+        .get::<PendingTransaction>(&url)
+        .unwrap_err();
+    assert_matches!(err, ApiError::NotFound(_));
+
+    let now = || {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64
+    };
+    let sent_at = now();
+    api.send(tx.clone());
+    testkit.poll_events();
+    let pending: PendingTransaction = api.public(ApiKind::Explorer).get(&url).unwrap();
+    assert_eq!(pending.content, tx);
+    let arrived_at = pending.arrived_at.expect("No arrival time").timestamp();
+    assert!(arrived_at >= sent_at && arrived_at <= now());
+
+    // Committed transaction is not in the pool anymore.
+    testkit.create_block();
+    let err = api
+        .public(ApiKind::Explorer)
+        .get::<PendingTransaction>(&url)
+        .unwrap_err();
+    assert_matches!(err, ApiError::NotFound(_));
+}
+
+#[test]
+fn test_explorer_add_transaction_with_idempotency_key() {
+    use exonum::api::node::public::explorer::{
+        TransactionHex, TransactionResponse as AddTransactionResponse, IDEMPOTENCY_KEY_HEADER,
+    };
+
+    let (mut testkit, api) = init_testkit();
+    let (pubkey, key) = crypto::gen_keypair();
+    let tx = TxIncrement::sign(&pubkey, 5, &key);
+    // The client retries the submission with a regenerated transaction.
+    let retried_tx = TxIncrement::sign(&pubkey, 6, &key);
+    assert_ne!(tx.hash(), retried_tx.hash());
+    // Another client uses the same idempotency key.
+    let (other_pubkey, other_key) = crypto::gen_keypair();
+    let other_tx = TxIncrement::sign(&other_pubkey, 3, &other_key);
+
+    let submit = |tx: &Signed<RawTransaction>| -> AddTransactionResponse {
+        api.public(ApiKind::Explorer)
+            .with_header(IDEMPOTENCY_KEY_HEADER, "increment-5")
+            .query(&TransactionHex {
+                tx_body: messages::to_hex_string(tx),
+            })
+            .post("v1/transactions")
+            .unwrap()
+    };
+    let response = submit(&tx);
+    let retried_response = submit(&retried_tx);
+    assert_eq!(response, retried_response);
+    assert_eq!(response.tx_hash, tx.hash());
+    let other_response = submit(&other_tx);
+    assert_eq!(other_response.tx_hash, other_tx.hash());
+
+    let block = testkit.create_block();
+    assert_eq!(block.len(), 2);
+    assert!(block.iter().all(|tx| tx.content().message() != &retried_tx));
+    let snapshot = testkit.snapshot();
+    assert_eq!(CounterSchema::new(&snapshot).count(), Some(8));
+}
+
+#[test]
+fn test_explorer_transaction_hashes() {
+    use exonum::api::node::public::explorer::{
+        TransactionHex, TransactionResponse as AddTransactionResponse,
+    };
+
+    let (mut testkit, api) = init_testkit();
+    let (pubkey, key) = crypto::gen_keypair();
+    let tx = TxIncrement::sign(&pubkey, 5, &key);
+
+    let response: AddTransactionResponse = api
+        .public(ApiKind::Explorer)
+        .query(&TransactionHex {
+            tx_body: messages::to_hex_string(&tx),
+        })
+        .post("v1/transactions")
+        .unwrap();
+    assert_eq!(response.tx_hash, tx.hash());
+    assert_eq!(response.payload_hash, tx.payload().payload_hash());
+    assert_ne!(response.tx_hash, response.payload_hash);
+
+    testkit.create_block();
+    let info: Value = api
+        .public(ApiKind::Explorer)
+        .get(&format!("v1/transactions?hash={}", &tx.hash().to_hex()))
+        .unwrap();
+    assert_eq!(info["content"]["hash"], json!(response.tx_hash));
+    assert_eq!(
+        info["content"]["payload_hash"],
+        json!(response.payload_hash)
+    );
+}
+
+#[test]
+fn test_explorer_add_transaction_form_encoded() {
+    use exonum::api::node::public::explorer::{
+        TransactionHex, TransactionResponse as AddTransactionResponse,
+    };
+
+    let (mut testkit, api) = init_testkit();
+    let txs = (1..=2)
+        .map(|by| {
+            let (pubkey, key) = crypto::gen_keypair();
+            TxIncrement::sign(&pubkey, by, &key)
+        })
+        .collect::<Vec<_>>();
+    let queries = txs
+        .iter()
+        .map(|tx| TransactionHex {
+            tx_body: messages::to_hex_string(tx),
+        })
+        .collect::<Vec<_>>();
+
+    let json_response: AddTransactionResponse = api
+        .public(ApiKind::Explorer)
+        .query(&queries[0])
+        .post("v1/transactions")
+        .unwrap();
+    let form_response: AddTransactionResponse = api
+        .public(ApiKind::Explorer)
+        .query(&queries[1])
+        .post_form("v1/transactions")
+        .unwrap();
+    assert_eq!(json_response.tx_hash, txs[0].hash());
+    assert_eq!(form_response.tx_hash, txs[1].hash());
+
+    let block = testkit.create_block();
+    assert_eq!(block.len(), 2);
+    assert!(block.iter().all(|tx| tx.status().is_ok()));
+    let snapshot = testkit.snapshot();
+    assert_eq!(CounterSchema::new(&snapshot).count(), Some(3));
+}
+
+#[test]
+fn test_explorer_transactions_range_ndjson() {
+    use exonum::api::node::public::explorer::TransactionsRangeQuery;
+    use exonum::explorer::TransactionInfo;
+
+    let (mut testkit, api) = init_testkit();
+    let txs = (1..=3)
+        .map(|by| {
+            let (pubkey, key) = crypto::gen_keypair();
+            TxIncrement::sign(&pubkey, by, &key)
+        })
+        .collect::<Vec<_>>();
+    testkit.create_block_with_transactions(txvec![txs[0].clone(), txs[1].clone()]);
+    testkit.create_block();
+    testkit.create_block_with_transactions(txvec![txs[2].clone()]);
+
+    let query = TransactionsRangeQuery {
+        earliest: Some(Height(1)),
+        ..Default::default()
+    };
+    let lines: Vec<TransactionInfo> = api
+        .public(ApiKind::Explorer)
+        .query(&query)
+        .get_ndjson("v1/transactions/range")
+        .unwrap();
+    assert_eq!(lines.len(), txs.len());
+    for (info, tx) in lines.iter().zip(&txs) {
+        assert!(info.is_committed());
+        assert_eq!(info.content().message(), tx);
+    }
+    let heights = lines
+        .iter()
+        .map(|info| info.as_committed().unwrap().location().block_height())
+        .collect::<Vec<_>>();
+    assert_eq!(heights, vec![Height(1), Height(1), Height(3)]);
+
+    // JSON array form returns the same transactions.
+    let array: Vec<TransactionInfo> = api
+        .public(ApiKind::Explorer)
+        .query(&query)
+        .get("v1/transactions/range")
+        .unwrap();
+    assert_eq!(array.len(), txs.len());
+
+    let query = TransactionsRangeQuery {
+        latest: Some(Height(2)),
+        count: Some(1),
+        ..Default::default()
+    };
+    let lines: Vec<TransactionInfo> = api
+        .public(ApiKind::Explorer)
+        .query(&query)
+        .get_ndjson("v1/transactions/range")
+        .unwrap();
+    assert_eq!(lines.len(), 1);
+    assert_eq!(lines[0].content().message(), &txs[0]);
+}
+
+#[test]
+fn test_panicking_transaction_does_not_abort_block() {
+    use exonum::blockchain::TransactionResult;
+
+    let (mut testkit, _) = init_testkit();
+    let (pubkey, key) = crypto::gen_keypair();
+    let txs = txvec![
+        TxIncrement::sign(&pubkey, 5, &key),
+        // Overflows the counter and panics.
+        TxIncrement::sign(&pubkey, u64::max_value() - 3, &key),
+        TxIncrement::sign(&pubkey, 3, &key),
+    ];
+
+    let block = testkit.create_block_with_transactions(txs);
+    assert_eq!(testkit.height(), Height(1));
+
+    let statuses: Vec<_> = block
+        .transactions
+        .iter()
+        .map(|tx| TransactionResult(tx.status().map_err(Clone::clone)))
+        .collect();
+    assert!(statuses[0].0.is_ok());
+    assert_matches!(
+        statuses[1],
+        TransactionResult(Err(ref err)) if err.error_type() == ErrorType::Panic
+            && err.description() == Some("attempt to add with overflow")
+    );
+    assert!(statuses[2].0.is_ok());
+
+    // Changes of the panicked transaction are discarded, the following one is executed.
+    let snapshot = testkit.snapshot();
+    assert_eq!(CounterSchema::new(&snapshot).count(), Some(8));
+}
+
 #[test]
 fn test_explorer_transaction_statuses() {
     use exonum::blockchain::TransactionResult;
@@ -883,6 +1587,140 @@ fn test_explorer_transaction_statuses() {
     check_statuses(&statuses);
 }
 
+#[test]
+fn test_cancel_pooled_transaction() {
+    use exonum::api::node::private::CancelTransactionQuery;
+
+    let (mut testkit, api) = init_testkit();
+    let (pubkey, key) = crypto::gen_keypair();
+    let tx = TxIncrement::sign(&pubkey, 5, &key);
+    api.send(tx.clone());
+    testkit.poll_events();
+    assert!(testkit.is_tx_in_pool(&tx.hash()));
+
+    // Signature made by another key is rejected.
+    let (_, other_key) = crypto::gen_keypair();
+    let err = api
+        .private(ApiKind::System)
+        .query(&CancelTransactionQuery::new(tx.hash(), &other_key))
+        .post::<()>("v1/transactions/cancel")
+        .unwrap_err();
+    assert_matches!(err, ApiError::BadRequest(_));
+    testkit.poll_events();
+    assert!(testkit.is_tx_in_pool(&tx.hash()));
+
+    // Signature of the bare transaction hash, without the context tag, is rejected.
+    let err = api
+        .private(ApiKind::System)
+        .query(&CancelTransactionQuery {
+            tx_hash: tx.hash(),
+            signature: crypto::sign(tx.hash().as_ref(), &key),
+        })
+        .post::<()>("v1/transactions/cancel")
+        .unwrap_err();
+    assert_matches!(err, ApiError::BadRequest(_));
+    testkit.poll_events();
+    assert!(testkit.is_tx_in_pool(&tx.hash()));
+
+    api.private(ApiKind::System)
+        .query(&CancelTransactionQuery::new(tx.hash(), &key))
+        .post::<()>("v1/transactions/cancel")
+        .unwrap();
+    testkit.poll_events();
+    assert!(!testkit.is_tx_in_pool(&tx.hash()));
+
+    let block = testkit.create_block();
+    assert!(block.is_empty());
+}
+
+#[test]
+fn test_cancel_missing_transaction() {
+    use exonum::api::node::private::CancelTransactionQuery;
+
+    let (_testkit, api) = init_testkit();
+    let (pubkey, key) = crypto::gen_keypair();
+    let tx = TxIncrement::sign(&pubkey, 5, &key);
+
+    let err = api
+        .private(ApiKind::System)
+        .query(&CancelTransactionQuery::new(tx.hash(), &key))
+        .post::<()>("v1/transactions/cancel")
+        .unwrap_err();
+    assert_matches!(err, ApiError::NotFound(_));
+}
+
+#[test]
+fn test_cancel_committed_transaction() {
+    use exonum::api::node::private::CancelTransactionQuery;
+
+    let (mut testkit, api) = init_testkit();
+    let (pubkey, key) = crypto::gen_keypair();
+    let tx = TxIncrement::sign(&pubkey, 5, &key);
+    testkit.create_block_with_transaction(tx.clone());
+
+    let err = api
+        .private(ApiKind::System)
+        .query(&CancelTransactionQuery::new(tx.hash(), &key))
+        .post::<()>("v1/transactions/cancel")
+        .unwrap_err();
+    assert_matches!(err, ApiError::BadRequest(_));
+
+    let snapshot = testkit.snapshot();
+    assert!(exonum::blockchain::Schema::new(&snapshot)
+        .transactions_locations()
+        .contains(&tx.hash()));
+}
+
+#[test]
+fn test_replay_transaction() {
+    use exonum::api::node::private::{TransactionReplay, TransactionReplayQuery};
+    use exonum::blockchain::TransactionResult;
+
+    let (mut testkit, api) = init_testkit();
+    let (pubkey, key) = crypto::gen_keypair();
+    let tx = TxIncrement::sign(&pubkey, 5, &key);
+    testkit.create_block_with_transaction(tx.clone());
+
+    let replay: TransactionReplay = api
+        .private(ApiKind::System)
+        .query(&TransactionReplayQuery { hash: tx.hash() })
+        .get("v1/transactions/replay")
+        .unwrap();
+    assert_eq!(replay.status, TransactionResult(Ok(())));
+    assert_eq!(replay.committed_status, TransactionResult(Ok(())));
+
+    // Changes of the replayed transaction are discarded.
+    let snapshot = testkit.snapshot();
+    assert_eq!(CounterSchema::new(&snapshot).count(), Some(5));
+
+    // The same transaction overflows the counter if executed against the current state.
+    testkit.create_block_with_transaction(TxIncrement::sign(&pubkey, u64::max_value() - 5, &key));
+    let replay: TransactionReplay = api
+        .private(ApiKind::System)
+        .query(&TransactionReplayQuery { hash: tx.hash() })
+        .get("v1/transactions/replay")
+        .unwrap();
+    assert_matches!(
+        replay.status,
+        TransactionResult(Err(ref err)) if err.error_type() == ErrorType::Panic
+    );
+    assert_eq!(replay.committed_status, TransactionResult(Ok(())));
+    let snapshot = testkit.snapshot();
+    assert_eq!(
+        CounterSchema::new(&snapshot).count(),
+        Some(u64::max_value())
+    );
+
+    let err = api
+        .private(ApiKind::System)
+        .query(&TransactionReplayQuery {
+            hash: crypto::hash(&[]),
+        })
+        .get::<TransactionReplay>("v1/transactions/replay")
+        .unwrap_err();
+    assert_matches!(err, ApiError::NotFound(_));
+}
+
 // Make sure that boxed transaction can be used in the `TestKitApi::send`.
 #[test]
 fn test_boxed_tx() {
@@ -14,17 +14,51 @@
 
 #[macro_use]
 extern crate pretty_assertions;
+#[macro_use]
+extern crate assert_matches;
 
 use exonum::{
-    api::node::{
-        private::NodeInfo,
-        public::system::{ConsensusStatus, HealthCheckInfo, StatsInfo},
+    api::{
+        node::{
+            private::{IndexMapProof, IndexProof, NodeInfo},
+            public::system::{ConsensusStatus, HealthCheckInfo, SoftwareInfo, StatsInfo},
+        },
+        Error as ApiError,
     },
-    helpers::user_agent,
-    messages::PROTOCOL_MAJOR_VERSION,
+    blockchain::{Schema, Service, Transaction},
+    crypto::{self, Hash, PublicKey},
+    helpers::{user_agent, Height},
+    messages::{RawTransaction, PROTOCOL_MAJOR_VERSION},
 };
+use exonum_merkledb::{ObjectHash, ProofMapIndex, Snapshot};
 use exonum_testkit::{ApiKind, TestKitBuilder};
 
+const PROOF_SERVICE_ID: u16 = 512;
+
+/// Service aggregating two proof maps into the state hash of the blockchain.
+struct ProofService;
+
+impl Service for ProofService {
+    fn service_id(&self) -> u16 {
+        PROOF_SERVICE_ID
+    }
+
+    fn service_name(&self) -> &str {
+        "proof"
+    }
+
+    fn state_hash(&self, snapshot: &dyn Snapshot) -> Vec<Hash> {
+        let by_key: ProofMapIndex<_, PublicKey, String> =
+            ProofMapIndex::new("proof.by_key", snapshot);
+        let by_hash: ProofMapIndex<_, Hash, String> = ProofMapIndex::new("proof.by_hash", snapshot);
+        vec![by_key.object_hash(), by_hash.object_hash()]
+    }
+
+    fn tx_from_raw(&self, _raw: RawTransaction) -> Result<Box<dyn Transaction>, failure::Error> {
+        unreachable!("The service has no transactions")
+    }
+}
+
 #[test]
 fn healthcheck() {
     // This test checks whether the endpoint returns expected result and correctness of
@@ -39,10 +73,24 @@ fn healthcheck() {
     let expected = HealthCheckInfo {
         consensus_status: ConsensusStatus::Enabled,
         connected_peers: 0,
+        node_label: None,
+        ready: true,
+        uptime: None,
+        first_start: None,
+        restarts: 0,
     };
     assert_eq!(info, expected);
 }
 
+#[test]
+fn healthcheck_from_older_nodes() {
+    // Nodes which do not report readiness are considered ready.
+    let json = r#"{ "consensus_status": "Active", "connected_peers": 3 }"#;
+    let info: HealthCheckInfo = serde_json::from_str(json).unwrap();
+    assert!(info.ready);
+    assert_eq!(info.restarts, 0);
+}
+
 #[test]
 fn stats() {
     let testkit = TestKitBuilder::validator().with_validators(2).create();
@@ -66,6 +114,28 @@ fn user_agent_info() {
     assert_eq!(info, expected);
 }
 
+#[test]
+fn software_info() {
+    let testkit = TestKitBuilder::validator().with_validators(2).create();
+    let api = testkit.api();
+
+    let info: SoftwareInfo = api.public(ApiKind::System).get("v1/info").unwrap();
+    assert_eq!(info.protocol_version, PROTOCOL_MAJOR_VERSION);
+    assert_eq!(
+        info.git_hash.as_ref().map(String::as_str),
+        user_agent::git_hash()
+    );
+
+    // The user agent starts with `exonum <version>/`.
+    let user_agent = user_agent::get();
+    let version = user_agent
+        .split('/')
+        .next()
+        .and_then(|package| package.split(' ').nth(1))
+        .unwrap();
+    assert_eq!(info.core_version, version);
+}
+
 #[test]
 fn network() {
     let testkit = TestKitBuilder::validator().with_validators(2).create();
@@ -102,3 +172,87 @@ fn rebroadcast() {
         ()
     )
 }
+
+#[test]
+fn index_proof() {
+    let mut testkit = TestKitBuilder::validator()
+        .with_validators(2)
+        .with_service(ProofService)
+        .create();
+    let (pk, _) = crypto::gen_keypair();
+    let hash_key = crypto::hash(b"key");
+
+    let fork = testkit.blockchain().fork();
+    {
+        let mut by_key: ProofMapIndex<_, PublicKey, String> =
+            ProofMapIndex::new("proof.by_key", &fork);
+        by_key.put(&pk, "value".to_owned());
+        let mut by_hash: ProofMapIndex<_, Hash, String> =
+            ProofMapIndex::new("proof.by_hash", &fork);
+        by_hash.put(&hash_key, "other value".to_owned());
+    }
+    testkit.blockchain_mut().merge(fork.into_patch()).unwrap();
+    // The hashes of the indices are aggregated into the state hash of the new block.
+    testkit.create_block();
+
+    let snapshot = testkit.snapshot();
+    let schema = Schema::new(&snapshot);
+    let state_hash = *schema.last_block().state_hash();
+    // Checks that the index hash is aggregated into the state hash as the given table.
+    let check_index_hash = |table_idx: usize, index_hash: Hash| {
+        let to_table = schema
+            .get_proof_to_service_table(PROOF_SERVICE_ID, table_idx)
+            .check()
+            .unwrap();
+        assert_eq!(to_table.root_hash(), state_hash);
+        assert_eq!(
+            to_table
+                .entries()
+                .map(|(_, hash)| *hash)
+                .collect::<Vec<_>>(),
+            vec![index_hash]
+        );
+    };
+
+    let api = testkit.api();
+    let IndexProof { height, proof } = api
+        .private(ApiKind::System)
+        .get(&format!(
+            "v1/proof?index=proof.by_key&key={}",
+            hex::encode(&pk)
+        ))
+        .unwrap();
+    assert_eq!(height, Height(1));
+    let proof = match proof {
+        IndexMapProof::Bytes(proof) => proof.check().unwrap(),
+        other => panic!("Unexpected proof: {:?}", other),
+    };
+    assert_eq!(
+        proof.entries().collect::<Vec<_>>(),
+        vec![(&pk.as_ref().to_vec(), &b"value".to_vec())]
+    );
+    check_index_hash(0, proof.root_hash());
+
+    let IndexProof { proof, .. } = api
+        .private(ApiKind::System)
+        .get(&format!(
+            "v1/proof?index=proof.by_hash&key={}&key_type=hash",
+            hex::encode(&hash_key)
+        ))
+        .unwrap();
+    let proof = match proof {
+        IndexMapProof::Hash(proof) => proof.check().unwrap(),
+        other => panic!("Unexpected proof: {:?}", other),
+    };
+    assert_eq!(
+        proof.entries().collect::<Vec<_>>(),
+        vec![(&hash_key, &b"other value".to_vec())]
+    );
+    check_index_hash(1, proof.root_hash());
+
+    let err = api
+        .private(ApiKind::System)
+        .get::<IndexProof>("v1/proof?index=test.unknown&key=00")
+        .unwrap_err();
+    assert_matches!(err, ApiError::NotFound(_));
+}